@@ -0,0 +1,149 @@
+//! The `orch-actions` block the orchestrator ends a daemon run with, so orch
+//! knows what it did, and checking its claims against the tasks dir and tmux
+//! (when `reconcile_actions` is on).
+
+use std::{collections::BTreeMap, fmt, path::Path};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, session, task, tmux};
+
+/// Appended to the orchestrator's system prompt.
+pub const PROMPT: &str = "\
+    End your reply with a fenced code block tagged `orch-actions` holding a JSON array of \
+    the actions you took, e.g. [{\"action\": \"started\", \"task\": \"auth\", \
+    \"session\": \"task-auth\"}]. Actions: started (a worker), killed (a worker), \
+    blocked (the task needs the user; add a \"reason\"), updated (its status), closed. \
+    Use [] if you did nothing.";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    // Aliases are the names used before blocked was added
+    #[serde(alias = "start_worker")]
+    Started,
+    #[serde(alias = "kill_worker")]
+    Killed,
+    Blocked,
+    #[serde(alias = "update_status")]
+    Updated,
+    #[serde(alias = "close")]
+    Closed,
+    /// Anything else the orchestrator made up; kept so one odd entry doesn't
+    /// drop the whole block.
+    #[serde(other)]
+    Other,
+}
+
+impl fmt::Display for ActionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ActionKind::Started => "started",
+            ActionKind::Killed => "killed",
+            ActionKind::Blocked => "blocked",
+            ActionKind::Updated => "updated",
+            ActionKind::Closed => "closed",
+            ActionKind::Other => "other",
+        })
+    }
+}
+
+/// One thing the orchestrator says it did in a run.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScanAction {
+    #[serde(rename = "action")]
+    pub kind: ActionKind,
+    pub task: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    /// Why a task is blocked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl fmt::Display for ScanAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<8} {}", self.kind, self.task)?;
+        if let Some(session) = &self.session {
+            write!(f, " ({session})")?;
+        }
+        if let Some(reason) = &self.reason {
+            write!(f, ": {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+/// "2 started, 1 blocked", or "nothing" for an empty run.
+pub fn summary(actions: &[ScanAction]) -> String {
+    let mut counts = BTreeMap::new();
+    for action in actions {
+        *counts.entry(action.kind).or_insert(0) += 1;
+    }
+    if counts.is_empty() {
+        return "nothing".into();
+    }
+    counts
+        .iter()
+        .map(|(kind, n)| format!("{n} {kind}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The last `orch-actions` block in `output`. None if there isn't one (older
+/// prompts) or it isn't valid JSON, which is logged but not an error.
+pub fn parse(output: &str) -> Option<Vec<ScanAction>> {
+    let start = output.rfind("```orch-actions")?;
+    let body = output[start..].split_once('\n')?.1;
+    let body = &body[..body.find("```")?];
+    serde_json::from_str(body)
+        .map_err(|e| eprintln!("[orch] ignoring malformed orch-actions block: {e}"))
+        .ok()
+}
+
+/// Claims that don't hold, one line each. `started` is when the run began.
+pub fn reconcile(
+    dir: &Path,
+    config: &Config,
+    actions: &[ScanAction],
+    started: DateTime<Local>,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    for action in actions {
+        let name = action.task.as_str();
+        let path = task::find(dir, name, config);
+        let session = || match (&action.session, &path) {
+            (Some(session), _) => session.clone(),
+            (None, Some(path)) => match task::read(path, config) {
+                Ok(content) => task::session(config, &task::label(dir, path), &content),
+                Err(_) => session::name(config, name),
+            },
+            (None, None) => session::name(config, name),
+        };
+        let problem = match action.kind {
+            ActionKind::Started if !tmux::has_session(&session()) => {
+                format!(
+                    "started a worker for {name}, but {} isn't running",
+                    session()
+                )
+            }
+            ActionKind::Killed if tmux::has_session(&session()) => {
+                format!("killed the worker for {name}, but {} is running", session())
+            }
+            ActionKind::Updated => match &path {
+                None => format!("updated {name}, but there's no such task"),
+                Some(path) if task::load(dir, path, config).modified < started => {
+                    format!("updated {name}, but its file hasn't changed")
+                }
+                Some(_) => continue,
+            },
+            ActionKind::Closed if path.is_some() => {
+                format!("closed {name}, but it's still in ~/tasks")
+            }
+            _ => continue,
+        };
+        problems.push(problem);
+    }
+    problems
+}
@@ -0,0 +1,100 @@
+//! Opt-in record of every orch invocation (`[audit_log] enabled = true`):
+//! one JSON line per command with its arguments, exit code and duration,
+//! appended to `.orch/audit.jsonl` or `audit_log.path`. Arguments that look
+//! like secrets are redacted first.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    time::Duration,
+};
+
+use chrono::Local;
+use serde_json::json;
+
+use crate::{config::AuditLogConfig, error, paths, task};
+
+const REDACTED: &str = "[redacted]";
+
+/// A flag or `key=value` key containing one of these takes a secret.
+const SECRET_KEYS: &[&str] = &[
+    "token", "secret", "password", "passwd", "api_key", "api-key", "apikey",
+];
+
+/// Prefixes of well-known credential formats, matched anywhere in an
+/// argument.
+const SECRET_VALUES: &[&str] = &[
+    "sk-ant-",
+    "sk-",
+    "ghp_",
+    "gho_",
+    "github_pat_",
+    "xoxb-",
+    "xoxp-",
+    "AKIA",
+    "Bearer ",
+];
+
+fn secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEYS.iter().any(|k| key.contains(k))
+}
+
+/// `args` with secrets replaced: values of secret-named flags and `key=`
+/// pairs, and anything containing a credential prefix or one of `extra`.
+pub fn redact(args: &[String], extra: &[String]) -> Vec<String> {
+    let mut out = Vec::with_capacity(args.len());
+    let mut after_secret_flag = false;
+    for arg in args {
+        let value_secret = SECRET_VALUES.iter().any(|p| arg.contains(p))
+            || extra
+                .iter()
+                .any(|p| !p.is_empty() && arg.contains(p.as_str()));
+        let redacted = if after_secret_flag || value_secret {
+            REDACTED.to_string()
+        } else if let Some((key, _)) = arg.split_once('=')
+            && secret_key(key)
+        {
+            format!("{key}={REDACTED}")
+        } else {
+            arg.clone()
+        };
+        after_secret_flag = arg.starts_with('-') && !arg.contains('=') && secret_key(arg);
+        out.push(redacted);
+    }
+    out
+}
+
+/// Appends this invocation to the audit log, if it's enabled. Failing to
+/// write it is reported, not fatal.
+pub fn record(dir: &Path, config: &AuditLogConfig, exit: i32, took: Duration) {
+    if !config.enabled {
+        return;
+    }
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let line = json!({
+        "at": task::timestamp(Local::now()),
+        "args": redact(&args, &config.redact),
+        "exit": exit,
+        "duration_secs": took.as_secs_f64(),
+    });
+    let path = config
+        .path
+        .clone()
+        .unwrap_or_else(|| paths::audit_path(dir));
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| {
+            let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+            writeln!(file, "{line}")
+        });
+    match result {
+        Ok(()) => {}
+        // A read-only tasks dir can't keep a log, and read commands are
+        // meant to work there without a complaint each time
+        Err(e) if error::read_only(&e) => {}
+        Err(e) => eprintln!("[orch] failed to append to {}: {e}", path.display()),
+    }
+}
@@ -0,0 +1,45 @@
+//! Runs' prompts and outputs, one file per distinct text in `.orch/blobs/`,
+//! named by its FNV-1a hash. A run record refers to its texts by id, so
+//! the hourly scan's identical prompt is stored once. Only the newest
+//! `run_history` runs keep theirs ([`prune`]).
+
+use std::{collections::HashSet, fs, path::Path};
+
+use crate::{config, paths, task};
+
+/// Stores `text`, returning its id. Failures are logged and give None: the
+/// run itself already happened.
+pub fn put(dir: &Path, text: &str) -> Option<String> {
+    let id = format!("{:016x}", config::fnv(text.as_bytes()));
+    let blobs = paths::blobs_dir(dir);
+    let path = blobs.join(&id);
+    if path.exists() {
+        return Some(id);
+    }
+    let result = fs::create_dir_all(&blobs).and_then(|_| task::write_atomic(&path, text));
+    match result {
+        Ok(()) => Some(id),
+        Err(e) => {
+            eprintln!("[orch] failed to save {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+pub fn get(dir: &Path, id: &str) -> Option<String> {
+    fs::read_to_string(paths::blobs_dir(dir).join(id)).ok()
+}
+
+/// Removes every blob not in `keep`.
+pub fn prune(dir: &Path, keep: &HashSet<String>) {
+    let Ok(read) = fs::read_dir(paths::blobs_dir(dir)) else {
+        return;
+    };
+    for entry in read.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // Dot files are writes in progress
+        if !name.starts_with('.') && !keep.contains(&name) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Per-task scan budgets. A task's `budget:` frontmatter caps how many
+//! orchestrator runs may be about it; usage is counted in `.orch/usage.json`.
+//! Over budget, the daemon stops scanning it until `orch scan <name> --force`.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::{config::Config, frontmatter, paths, state, task};
+
+/// The `budget:` field, in scans.
+pub fn limit(content: &str) -> Option<u32> {
+    frontmatter::get(content, "budget").and_then(|b| b.trim().parse().ok())
+}
+
+fn load(dir: &Path) -> BTreeMap<String, u32> {
+    state::load(&paths::usage_path(dir)).unwrap_or_default()
+}
+
+fn save(dir: &Path, usage: &BTreeMap<String, u32>) {
+    let path = paths::usage_path(dir);
+    let result = fs::create_dir_all(paths::state_dir(dir)).and_then(|_| {
+        let json = serde_json::to_string_pretty(usage).unwrap_or_default();
+        task::write_atomic(&path, &json)
+    });
+    if let Err(e) = result {
+        eprintln!("[orch] failed to save {}: {e}", path.display());
+    }
+}
+
+/// Scans counted against task `name`.
+pub fn used(dir: &Path, name: &str) -> u32 {
+    load(dir).get(name).copied().unwrap_or(0)
+}
+
+/// `(used, limit)` if the task has a budget and has used it up.
+pub fn over(dir: &Path, name: &str, content: &str) -> Option<(u32, u32)> {
+    let limit = limit(content)?;
+    let used = used(dir, name);
+    (used >= limit).then_some((used, limit))
+}
+
+/// Counts one scan against each of `names`.
+pub fn record(dir: &Path, names: &[String]) {
+    if names.is_empty() {
+        return;
+    }
+    let mut usage = load(dir);
+    for name in names {
+        *usage.entry(name.clone()).or_default() += 1;
+    }
+    save(dir, &usage);
+}
+
+/// Starts task `name`'s count over.
+pub fn reset(dir: &Path, name: &str) {
+    let mut usage = load(dir);
+    if usage.remove(name).is_some() {
+        save(dir, &usage);
+    }
+}
+
+/// Tasks that have used up their budget, as `(name, used, limit)`.
+pub fn exhausted(dir: &Path, config: &Config) -> Vec<(String, u32, u32)> {
+    task::list(dir, config)
+        .into_iter()
+        .filter_map(|t| {
+            let name = t.label.rsplit('/').next().unwrap_or(&t.label).to_string();
+            let (used, limit) = over(dir, &name, &t.content)?;
+            Some((name, used, limit))
+        })
+        .collect()
+}
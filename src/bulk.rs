@@ -0,0 +1,105 @@
+//! What bulk commands (kill, close, bump, archive) just did, kept in
+//! `.orch/bulk.jsonl` until the daemon's next scan hands it to the
+//! orchestrator, so a deliberate human change isn't re-derived from file
+//! events or undone.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::{fmt, paths, state, task};
+
+#[derive(Serialize, Deserialize)]
+pub struct Change {
+    pub at: String,
+    /// Past tense, as the command reports it: `closed`, `bumped`.
+    pub verb: String,
+    /// Task names, each with what happened to it when that varies
+    /// (`auth: priority 3`).
+    pub tasks: Vec<String>,
+}
+
+impl Change {
+    /// The task name an entry in `tasks` is about.
+    fn names(&self) -> impl Iterator<Item = &str> {
+        self.tasks
+            .iter()
+            .map(|t| t.split_once(": ").map_or(t.as_str(), |(name, _)| name))
+    }
+}
+
+/// Notes that `verb` was applied to `tasks`. Failures are logged, not
+/// returned: the command itself already succeeded.
+pub fn record(dir: &Path, verb: &str, tasks: Vec<String>) {
+    if tasks.is_empty() {
+        return;
+    }
+    let change = Change {
+        at: task::timestamp(Local::now()),
+        verb: verb.to_string(),
+        tasks,
+    };
+    let path = paths::bulk_path(dir);
+    let line = serde_json::to_string(&change).unwrap_or_default();
+    let result = fs::create_dir_all(paths::state_dir(dir)).and_then(|_| {
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(file, "{line}")
+    });
+    if let Err(e) = result {
+        eprintln!("failed to append to {}: {e}", path.display());
+    }
+}
+
+/// Changes not yet given to a scan, oldest first.
+pub fn pending(dir: &Path) -> Vec<Change> {
+    state::lines(&paths::bulk_path(dir)).collect()
+}
+
+/// Task names the pending changes are about.
+pub fn tasks(changes: &[Change]) -> Vec<&str> {
+    changes.iter().flat_map(Change::names).collect()
+}
+
+/// The pending changes as one `[bulk]` message part, removing them so
+/// only one scan sees them.
+pub fn take(dir: &Path) -> Option<String> {
+    let changes = pending(dir);
+    let _ = fs::remove_file(paths::bulk_path(dir));
+    summary(&changes)
+}
+
+/// `changes` as a `[bulk]` message part.
+pub fn summary(changes: &[Change]) -> Option<String> {
+    if changes.is_empty() {
+        return None;
+    }
+    let lines: Vec<_> = changes
+        .iter()
+        .map(|c| {
+            let tasks: Vec<_> = c
+                .tasks
+                .iter()
+                .map(|t| match t.split_once(": ") {
+                    Some((name, what)) => format!("{name} ({what})"),
+                    None => t.clone(),
+                })
+                .collect();
+            format!(
+                "- orch {} {}: {}",
+                c.verb,
+                fmt::count(tasks.len(), "task"),
+                tasks.join(", ")
+            )
+        })
+        .collect();
+    Some(format!(
+        "[bulk] The user ran these orch commands by hand. They're deliberate; \
+         don't undo or redo them:\n{}",
+        lines.join("\n")
+    ))
+}
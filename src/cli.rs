@@ -0,0 +1,895 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+
+use crate::{export::ExportFormat, hooks::HookEvent, render::Format};
+
+#[derive(Parser)]
+#[command(
+    name = "orch",
+    version,
+    about = "Task orchestrator for Claude Code workers"
+)]
+pub struct Cli {
+    /// Write command output to FILE instead of stdout (truncates it)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+    /// Append to the --output file instead of truncating it
+    #[arg(long, global = true, requires = "output")]
+    pub append: bool,
+    /// Take task names literally: no prefix or substring matching
+    #[arg(long, global = true)]
+    pub exact: bool,
+    /// Refuse anything that would change tasks, workers or orch's state
+    #[arg(long, global = true)]
+    pub read_only: bool,
+    /// ASCII glyphs in status and inbox, whatever the locale says
+    #[arg(long, global = true)]
+    pub ascii: bool,
+    /// Output format, for commands that have more than one (status, inbox,
+    /// show, runs); json works for all of them
+    #[arg(long, global = true, value_enum, value_name = "FORMAT")]
+    pub output_format: Option<Format>,
+    /// Exit 1 if anything printed a warning, even when the command worked
+    #[arg(long, global = true)]
+    pub deny_warnings: bool,
+    /// Show times in UTC instead of local time
+    #[arg(long, global = true)]
+    pub utc: bool,
+    #[command(subcommand)]
+    pub command: Option<Cmd>,
+}
+
+impl Cli {
+    /// Whether the command prints JSON, so warnings are held for it rather
+    /// than printed as they come ([`crate::warn`]).
+    pub fn json(&self) -> bool {
+        if matches!(self.output_format, Some(Format::Json | Format::Jsonl)) {
+            return true;
+        }
+        match &self.command {
+            Some(Cmd::Status(args)) => args.json || args.json_lines,
+            Some(Cmd::Inbox(args)) => args.json,
+            Some(Cmd::Show { json, .. }) => *json,
+            Some(
+                Cmd::Kill { plan, .. }
+                | Cmd::Close { plan, .. }
+                | Cmd::Archive { plan, .. }
+                | Cmd::Move { plan, .. },
+            ) => plan.json,
+            Some(Cmd::Daemon {
+                command: Some(DaemonCmd::Status { json }),
+                ..
+            }) => *json,
+            Some(Cmd::Timeline { json, .. } | Cmd::Stats { json, .. }) => *json,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Cmd {
+    /// Run the background watcher daemon
+    #[command(args_conflicts_with_subcommands = true)]
+    Daemon {
+        #[command(subcommand)]
+        command: Option<DaemonCmd>,
+        /// Poll ~/tasks for changes instead of using filesystem events
+        /// (for NFS and other network filesystems)
+        #[arg(long)]
+        poll_watch: bool,
+        /// Poll for changes this often (e.g. 1s); implies --poll-watch
+        #[arg(long, value_name = "DURATION")]
+        poll_interval: Option<String>,
+        /// Exit after this many orchestrator runs, counting the initial scan;
+        /// a scan split between roots is a run per root prompt
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        max_scans: Option<u32>,
+        /// Orchestrator prompt file for every scan, over the config and any
+        /// root's own prompt
+        #[arg(long, value_name = "FILE")]
+        prompt: Option<PathBuf>,
+    },
+    /// Show status of all tasks and workers
+    Status(StatusArgs),
+    /// One task in full: worker, state, summary and its status log
+    Show {
+        /// Default: the focused task (`orch focus`)
+        name: Option<String>,
+        /// Print it as JSON, with the file's content
+        #[arg(long)]
+        json: bool,
+    },
+    /// Popup-sized overview: inbox, workers, daemon health
+    Summary {
+        /// Jump to the Nth task listed
+        #[arg(long, value_name = "N")]
+        pick: Option<usize>,
+        /// Print a tmux bind-key line that opens the summary in a popup
+        #[arg(long, conflicts_with = "pick")]
+        tmux_bind: bool,
+    },
+    /// List tasks that need your attention
+    Inbox(InboxArgs),
+    /// Talk to the orchestrator interactively in the orch-console tmux
+    /// session, started with a snapshot of the tasks if it isn't running
+    Console {
+        /// Send a new snapshot to the running console instead of attaching
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Attach to a task's tmux session
+    Jump(JumpArgs),
+    /// Have the orchestrator start workers for tasks, highest priority
+    /// first, then oldest
+    Spawn {
+        /// Tasks to start workers for
+        #[arg(required_unless_present = "all_unstarted")]
+        names: Vec<String>,
+        /// Every task without a worker that isn't done, held, scheduled for
+        /// later or over its budget, up to `max_workers`
+        #[arg(long, conflicts_with = "names")]
+        all_unstarted: bool,
+        /// Start them one at a time, this long apart (30s, 2m). With a
+        /// daemon running, it carries out the rollout
+        #[arg(long, value_name = "DURATION")]
+        stagger: Option<String>,
+        /// List the order they'd start in, and what's skipped
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Kill tasks' worker sessions
+    Kill {
+        #[command(flatten)]
+        batch: BatchArgs,
+        #[command(flatten)]
+        plan: PlanArgs,
+    },
+    /// Kill tasks' workers and move their files to ~/tasks/done/
+    Close {
+        #[command(flatten)]
+        batch: BatchArgs,
+        #[command(flatten)]
+        plan: PlanArgs,
+        /// Close held tasks too
+        #[arg(long)]
+        force: bool,
+        /// How the task ended, stamped as `outcome:` [default: done, or
+        /// wontfix for a task that was never started]
+        #[arg(long, value_enum)]
+        outcome: Option<CloseOutcome>,
+    },
+    /// Close every task matching the filters into ~/tasks/done/, e.g.
+    /// `--status done --older-than 7d`. Held tasks are skipped
+    Archive {
+        /// Only tasks with no file edit, status entry or worker output for
+        /// this long
+        #[arg(long, value_name = "DURATION")]
+        older_than: Option<String>,
+        /// Only tasks whose `status:` field or latest status entry mentions
+        /// TEXT (ignoring case)
+        #[arg(long, value_name = "TEXT")]
+        status: Option<String>,
+        #[command(flatten)]
+        plan: PlanArgs,
+    },
+    /// Raise tasks' priority by one
+    Bump(BatchArgs),
+    /// Create a task file
+    New {
+        name: String,
+        /// Task description; read from stdin when omitted
+        text: Vec<String>,
+        /// Have claude expand the description into a full task brief
+        #[arg(long)]
+        ask: bool,
+        /// With --ask, keep the draft without asking
+        #[arg(long, requires = "ask")]
+        no_confirm: bool,
+        /// Git work tree the task is about: recorded as `workdir:`, with its
+        /// branch, recent commits and uncommitted changes added to the body
+        #[arg(long, value_name = "PATH")]
+        repo: Option<PathBuf>,
+        /// With --repo, switch it to this branch (created if needed)
+        #[arg(long, requires = "repo")]
+        branch: Option<String>,
+    },
+    /// Make a tmux session started by hand a worker, with a task file
+    Adopt {
+        session: String,
+        /// Task name; default the session's, without the session prefix
+        #[arg(long)]
+        name: Option<String>,
+        /// Don't offer to rename the session to fit session_format
+        #[arg(long)]
+        keep_name: bool,
+    },
+    /// Hide a task from the inbox for a while
+    Snooze {
+        /// Default: the focused task (`orch focus`)
+        name: Option<String>,
+        /// How long, e.g. 30m, 2h, 1d
+        #[arg(long = "for", value_name = "DURATION")]
+        duration: String,
+    },
+    /// Bring a snoozed task back into the inbox
+    Unsnooze {
+        /// Default: the focused task (`orch focus`)
+        name: Option<String>,
+    },
+    /// Keep a task from starting before a time; the daemon scans it then
+    #[command(group = clap::ArgGroup::new("when").required(true))]
+    Schedule {
+        /// Default: the focused task (`orch focus`)
+        name: Option<String>,
+        /// Local time, e.g. "2026-05-06 09:00" (or RFC 3339)
+        #[arg(long, group = "when")]
+        at: Option<String>,
+        /// From now, e.g. 2h, 1d
+        #[arg(long = "in", value_name = "DURATION", group = "when")]
+        after: Option<String>,
+        /// Drop the schedule
+        #[arg(long, group = "when")]
+        clear: bool,
+    },
+    /// Park a task: no daemon scans for it, and the orchestrator leaves it be
+    Hold {
+        /// Default: the focused task (`orch focus`)
+        name: Option<String>,
+    },
+    /// Release a held task
+    Unhold {
+        /// Default: the focused task (`orch focus`)
+        name: Option<String>,
+    },
+    /// Keep a task at the top of status, inbox and summary
+    Pin {
+        /// Default: the focused task (`orch focus`)
+        name: Option<String>,
+    },
+    /// Stop keeping a task at the top
+    Unpin {
+        /// Default: the focused task (`orch focus`)
+        name: Option<String>,
+    },
+    /// Ask the daemon to reconsider a task without editing its content
+    Touch {
+        /// Default: the focused task (`orch focus`)
+        name: Option<String>,
+    },
+    /// Record everything the task's worker pane prints to a transcript
+    Record {
+        /// Default: the focused task (`orch focus`)
+        name: Option<String>,
+        /// Stop recording
+        #[arg(long)]
+        stop: bool,
+    },
+    /// Have a second claude review a task's work: the workdir's diff
+    /// against its base branch, else the worker's output
+    Review {
+        /// Default: the focused task (`orch focus`)
+        name: Option<String>,
+        /// Open the reviewer as a window in the task's session instead
+        #[arg(long, short)]
+        interactive: bool,
+        /// Print what the reviewer would be given instead
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Move a task into a project subfolder
+    #[command(alias = "mv")]
+    Move {
+        name: String,
+        /// Project folder under ~/tasks (created if needed), or `.` for the
+        /// top level
+        project: String,
+        #[command(flatten)]
+        plan: PlanArgs,
+    },
+    /// Rename workers from one session prefix to another (after changing
+    /// `session_prefix`)
+    MigratePrefix {
+        old: String,
+        new: String,
+        /// Show what would change without touching tmux or task files
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// What orch did to one task outside its file: holds, snoozes, messages,
+    /// delegations, recording, ... and where its transcript is
+    Log {
+        /// Default: the focused task (`orch focus`)
+        name: Option<String>,
+        /// One JSON object per line
+        #[arg(long)]
+        json: bool,
+    },
+    /// What happened across all tasks, oldest first, by day
+    Timeline {
+        /// Start of the range: a duration ago (3h, 2d) or a timestamp
+        #[arg(long, default_value = "7d")]
+        since: String,
+        /// End of the range (default now)
+        #[arg(long)]
+        until: Option<String>,
+        /// Only this task
+        #[arg(long)]
+        task: Option<String>,
+        /// One JSON object per line, for export
+        #[arg(long)]
+        json: bool,
+    },
+    /// Trigger a one-shot orchestrator scan
+    Scan {
+        /// Only this task
+        name: Option<String>,
+        /// Scan the task even if it's held or over its budget, and start its
+        /// budget count over
+        #[arg(long, requires = "name")]
+        force: bool,
+        /// Wait for the daemon to run it, then print what the orchestrator did
+        #[arg(long)]
+        wait: bool,
+        /// Print the message the orchestrator would get, with `[messages]`
+        /// templates filled in, instead of sending it
+        #[arg(long, conflicts_with = "wait")]
+        dry_run: bool,
+        /// Return at once with a request id for `orch runs show`: the
+        /// daemon queues the scan, or without one a detached orch runs it
+        #[arg(long = "async", conflicts_with_all = ["wait", "dry_run"])]
+        detach: bool,
+    },
+    /// Runs an `orch scan --async` scan when no daemon is running
+    #[command(name = "_scan-run", hide = true)]
+    ScanRun { id: String, message: String },
+    /// Serve tasks, the inbox and daemon state as JSON on 127.0.0.1
+    Serve {
+        /// Port to listen on, instead of `serve.port` from the config
+        #[arg(long)]
+        port: Option<u16>,
+    },
+    /// Send a message to the orchestrator
+    #[command(name = "-")]
+    Msg {
+        /// Save the message as a new task file first (`--as-task=<name>` to
+        /// pick the name, else it's derived from the first sentence)
+        #[arg(long, value_name = "NAME", num_args = 0..=1, require_equals = true, default_missing_value = "")]
+        as_task: Option<String>,
+        /// Send it to this task's worker instead: typed into its live
+        /// session, else added to the task file for the next one
+        #[arg(long, value_name = "TASK", conflicts_with = "as_task")]
+        to: Option<String>,
+        /// While the daemon's runs fail for lack of a connection, hold the
+        /// message in the queue until they don't (`queue_on_failure`)
+        #[arg(long, conflicts_with_all = ["as_task", "to"])]
+        queue_on_failure: bool,
+        message: Vec<String>,
+    },
+    /// Messages held back while claude couldn't be reached
+    Queue {
+        #[command(subcommand)]
+        command: QueueCmd,
+    },
+    /// Set or show the task commands act on when none is named
+    Focus {
+        /// The task to focus
+        name: Option<String>,
+        /// Drop the focused task
+        #[arg(long, conflicts_with = "name")]
+        clear: bool,
+        /// Make the task of the tmux session you're attached to the focus
+        /// while you're in it
+        #[arg(long, value_enum, value_name = "ON|OFF")]
+        auto: Option<Toggle>,
+    },
+    /// Hand part of a task's work to another task
+    Delegate {
+        /// The task handing the work off
+        name: String,
+        /// The task taking it on
+        #[arg(long, value_name = "TASK")]
+        to: String,
+        /// What to hand over; read from stdin when omitted
+        text: Vec<String>,
+        /// Create the receiving task if it doesn't exist
+        #[arg(long)]
+        create: bool,
+    },
+    /// Records a worker's exit; run by the tmux hook the daemon puts on
+    /// worker panes
+    #[command(name = "_worker-exited", hide = true)]
+    WorkerExited {
+        name: String,
+        /// The pane's exit status; none if it was killed by a signal
+        code: Option<i32>,
+    },
+    /// Re-send the last orchestrator prompt that mentioned a task, or a
+    /// past run's exact prompt
+    Replay {
+        /// A task, or a run id from `orch runs` (or a prefix of one).
+        /// Default: the focused task (`orch focus`)
+        name: Option<String>,
+        /// Run it on this model instead of opus
+        #[arg(long)]
+        model: Option<String>,
+        /// Print the prompt instead of sending it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print the orchestrator prompt a root's scans run with, or each
+    /// root's prompt files
+    Prompt {
+        /// Project folder, or `.` for the top of the tasks dir
+        #[arg(long, value_name = "ROOT")]
+        root: Option<String>,
+        /// Resolve as if the daemon were started with this --prompt
+        #[arg(long, value_name = "FILE")]
+        prompt: Option<PathBuf>,
+    },
+    /// Show where orch finds its config, tasks and orchestrator prompts
+    Which {
+        /// Resolve as if the daemon were started with this --prompt
+        #[arg(long, value_name = "FILE")]
+        prompt: Option<PathBuf>,
+    },
+    /// Tasks created and completed and orchestrator runs, per day, week or
+    /// month
+    Stats {
+        #[arg(long, value_enum, default_value = "week")]
+        by: Period,
+        /// Start of the range: a duration ago (8w) or a timestamp
+        #[arg(long, value_name = "WHEN", default_value = "8w")]
+        since: String,
+        /// End of the range (default now)
+        #[arg(long, value_name = "WHEN")]
+        until: Option<String>,
+        /// Comma-separated, with a header row
+        #[arg(long, conflicts_with = "json")]
+        csv: bool,
+        /// Instead, what orchestrator runs have cost per task so far, most
+        /// expensive first
+        #[arg(long, conflicts_with_all = ["by", "since", "until"])]
+        by_task: bool,
+        /// The summary and buckets as a JSON object
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the daemon's orchestrator runs, newest first
+    #[command(args_conflicts_with_subcommands = true)]
+    Runs {
+        #[command(subcommand)]
+        command: Option<RunsCmd>,
+        /// How many to list
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Check the setup: tasks dir, config, tmux, claude and its login
+    Doctor {
+        /// Merge tasks' repeated ## Status sections into one
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Check every task file's structure: readable, one ## Status
+    /// section, no repeated frontmatter keys. Exits 1 if anything's wrong
+    Validate {
+        /// Merge tasks' repeated ## Status sections into one
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Print the orch version
+    Version {
+        /// Include tool versions and resolved paths, for bug reports
+        #[arg(long, short)]
+        verbose: bool,
+    },
+    /// Mirror tasks into an Obsidian vault folder or a todo.txt file;
+    /// task files are only read
+    Export {
+        #[arg(value_enum)]
+        to: ExportFormat,
+        /// The folder for obsidian, the file for todo-txt
+        path: PathBuf,
+        /// Update what an earlier export wrote there instead of refusing
+        #[arg(long)]
+        sync: bool,
+    },
+    /// Work with configured hooks
+    Hooks {
+        #[command(subcommand)]
+        command: HooksCmd,
+    },
+    /// Check orch's own files under .orch/
+    State {
+        #[command(subcommand)]
+        command: StateCmd,
+    },
+}
+
+impl Cmd {
+    /// The command as the user would name it, if running it would change
+    /// tasks, workers or orch's own state; `--read-only` refuses these.
+    /// Every new command has to be sorted here, so none slips through.
+    pub fn mutation(&self) -> Option<&'static str> {
+        match self {
+            Cmd::Daemon { command: None, .. } => Some("daemon"),
+            Cmd::Daemon {
+                command: Some(DaemonCmd::Status { .. }),
+                ..
+            } => None,
+            Cmd::Status(args) => args.refresh_sessions.then_some("status --refresh-sessions"),
+            Cmd::Jump(args) => args.spawn.then_some("jump --spawn"),
+            Cmd::Spawn { dry_run, .. } => (!dry_run).then_some("spawn"),
+            Cmd::Kill { plan, .. } => (!plan.dry_run).then_some("kill"),
+            Cmd::Close { plan, .. } => (!plan.dry_run).then_some("close"),
+            Cmd::Archive { plan, .. } => (!plan.dry_run).then_some("archive"),
+            Cmd::Move { plan, .. } => (!plan.dry_run).then_some("move"),
+            Cmd::MigratePrefix { dry_run, .. } => (!dry_run).then_some("migrate-prefix"),
+            Cmd::Replay { dry_run, .. } => (!dry_run).then_some("replay"),
+            Cmd::Doctor { fix } => fix.then_some("doctor --fix"),
+            Cmd::Validate { fix } => fix.then_some("validate --fix"),
+            Cmd::Inbox(args) => args.interactive.then_some("inbox --interactive"),
+            Cmd::Console { .. } => Some("console"),
+            Cmd::Bump(_) => Some("bump"),
+            Cmd::New { .. } => Some("new"),
+            Cmd::Adopt { .. } => Some("adopt"),
+            Cmd::Snooze { .. } => Some("snooze"),
+            Cmd::Unsnooze { .. } => Some("unsnooze"),
+            Cmd::Schedule { .. } => Some("schedule"),
+            Cmd::Hold { .. } => Some("hold"),
+            Cmd::Unhold { .. } => Some("unhold"),
+            Cmd::Pin { .. } => Some("pin"),
+            Cmd::Unpin { .. } => Some("unpin"),
+            Cmd::Touch { .. } => Some("touch"),
+            Cmd::Record { .. } => Some("record"),
+            Cmd::Review { dry_run, .. } => (!dry_run).then_some("review"),
+            Cmd::Scan { dry_run, .. } => (!dry_run).then_some("scan"),
+            Cmd::ScanRun { .. } => Some("_scan-run"),
+            Cmd::Msg { .. } => Some("-"),
+            Cmd::Delegate { .. } => Some("delegate"),
+            Cmd::Focus {
+                name, clear, auto, ..
+            } => (name.is_some() || *clear || auto.is_some()).then_some("focus"),
+            Cmd::WorkerExited { .. } => Some("_worker-exited"),
+            Cmd::Queue {
+                command: QueueCmd::Drop { .. },
+            } => Some("queue drop"),
+            Cmd::Queue {
+                command: QueueCmd::List,
+            } => None,
+            Cmd::Hooks { .. } => Some("hooks test"),
+            Cmd::Export { .. } => None,
+            Cmd::State {
+                command: StateCmd::Check { repair },
+            } => repair.then_some("state check --repair"),
+            Cmd::Summary { .. }
+            | Cmd::Log { .. }
+            | Cmd::Timeline { .. }
+            | Cmd::Serve { .. }
+            | Cmd::Stats { .. }
+            | Cmd::Runs { .. }
+            | Cmd::Show { .. }
+            | Cmd::Prompt { .. }
+            | Cmd::Which { .. }
+            | Cmd::Version { .. } => None,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct BatchArgs {
+    pub names: Vec<String>,
+    /// Also read task names from this file, one per line
+    #[arg(long)]
+    pub from_file: Option<PathBuf>,
+}
+
+/// Preview and confirmation for destructive commands, see [`crate::plan`].
+#[derive(Args)]
+pub struct PlanArgs {
+    /// Show what would be done without doing it
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Ask before each task (y/n, a for all the rest, q to stop)
+    #[arg(long, short)]
+    pub interactive: bool,
+    /// Print the plan and each task's outcome as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args)]
+pub struct InboxArgs {
+    /// Send a test notification through the configured backend
+    #[arg(long)]
+    pub notify_test: bool,
+    /// Show how each item's urgency score adds up
+    #[arg(long, short)]
+    pub verbose: bool,
+    /// Print the items as JSON, with their scores
+    #[arg(long, conflicts_with = "verbose")]
+    pub json: bool,
+    /// One line per item: task and reason. Below 60 columns, with state
+    /// glyphs (the default there)
+    #[arg(long, conflicts_with_all = ["json", "verbose"])]
+    pub compact: bool,
+    /// Ring the terminal bell once per item that's new since the last look
+    #[arg(long)]
+    pub bell: bool,
+    /// Include held tasks
+    #[arg(long)]
+    pub all: bool,
+    /// Go through the items one by one: jump, answer, snooze, ack or skip
+    /// each, then send the orchestrator one summary of what changed
+    #[arg(long, short, conflicts_with_all = ["json", "notify_test"])]
+    pub interactive: bool,
+}
+
+#[derive(Args, Default)]
+pub struct JumpArgs {
+    /// A task name (or part of one) or a worker session [default: the
+    /// focused task]
+    pub name: Option<String>,
+    /// Print the tmux command instead of running it
+    #[arg(long)]
+    pub print_command: bool,
+    /// Argument separator for --print-command (also `--format`, its old
+    /// name)
+    #[arg(long, alias = "format", value_enum, default_value_t = ArgvFormat::Space)]
+    pub sep: ArgvFormat,
+    /// Inside tmux, open the worker in a split of the current window
+    /// (h: side by side, v: stacked)
+    #[arg(long, value_enum, value_name = "DIR", num_args = 0..=1, default_missing_value = "h")]
+    pub split: Option<SplitDir>,
+    /// Inside tmux, open the worker in a new window of the current session
+    #[arg(long, conflicts_with = "split")]
+    pub window: bool,
+    /// Don't print the task's title, status and context first
+    #[arg(long)]
+    pub no_banner: bool,
+    /// Start the worker without asking if the task has none
+    #[arg(long)]
+    pub spawn: bool,
+    /// Never start a worker; fail with how to start one instead
+    #[arg(long, conflicts_with = "spawn")]
+    pub no_spawn: bool,
+}
+
+#[derive(Args, Default)]
+pub struct StatusArgs {
+    /// Only tasks whose file, status log or worker changed since WHEN
+    /// (a duration like 3h, or a timestamp)
+    #[arg(long, value_name = "WHEN")]
+    pub changed_since: Option<String>,
+    /// One aligned line per task: name, latest status, worker. Below 60
+    /// columns, one short line per task with state glyphs (the default
+    /// there)
+    #[arg(long)]
+    pub compact: bool,
+    /// Also list tasks closed into ~/tasks/done/
+    #[arg(long)]
+    pub include_archive: bool,
+    /// With --include-archive, only tasks archived since WHEN
+    #[arg(long, value_name = "WHEN", requires = "include_archive")]
+    pub since: Option<String>,
+    /// Cluster tasks under headers, each with a tally of its tasks
+    /// (default `status_group_by` from the config)
+    #[arg(long, value_enum)]
+    pub group_by: Option<GroupBy>,
+    /// With --group-by status, list the done tasks instead of counting them
+    #[arg(long)]
+    pub show_done: bool,
+    /// First fix `session:` lines that point at sessions that aren't
+    /// running: adopt the default session name if that one is, else drop
+    /// the line
+    #[arg(long)]
+    pub refresh_sessions: bool,
+    /// Redraw every --interval until interrupted. Read-only: never runs
+    /// claude, so it's safe alongside the daemon
+    #[arg(long, conflicts_with = "refresh_sessions")]
+    pub watch: bool,
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "5s",
+        requires = "watch"
+    )]
+    pub interval: String,
+    /// With --watch, send a notification when a task enters the inbox
+    #[arg(long, requires = "watch")]
+    pub notify: bool,
+    /// Only list tasks whose `status:` says running but whose worker
+    /// session isn't; exits 1 if there are any
+    #[arg(long, conflicts_with_all = ["compact", "group_by", "include_archive", "json", "json_lines"])]
+    pub sessions: bool,
+    /// What each task's orchestrator runs have cost so far
+    #[arg(long, conflicts_with = "sessions")]
+    pub cost: bool,
+    /// Print the tasks as a JSON array
+    #[arg(long, conflicts_with_all = ["compact", "group_by", "include_archive", "watch", "refresh_sessions"])]
+    pub json: bool,
+    /// Print each task as a JSON object on its own line
+    #[arg(
+        long,
+        alias = "jsonl",
+        conflicts_with_all = ["json", "compact", "group_by", "include_archive", "watch", "refresh_sessions"]
+    )]
+    pub json_lines: bool,
+}
+
+/// How a task ended, recorded as `outcome:` when it's closed.
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum CloseOutcome {
+    Done,
+    Wontfix,
+    Duplicate,
+    Superseded,
+}
+
+impl CloseOutcome {
+    pub fn name(self) -> &'static str {
+        match self {
+            CloseOutcome::Done => "done",
+            CloseOutcome::Wontfix => "wontfix",
+            CloseOutcome::Duplicate => "duplicate",
+            CloseOutcome::Superseded => "superseded",
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Period {
+    Day,
+    /// Monday to Sunday
+    Week,
+    Month,
+}
+
+#[derive(Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    /// Project folder; top-level tasks go under "(ungrouped)"
+    Project,
+    /// `status:` as new, blocked, in progress, other and done; done is
+    /// collapsed to a count unless --show-done
+    Status,
+    /// No groups, to override `status_group_by` in the config
+    #[value(name = "none")]
+    #[serde(rename = "none")]
+    Ungrouped,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum ArgvFormat {
+    /// Space-separated, newline-terminated
+    #[default]
+    Space,
+    /// Each argument NUL-terminated
+    Nul,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Toggle {
+    On,
+    Off,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SplitDir {
+    H,
+    V,
+}
+
+#[derive(Subcommand)]
+pub enum RunsCmd {
+    /// One run in full: actions and anything suspicious
+    Show {
+        /// Run id from `orch runs` (or a prefix of one); the latest if omitted
+        id: Option<String>,
+    },
+    /// A unified diff of two runs' outputs, e.g. a run and its replay
+    Diff {
+        /// Run ids from `orch runs` (or prefixes)
+        a: String,
+        b: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum QueueCmd {
+    /// Queued messages, oldest first
+    List,
+    /// Remove a queued message without sending it
+    Drop {
+        /// Id from `orch queue list` (or a prefix of one)
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DaemonCmd {
+    /// Show the daemon's state: pid, uptime, watch, last scan, backoff.
+    /// Exits 1 if it isn't running
+    Status {
+        /// Print it as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum HooksCmd {
+    /// Run an event's hook with a synthesized payload
+    Test {
+        event: HookEvent,
+        /// Fill the payload from this task instead of dummy data
+        #[arg(long)]
+        task: Option<String>,
+        /// Use this file as the JSON payload
+        #[arg(long)]
+        payload: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum StateCmd {
+    /// Parse every state file and look for transcripts of tasks that are
+    /// gone. Exits 1 if anything's wrong
+    Check {
+        /// Move lines and files that don't parse to a `.corrupt` file next
+        /// to them, and rebuild what can be rebuilt
+        #[arg(long)]
+        repair: bool,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(line.split(' '))
+    }
+
+    fn sep(line: &str) -> ArgvFormat {
+        match parse(line).unwrap().command {
+            Some(Cmd::Jump(args)) => args.sep,
+            _ => panic!("not a jump: {line}"),
+        }
+    }
+
+    #[test]
+    fn jump_takes_its_old_format_flag() {
+        assert!(matches!(
+            sep("orch jump auth --print-command --format nul"),
+            ArgvFormat::Nul
+        ));
+        assert!(matches!(
+            sep("orch jump auth --print-command --format=nul"),
+            ArgvFormat::Nul
+        ));
+        assert!(matches!(
+            sep("orch --output out.txt jump auth --format space"),
+            ArgvFormat::Space
+        ));
+        assert!(matches!(
+            sep("orch jump auth --print-command --sep nul"),
+            ArgvFormat::Nul
+        ));
+        assert!(parse("orch jump auth --format json").is_err());
+    }
+
+    #[test]
+    fn output_format_is_global() {
+        let cli = parse("orch --output-format json status").unwrap();
+        assert!(cli.output_format == Some(Format::Json));
+        let cli = parse("orch status --output-format jsonl").unwrap();
+        assert!(cli.output_format == Some(Format::Jsonl));
+        // Still the global flag, which main refuses for jump
+        let cli = parse("orch jump auth --output-format json").unwrap();
+        assert!(cli.output_format == Some(Format::Json));
+        assert!(parse("orch status --output-format nul").is_err());
+        assert!(parse("orch status --format json").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use std::path::Path;
+
+use chrono::Local;
+
+use super::new::confirm;
+use crate::{
+    config::Config,
+    edits,
+    error::OrchError,
+    frontmatter,
+    log::{self, Event},
+    messages, outln, paths, session, task, tmux,
+};
+
+/// How much of the pane goes into the task.
+const CONTEXT_LINES: usize = 100;
+
+/// The task name for `session`: without the `session_format` prefix, and
+/// with anything a file name can't hold turned into dashes.
+fn name_for(config: &Config, session: &str) -> String {
+    let prefix = session::prefix(config);
+    let rest = session
+        .strip_prefix(prefix)
+        .filter(|rest| !prefix.is_empty() && !rest.is_empty())
+        .unwrap_or(session);
+    rest.chars()
+        .map(|c| {
+            if c == '/' || c == '\\' || c.is_whitespace() {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect::<String>()
+        .trim_matches(['-', '.'])
+        .to_string()
+}
+
+/// A code fence longer than any run of backticks in `text`.
+fn fence(text: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// Makes a session started by hand a worker: a task file for it (named
+/// after the session, or `name`) with the pane's recent output, its
+/// `session:` recorded, and the session renamed to fit `session_format`
+/// unless `keep_name` or the user says no.
+pub fn cmd_adopt(
+    dir: &Path,
+    config: &Config,
+    session: &str,
+    name: Option<&str>,
+    keep_name: bool,
+) -> Result<(), OrchError> {
+    if session == session::CONSOLE {
+        return Err(OrchError::Usage(format!(
+            "{} is the `orch console` session, not a worker.",
+            session::CONSOLE
+        )));
+    }
+    if !tmux::has_session(session) {
+        return Err(OrchError::NotFound(format!("No tmux session '{session}'.")));
+    }
+    if let Some(existing) = session::task_for(dir, config, session) {
+        return Err(OrchError::Failed(format!(
+            "{session} is already the worker of {}.",
+            existing.display()
+        )));
+    }
+    let name = name.map_or_else(|| name_for(config, session), str::to_string);
+    task::validate_name(&name)
+        .map_err(|e| OrchError::Usage(format!("{e}; pass --name to pick one.")))?;
+    if let Some(existing) = task::find(dir, &name, config) {
+        return Err(OrchError::Failed(format!(
+            "{} already exists; pass --name to pick another.",
+            existing.display()
+        )));
+    }
+    let conforming = session::name(config, &name);
+    if conforming == session::CONSOLE {
+        return Err(OrchError::Usage(format!(
+            "'{name}' would get the session {}, which `orch console` uses; pass --name.",
+            session::CONSOLE
+        )));
+    }
+
+    // Captured before a rename, which would make it harder to find
+    let pane = tmux::capture_history(session, CONTEXT_LINES);
+    let mut current = session.to_string();
+    if current != conforming && !keep_name {
+        if tmux::has_session(&conforming) {
+            eprintln!("Keeping the name {session}: a session {conforming} already exists.");
+        } else if confirm(&format!(
+            "{session} doesn't follow session_format. Rename it to {conforming}?"
+        )) {
+            match tmux::rename_session(session, &conforming) {
+                Ok(()) => current = conforming,
+                Err(e) => eprintln!("warning: keeping the name {session}: {e}"),
+            }
+        }
+    }
+
+    let context = if pane.trim().is_empty() {
+        "(the pane was empty)".to_string()
+    } else {
+        let fence = fence(&pane);
+        format!("{fence}\n{pane}\n{fence}")
+    };
+    let body = format!(
+        "Adopted from the tmux session `{session}`, which was started by hand.\n\n\
+         ## Context at adoption\n\n{context}\n"
+    );
+    let path = task::create(dir, &name, &body)
+        .map_err(|e| OrchError::io(dir.join(format!("{name}.md")), e))?;
+    let now = Local::now();
+    let note = if current == session {
+        format!("Adopted the tmux session {session}")
+    } else {
+        format!("Adopted the tmux session {session}, renamed to {current}")
+    };
+    edits::apply(dir, config, &path, |content| {
+        let content = frontmatter::set(content, "session", Some(&current));
+        let content = frontmatter::set(&content, "adopted", Some(&task::timestamp(now)));
+        Some(task::add_status(&content, now, &note))
+    })?;
+    log::record(
+        dir,
+        &name,
+        Event::Adopted {
+            session: current.clone(),
+        },
+    );
+    messages::write(
+        &paths::inbox_dir(dir),
+        &format!(
+            "Task {name}.md was adopted from the tmux session {current}, which is its worker \
+             already. Don't start another; check on it and update its status."
+        ),
+    )?;
+    outln!("{}", path.display());
+    Ok(())
+}
@@ -0,0 +1,88 @@
+//! `orch archive`: `close` for every task matching some filters, as one plan.
+
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+
+use super::batch::{close_stamp, close_steps, execute};
+use crate::{
+    cli::PlanArgs, config::Config, duration, error::OrchError, frontmatter, outln, plan::Plan,
+    task, tmux,
+};
+
+/// When anything last happened to the task: a file edit, a `## Status`
+/// entry or output from its worker.
+fn last_activity(config: &Config, task: &task::Task) -> DateTime<Local> {
+    let session = task::session(config, &task.label, &task.content);
+    [
+        Some(task.modified),
+        task::latest_status(&task.content),
+        tmux::session_activity(&session),
+    ]
+    .into_iter()
+    .flatten()
+    .max()
+    .unwrap_or(task.modified)
+}
+
+/// Whether the task's `status:` field or latest `## Status` entry mentions
+/// `status`, ignoring case.
+fn status_matches(content: &str, status: &str) -> bool {
+    let status = status.to_lowercase();
+    let field = frontmatter::get(content, "status");
+    let latest = task::section(content, "## Status").last().copied();
+    field
+        .as_deref()
+        .into_iter()
+        .chain(latest)
+        .any(|s| s.to_lowercase().contains(&status))
+}
+
+/// Closes every task idle for at least `older_than` and/or whose status
+/// mentions `status`. Held and unreadable tasks are left alone.
+pub fn cmd_archive(
+    dir: &Path,
+    config: &Config,
+    older_than: Option<&str>,
+    status: Option<&str>,
+    plan_args: &PlanArgs,
+) -> Result<(), OrchError> {
+    if older_than.is_none() && status.is_none() {
+        return Err(OrchError::Usage(
+            "Give --older-than and/or --status; use orch close to close tasks by name.".into(),
+        ));
+    }
+    let cutoff = older_than
+        .map(|d| {
+            let age = duration::parse(d).map_err(OrchError::Parse)?;
+            chrono::Duration::from_std(age)
+                .ok()
+                .and_then(|age| Local::now().checked_sub_signed(age))
+                .ok_or_else(|| OrchError::Parse(format!("duration '{d}' is too long")))
+        })
+        .transpose()?;
+
+    let stamp = close_stamp();
+    let mut plan = Plan::new(dir, config);
+    for task in task::list(dir, config) {
+        if task.unreadable.is_some() || task::held(&task.content) {
+            continue;
+        }
+        if cutoff.is_some_and(|cutoff| last_activity(config, &task) > cutoff) {
+            continue;
+        }
+        if status.is_some_and(|s| !status_matches(&task.content, s)) {
+            continue;
+        }
+        let path = dir.join(format!("{}.md", task.label));
+        match close_steps(dir, config, &path, &stamp, None) {
+            Ok(steps) => plan.add(&task.name, steps),
+            Err(e) => plan.add_error(&task.name, e),
+        }
+    }
+    if plan.items.is_empty() {
+        outln!("No tasks match.");
+        return Ok(());
+    }
+    execute(&mut plan, plan_args, "archived")
+}
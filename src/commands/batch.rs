@@ -0,0 +1,338 @@
+//! Commands that take many task names: kill, close, bump. The destructive
+//! ones go through a [`Plan`].
+
+use std::{
+    cell::Cell,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+
+use crate::{
+    bulk,
+    cli::{BatchArgs, CloseOutcome, PlanArgs},
+    config::{self, Config},
+    edits,
+    error::OrchError,
+    frontmatter,
+    log::{self, Event},
+    outln, pauses,
+    plan::{Outcome, Plan, Step},
+    session,
+    task::{self, Progress},
+    tmux, transcript,
+};
+
+/// Names given on the command line plus those in `from_file` (blank lines and
+/// `#` comments skipped).
+fn batch_names(args: &BatchArgs) -> Result<Vec<String>, OrchError> {
+    let mut names = args.names.clone();
+    if let Some(file) = &args.from_file {
+        let text = fs::read_to_string(file).map_err(|e| OrchError::io(file, e))?;
+        names.extend(
+            text.lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(str::to_string),
+        );
+    }
+    if names.is_empty() {
+        return Err(OrchError::Usage("No tasks given.".into()));
+    }
+    Ok(names)
+}
+
+/// The task `name` resolves to, as (name, path), or a short reason why not.
+fn resolve(dir: &Path, config: &Config, name: &str) -> Result<(String, PathBuf), String> {
+    task::resolve(dir, name, config).map_err(|e| match e {
+        OrchError::NotFound(_) => "no such task".into(),
+        e => e.to_string(),
+    })
+}
+
+/// Applies `op` to every named task, carrying on past failures, then prints a
+/// summary. Fails with exit 1 if anything did.
+fn run_batch(
+    dir: &Path,
+    config: &Config,
+    args: &BatchArgs,
+    verb: &str,
+    op: impl Fn(&Path, &str) -> Result<String, String>,
+) -> Result<(), OrchError> {
+    let names = batch_names(args)?;
+
+    let mut failed = Vec::new();
+    let mut changed = Vec::new();
+    for name in &names {
+        let (name, result) = match resolve(dir, config, name) {
+            Ok((name, path)) => {
+                let result = op(&path, &name);
+                (name, result)
+            }
+            Err(e) => (name.clone(), Err(e)),
+        };
+        match result {
+            Ok(msg) => {
+                outln!("{name}: {msg}");
+                changed.push(format!("{name}: {msg}"));
+            }
+            Err(e) => {
+                eprintln!("{name}: {e}");
+                failed.push(name);
+            }
+        }
+    }
+
+    bulk::record(dir, verb, changed);
+
+    if names.len() > 1 || !failed.is_empty() {
+        eprintln!(
+            "{verb} {} of {}{}",
+            names.len() - failed.len(),
+            names.len(),
+            if failed.is_empty() {
+                String::new()
+            } else {
+                format!("; failed: {}", failed.join(", "))
+            }
+        );
+    }
+    if !failed.is_empty() {
+        return Err(OrchError::Reported(1));
+    }
+    Ok(())
+}
+
+/// Like [`run_batch`] for destructive commands: `build` turns each task into
+/// [`Step`]s, and the plan is previewed, confirmed or run per `plan_args`.
+fn run_plan(
+    dir: &Path,
+    config: &Config,
+    args: &BatchArgs,
+    plan_args: &PlanArgs,
+    verb: &str,
+    build: impl Fn(&Path) -> Result<Vec<Step>, String>,
+) -> Result<(), OrchError> {
+    let names = batch_names(args)?;
+
+    let mut plan = Plan::new(dir, config);
+    for name in &names {
+        match resolve(dir, config, name) {
+            Ok((name, path)) => match build(&path) {
+                Ok(steps) => plan.add(&name, steps),
+                Err(e) => plan.add_error(&name, e),
+            },
+            Err(e) => plan.add_error(name, e),
+        }
+    }
+    execute(&mut plan, plan_args, verb)
+}
+
+/// Notes each task `plan` closed in its log, with its transcript if it was
+/// ever recorded.
+fn log_closed(plan: &Plan) {
+    for item in plan.items.iter().filter(|i| i.outcome == Outcome::Done) {
+        let outcome = item.steps.iter().find_map(|step| match step {
+            Step::EditFrontmatter {
+                key,
+                value: Some(value),
+                ..
+            } if key == "outcome" => Some(value.clone()),
+            _ => None,
+        });
+        let Some(outcome) = outcome else {
+            continue;
+        };
+        let transcript = transcript::path(plan.dir(), &item.task);
+        let event = Event::Closed {
+            outcome,
+            transcript: transcript.exists().then_some(transcript),
+        };
+        log::record(plan.dir(), &item.task, event);
+    }
+}
+
+/// Confirms and runs `plan` per `plan_args` and reports how it went, with a
+/// "{verb} N of M" summary for more than one task. Fails if any task did.
+pub(super) fn execute(plan: &mut Plan, plan_args: &PlanArgs, verb: &str) -> Result<(), OrchError> {
+    let count = plan.items.len();
+    if plan_args.interactive && !plan_args.dry_run {
+        plan.confirm().map_err(OrchError::Usage)?;
+    }
+    if !plan_args.dry_run {
+        plan.execute();
+        let done = plan.items.iter().filter(|i| i.outcome == Outcome::Done);
+        bulk::record(plan.dir(), verb, done.map(|i| i.task.clone()).collect());
+        log_closed(plan);
+    }
+
+    if plan_args.json {
+        outln!(
+            "{}",
+            serde_json::to_string_pretty(&plan.items).unwrap_or_default()
+        );
+    } else {
+        for (ok, line) in plan.lines() {
+            if ok {
+                outln!("{line}");
+            } else {
+                eprintln!("{line}");
+            }
+        }
+    }
+
+    let failed = plan.failed();
+    if !plan_args.dry_run && (count > 1 || !failed.is_empty()) {
+        let done = plan
+            .items
+            .iter()
+            .filter(|i| i.outcome == Outcome::Done)
+            .count();
+        eprintln!(
+            "{verb} {done} of {count}{}",
+            if failed.is_empty() {
+                String::new()
+            } else {
+                format!("; failed: {}", failed.join(", "))
+            }
+        );
+    }
+    if !failed.is_empty() {
+        return Err(OrchError::Reported(1));
+    }
+    Ok(())
+}
+
+/// The task's worker session, if it's running.
+pub(super) fn running_session(dir: &Path, config: &Config, path: &Path) -> Option<String> {
+    let content = task::read(path, config).ok()?;
+    let session = task::session(config, &task::label(dir, path), &content);
+    (session != session::CONSOLE && tmux::has_session(&session)).then_some(session)
+}
+
+pub fn cmd_kill(
+    dir: &Path,
+    config: &Config,
+    args: &BatchArgs,
+    plan_args: &PlanArgs,
+) -> Result<(), OrchError> {
+    run_plan(
+        dir,
+        config,
+        args,
+        plan_args,
+        "killed",
+        |path| match running_session(dir, config, path) {
+            Some(session) => Ok(vec![Step::KillSession { session }]),
+            None => Err("no running worker".into()),
+        },
+    )
+}
+
+/// Closing one task: kill its worker if it's running, stamp `closed:`,
+/// `outcome:` and `active_secs:` (time since `created:` less time held or
+/// snoozed), drop `pinned:`, then move the file into `done/` under a
+/// `stamp`-prefixed name.
+/// With no `outcome`, a task never started closes as wontfix, anything else
+/// as done.
+pub(super) fn close_steps(
+    dir: &Path,
+    config: &Config,
+    path: &Path,
+    stamp: &str,
+    outcome: Option<CloseOutcome>,
+) -> Result<Vec<Step>, String> {
+    let content = task::read(path, config).map_err(|e| e.to_string())?;
+    let mut steps = Vec::new();
+    if let Some(session) = running_session(dir, config, path) {
+        steps.push(Step::KillSession { session });
+    }
+    let name = path.file_stem().unwrap_or_default().to_string_lossy();
+    let outcome = outcome.unwrap_or_else(|| {
+        if never_started(&content) {
+            eprintln!("{name}: never started, closing as wontfix (--outcome to choose)");
+            CloseOutcome::Wontfix
+        } else {
+            CloseOutcome::Done
+        }
+    });
+    let now = Local::now();
+    let hash = config::fnv(content.as_bytes());
+    let active = frontmatter::get(&content, "created")
+        .and_then(|c| task::parse_timestamp(&c))
+        .map(|created| {
+            let total = (now - created).num_seconds();
+            (total - pauses::paused_secs(dir, &name, created, now)).max(0)
+        });
+    let edits = [
+        ("closed", Some(task::timestamp(now))),
+        ("outcome", Some(outcome.name().to_string())),
+        ("active_secs", active.map(|secs| secs.to_string())),
+    ];
+    for (key, value) in edits {
+        if let Some(value) = value {
+            steps.push(Step::EditFrontmatter {
+                path: path.to_path_buf(),
+                key: key.into(),
+                value: Some(value),
+                hash,
+            });
+        }
+    }
+    if task::pinned(&content) {
+        steps.push(Step::EditFrontmatter {
+            path: path.to_path_buf(),
+            key: "pinned".into(),
+            value: None,
+            hash,
+        });
+    }
+    let file = path.file_name().unwrap_or_default().to_string_lossy();
+    steps.push(Step::MoveFile {
+        from: path.to_path_buf(),
+        to: dir.join("done").join(format!("{stamp}-{file}")),
+    });
+    Ok(steps)
+}
+
+/// No `## Status` entries and a `status:` that's new or missing.
+fn never_started(content: &str) -> bool {
+    task::progress(content) == Progress::New && task::section(content, "## Status").is_empty()
+}
+
+/// The prefix [`close_steps`] gives closed files.
+pub(super) fn close_stamp() -> String {
+    Local::now().format("%Y%m%d-%H%M%S").to_string()
+}
+
+pub fn cmd_close(
+    dir: &Path,
+    config: &Config,
+    args: &BatchArgs,
+    plan_args: &PlanArgs,
+    force: bool,
+    outcome: Option<CloseOutcome>,
+) -> Result<(), OrchError> {
+    let stamp = close_stamp();
+    run_plan(dir, config, args, plan_args, "closed", |path| {
+        let content = task::read(path, config).map_err(|e| e.to_string())?;
+        if !force && task::held(&content) {
+            return Err("held; use --force to close it".into());
+        }
+        close_steps(dir, config, path, &stamp, outcome)
+    })
+}
+
+pub fn cmd_bump(dir: &Path, config: &Config, args: &BatchArgs) -> Result<(), OrchError> {
+    run_batch(dir, config, args, "bumped", |path, _| {
+        let priority = Cell::new(0);
+        edits::apply(dir, config, path, |content| {
+            let (updated, bumped) = task::bump_priority(content);
+            priority.set(bumped);
+            Some(updated)
+        })
+        .map_err(|e| e.to_string())?;
+        Ok(format!("priority {}", priority.get()))
+    })
+}
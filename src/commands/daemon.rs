@@ -0,0 +1,245 @@
+//! `orch daemon status`: what the daemon last wrote to `.orch/daemon.json`,
+//! plus what can be read now (queued messages, whether the config changed).
+
+use std::{collections::BTreeMap, path::Path};
+
+use serde::Serialize;
+
+use crate::{
+    actions::ScanAction,
+    config::{self, Config},
+    error::OrchError,
+    fmt,
+    health::{self, MissingDir, Tripped},
+    messages, outln, paths, queue, task, warn,
+};
+
+/// Bumped when a field is renamed or removed; new fields don't bump it.
+const SCHEMA: u32 = 1;
+
+#[derive(Serialize)]
+struct LastScan {
+    at: String,
+    trigger: Option<String>,
+    duration_secs: Option<f64>,
+    ok: Option<bool>,
+    actions: Option<Vec<ScanAction>>,
+}
+
+/// The `--json` payload. With no daemon running, the fields hold what the
+/// last one left behind.
+#[derive(Serialize)]
+struct DaemonStatus {
+    schema: u32,
+    running: bool,
+    pid: Option<u32>,
+    started: Option<String>,
+    /// Only while running.
+    uptime_secs: Option<i64>,
+    last_tick: Option<String>,
+    config_hash: Option<String>,
+    /// The config file no longer matches what the daemon loaded.
+    config_changed: bool,
+    watching: Vec<String>,
+    watch_mode: Option<String>,
+    scanning_since: Option<String>,
+    last_scan: Option<LastScan>,
+    /// The tasks dir went missing and re-watching it is backing off.
+    backoff: Option<MissingDir>,
+    /// Messages queued for the daemon's next scan.
+    pending_messages: usize,
+    notifications: Option<String>,
+    /// Set while notifications are held back for digests.
+    breaker: Option<Tripped>,
+    /// Runs have been failing for lack of a connection since then.
+    offline_since: Option<String>,
+    /// Messages held back until claude is reachable.
+    queued_messages: usize,
+    /// Restarts after a panic, by subsystem, since the process started.
+    restarts: BTreeMap<String, u32>,
+    last_panic: Option<String>,
+}
+
+fn load(dir: &Path) -> DaemonStatus {
+    let pending_messages = messages::pending(&paths::inbox_dir(dir));
+    let queued_messages = queue::list(dir).len();
+    let Some(h) = health::load(dir) else {
+        return DaemonStatus {
+            schema: SCHEMA,
+            running: false,
+            pid: None,
+            started: None,
+            uptime_secs: None,
+            last_tick: None,
+            config_hash: None,
+            config_changed: false,
+            watching: Vec::new(),
+            watch_mode: None,
+            scanning_since: None,
+            last_scan: None,
+            backoff: None,
+            pending_messages,
+            notifications: None,
+            breaker: None,
+            offline_since: None,
+            queued_messages,
+            restarts: BTreeMap::new(),
+            last_panic: None,
+        };
+    };
+    let running = h.alive();
+    let uptime_secs = task::parse_timestamp(&h.started)
+        .filter(|_| running)
+        .map(|t| (chrono::Local::now() - t).num_seconds().max(0));
+    let config_changed = h.config_hash.as_ref().is_some_and(|hash| {
+        paths::config_path().and_then(|p| config::hash(&p)).as_ref() != Some(hash)
+    });
+    DaemonStatus {
+        schema: SCHEMA,
+        running,
+        pid: Some(h.pid),
+        started: Some(h.started),
+        uptime_secs,
+        last_tick: Some(h.last_tick),
+        config_hash: h.config_hash,
+        config_changed,
+        watching: h.watching.iter().map(|p| p.display().to_string()).collect(),
+        watch_mode: h.watch_mode,
+        scanning_since: h.scanning_since,
+        last_scan: h.last_scan.map(|at| LastScan {
+            at,
+            trigger: h.last_scan_trigger,
+            duration_secs: h.last_scan_duration_secs,
+            ok: h.last_scan_ok,
+            actions: h.last_scan_actions,
+        }),
+        backoff: h.missing,
+        pending_messages,
+        notifications: h.notifications,
+        breaker: h.breaker.filter(|_| running),
+        offline_since: h.offline_since,
+        queued_messages,
+        restarts: h.restarts,
+        last_panic: h.last_panic,
+    }
+}
+
+/// "3h ago", or the raw value if it doesn't parse.
+fn ago(at: &str) -> String {
+    task::parse_timestamp(at).map_or_else(|| at.to_string(), |t| format!("{} ago", fmt::age(t)))
+}
+
+/// Exits 1 when no daemon is running, so scripts can check it.
+pub fn cmd_daemon_status(dir: &Path, _config: &Config, json: bool) -> Result<(), OrchError> {
+    let status = load(dir);
+    if json {
+        let value = serde_json::to_value(&status)
+            .map_err(|e| OrchError::Failed(format!("failed to serialize daemon status: {e}")))?;
+        let text = serde_json::to_string_pretty(&warn::attach(value))
+            .map_err(|e| OrchError::Failed(format!("failed to serialize daemon status: {e}")))?;
+        outln!("{text}");
+    } else {
+        print(&status);
+    }
+    if !status.running {
+        return Err(OrchError::Reported(1));
+    }
+    Ok(())
+}
+
+fn print(s: &DaemonStatus) {
+    match (&s.pid, &s.last_tick) {
+        (Some(pid), _) if s.running => {
+            let up = s
+                .started
+                .as_deref()
+                .and_then(task::parse_timestamp)
+                .map(fmt::age)
+                .unwrap_or_else(|| "?".into());
+            outln!("daemon:         running (pid {pid}, up {up})");
+        }
+        (Some(pid), Some(tick)) => {
+            outln!(
+                "daemon:         not running (pid {pid} last seen {})",
+                ago(tick)
+            );
+        }
+        _ => outln!("daemon:         not running (no state in .orch/daemon.json)"),
+    }
+    if let Some(hash) = &s.config_hash {
+        let changed = if s.config_changed {
+            " (changed since the daemon loaded it; restart to apply)"
+        } else {
+            ""
+        };
+        outln!("config:         {hash}{changed}");
+    }
+    if !s.watching.is_empty() {
+        let mode = s.watch_mode.as_deref().unwrap_or("?");
+        outln!("watching:       {} ({mode})", s.watching.join(", "));
+    }
+    if let Some(n) = &s.notifications {
+        outln!("notifications:  {n}");
+    }
+    if let Some(b) = &s.breaker {
+        let held: u64 = b.held.values().sum();
+        let counts: Vec<_> = b.held.iter().map(|(k, n)| format!("{n} {k}")).collect();
+        let counts = if counts.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", counts.join(", "))
+        };
+        outln!(
+            "  held back:    since {}, {} waiting for the next digest{counts}",
+            ago(&b.since),
+            fmt::count(held as usize, "notification")
+        );
+    }
+    if let Some(since) = &s.scanning_since {
+        outln!("scanning:       since {}", ago(since));
+    }
+    if let Some(scan) = &s.last_scan {
+        let result = match scan.ok {
+            Some(true) => "ok",
+            Some(false) => "failed",
+            None => "?",
+        };
+        let took = scan
+            .duration_secs
+            .map(|d| format!(", took {}", fmt::elapsed(d)))
+            .unwrap_or_default();
+        outln!("last scan:      {}, {result}{took}", ago(&scan.at));
+        if let Some(trigger) = &scan.trigger {
+            outln!("  trigger:      {trigger}");
+        }
+    }
+    if let Some(b) = &s.backoff {
+        let retry = task::parse_timestamp(&b.retry_at)
+            .map_or_else(|| b.retry_at.clone(), |t| format!("in {}", fmt::until(t)));
+        outln!(
+            "tasks dir:      missing since {}, next re-watch {retry} (backoff {})",
+            ago(&b.since),
+            fmt::secs(b.backoff_secs as i64)
+        );
+    }
+    if !s.restarts.is_empty() {
+        let counts: Vec<_> = s.restarts.iter().map(|(k, n)| format!("{k} {n}")).collect();
+        outln!("restarts:       {}", counts.join(", "));
+        if let Some(panic) = &s.last_panic {
+            outln!("  last panic:   {panic}");
+        }
+    }
+    if let Some(since) = &s.offline_since {
+        outln!("offline:        claude unreachable since {}", ago(since));
+    }
+    outln!(
+        "pending:        {}",
+        fmt::count(s.pending_messages, "message")
+    );
+    if s.queued_messages > 0 {
+        outln!(
+            "queued:         {}, sent once claude is reachable",
+            fmt::count(s.queued_messages, "message")
+        );
+    }
+}
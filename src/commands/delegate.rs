@@ -0,0 +1,28 @@
+//! `orch delegate`: hands part of one task's work to another.
+
+use std::{io::Read, path::Path};
+
+use crate::{
+    config::Config,
+    delegation::{self, Via},
+    error::OrchError,
+    outln,
+};
+
+pub fn cmd_delegate(
+    dir: &Path,
+    config: &Config,
+    name: &str,
+    to: &str,
+    text: &[String],
+    create: bool,
+) -> Result<(), OrchError> {
+    let mut text = text.join(" ");
+    if text.is_empty() {
+        let _ = std::io::stdin().read_to_string(&mut text);
+    }
+    let d = delegation::delegate(dir, config, name, to, &text, create, Via::Command)?;
+    let created = if d.created { " (created)" } else { "" };
+    outln!("{} -> {}{created}", d.from, d.to);
+    Ok(())
+}
@@ -0,0 +1,291 @@
+use std::{
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use super::{validate::merge_status, version::tool_version};
+use crate::{
+    config::Config, daemon, duration, error::OrchError, fmt, include, lint, orchestrator, outln,
+    paths, roots, session, task,
+};
+
+/// Prints one check line; returns whether it passed.
+fn report(name: &str, result: Result<String, String>) -> bool {
+    match result {
+        Ok(detail) => {
+            outln!("ok    {name}: {detail}");
+            true
+        }
+        Err(detail) => {
+            outln!("FAIL  {name}: {detail}");
+            false
+        }
+    }
+}
+
+/// Roots that have their own prompt file, as (shown root, file).
+fn root_prompts(dir: &Path, config: &Config) -> Vec<(String, PathBuf)> {
+    roots::roots(dir)
+        .into_iter()
+        .map(|root| {
+            let path = roots::local(dir, config, root.as_deref());
+            (roots::show(root.as_deref()), path)
+        })
+        .filter(|(_, path)| path.is_file())
+        .collect()
+}
+
+fn check_tool(program: &str, arg: &str) -> Result<String, String> {
+    match tool_version(program, arg) {
+        v if v == "not found" => Err("not found on PATH".into()),
+        v => Ok(v),
+    }
+}
+
+/// Tasks with [`task::warnings`]. With `fix`, repeated `## Status` sections
+/// are merged first ([`task::merge_status`]); repeated frontmatter keys are
+/// left for a person to pick from.
+fn check_structure(dir: &Path, config: &Config, fix: bool) -> Result<String, String> {
+    let mut fixed = 0;
+    let mut problems = Vec::new();
+    for path in task::files(dir, config) {
+        let label = task::label(dir, &path);
+        // Unreadable files are the unreadable check's
+        let Ok(mut content) = task::read_checked(&path, config.max_task_bytes) else {
+            continue;
+        };
+        if fix {
+            match merge_status(dir, config, &path, &content) {
+                Ok(Some(merged)) => {
+                    content = merged;
+                    fixed += 1;
+                }
+                Ok(None) => {}
+                Err(e) => problems.push(format!("{label}.md: failed to fix: {e}")),
+            }
+        }
+        problems.extend(
+            task::warnings(&content)
+                .into_iter()
+                .map(|w| format!("{label}.md: {w}")),
+        );
+    }
+    let fixed = match fixed {
+        0 => String::new(),
+        n => format!(" (merged ## Status in {})", fmt::count(n, "file")),
+    };
+    if problems.is_empty() {
+        return Ok(format!("no repeated sections or keys{fixed}"));
+    }
+    let hint = if fix {
+        ""
+    } else {
+        "; `orch doctor --fix` merges ## Status sections"
+    };
+    Err(format!("{}{fixed}{hint}", problems.join("; ")))
+}
+
+/// Tasks whose bodies look like they try to instruct the orchestrator
+/// ([`lint`]), each finding listed.
+fn check_bodies(dir: &Path, config: &Config) -> Result<String, String> {
+    let flagged = lint::flagged(dir, config);
+    if flagged.is_empty() {
+        return Ok("nothing that looks like prompt injection".into());
+    }
+    let problems: Vec<_> = flagged
+        .iter()
+        .flat_map(|(label, findings)| findings.iter().map(move |f| format!("{label}.md {f}")))
+        .collect();
+    Err(format!(
+        "{}; scans tell the orchestrator to treat these files as untrusted",
+        problems.join("; ")
+    ))
+}
+
+/// Tasks whose recorded worker session doesn't use the configured prefix,
+/// as after changing `session_prefix`.
+fn check_prefix(dir: &Path, config: &Config) -> Result<String, String> {
+    let prefix = session::prefix(config);
+    let stale: Vec<_> = task::list(dir, config)
+        .into_iter()
+        .filter_map(|t| {
+            let session = task::session(config, &t.label, &t.content);
+            (!session.starts_with(prefix)).then_some((t.label, session))
+        })
+        .collect();
+    let Some((label, session)) = stale.first() else {
+        return Ok(format!("'{prefix}'"));
+    };
+    let name = label.rsplit('/').next().unwrap_or(label);
+    let old = session.strip_suffix(name).unwrap_or("OLD");
+    Err(format!(
+        "{} {} sessions without '{prefix}' (e.g. {session}); \
+         run `orch migrate-prefix {old} {prefix}`",
+        fmt::count(stale.len(), "task"),
+        if stale.len() == 1 { "has" } else { "have" }
+    ))
+}
+
+/// The daemon's startup block on stderr: a ✓/✗ line per prerequisite, then
+/// what it will watch and how often. Fails if a prerequisite is missing, so
+/// the daemon exits instead of sitting idle.
+pub fn daemon_preflight(dir: &Path, config: &Config) -> Result<(), OrchError> {
+    let color = io::stderr().is_terminal();
+    let mut ok = true;
+    let mut check = |name: &str, result: Result<String, String>| {
+        let (mark, detail) = match &result {
+            Ok(detail) => (if color { "\x1b[32m✓\x1b[0m" } else { "✓" }, detail),
+            Err(detail) => (if color { "\x1b[31m✗\x1b[0m" } else { "✗" }, detail),
+        };
+        eprintln!("[orch]   {mark} {name}: {detail}");
+        ok &= result.is_ok();
+    };
+
+    eprintln!("[orch] startup checks:");
+    // A first run creates the tasks dir; it only fails if that can't be done
+    let existed = dir.is_dir();
+    check(
+        "tasks dir",
+        fs::create_dir_all(dir)
+            .map(|()| {
+                let created = if existed { "" } else { " (created)" };
+                format!("{}{created}", dir.display())
+            })
+            .map_err(|e| format!("{}: {e}", dir.display())),
+    );
+    check(
+        "orchestrator prompt",
+        match &roots::global(config).1 {
+            None => Ok("the orchestrator agent".into()),
+            Some(path) => include::expand(path).and_then(|p| {
+                if p.trim().is_empty() {
+                    Err(format!("{} is empty", path.display()))
+                } else {
+                    Ok(format!("{} ({} lines)", path.display(), p.lines().count()))
+                }
+            }),
+        },
+    );
+    for (root, path) in root_prompts(dir, config) {
+        check(
+            &format!("prompt for {root}"),
+            include::expand(&path)
+                .map(|p| format!("{} ({} lines)", path.display(), p.lines().count())),
+        );
+    }
+    check("tmux", check_tool("tmux", "-V"));
+    check("claude", check_tool("claude", "--version"));
+
+    // Same fallback as the watcher
+    let interval = duration::parse(&config.poll_interval).unwrap_or(Duration::from_secs(5));
+    let watch = if config.poll_watch {
+        format!("polling every {}", fmt::duration(interval))
+    } else {
+        format!(
+            "native (polling every {} if it fails)",
+            fmt::duration(interval)
+        )
+    };
+    eprintln!(
+        "[orch]   file watch: {watch}, debounce {}",
+        fmt::duration(daemon::DEBOUNCE)
+    );
+    eprintln!(
+        "[orch]   periodic scan every {}, dir check every {}",
+        fmt::duration(daemon::POLL),
+        fmt::duration(daemon::TICK)
+    );
+    eprintln!("[orch]   watching: {} (recursive)", dir.display());
+    eprintln!("[orch]   inbox: {}", paths::inbox_dir(dir).display());
+
+    if !ok {
+        return Err(OrchError::Failed(
+            "daemon not started: a startup check failed (`orch doctor` has more)".into(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn cmd_doctor(
+    dir: &Path,
+    config: &Config,
+    config_path: Option<&Path>,
+    fix: bool,
+) -> Result<(), OrchError> {
+    let mut ok = true;
+
+    ok &= report(
+        "tasks dir",
+        if dir.is_dir() {
+            Ok(dir.display().to_string())
+        } else {
+            Err(format!("{} does not exist", dir.display()))
+        },
+    );
+    ok &= report(
+        "config",
+        match config_path.map(|path| (path, fs::read_to_string(path))) {
+            None => Ok(paths::NO_CONFIG.into()),
+            Some((path, Err(_))) => Ok(format!("{} (not found, defaults)", path.display())),
+            Some((path, Ok(text))) => toml::from_str::<Config>(&text)
+                .map(|_| path.display().to_string())
+                .map_err(|e| format!("{}: {e}", path.display())),
+        },
+    );
+    if dir.is_dir() {
+        let unreadable = task::unreadable(dir, config);
+        ok &= report(
+            "task files",
+            if unreadable.is_empty() {
+                Ok("all readable".into())
+            } else {
+                Err(unreadable
+                    .iter()
+                    .map(|(label, reason)| format!("{label}.md: {reason}"))
+                    .collect::<Vec<_>>()
+                    .join("; "))
+            },
+        );
+    }
+    if dir.is_dir() {
+        ok &= report("task structure", check_structure(dir, config, fix));
+        ok &= report("session prefix", check_prefix(dir, config));
+        ok &= report("task bodies", check_bodies(dir, config));
+    }
+    ok &= report(
+        "ORCH_REPO",
+        std::env::var("ORCH_REPO").map_err(|_| "unset (the daemon needs it)".into()),
+    );
+    if let Some(path) = &roots::global(config).1 {
+        ok &= report(
+            "orchestrator prompt",
+            include::expand(path)
+                .map(|p| format!("{} ({} lines)", path.display(), p.lines().count())),
+        );
+    }
+    for (root, path) in root_prompts(dir, config) {
+        ok &= report(
+            &format!("prompt for {root}"),
+            include::expand(&path)
+                .map(|p| format!("{} ({} lines)", path.display(), p.lines().count())),
+        );
+    }
+    ok &= report("tmux", check_tool("tmux", "-V"));
+
+    let claude = check_tool("claude", "--version");
+    let found = claude.is_ok();
+    ok &= report("claude", claude);
+    if found && !config.read_only {
+        ok &= report(
+            "claude auth",
+            orchestrator::check_auth().map(|()| "logged in".into()),
+        );
+    }
+
+    if !ok {
+        return Err(OrchError::Reported(1));
+    }
+    Ok(())
+}
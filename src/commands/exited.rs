@@ -0,0 +1,14 @@
+//! `orch _worker-exited`: what a worker pane's exit hook runs.
+
+use std::path::Path;
+
+use crate::{config::Config, error::OrchError, exits};
+
+pub fn cmd_worker_exited(
+    dir: &Path,
+    config: &Config,
+    name: &str,
+    code: Option<i32>,
+) -> Result<(), OrchError> {
+    exits::exited(dir, config, name, code)
+}
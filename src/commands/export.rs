@@ -0,0 +1,42 @@
+use std::path::Path;
+
+use crate::{
+    config::Config,
+    error::OrchError,
+    export::{self, ExportFormat},
+    fmt, outln,
+};
+
+/// Writes every task to `path` in the format `to`. With `sync`, what an
+/// earlier export wrote for a task is updated in place, found by the id it
+/// carries.
+pub fn cmd_export(
+    dir: &Path,
+    config: &Config,
+    to: ExportFormat,
+    path: &Path,
+    sync: bool,
+) -> Result<(), OrchError> {
+    let exporter = to.exporter();
+    let earlier = exporter.exported(path);
+    let entries = export::entries(dir, config, &earlier);
+    let written = exporter.write(&entries, path, sync)?;
+    let mut parts = vec![format!("{} new", written.created)];
+    if sync {
+        parts.push(format!("{} updated", written.updated));
+        parts.push(format!("{} unchanged", written.unchanged));
+    }
+    outln!(
+        "Exported {} to {} ({}).",
+        fmt::count(entries.len(), "task"),
+        path.display(),
+        parts.join(", ")
+    );
+    if written.orphaned > 0 {
+        outln!(
+            "Left {} exported earlier for tasks that are gone as they were.",
+            fmt::count(written.orphaned, "entry")
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use crate::{
+    cli::Toggle,
+    config::Config,
+    error::OrchError,
+    focus::{self, Source},
+    outln, task,
+};
+
+/// Sets, clears or shows the focused task, and turns following the
+/// attached tmux session on or off.
+pub fn cmd_focus(
+    dir: &Path,
+    config: &Config,
+    name: Option<&str>,
+    clear: bool,
+    auto: Option<Toggle>,
+) -> Result<(), OrchError> {
+    let mut saved = focus::load(dir);
+    if name.is_none() && !clear && auto.is_none() {
+        match focus::current(dir, config) {
+            Some((name, Source::Attached)) => outln!("{name} (the attached session's task)"),
+            Some((name, Source::Explicit)) => outln!("{name}"),
+            None => outln!("No task focused."),
+        }
+        let auto = if saved.auto { "on" } else { "off" };
+        outln!("auto: {auto}");
+        return Ok(());
+    }
+    if let Some(name) = name {
+        let (name, _) = task::resolve_open(dir, name, config, "focused")?;
+        outln!("Focused on {name}.");
+        saved.task = Some(name);
+    }
+    if clear {
+        saved.task = None;
+        outln!("Cleared the focus.");
+    }
+    if let Some(auto) = auto {
+        saved.auto = auto == Toggle::On;
+        outln!(
+            "{}",
+            if saved.auto {
+                "The focus follows the tmux session you're attached to."
+            } else {
+                "The focus no longer follows tmux."
+            }
+        );
+    }
+    focus::save(dir, &saved)
+}
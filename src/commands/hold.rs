@@ -0,0 +1,38 @@
+use std::path::Path;
+
+use chrono::Local;
+
+use crate::{
+    config::Config,
+    edits,
+    error::OrchError,
+    frontmatter,
+    log::{self, Event},
+    outln, task,
+};
+
+/// Parks the task: the daemon won't scan for it and the orchestrator is told
+/// to leave it alone.
+pub fn cmd_hold(dir: &Path, config: &Config, name: &str) -> Result<(), OrchError> {
+    let (name, path) = task::resolve(dir, name, config)?;
+    edits::apply(dir, config, &path, |content| {
+        Some(frontmatter::set(content, "hold", Some("true")))
+    })?;
+    log::record(dir, &name, Event::Held);
+    outln!("{name}: held");
+    Ok(())
+}
+
+/// Releases a held task, stamping `touched_at:` so the daemon picks it back
+/// up.
+pub fn cmd_unhold(dir: &Path, config: &Config, name: &str) -> Result<(), OrchError> {
+    let (name, path) = task::resolve(dir, name, config)?;
+    let now = task::timestamp(Local::now());
+    edits::apply(dir, config, &path, |content| {
+        let content = frontmatter::set(content, "hold", None);
+        Some(frontmatter::set(&content, "touched_at", Some(&now)))
+    })?;
+    log::record(dir, &name, Event::Released);
+    outln!("{name}: released");
+    Ok(())
+}
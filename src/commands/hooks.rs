@@ -0,0 +1,104 @@
+use std::{fs, path::Path, time::Instant};
+
+use chrono::{Local, TimeDelta};
+use serde_json::json;
+
+use crate::{
+    config::Config,
+    error::OrchError,
+    fmt,
+    hooks::{self, HookEvent},
+    out, outln, session, task, templates,
+};
+
+pub fn cmd_hooks_test(
+    dir: &Path,
+    config: &Config,
+    config_path: Option<&Path>,
+    event: HookEvent,
+    task: Option<&str>,
+    payload: Option<&Path>,
+) -> Result<(), OrchError> {
+    let command = config.hooks.get(event.name()).ok_or_else(|| {
+        OrchError::Config(match config_path {
+            Some(path) => format!(
+                "No hook configured for '{}' in {}.",
+                event.name(),
+                path.display()
+            ),
+            None => format!("No hook configured for '{}': no config file.", event.name()),
+        })
+    })?;
+
+    let payload = if let Some(file) = payload {
+        let text = fs::read_to_string(file).map_err(|e| OrchError::io(file, e))?;
+        serde_json::from_str(&text)
+            .map_err(|e| OrchError::Parse(format!("bad payload {}: {e}", file.display())))?
+    } else {
+        let (name, path) = match task {
+            Some(name) => task::resolve(dir, name, config)?,
+            None => ("example".to_string(), dir.join("example.md")),
+        };
+        match event {
+            HookEvent::NewTask => json!({
+                "event": event.name(),
+                "task": name,
+                "path": path,
+            }),
+            HookEvent::Message => json!({
+                "event": event.name(),
+                "task": name,
+                "message": format!("{}: test message", session::name(config, &name)),
+            }),
+            HookEvent::ScanFinished | HookEvent::ScanFailed => json!({
+                "event": event.name(),
+                "task": name,
+                "trigger": format!("[new-task] {name}.md"),
+                "duration_secs": 0.0,
+                "actions": [{
+                    "action": "started",
+                    "task": name,
+                    "session": session::name(config, &name),
+                }],
+            }),
+            HookEvent::ScanDiscrepancy => json!({
+                "event": event.name(),
+                "task": name,
+                "trigger": templates::scan(config, dir),
+                "discrepancies": [format!(
+                    "started a worker for {name}, but {} isn't running",
+                    session::name(config, &name)
+                )],
+            }),
+            HookEvent::TasksDirMissing => json!({
+                "event": event.name(),
+                "path": dir,
+                "missing_secs": 300,
+            }),
+            HookEvent::InboxEscalated => json!({
+                "event": event.name(),
+                "task": name,
+                "label": name,
+                "reason": "needs input: which API version?",
+                "since": task::timestamp(Local::now() - TimeDelta::days(1)),
+                "step": "priority",
+                "priority": 1,
+            }),
+        }
+    };
+
+    let start = Instant::now();
+    let out = hooks::run(command, event.name(), &payload, dir)
+        .map_err(|e| OrchError::Failed(format!("failed to run hook: {e}")))?;
+    let elapsed = start.elapsed();
+
+    out!("{}", String::from_utf8_lossy(&out.stdout));
+    eprint!("{}", String::from_utf8_lossy(&out.stderr));
+    let code = out
+        .status
+        .code()
+        .map_or_else(|| out.status.to_string(), |c| c.to_string());
+    outln!("exit: {code}");
+    outln!("elapsed: {}", fmt::duration(elapsed));
+    Ok(())
+}
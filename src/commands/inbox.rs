@@ -0,0 +1,609 @@
+use std::{
+    fmt::Write as _,
+    io::{self, IsTerminal, Read, Write},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::{
+    cli::{InboxArgs, JumpArgs},
+    config::{Config, EscalationConfig},
+    error::OrchError,
+    escalation::{self, Severity},
+    fmt,
+    inbox::{self, InboxItem},
+    messages,
+    notify::{EventKind, Notifiers, TaskEvent},
+    outln, paths,
+    render::{self, Format, Report, Style},
+    task, tmux,
+};
+use chrono::Local;
+use serde::Serialize;
+
+use super::{jump, snooze};
+
+/// Pane lines shown with an item in `--interactive`.
+const PANE_LINES: usize = 10;
+
+/// One key from the terminal, without waiting for Enter when `stty` can
+/// switch the tty to cbreak mode; else the first character of a line.
+fn read_key() -> Option<char> {
+    let stty = |args: &[&str]| {
+        Command::new("stty")
+            .args(args)
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    };
+    if !stty(&["-icanon", "-echo", "min", "1"]) {
+        let mut line = String::new();
+        io::stdin().read_line(&mut line).ok()?;
+        return Some(line.trim().chars().next().unwrap_or('\n'));
+    }
+    let mut byte = [0u8; 1];
+    let read = io::stdin().read(&mut byte);
+    stty(&["icanon", "echo"]);
+    eprintln!();
+    match read {
+        Ok(1) => Some(byte[0] as char),
+        _ => None,
+    }
+}
+
+/// A line of text after `prompt`, trimmed; None if it's empty.
+fn read_line(prompt: &str) -> Option<String> {
+    eprint!("{prompt}");
+    let _ = io::stderr().flush();
+    let mut line = String::new();
+    io::stdin().read_line(&mut line).ok()?;
+    let line = line.trim();
+    (!line.is_empty()).then(|| line.to_string())
+}
+
+/// What the user did with an item in `--interactive`.
+enum Done {
+    Answered(String),
+    /// The answer went nowhere yet: no live worker to type it into.
+    Relayed(String),
+    Snoozed(String),
+    Acked,
+}
+
+impl Done {
+    /// How the summary to the orchestrator puts it.
+    fn describe(&self) -> String {
+        match self {
+            Done::Answered(text) => format!("answered the worker: {text:?}"),
+            Done::Relayed(text) => {
+                format!("answered, but it has no live worker; pass this on: {text:?}")
+            }
+            Done::Snoozed(duration) => format!("snoozed for {duration}"),
+            Done::Acked => "acknowledged; follow up on it".into(),
+        }
+    }
+}
+
+/// Prints an item with its task's title, status, context and the tail of
+/// its worker's pane.
+fn show_item(dir: &Path, config: &Config, item: &InboxItem, n: usize, of: usize) {
+    let session = item.session.as_deref().unwrap_or("no worker");
+    eprintln!(
+        "\n[{n}/{of}] {}  ({session}, {} in inbox)",
+        item.label,
+        fmt::age(item.since)
+    );
+    eprintln!("   {}", item.reason);
+    if let Ok((_, path)) = task::resolve(dir, &item.label, config)
+        && let Ok(content) = task::read(&path, config)
+    {
+        jump::banner(&item.name, &content);
+    }
+    if let Some(session) = &item.session {
+        let pane = tmux::capture_pane(session);
+        let lines: Vec<_> = pane.lines().filter(|l| !l.trim().is_empty()).collect();
+        eprintln!("   pane:");
+        for line in &lines[lines.len().saturating_sub(PANE_LINES)..] {
+            eprintln!("     {line}");
+        }
+    }
+}
+
+/// `--interactive`: walks `items`, acting on each with the same code as
+/// `orch jump` and `orch snooze`, then sends the orchestrator one message
+/// about everything that changed, so the daemon runs a single scan.
+fn walk(dir: &Path, config: &Config, items: &[InboxItem]) -> Result<(), OrchError> {
+    if !io::stdin().is_terminal() {
+        return Err(OrchError::Usage(
+            "--interactive needs a terminal on stdin.".into(),
+        ));
+    }
+    if items.is_empty() {
+        outln!("  (nothing needs attention)");
+        return Ok(());
+    }
+    let mut done: Vec<(&InboxItem, Done)> = Vec::new();
+    let mut skipped = 0;
+    let mut quit_at = items.len();
+    'items: for (i, item) in items.iter().enumerate() {
+        show_item(dir, config, item, i + 1, items.len());
+        loop {
+            eprint!("[j]ump [a]nswer [s]nooze ac[k] [n]ext [q]uit > ");
+            let _ = io::stderr().flush();
+            match read_key() {
+                Some('j') => {
+                    let args = JumpArgs {
+                        name: Some(item.label.clone()),
+                        no_banner: true,
+                        no_spawn: true,
+                        ..JumpArgs::default()
+                    };
+                    if let Err(e) = jump::cmd_jump(dir, config, &args) {
+                        eprintln!("{e}");
+                    }
+                }
+                Some('a') => {
+                    let Some(text) = read_line("answer: ") else {
+                        continue;
+                    };
+                    let sent = item.session.as_deref().map(|s| tmux::send_line(s, &text));
+                    match sent {
+                        Some(Ok(())) => done.push((item, Done::Answered(text))),
+                        Some(Err(e)) => {
+                            eprintln!("couldn't type it into the worker: {e}");
+                            done.push((item, Done::Relayed(text)));
+                        }
+                        None => {
+                            eprintln!("no live worker; the orchestrator will pass it on");
+                            done.push((item, Done::Relayed(text)));
+                        }
+                    }
+                    continue 'items;
+                }
+                Some('s') => {
+                    let Some(duration) = read_line("snooze for (e.g. 2h): ") else {
+                        continue;
+                    };
+                    match snooze::cmd_snooze(dir, config, &item.label, &duration) {
+                        Ok(()) => {
+                            done.push((item, Done::Snoozed(duration)));
+                            continue 'items;
+                        }
+                        Err(e) => eprintln!("{e}"),
+                    }
+                }
+                Some('k') => {
+                    done.push((item, Done::Acked));
+                    continue 'items;
+                }
+                Some('n' | '\n' | ' ') => {
+                    skipped += 1;
+                    continue 'items;
+                }
+                Some('q') | None => {
+                    quit_at = i;
+                    break 'items;
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    outln!();
+    for (item, what) in &done {
+        outln!("  {}: {}", item.label, what.describe());
+    }
+    outln!(
+        "{} done, {skipped} skipped{}",
+        done.len(),
+        if quit_at < items.len() {
+            format!(", {} left", items.len() - quit_at)
+        } else {
+            String::new()
+        }
+    );
+    if done.is_empty() {
+        return Ok(());
+    }
+    let lines: Vec<_> = done
+        .iter()
+        .map(|(item, what)| format!("- {}.md: {}", item.label, what.describe()))
+        .collect();
+    messages::write(
+        &paths::inbox_dir(dir),
+        &format!(
+            "[inbox] The user went through the inbox:\n{}\nCheck on these tasks and update their status.",
+            lines.join("\n")
+        ),
+    )?;
+    eprintln!("[orch] sent the orchestrator a summary");
+    Ok(())
+}
+
+/// The item's label with its pinned and held marks.
+fn marked(item: &InboxItem) -> String {
+    let mut label = item.label.clone();
+    if item.pinned {
+        label.push(' ');
+        label.push_str(render::PIN);
+    }
+    if item.held {
+        label.push_str(" ⏸");
+    }
+    label
+}
+
+/// A glyph of the narrow `--compact` layout: (glyph, ASCII, meaning).
+type Glyph = (&'static str, &'static str, &'static str);
+
+const WAITING: Glyph = ("◆", "?", "worker waiting");
+const WORKER: Glyph = ("●", "*", "worker");
+const NO_WORKER: Glyph = ("○", "-", "no worker");
+const AGE: Glyph = ("⏱", "@", "in inbox");
+
+/// The item's glyph: is a worker there, and waiting on you.
+fn glyph(item: &InboxItem) -> Glyph {
+    match &item.session {
+        Some(_) if item.score.waiting > 0.0 => WAITING,
+        Some(_) => WORKER,
+        None => NO_WORKER,
+    }
+}
+
+/// `orch inbox`, in any format. As JSON it's the items as
+/// [`InboxItem::to_json`] has them, which `orch serve` shares.
+#[derive(Serialize)]
+#[serde(transparent)]
+struct InboxReport<'a> {
+    json: Vec<serde_json::Value>,
+    #[serde(skip)]
+    items: &'a [InboxItem],
+    /// Show how each score adds up.
+    #[serde(skip)]
+    verbose: bool,
+    /// For coloring how long items have been in the inbox.
+    #[serde(skip)]
+    escalation: &'a EscalationConfig,
+}
+
+impl InboxReport<'_> {
+    /// One line per item, `◆ recon ⏱2h <reason>`, cut to the terminal, and
+    /// a legend for the glyphs shown.
+    fn narrow_text(&self, text: &mut String, style: Style) {
+        let width = style.width.unwrap_or(render::NARROW);
+        let labels: Vec<&str> = self.items.iter().map(|i| i.label.as_str()).collect();
+        let mut shown: Vec<Glyph> = Vec::new();
+        for item in self.items {
+            let glyph = glyph(item);
+            if !shown.contains(&glyph) {
+                shown.push(glyph);
+            }
+            let mut label = render::shorten(&item.label, &labels, width / 2);
+            if item.pinned {
+                label.push(' ');
+                label.push_str(style.glyph(render::PIN, "^"));
+            }
+            if item.held {
+                label.push(' ');
+                label.push_str(style.glyph("⏸", "="));
+            }
+            let line = format!(
+                "{} {label} {}{} {}",
+                style.glyph(glyph.0, glyph.1),
+                style.glyph(AGE.0, AGE.1),
+                fmt::age(item.since),
+                item.reason
+            );
+            let _ = writeln!(text, "{}", render::shorten(&line, &[], width));
+        }
+        if shown.is_empty() {
+            text.push_str("(nothing needs attention)\n");
+        } else {
+            shown.push(AGE);
+            let _ = writeln!(text, "\n{}", style.legend(&shown));
+        }
+    }
+}
+
+impl Report for InboxReport<'_> {
+    const FORMATS: &'static [Format] = &[Format::Compact, Format::Porcelain, Format::Csv];
+
+    fn render(&self, format: Format, style: Style) -> String {
+        let mut text = String::new();
+        let fields = |item: &InboxItem| {
+            [
+                item.label.clone(),
+                item.session.clone().unwrap_or_default(),
+                format!("{:.1}", item.score.total()),
+                item.reason.clone(),
+            ]
+        };
+        match format {
+            Format::Porcelain => {
+                for item in self.items {
+                    text.push_str(&render::porcelain_row(
+                        &fields(item).each_ref().map(String::as_str),
+                    ));
+                }
+            }
+            Format::Csv => {
+                text.push_str(&render::csv_row(&["label", "session", "score", "reason"]));
+                for item in self.items {
+                    text.push_str(&render::csv_row(
+                        &fields(item).each_ref().map(String::as_str),
+                    ));
+                }
+            }
+            Format::Compact if style.narrow() => self.narrow_text(&mut text, style),
+            Format::Compact => {
+                let width = self
+                    .items
+                    .iter()
+                    .map(|i| render::width(&marked(i)))
+                    .max()
+                    .unwrap_or(0);
+                for item in self.items {
+                    let label = render::pad(&marked(item), width);
+                    let _ = writeln!(text, "  {label}  {}", item.reason);
+                }
+            }
+            _ => {
+                text.push_str("## Inbox\n\n");
+                if self.items.is_empty() {
+                    text.push_str("  (nothing needs attention)\n");
+                }
+                for item in self.items {
+                    let age = format!("{} in inbox", fmt::age(item.since));
+                    let age = match escalation::severity(self.escalation, item.since) {
+                        Severity::Fresh => age,
+                        Severity::Overdue => style.yellow(&age),
+                        Severity::Escalated => style.red(&age),
+                    };
+                    match &item.session {
+                        Some(session) => {
+                            let _ = writeln!(text, "  {}  ({session}, {age})", marked(item));
+                        }
+                        None => {
+                            let _ = writeln!(text, "  {}  ({age})", marked(item));
+                        }
+                    }
+                    let _ = writeln!(text, "    {}", item.reason);
+                    if self.verbose {
+                        let s = &item.score;
+                        let _ = writeln!(
+                            text,
+                            "    score {:.1} = priority {:.1} + blocked {:.1} ({} in inbox) + due {:.1} + waiting {:.1}",
+                            s.total(),
+                            s.priority,
+                            s.blocked,
+                            fmt::age(item.since),
+                            s.due,
+                            s.waiting
+                        );
+                    }
+                    text.push('\n');
+                }
+            }
+        }
+        text
+    }
+
+    fn lines(&self) -> Vec<String> {
+        self.json.iter().map(|item| item.to_string()).collect()
+    }
+}
+
+pub fn cmd_inbox(
+    dir: &Path,
+    config: &Config,
+    args: &InboxArgs,
+    format: Option<Format>,
+) -> Result<(), OrchError> {
+    if args.notify_test {
+        let notifiers = Notifiers::from_config(&config.notifications, dir);
+        if notifiers.is_empty() {
+            return Err(OrchError::Config(
+                "No notification backend available (notifications.backend).".into(),
+            ));
+        }
+        let event = TaskEvent {
+            kind: EventKind::Test,
+            task: None,
+            title: "orch".into(),
+            body: "Test notification".into(),
+        };
+        notifiers
+            .notify(&event)
+            .map_err(|e| OrchError::Failed(format!("notification failed: {e}")))?;
+        outln!("sent via {}", notifiers.describe());
+        return Ok(());
+    }
+
+    let looked = Local::now();
+    let items = if args.all {
+        inbox::items_with_held(dir, config)
+    } else {
+        inbox::items(dir, config)
+    };
+    if args.bell {
+        // Items first seen by this call are stamped after `looked`
+        for _ in items.iter().filter(|item| item.since >= looked) {
+            eprint!("\x07");
+        }
+    }
+    if args.interactive {
+        return walk(dir, config, &items);
+    }
+    let report = InboxReport {
+        json: items.iter().map(InboxItem::to_json).collect(),
+        items: &items,
+        verbose: args.verbose,
+        escalation: &config.escalation,
+    };
+    let format = if args.json {
+        Format::Json
+    } else if args.compact {
+        Format::Compact
+    } else {
+        format.unwrap_or_else(|| {
+            if args.verbose {
+                Format::Human
+            } else {
+                render::default_format()
+            }
+        })
+    };
+    render::emit("inbox", &report, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::inbox::Score;
+
+    /// `lines`, each ending in a newline: a rendering as it would print.
+    fn lines(lines: &[&str]) -> String {
+        lines.iter().map(|line| format!("{line}\n")).collect()
+    }
+
+    fn style(width: usize, ascii: bool) -> Style {
+        Style {
+            color: false,
+            width: Some(width),
+            ascii,
+        }
+    }
+
+    fn item(label: &str, session: Option<&str>, hours: i64, reason: &str) -> InboxItem {
+        InboxItem {
+            name: label.rsplit('/').next().unwrap_or(label).to_string(),
+            label: label.to_string(),
+            session: session.map(str::to_string),
+            reason: reason.to_string(),
+            since: Local::now() - Duration::hours(hours),
+            score: Score {
+                priority: 1.0,
+                blocked: hours as f64,
+                due: 0.0,
+                waiting: 0.0,
+            },
+            held: false,
+            pinned: false,
+        }
+    }
+
+    fn items() -> Vec<InboxItem> {
+        let mut recon = item(
+            "recon",
+            Some("task-recon"),
+            3,
+            "needs: which API key should the survey use for the staging cluster?",
+        );
+        recon.pinned = true;
+        recon.score.waiting = 2.0;
+        let mut auth = item(
+            "backend/auth",
+            None,
+            26,
+            "blocked: token lifetime undecided",
+        );
+        auth.held = true;
+        vec![
+            recon,
+            auth,
+            item(
+                "backend/auth-tests",
+                Some("task-auth-tests"),
+                1,
+                "question: mock the clock?",
+            ),
+        ]
+    }
+
+    fn render(items: &[InboxItem], verbose: bool, format: Format, style: Style) -> String {
+        let report = InboxReport {
+            json: Vec::new(),
+            items,
+            verbose,
+            escalation: &EscalationConfig::default(),
+        };
+        report.render(format, style)
+    }
+
+    #[test]
+    fn human_snapshot() {
+        assert_eq!(
+            render(&items(), false, Format::Human, style(80, false)),
+            lines(&[
+                "## Inbox",
+                "",
+                "  recon 📌  (task-recon, 3h in inbox)",
+                "    needs: which API key should the survey use for the staging cluster?",
+                "",
+                "  backend/auth ⏸  (1d in inbox)",
+                "    blocked: token lifetime undecided",
+                "",
+                "  backend/auth-tests  (task-auth-tests, 1h in inbox)",
+                "    question: mock the clock?",
+                "",
+            ])
+        );
+    }
+
+    #[test]
+    fn verbose_snapshot() {
+        assert_eq!(
+            render(&items()[..1], true, Format::Human, style(80, false)),
+            lines(&[
+                "## Inbox",
+                "",
+                "  recon 📌  (task-recon, 3h in inbox)",
+                "    needs: which API key should the survey use for the staging cluster?",
+                "    score 6.0 = priority 1.0 + blocked 3.0 (3h in inbox) + due 0.0 + waiting 2.0",
+                "",
+            ])
+        );
+    }
+
+    #[test]
+    fn compact_snapshot_at_80_columns() {
+        assert_eq!(
+            render(&items(), false, Format::Compact, style(80, false)),
+            lines(&[
+                "  recon 📌            needs: which API key should the survey use for the staging cluster?",
+                "  backend/auth ⏸      blocked: token lifetime undecided",
+                "  backend/auth-tests  question: mock the clock?",
+            ])
+        );
+    }
+
+    #[test]
+    fn compact_snapshot_at_40_columns() {
+        assert_eq!(
+            render(&items(), false, Format::Compact, style(40, true)),
+            lines(&[
+                "? recon ^ @3h needs: which API key shou…",
+                "- backend/auth = @1d blocked: token lif…",
+                "* backend/auth-tests @1h question: mock…",
+                "",
+                "? worker waiting  - no worker  * worker",
+                "@ in inbox",
+            ])
+        );
+    }
+
+    #[test]
+    fn empty_snapshot() {
+        assert_eq!(
+            render(&[], false, Format::Human, style(80, false)),
+            lines(&["## Inbox", "", "  (nothing needs attention)"])
+        );
+        assert_eq!(
+            render(&[], false, Format::Compact, style(40, false)),
+            "(nothing needs attention)\n"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::{path::Path, thread, time::Duration};
+
+use super::{new::confirm, scan::scoped_message};
+use crate::{
+    cli::{ArgvFormat, JumpArgs, SplitDir},
+    config::Config,
+    error::OrchError,
+    fmt, focus, frontmatter, health, messages, out, outln, paths, session, task, tmux,
+};
+
+/// Most lines of each section the banner shows.
+const BANNER_LINES: usize = 8;
+/// How long `--spawn` waits for the orchestrator to start the worker.
+const SPAWN_WAIT: Duration = Duration::from_secs(5 * 60);
+
+/// The `status:` field, else the latest `## Status` entry.
+fn status_line(content: &str) -> Option<String> {
+    frontmatter::get(content, "status").or_else(|| {
+        task::section(content, "## Status")
+            .last()
+            .map(|l| l.trim().trim_start_matches("- ").to_string())
+    })
+}
+
+/// Orientation before attaching, on stderr: the task's title, its latest
+/// status, and its `## Context` and `## Next` sections.
+pub(super) fn banner(name: &str, content: &str) {
+    let body = frontmatter::split(content).1;
+    let title = body
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("# "))
+        .unwrap_or(name);
+    eprintln!("── {title}");
+    if let Some(status) = status_line(content) {
+        eprintln!("   status: {status}");
+    }
+    for heading in ["## Context", "## Next"] {
+        let lines = task::section(content, heading);
+        if lines.is_empty() {
+            continue;
+        }
+        eprintln!("   {}:", heading.trim_start_matches("## "));
+        for line in lines.iter().take(BANNER_LINES) {
+            eprintln!("     {}", line.trim_end());
+        }
+        if lines.len() > BANNER_LINES {
+            eprintln!("     … {} more", lines.len() - BANNER_LINES);
+        }
+    }
+}
+
+/// Edit distance between `a` and `b`, by chars.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (diagonal + usize::from(ca != *cb))
+                .min(row[j] + 1)
+                .min(above + 1);
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Up to five task labels spelled most like `name`, closest first.
+fn closest(dir: &Path, config: &Config, name: &str) -> Vec<String> {
+    let name = name.to_lowercase();
+    let limit = (name.chars().count() / 3).max(2);
+    let mut near: Vec<_> = task::files(dir, config)
+        .iter()
+        .map(|path| task::label(dir, path))
+        .filter_map(|label| {
+            let stem = label.rsplit('/').next().unwrap_or(&label).to_lowercase();
+            let d = distance(&name, &stem);
+            (d <= limit).then_some((d, label))
+        })
+        .collect();
+    near.sort();
+    near.into_iter().take(5).map(|(_, label)| label).collect()
+}
+
+/// Has the daemon's orchestrator start the task's worker, as `orch scan`
+/// would, then waits for `session` to come up.
+fn start_worker(dir: &Path, config: &Config, label: &str, session: &str) -> Result<(), OrchError> {
+    if !health::load(dir).is_some_and(|h| h.alive()) {
+        return Err(OrchError::Failed(format!(
+            "The daemon isn't running to start a worker for {label}; start it with `orch daemon`."
+        )));
+    }
+    messages::write(
+        &paths::inbox_dir(dir),
+        &scoped_message(dir, config, label, false, false)?,
+    )?;
+    eprintln!("Asked the orchestrator to start {session}; waiting...");
+    let mut waited = Duration::ZERO;
+    while !tmux::has_session(session) {
+        if waited >= SPAWN_WAIT {
+            return Err(OrchError::Failed(format!(
+                "{session} didn't start within {}; see `orch daemon status`.",
+                fmt::long(SPAWN_WAIT)
+            )));
+        }
+        thread::sleep(Duration::from_secs(1));
+        waited += Duration::from_secs(1);
+    }
+    Ok(())
+}
+
+/// `args.name` is a task name or a worker session name, the focused task if
+/// there's neither. A task without a worker gets one started (asking first
+/// unless `--spawn`).
+pub fn cmd_jump(dir: &Path, config: &Config, args: &JumpArgs) -> Result<(), OrchError> {
+    let name = focus::resolve(dir, config, args.name.clone())?;
+    let name = name.as_str();
+    let print_command = args.print_command.then_some(args.sep);
+    let (split, window) = (args.split, args.window);
+    let task_of = |path: &Path| {
+        let (label, content) = (task::label(dir, path), task::read(path, config)?);
+        Ok::<_, OrchError>((
+            task::session(config, &label, &content),
+            Some((label, content)),
+        ))
+    };
+    // A session name is tried before partial task names
+    let (session, found) = match task::find(dir, name, config) {
+        Some(path) => task_of(&path)?,
+        None if session::task_for(dir, config, name).is_some() || tmux::has_session(name) => {
+            (name.to_string(), None)
+        }
+        None => match task::resolve(dir, name, config) {
+            Ok((_, path)) => task_of(&path)?,
+            Err(OrchError::NotFound(_)) => (session::name(config, name), None),
+            Err(e) => return Err(e),
+        },
+    };
+
+    if !tmux::has_session(&session) {
+        let Some((label, content)) = &found else {
+            let near = closest(dir, config, name);
+            let hint = if near.is_empty() {
+                String::new()
+            } else {
+                format!(" Closest tasks: {}.", near.join(", "))
+            };
+            return Err(OrchError::NotFound(format!(
+                "No task or tmux session '{name}'.{hint}"
+            )));
+        };
+        let spawn = !args.no_spawn
+            && !config.read_only
+            && print_command.is_none()
+            && (args.spawn || confirm(&format!("{label} has no worker. Start one?")));
+        if !spawn {
+            let status = status_line(content)
+                .map(|s| format!(" (status: {s})"))
+                .unwrap_or_default();
+            return Err(OrchError::NotFound(format!(
+                "No tmux session '{session}' for {label}{status}. \
+                 Start one with `orch jump {label} --spawn` or `orch scan {label}`."
+            )));
+        }
+        start_worker(dir, config, label, &session)?;
+    }
+
+    // $TMUX is "<socket>,<pid>,<session>"
+    let socket = std::env::var("TMUX")
+        .ok()
+        .and_then(|t| t.split(',').next().map(str::to_string));
+    let in_tmux = socket.is_some();
+    if !in_tmux && (split.is_some() || window) {
+        eprintln!("Not inside tmux; attaching to {session} instead.");
+    }
+
+    // Splits and windows run a nested read-only client on the same server,
+    // so closing them only detaches it and leaves the worker running.
+    let nested = tmux::nested_attach(&socket.unwrap_or_default(), &session);
+    let target = tmux::exact(&session);
+    let argv: Vec<&str> = match (in_tmux, split) {
+        (true, Some(SplitDir::H)) => vec!["tmux", "split-window", "-h", &nested],
+        (true, Some(SplitDir::V)) => vec!["tmux", "split-window", "-v", &nested],
+        (true, None) if window => vec!["tmux", "new-window", "-n", &session, &nested],
+        (true, None) => vec!["tmux", "switch-client", "-t", &target],
+        (false, _) => vec!["tmux", "attach-session", "-t", &target],
+    };
+    if !args.no_banner
+        && print_command.is_none()
+        && let Some((label, content)) = &found
+    {
+        banner(label, content);
+    }
+    match print_command {
+        Some(ArgvFormat::Space) => outln!("{}", argv.join(" ")),
+        Some(ArgvFormat::Nul) => out!("{}\0", argv.join("\0")),
+        None => {
+            if !tmux::run(&argv[1..]) {
+                return Err(OrchError::Tmux(format!("`{}` failed", argv.join(" "))));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! `orch log`: one task's log, oldest first.
+
+use std::{fs, path::Path};
+
+use crate::{
+    config::Config,
+    error::OrchError,
+    fmt,
+    log::{self, Entry},
+    outln, task, transcript,
+};
+
+/// The task's name, open or closed. A closed task is found by its exact
+/// name only, through its archived file or its log.
+fn name(dir: &Path, config: &Config, input: &str) -> Result<String, OrchError> {
+    match task::resolve(dir, input, config) {
+        Err(OrchError::NotFound(e)) => {
+            let closed = task::archived(dir).iter().any(|a| a.name == input)
+                || log::path(dir, input).exists();
+            if closed && task::validate_name(input).is_ok() {
+                Ok(input.to_string())
+            } else {
+                Err(OrchError::NotFound(e))
+            }
+        }
+        found => Ok(found?.0),
+    }
+}
+
+pub fn cmd_log(dir: &Path, config: &Config, input: &str, json: bool) -> Result<(), OrchError> {
+    let name = name(dir, config, input)?;
+    let entries: Vec<Entry> = log::entries(dir, &name).collect();
+    let file = transcript::path(dir, &name);
+    let transcript = fs::metadata(&file).ok().map(|m| (file, m.len()));
+
+    if json {
+        for entry in &entries {
+            outln!("{}", serde_json::to_string(entry).unwrap_or_default());
+        }
+        return Ok(());
+    }
+    if entries.is_empty() {
+        outln!("(nothing logged for {name})");
+    }
+    for entry in &entries {
+        let at = entry.at().map_or_else(|| entry.at.clone(), fmt::date_time);
+        let line = format!("{at}  {:<8} {}", entry.event.kind(), entry.event.detail());
+        outln!("{}", line.trim_end());
+    }
+    if let Some((file, size)) = transcript {
+        outln!("\ntranscript: {} ({})", file.display(), fmt::bytes(size));
+    }
+    Ok(())
+}
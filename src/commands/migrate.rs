@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use crate::{
+    config::{self, Config},
+    edits,
+    error::OrchError,
+    outln, session, task, tmux,
+};
+
+/// Moves this tasks dir's workers from sessions starting with `old` to ones
+/// starting with `new`: renames the running tmux sessions and rewrites the
+/// tasks' `session:` lines. Sessions that don't belong to a task here are
+/// left alone, so another orch setup sharing the prefix isn't touched.
+pub fn cmd_migrate_prefix(
+    dir: &Path,
+    config: &Config,
+    old: &str,
+    new: &str,
+    dry_run: bool,
+) -> Result<(), OrchError> {
+    let live = tmux::sessions();
+    let mut failed = false;
+    let mut moved = 0;
+    for path in task::files(dir, config) {
+        let label = task::label(dir, &path);
+        let name = label.rsplit('/').next().unwrap_or(&label);
+        let content = match task::read(&path, config) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("{label}: {e}");
+                failed = true;
+                continue;
+            }
+        };
+        let recorded = content
+            .lines()
+            .find_map(|l| l.trim().strip_prefix("session:").map(str::trim));
+        let from = recorded.map_or_else(|| format!("{old}{name}"), str::to_string);
+        let Some(rest) = from.strip_prefix(old) else {
+            continue;
+        };
+        let to = format!("{new}{rest}");
+        let running = live.contains(&from);
+        if !running && recorded.is_none() {
+            continue;
+        }
+
+        let mut done = Vec::new();
+        if running {
+            if live.contains(&to) {
+                eprintln!("{label}: {to} is already running, leaving {from} alone");
+                failed = true;
+                continue;
+            }
+            if !dry_run && let Err(e) = tmux::rename_session(&from, &to) {
+                eprintln!("{label}: {e}");
+                failed = true;
+                continue;
+            }
+            done.push("renamed session");
+        }
+        if task::set_session(&content, Some(&to)).is_some() {
+            if !dry_run {
+                let hash = config::fnv(content.as_bytes());
+                let edit = edits::edit_planned(dir, config, &path, hash, |content| {
+                    task::set_session(content, Some(&to))
+                });
+                let error = match edit {
+                    Ok(edits::Outcome::Dropped) => Some("it changed while orch read it".into()),
+                    Ok(_) => None,
+                    Err(e) => Some(e.to_string()),
+                };
+                if let Some(e) = error {
+                    eprintln!("{label}: failed to update {}: {e}", path.display());
+                    failed = true;
+                    continue;
+                }
+            }
+            done.push("updated file");
+        }
+        moved += 1;
+        let verb = if dry_run { "would move" } else { "moved" };
+        outln!("{label}: {verb} {from} -> {to} ({})", done.join(", "));
+    }
+
+    if moved == 0 {
+        outln!("No workers with the prefix '{old}'.");
+    }
+    if session::prefix(config) != new {
+        outln!("Set session_prefix = \"{new}\" in the config so new workers use it.");
+    }
+    if failed {
+        return Err(OrchError::Reported(1));
+    }
+    Ok(())
+}
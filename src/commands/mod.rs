@@ -0,0 +1,82 @@
+//! One function per subcommand. Output goes through [`crate::output`] (stdout
+//! or `--output`); errors go to stderr.
+
+mod adopt;
+mod archive;
+mod batch;
+mod console;
+mod daemon;
+mod delegate;
+mod doctor;
+mod exited;
+mod export;
+mod focus;
+mod hold;
+mod hooks;
+mod inbox;
+mod jump;
+mod log;
+mod migrate;
+mod msg;
+mod mv;
+mod new;
+mod pin;
+mod prompt;
+mod queue;
+mod record;
+mod replay;
+mod review;
+mod runs;
+mod scan;
+mod schedule;
+mod serve;
+mod snooze;
+mod spawn;
+mod state;
+mod stats;
+mod status;
+mod summary;
+mod timeline;
+mod touch;
+mod validate;
+mod version;
+
+pub use adopt::cmd_adopt;
+pub use archive::cmd_archive;
+pub use batch::{cmd_bump, cmd_close, cmd_kill};
+pub use console::cmd_console;
+pub use daemon::cmd_daemon_status;
+pub use delegate::cmd_delegate;
+pub use doctor::{cmd_doctor, daemon_preflight};
+pub use exited::cmd_worker_exited;
+pub use export::cmd_export;
+pub use focus::cmd_focus;
+pub use hold::{cmd_hold, cmd_unhold};
+pub use hooks::cmd_hooks_test;
+pub use inbox::cmd_inbox;
+pub use jump::cmd_jump;
+pub use log::cmd_log;
+pub use migrate::cmd_migrate_prefix;
+pub use msg::{cmd_msg, cmd_msg_to};
+pub use mv::cmd_move;
+pub use new::{cmd_msg_as_task, cmd_new};
+pub use pin::{cmd_pin, cmd_unpin};
+pub use prompt::{cmd_prompt, cmd_which};
+pub use queue::{cmd_queue_drop, cmd_queue_list};
+pub use record::cmd_record;
+pub use replay::cmd_replay;
+pub use review::cmd_review;
+pub use runs::{cmd_runs, cmd_runs_diff, cmd_runs_show};
+pub use scan::{cmd_scan, cmd_scan_run};
+pub use schedule::cmd_schedule;
+pub use serve::cmd_serve;
+pub use snooze::{cmd_snooze, cmd_unsnooze};
+pub use spawn::cmd_spawn;
+pub use state::cmd_state_check;
+pub use stats::{cmd_stats, cmd_stats_by_task};
+pub use status::{cmd_show, cmd_status};
+pub use summary::cmd_summary;
+pub use timeline::cmd_timeline;
+pub use touch::cmd_touch;
+pub use validate::cmd_validate;
+pub use version::cmd_version;
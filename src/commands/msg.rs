@@ -0,0 +1,94 @@
+//! `orch -`: a message for the orchestrator, or with `--to` for one task's
+//! worker.
+
+use std::path::Path;
+
+use chrono::Local;
+
+use super::batch::running_session;
+use crate::{
+    config::Config,
+    edits,
+    error::OrchError,
+    health,
+    log::{self, Event},
+    messages::{self, Via},
+    outln, paths, queue, task, tmux,
+};
+
+/// Spools `message` for the daemon's next scan, or with `queue_on_failure`
+/// (the flag or the config) queues it while the daemon's runs are failing
+/// offline.
+pub fn cmd_msg(
+    dir: &Path,
+    config: &Config,
+    message: &str,
+    queue_on_failure: bool,
+) -> Result<(), OrchError> {
+    let offline = health::load(dir)
+        .filter(|h| h.alive())
+        .and_then(|h| h.offline_since);
+    if let Some(since) = offline.filter(|_| queue_on_failure || config.queue_on_failure) {
+        queue::push(dir, message)?;
+        outln!("queued, will send when available");
+        eprintln!("(claude has been unreachable since {since})");
+        return Ok(());
+    }
+    messages::write(&paths::inbox_dir(dir), message)?;
+    eprintln!("[orch] message sent");
+    Ok(())
+}
+
+/// Types `message` into the task's live worker, or adds it to the task
+/// file's `## Instructions` and asks the orchestrator to start a worker.
+/// Either way it's noted in the task's log.
+pub fn cmd_msg_to(dir: &Path, config: &Config, name: &str, message: &str) -> Result<(), OrchError> {
+    if message.trim().is_empty() {
+        return Err(OrchError::Usage("No message given.".into()));
+    }
+    let (name, path) = task::resolve_open(dir, name, config, "messaged")?;
+    let label = task::label(dir, &path);
+
+    if let Some(session) = running_session(dir, config, &path) {
+        tmux::send_text(&session, message)
+            .map_err(|e| OrchError::Tmux(format!("couldn't type into {session}: {e}")))?;
+        log::record(
+            dir,
+            &name,
+            Event::Message {
+                via: Via::Session,
+                message: message.to_string(),
+            },
+        );
+        outln!("sent to {session}");
+        return Ok(());
+    }
+
+    let now = Local::now();
+    edits::apply(dir, config, &path, |content| {
+        Some(task::add_instruction(content, now, message))
+    })?;
+    messages::write(
+        &paths::inbox_dir(dir),
+        &format!(
+            "{label}.md has new instructions under ## Instructions and no live worker. \
+             Spin one up for it."
+        ),
+    )?;
+    log::record(
+        dir,
+        &name,
+        Event::Message {
+            via: Via::TaskFile,
+            message: message.to_string(),
+        },
+    );
+    outln!(
+        "no live worker; added to {} under ## Instructions",
+        path.display()
+    );
+    if !health::load(dir).is_some_and(|h| h.alive()) {
+        eprintln!("The daemon isn't running; it'll see this at its next scan.");
+    }
+    Ok(())
+}
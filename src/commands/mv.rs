@@ -0,0 +1,108 @@
+use std::path::Path;
+
+use crate::{
+    cli::PlanArgs,
+    config::{self, Config},
+    error::OrchError,
+    escalation, export, inbox,
+    plan::{Outcome, Plan, Step},
+    session, task, tmux,
+};
+
+/// The steps to move the task at `src` to `dest`. A worker under the
+/// default session name follows the task when that name encodes the project.
+fn steps(
+    config: &Config,
+    label: &str,
+    new_label: &str,
+    src: &Path,
+    dest: &Path,
+) -> Result<Vec<Step>, String> {
+    let content = task::read(src, config).map_err(|e| e.to_string())?;
+    let current = task::session(config, label, &content);
+    let old_default = session::name(config, label);
+    let new_default = session::name(config, new_label);
+
+    let mut steps = Vec::new();
+    let follow = current == old_default && old_default != new_default;
+    if follow && tmux::has_session(&current) {
+        if tmux::has_session(&new_default) {
+            return Err(format!("{new_default} is already running"));
+        }
+        steps.push(Step::RenameSession {
+            from: current,
+            to: new_default.clone(),
+        });
+    }
+    steps.push(Step::MoveFile {
+        from: src.to_path_buf(),
+        to: dest.to_path_buf(),
+    });
+    if follow && task::set_session(&content, None).is_some() {
+        steps.push(Step::SetSession {
+            path: dest.to_path_buf(),
+            session: Some(new_default),
+            hash: config::fnv(content.as_bytes()),
+        });
+    }
+    Ok(steps)
+}
+
+/// Moves a task into another project folder (`.` for the top level),
+/// keeping its worker and inbox state with it.
+pub fn cmd_move(
+    dir: &Path,
+    config: &Config,
+    name: &str,
+    project: &str,
+    plan_args: &PlanArgs,
+) -> Result<(), OrchError> {
+    let (name, src) = task::resolve(dir, name, config)?;
+    let name = name.as_str();
+    task::validate_project(project).map_err(OrchError::Usage)?;
+
+    let dest_dir = match project {
+        "." => dir.to_path_buf(),
+        _ => dir.join(project.trim_end_matches('/')),
+    };
+    // A symlinked project folder could still lead out of the tasks dir
+    if let (Ok(root), Ok(real)) = (dir.canonicalize(), dest_dir.canonicalize())
+        && !real.starts_with(&root)
+    {
+        return Err(OrchError::Usage(format!(
+            "{} is outside {}",
+            real.display(),
+            root.display()
+        )));
+    }
+    let here = src.parent().and_then(|p| p.canonicalize().ok());
+    if here.is_some() && here == dest_dir.canonicalize().ok() {
+        let there = match project {
+            "." => "at the top level".to_string(),
+            _ => format!("in {}/", project.trim_end_matches('/')),
+        };
+        return Err(OrchError::Failed(format!("{name} is already {there}.")));
+    }
+    let dest = dest_dir.join(src.file_name().unwrap_or_default());
+    if dest.exists() {
+        return Err(OrchError::Failed(format!(
+            "{} already exists.",
+            dest.display()
+        )));
+    }
+
+    let label = task::label(dir, &src);
+    let new_label = task::label(dir, &dest);
+    let mut plan = Plan::new(dir, config);
+    match steps(config, &label, &new_label, &src, &dest) {
+        Ok(steps) => plan.add(name, steps),
+        Err(e) => plan.add_error(name, e),
+    }
+    let result = super::batch::execute(&mut plan, plan_args, "moved");
+    if plan.items.iter().any(|i| i.outcome == Outcome::Done) {
+        inbox::relabel(dir, &label, &new_label);
+        escalation::relabel(dir, &label, &new_label);
+        export::relabel(dir, &label, &new_label);
+    }
+    result
+}
@@ -0,0 +1,183 @@
+use std::{
+    fs,
+    io::{IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::Config, edits, error::OrchError, frontmatter, git, messages, orchestrator, outln,
+    paths, task,
+};
+
+/// The drafting prompt: `draft_prompt` from the config, else the built-in.
+fn draft_prompt(config: &Config) -> Result<String, OrchError> {
+    let Some(path) = &config.draft_prompt else {
+        return Ok(orchestrator::DRAFT_PROMPT.to_string());
+    };
+    fs::read_to_string(path).map_err(|e| OrchError::io(path, e))
+}
+
+/// `repo` as an absolute path, if it's a git work tree. A git that can't be
+/// run only warns, since the task is still worth creating.
+fn check_repo(repo: &Path) -> Result<PathBuf, OrchError> {
+    let path = repo.canonicalize().map_err(|e| OrchError::io(repo, e))?;
+    match git::is_work_tree(&path) {
+        Ok(true) => {}
+        Ok(false) => {
+            return Err(OrchError::Usage(format!(
+                "{} isn't a git work tree.",
+                path.display()
+            )));
+        }
+        Err(e) => eprintln!("warning: {e}"),
+    }
+    Ok(path)
+}
+
+/// Records `repo` (and `branch`, once switched to, with the branch it was
+/// switched from as `base:`) in the task's frontmatter and appends the
+/// repository context. Git failures only warn.
+fn add_repo(
+    dir: &Path,
+    path: &Path,
+    config: &Config,
+    repo: &Path,
+    branch: Option<&str>,
+) -> Result<(), OrchError> {
+    let workdir = repo.to_string_lossy();
+    let mut switched = None;
+    if let Some(branch) = branch {
+        let base = git::current_branch(repo).ok().flatten();
+        match git::switch_branch(repo, branch) {
+            Ok(()) => switched = Some((branch, base.filter(|base| base != branch))),
+            Err(e) => eprintln!("warning: not switching to {branch}: {e}"),
+        }
+    }
+    let (block, errors) = git::context(repo);
+    for e in errors {
+        eprintln!("warning: {e}");
+    }
+    edits::apply(dir, config, path, |content| {
+        let mut content = frontmatter::set(content, "workdir", Some(&workdir));
+        if let Some((branch, base)) = &switched {
+            content = frontmatter::set(&content, "branch", Some(branch));
+            if let Some(base) = base {
+                content = frontmatter::set(&content, "base", Some(base));
+            }
+        }
+        Some(format!(
+            "{}
+
+{block}",
+            content.trim_end()
+        ))
+    })?;
+    Ok(())
+}
+
+/// `ask` is `Some(confirm)` for `--ask`: the description is drafted into a
+/// full brief by claude, shown, and (if `confirm`) kept only on a yes.
+/// `repo` seeds the task with that repository's context (see [`add_repo`]),
+/// after the draft is kept.
+pub fn cmd_new(
+    dir: &Path,
+    config: &Config,
+    name: &str,
+    text: &[String],
+    ask: Option<bool>,
+    repo: Option<&Path>,
+    branch: Option<&str>,
+) -> Result<(), OrchError> {
+    task::check_new(dir, config, name)?;
+    let repo = repo.map(check_repo).transpose()?;
+
+    if ask == Some(true) && !std::io::stdin().is_terminal() {
+        return Err(OrchError::Usage(
+            "--ask asks before keeping the draft; pass --no-confirm when not on a terminal.".into(),
+        ));
+    }
+
+    let mut body = text.join(" ");
+    if body.is_empty() {
+        let _ = std::io::stdin().read_to_string(&mut body);
+    }
+    if ask.is_some() {
+        eprintln!("Drafting {name}.md...");
+        body = orchestrator::draft(&draft_prompt(config)?, &body).map_err(OrchError::Claude)?;
+    }
+    let path = task::create(dir, name, &body)
+        .map_err(|e| OrchError::io(dir.join(format!("{name}.md")), e))?;
+    if ask == Some(true) {
+        eprintln!("{}", task::read(&path, config)?);
+        if !confirm(&format!("Keep {}?", path.display())) {
+            let _ = fs::remove_file(&path);
+            return Err(OrchError::Failed("Discarded.".into()));
+        }
+    }
+    if let Some(repo) = &repo {
+        add_repo(dir, &path, config, repo, branch)?;
+    }
+    outln!("{}", path.display());
+    Ok(())
+}
+
+pub(super) fn confirm(question: &str) -> bool {
+    if !std::io::stdin().is_terminal() {
+        return false;
+    }
+    eprint!("{question} [y/N] ");
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    let _ = std::io::stdin().read_line(&mut answer);
+    answer.trim().eq_ignore_ascii_case("y")
+}
+
+/// `orch - --as-task`: writes the message as a task file (named `name` or a
+/// slug of its first sentence), then asks the orchestrator to start it.
+pub fn cmd_msg_as_task(
+    dir: &Path,
+    config: &Config,
+    name: Option<&str>,
+    message: &str,
+) -> Result<(), OrchError> {
+    if message.trim().is_empty() {
+        return Err(OrchError::Usage("No message given.".into()));
+    }
+    let base = match name {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => task::slug(message),
+    };
+    if base.is_empty() {
+        return Err(OrchError::Usage(
+            "Can't derive a task name from the message; pass --as-task=<name>.".into(),
+        ));
+    }
+    task::validate_name(&base).map_err(OrchError::Usage)?;
+
+    let mut name = base.clone();
+    if let Some(existing) = task::find(dir, &name, config) {
+        let alt = (2..)
+            .map(|n| format!("{base}-{n}"))
+            .find(|n| task::find(dir, n, config).is_none())
+            .unwrap_or_default();
+        if !confirm(&format!(
+            "{} already exists. Create {alt}.md instead?",
+            existing.display()
+        )) {
+            return Err(OrchError::Failed(format!(
+                "{} already exists; not created.",
+                existing.display()
+            )));
+        }
+        name = alt;
+    }
+
+    let path = task::create(dir, &name, message)
+        .map_err(|e| OrchError::io(dir.join(format!("{name}.md")), e))?;
+    messages::write(
+        &paths::inbox_dir(dir),
+        &format!("New task {name}.md was created from a message. Spin up a worker for it."),
+    )?;
+    outln!("{}", path.display());
+    Ok(())
+}
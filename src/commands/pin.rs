@@ -0,0 +1,23 @@
+use std::path::Path;
+
+use crate::{config::Config, edits, error::OrchError, frontmatter, outln, task};
+
+/// Keeps the task at the top of status, inbox and summary, whatever they're
+/// sorted by.
+pub fn cmd_pin(dir: &Path, config: &Config, name: &str) -> Result<(), OrchError> {
+    let (name, path) = task::resolve_open(dir, name, config, "pinned")?;
+    edits::apply(dir, config, &path, |content| {
+        Some(frontmatter::set(content, "pinned", Some("true")))
+    })?;
+    outln!("{name}: pinned");
+    Ok(())
+}
+
+pub fn cmd_unpin(dir: &Path, config: &Config, name: &str) -> Result<(), OrchError> {
+    let (name, path) = task::resolve(dir, name, config)?;
+    edits::apply(dir, config, &path, |content| {
+        Some(frontmatter::set(content, "pinned", None))
+    })?;
+    outln!("{name}: unpinned");
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! `orch prompt` and `orch which`: the orchestrator prompt each tasks root
+//! runs with, and where it was found ([`crate::roots`]).
+
+use std::path::Path;
+
+use crate::{
+    config::{Config, RootPromptMode},
+    error::OrchError,
+    include, outln, paths,
+    roots::{self, Source},
+};
+
+/// One line per root: how it's shown, what its prompt is made of, and the
+/// source that won.
+fn root_lines(dir: &Path, config: &Config) {
+    let roots = roots::roots(dir);
+    let width = roots
+        .iter()
+        .map(|r| roots::show(r.as_deref()).len())
+        .max()
+        .unwrap_or(0);
+    for root in roots {
+        let (source, prompt) = roots::resolve(dir, config, root.as_deref());
+        let appended = if prompt.append.is_some() {
+            " + root-local file"
+        } else {
+            ""
+        };
+        outln!(
+            "  {:width$}  {}  [{}{appended}]",
+            roots::show(root.as_deref()),
+            prompt.describe(),
+            source.label()
+        );
+    }
+}
+
+/// `--root`'s value as a root: `.` is the top level, anything else a project
+/// folder that has to exist.
+fn parse_root(dir: &Path, root: &str) -> Result<Option<String>, OrchError> {
+    let root = root.trim_end_matches('/');
+    if root == "." || root.is_empty() {
+        return Ok(None);
+    }
+    if !roots::roots(dir).iter().any(|r| r.as_deref() == Some(root)) {
+        return Err(OrchError::NotFound(format!(
+            "No project folder '{root}' in {}.",
+            dir.display()
+        )));
+    }
+    Ok(Some(root.to_string()))
+}
+
+/// Prints the prompt text the root's scans get, or with no root, each
+/// root's prompt files.
+pub fn cmd_prompt(dir: &Path, config: &Config, root: Option<&str>) -> Result<(), OrchError> {
+    let Some(root) = root else {
+        outln!("Prompt per root (`orch prompt --root ROOT` prints one):");
+        root_lines(dir, config);
+        return Ok(());
+    };
+    let root = parse_root(dir, root)?;
+    let prompt = roots::effective(dir, config, root.as_deref());
+    let expand = |path: &Path| {
+        include::expand(path).map_err(|e| OrchError::Config(format!("orchestrator prompt: {e}")))
+    };
+    match &prompt.base {
+        Some(path) => {
+            outln!("# {}", path.display());
+            outln!("{}", expand(path)?.trim_end());
+        }
+        None => match roots::agent_file().filter(|p| p.is_file()) {
+            Some(path) => {
+                outln!("# {} (claude's orchestrator agent)", path.display());
+                outln!("{}", expand(&path)?.trim_end());
+            }
+            None => outln!("# claude's orchestrator agent (not found under ~/.claude/agents)"),
+        },
+    }
+    if let Some(path) = &prompt.append {
+        outln!();
+        outln!("# {} (appended)", path.display());
+        outln!("{}", expand(path)?.trim_end());
+    }
+    Ok(())
+}
+
+/// Where the config, tasks and prompts are, with the prompt resolution
+/// order and what each root ends up with.
+pub fn cmd_which(dir: &Path, config: &Config, config_path: Option<&Path>) -> Result<(), OrchError> {
+    let found = |path: &Path| if path.exists() { "" } else { " (missing)" };
+    match config_path {
+        Some(path) => outln!("config: {}{}", path.display(), found(path)),
+        None => outln!("config: {}", paths::NO_CONFIG),
+    }
+    outln!("tasks dir: {}", dir.display());
+    outln!("state dir: {}", paths::state_dir(dir).display());
+
+    outln!("orchestrator prompt, first match wins:");
+    let file = &config.root_prompts.file;
+    let mode = match config.root_prompts.mode {
+        RootPromptMode::Append => "appended to the rest of this list",
+        RootPromptMode::Replace => "replaces the rest of this list",
+    };
+    let shown = |path: Option<&Path>| match path {
+        Some(path) => format!("{}{}", path.display(), found(path)),
+        None => "(unset)".into(),
+    };
+    for (i, source) in Source::ALL.into_iter().enumerate() {
+        let detail = match source {
+            Source::Flag => match &config.prompt_flag {
+                Some(path) => shown(Some(path)),
+                None => "(not given)".into(),
+            },
+            Source::RootLocal => format!("<root>/{file}, {mode}"),
+            Source::Config => shown(config.orchestrator_prompt.as_deref()),
+            Source::BesideExe => shown(roots::beside_exe().as_deref()),
+            Source::Agent => shown(roots::agent_file().as_deref()),
+        };
+        outln!("  {}. {}: {detail}", i + 1, source.label());
+    }
+    outln!("roots:");
+    root_lines(dir, config);
+    Ok(())
+}
@@ -0,0 +1,41 @@
+//! `orch queue`: messages waiting for claude to be reachable again.
+
+use std::path::Path;
+
+use crate::{error::OrchError, fmt, outln, queue, task};
+
+pub fn cmd_queue_list(dir: &Path) -> Result<(), OrchError> {
+    let entries = queue::list(dir);
+    if entries.is_empty() {
+        outln!("Nothing queued.");
+        return Ok(());
+    }
+    for entry in entries {
+        let age = task::parse_timestamp(&entry.at)
+            .map_or_else(|| entry.at.clone(), |t| format!("{} ago", fmt::age(t)));
+        let first = entry.message.lines().next().unwrap_or("");
+        outln!("  {}  {age:>8}  {first}", entry.id);
+    }
+    Ok(())
+}
+
+/// `id` may be a prefix.
+pub fn cmd_queue_drop(dir: &Path, id: &str) -> Result<(), OrchError> {
+    let matches: Vec<_> = queue::list(dir)
+        .into_iter()
+        .filter(|e| e.id.starts_with(id))
+        .collect();
+    let entry = match matches.as_slice() {
+        [] => return Err(OrchError::NotFound(format!("No queued message '{id}'."))),
+        [entry] => entry,
+        _ => {
+            return Err(OrchError::Usage(format!(
+                "'{id}' matches {} queued messages; give more of the id.",
+                matches.len()
+            )));
+        }
+    };
+    queue::remove(dir, &entry.id)?;
+    outln!("dropped {}", entry.id);
+    Ok(())
+}
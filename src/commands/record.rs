@@ -0,0 +1,67 @@
+use std::{fs, path::Path};
+
+use crate::{
+    config::Config,
+    error::OrchError,
+    fmt,
+    log::{self, Event},
+    outln, paths, task, tmux, transcript,
+};
+
+/// Starts (or with `stop`, stops) piping the task's worker pane into its
+/// transcript.
+pub fn cmd_record(dir: &Path, config: &Config, name: &str, stop: bool) -> Result<(), OrchError> {
+    let (name, path) = task::resolve(dir, name, config)?;
+    let name = name.as_str();
+    let session = task::session(
+        config,
+        &task::label(dir, &path),
+        &task::read(&path, config)?,
+    );
+    if !tmux::has_session(&session) {
+        return Err(OrchError::NotFound(format!(
+            "{name} has no running worker ({session})."
+        )));
+    }
+    let file = transcript::path(dir, name);
+
+    if stop {
+        if !transcript::recording(&session) {
+            outln!("{name}: not recording");
+            return Ok(());
+        }
+        tmux::unpipe_pane(&session).map_err(OrchError::Tmux)?;
+        log::record(
+            dir,
+            name,
+            Event::RecordingStopped {
+                transcript: file.clone(),
+            },
+        );
+        outln!("{name}: stopped; transcript in {}", file.display());
+        return Ok(());
+    }
+
+    if transcript::recording(&session) {
+        outln!("{name}: already recording to {}", file.display());
+        return Ok(());
+    }
+    let transcripts = paths::transcripts_dir(dir);
+    fs::create_dir_all(&transcripts).map_err(|e| OrchError::io(&transcripts, e))?;
+    tmux::pipe_pane(&session, &file).map_err(OrchError::Tmux)?;
+    log::record(
+        dir,
+        name,
+        Event::RecordingStarted {
+            transcript: file.clone(),
+        },
+    );
+    outln!("{name}: recording {session} to {}", file.display());
+    if let Some(size) = transcript::oversized(dir, config, name) {
+        eprintln!(
+            "The transcript is already {}; move it aside if it's no longer needed.",
+            fmt::bytes(size)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use std::{path::Path, time::Instant};
+
+use chrono::Local;
+
+use crate::{
+    config::Config,
+    costs,
+    error::OrchError,
+    orchestrator::{self, Claude, MODEL, Runner, Sent},
+    outln, paths, prompts, roots,
+    runs::{self, RunRecord},
+    task,
+};
+
+/// Whether `input` could be (part of) a run id like `20261015-042709`
+/// rather than a task name.
+fn run_id_like(input: &str) -> bool {
+    input.len() >= 8
+        && input.starts_with(|c: char| c.is_ascii_digit())
+        && input.chars().all(|c| c.is_ascii_digit() || c == '-')
+}
+
+fn print(sent: &Sent) {
+    match &sent.system_prompt {
+        Some(prompt) => outln!("# system prompt: {} lines", prompt.lines().count()),
+        None => outln!("# prompt: the orchestrator agent"),
+    }
+    if let Some(append) = &sent.append_system_prompt {
+        outln!("# appended: {} lines", append.lines().count());
+    }
+    outln!("{}", sent.message);
+}
+
+/// `name` is a task, whose last recorded prompt is sent again with the
+/// current prompt files, or a run, whose stored prompt is sent exactly as
+/// it was and recorded as a run replaying it.
+pub fn cmd_replay(
+    dir: &Path,
+    config: &Config,
+    name: &str,
+    model: Option<&str>,
+    dry_run: bool,
+) -> Result<(), OrchError> {
+    if run_id_like(name) {
+        let all: Vec<_> = runs::iter(dir).collect();
+        if let Ok(index) = super::runs::find(dir, &all, Some(name)) {
+            return replay_run(dir, config, &all[index], model, dry_run);
+        }
+    }
+    let prompt = prompts::last(dir, name)
+        .ok_or_else(|| OrchError::NotFound(format!("No recorded prompt for '{name}'.")))?;
+    if dry_run {
+        outln!("# sent {}", prompt.at);
+        outln!("{}", prompt.message);
+        return Ok(());
+    }
+    // With the prompt of the task's root, if it's still around
+    let label = task::find(dir, name, config).map(|path| task::label(dir, &path));
+    let root = label.as_deref().and_then(roots::root_of);
+    let runner = Claude {
+        repo: paths::repo_dir()?,
+        tasks_dir: dir.to_path_buf(),
+        sandbox: config.sandbox,
+        prompt: roots::effective(dir, config, root),
+        report_actions: false,
+    };
+    let ok = match model {
+        None => runner.run(&prompt.message).ok,
+        Some(model) => match runner.prepare(&prompt.message, &runner.prompt) {
+            Some(sent) => runner.send(sent, model).ok,
+            None => false,
+        },
+    };
+    if !ok {
+        // The runner has logged why
+        return Err(OrchError::Reported(1));
+    }
+    Ok(())
+}
+
+/// Sends `original`'s stored prompt again, as its own run.
+fn replay_run(
+    dir: &Path,
+    config: &Config,
+    original: &RunRecord,
+    model: Option<&str>,
+    dry_run: bool,
+) -> Result<(), OrchError> {
+    let id = original.id();
+    let sent = runs::sent(dir, original).ok_or_else(|| {
+        OrchError::NotFound(format!(
+            "Run {id}'s prompt isn't kept (only the newest `run_history` runs keep theirs)."
+        ))
+    })?;
+    if dry_run {
+        outln!("# run {id}, started {}", original.at);
+        print(&sent);
+        return Ok(());
+    }
+    let runner = Claude {
+        repo: paths::repo_dir()?,
+        tasks_dir: dir.to_path_buf(),
+        sandbox: config.sandbox,
+        prompt: roots::Prompt {
+            base: None,
+            append: None,
+        },
+        report_actions: true,
+    };
+    let about = prompts::mentioned(dir, config, &sent.message);
+    let lock = runs::Lock::take(dir);
+    let started = Local::now();
+    let start = Instant::now();
+    let run = runner.send(sent, model.unwrap_or(MODEL));
+    drop(lock);
+    let mut record = RunRecord {
+        at: task::timestamp(started),
+        ok: run.ok,
+        duration_secs: start.elapsed().as_secs_f64(),
+        actions: run.actions.clone(),
+        suspicious: Vec::new(),
+        no_effect: false,
+        offline: !run.ok && orchestrator::offline_error(&run.output),
+        cost_usd: run.cost_usd,
+        queued: None,
+        requests: Vec::new(),
+        prompt: None,
+        output: None,
+        replay_of: Some(id.clone()),
+        model: model.filter(|m| *m != MODEL).map(str::to_string),
+    };
+    runs::store(dir, config, &mut record, &run);
+    runs::append(dir, &record);
+    if let Some(usd) = record.cost_usd {
+        costs::attribute(dir, usd, &about);
+    }
+    if !run.ok {
+        // The runner has logged why
+        return Err(OrchError::Reported(1));
+    }
+    outln!(
+        "Replayed run {id} as run {}; `orch runs diff {id} {}` compares them.",
+        record.id(),
+        record.id()
+    );
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+
+use crate::{
+    config::Config,
+    edits,
+    error::OrchError,
+    frontmatter, orchestrator, outln, paths,
+    review::{self, Evidence},
+    sandbox, task, tmux,
+};
+
+/// The reviewer window's name in the worker's session.
+const WINDOW: &str = "review";
+
+/// Reviews the task's work with a one-shot claude and records the findings
+/// under its `## Review`, setting `status: needs-input` when something is
+/// blocking. With `interactive`, a reviewer opens in a window of the task's
+/// worker session instead and records its own findings.
+pub fn cmd_review(
+    dir: &Path,
+    config: &Config,
+    name: &str,
+    interactive: bool,
+    dry_run: bool,
+) -> Result<(), OrchError> {
+    let (name, path) = task::resolve(dir, name, config)?;
+    let label = task::label(dir, &path);
+    let content = task::read(&path, config)?;
+    let session = task::session(config, &label, &content);
+    let evidence = review::gather(dir, &name, &session, &content);
+    let mut input = review::input(&review::prompt(config)?, &label, &content, &evidence);
+    if interactive {
+        input.push_str(&format!(
+            "\nWhen you're done, add your findings to {} as one dated entry under \
+             `## Review` (before `## Status`), and if any are blocking, set \
+             `status: needs-input` in its frontmatter.\n",
+            path.display()
+        ));
+    }
+    if dry_run {
+        outln!("# reviewing {}", evidence.describe());
+        outln!("{input}");
+        return Ok(());
+    }
+    if interactive {
+        return open_reviewer(dir, config, &name, &session, &evidence, &input);
+    }
+
+    eprintln!("Reviewing {label} from {}...", evidence.describe());
+    let reply = orchestrator::ask(input).map_err(OrchError::Claude)?;
+    let (findings, blocking) = review::verdict(&reply);
+    let summary = match blocking {
+        Some(true) => "Blocking issues",
+        Some(false) => "No blocking issues",
+        None => "No verdict given",
+    };
+    let entry = format!("{summary} (reviewed {})\n{findings}", evidence.describe());
+    let now = Local::now();
+    edits::apply(dir, config, &path, |content| {
+        let mut content = task::add_review(content, now, &entry);
+        if blocking == Some(true) {
+            content = frontmatter::set(&content, "status", Some("needs-input"));
+            content = task::add_status(
+                &content,
+                now,
+                "Review found blocking issues, needs input (see ## Review)",
+            );
+        }
+        Some(content)
+    })?;
+
+    outln!("{findings}");
+    outln!();
+    match blocking {
+        Some(true) => outln!("{label}: {summary}; recorded under ## Review and set needs-input."),
+        _ => outln!("{label}: {summary}; recorded under ## Review."),
+    }
+    Ok(())
+}
+
+/// Starts an interactive reviewer given `input` in a window of the task's
+/// worker session, in its workdir when it has one.
+fn open_reviewer(
+    dir: &Path,
+    config: &Config,
+    name: &str,
+    session: &str,
+    evidence: &Evidence,
+    input: &str,
+) -> Result<(), OrchError> {
+    if !tmux::has_session(session) {
+        return Err(OrchError::NotFound(format!(
+            "{name} has no running worker ({session}) to open a reviewer in; \
+             `orch review {name}` reviews without one."
+        )));
+    }
+    let file = paths::review_input_path(dir, name);
+    file.parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| task::write_atomic(&file, input))
+        .map_err(|e| OrchError::io(&file, e))?;
+    let cwd = match evidence {
+        Evidence::Diff { repo, .. } => repo.clone(),
+        Evidence::Output { .. } => PathBuf::from(dir),
+    };
+    let mut words = vec![
+        "env".to_string(),
+        "-u".into(),
+        "CLAUDECODE".into(),
+        "claude".into(),
+        "--model".into(),
+        orchestrator::MODEL.into(),
+    ];
+    words.extend(sandbox::claude_args(config.sandbox, dir));
+    let mut command: Vec<String> = words.iter().map(|w| tmux::quote(w)).collect();
+    // Read by the shell, since a diff can be too big for a tmux command line
+    command.push(format!(
+        "\"$(cat {})\"",
+        tmux::quote(&file.display().to_string())
+    ));
+    tmux::new_window(session, WINDOW, &cwd, &command.join(" ")).map_err(OrchError::Tmux)?;
+    outln!(
+        "Opened a reviewer in {session}:{WINDOW}, reviewing {}; `orch jump {name}` to join it.",
+        evidence.describe()
+    );
+    Ok(())
+}
@@ -0,0 +1,430 @@
+//! `orch runs`: the daemon's scan history from `.orch/runs.jsonl`.
+
+use std::{fmt::Write as _, path::Path};
+
+use serde::Serialize;
+
+use crate::{
+    actions, blobs, costs,
+    error::OrchError,
+    fmt, out, outln,
+    render::{self, Format, Report, Style},
+    runs::{self, RunRecord},
+};
+
+fn result(run: &RunRecord) -> &'static str {
+    match run {
+        _ if run.offline => "offline",
+        _ if !run.ok => "failed",
+        _ if run.no_effect => "no effect",
+        _ => "ok",
+    }
+}
+
+/// The `orch scan --async` request starting with `id` that `run` answered.
+fn answered<'a>(run: &'a RunRecord, id: &str) -> Option<&'a String> {
+    run.requests.iter().find(|r| r.starts_with(id))
+}
+
+/// A run as every format reports it.
+#[derive(Serialize)]
+struct Run {
+    id: String,
+    result: &'static str,
+    #[serde(flatten)]
+    record: RunRecord,
+}
+
+impl Run {
+    fn new(record: RunRecord) -> Self {
+        Run {
+            id: record.id(),
+            result: result(&record),
+            record,
+        }
+    }
+
+    fn actions(&self) -> String {
+        self.record
+            .actions
+            .as_deref()
+            .map_or_else(|| "-".to_string(), actions::summary)
+    }
+}
+
+/// The newest runs, newest first.
+#[derive(Serialize)]
+#[serde(transparent)]
+struct RunList {
+    runs: Vec<Run>,
+}
+
+impl Report for RunList {
+    const FORMATS: &'static [Format] = &[Format::Porcelain, Format::Csv];
+
+    fn render(&self, format: Format, _style: Style) -> String {
+        let mut text = String::new();
+        let rows = self.runs.iter().map(|run| {
+            (
+                run,
+                [
+                    run.id.clone(),
+                    run.result.to_string(),
+                    format!("{:.0}", run.record.duration_secs),
+                    run.actions(),
+                    run.record.suspicious.len().to_string(),
+                ],
+            )
+        });
+        match format {
+            Format::Porcelain => {
+                for (_, row) in rows {
+                    text.push_str(&render::porcelain_row(&row.each_ref().map(String::as_str)));
+                }
+            }
+            Format::Csv => {
+                text.push_str(&render::csv_row(&[
+                    "id",
+                    "result",
+                    "duration_secs",
+                    "actions",
+                    "suspicious",
+                ]));
+                for (_, row) in rows {
+                    text.push_str(&render::csv_row(&row.each_ref().map(String::as_str)));
+                }
+            }
+            _ if self.runs.is_empty() => text.push_str("No runs recorded yet.\n"),
+            _ => {
+                for (run, [id, result, _, actions, _]) in rows {
+                    let took = fmt::elapsed(run.record.duration_secs);
+                    let flagged = if run.record.suspicious.is_empty() {
+                        String::new()
+                    } else {
+                        format!("  ⚠ {} suspicious", run.record.suspicious.len())
+                    };
+                    let _ = writeln!(text, "  {id}  {result:<9}  {took:>5}  {actions}{flagged}");
+                }
+            }
+        }
+        text
+    }
+
+    fn lines(&self) -> Vec<String> {
+        self.runs
+            .iter()
+            .map(|run| serde_json::to_string(run).unwrap_or_default())
+            .collect()
+    }
+}
+
+/// The newest `limit` runs, newest first.
+pub fn cmd_runs(dir: &Path, limit: usize, format: Option<Format>) -> Result<(), OrchError> {
+    let all: Vec<_> = runs::iter(dir).collect();
+    let runs = all.into_iter().rev().take(limit).map(Run::new).collect();
+    render::emit("runs", &RunList { runs }, format.unwrap_or(Format::Human))
+}
+
+impl Report for Run {
+    fn render(&self, _format: Format, _style: Style) -> String {
+        let run = &self.record;
+        let mut text = String::new();
+        let _ = writeln!(text, "run {}", self.id);
+        let ago = run
+            .at()
+            .map(|t| format!(" ({} ago)", fmt::age(t)))
+            .unwrap_or_default();
+        let _ = writeln!(text, "  started:  {}{ago}", run.at);
+        let _ = writeln!(
+            text,
+            "  result:   {}, took {}",
+            self.result,
+            fmt::elapsed(run.duration_secs)
+        );
+        if let Some(usd) = run.cost_usd {
+            let _ = writeln!(text, "  cost:     {}", costs::format(usd));
+        }
+        if let Some(id) = &run.replay_of {
+            let model = run
+                .model
+                .as_ref()
+                .map(|m| format!(" on {m}"))
+                .unwrap_or_default();
+            let _ = writeln!(text, "  replay:   of run {id}{model}");
+        }
+        if let Some(id) = &run.queued {
+            let _ = writeln!(text, "  replayed: queued message {id}");
+        }
+        if !run.requests.is_empty() {
+            let _ = writeln!(text, "  answered: {}", run.requests.join(", "));
+        }
+        match &run.actions {
+            None => text.push_str("  actions:  (not reported)\n"),
+            Some(list) if list.is_empty() => text.push_str("  actions:  none\n"),
+            Some(list) => {
+                text.push_str("  actions:\n");
+                for action in list {
+                    let _ = writeln!(text, "    {action}");
+                }
+            }
+        }
+        if !run.suspicious.is_empty() {
+            text.push_str("  suspicious:\n");
+            for s in &run.suspicious {
+                let _ = writeln!(text, "    {s}");
+            }
+        }
+        text
+    }
+}
+
+/// The index in `all` of the run `id` names (a prefix of a run id, or an
+/// `orch scan --async` request id), the latest when it's None.
+pub(super) fn find(dir: &Path, all: &[RunRecord], id: Option<&str>) -> Result<usize, OrchError> {
+    let index = match id {
+        None => all.len().checked_sub(1),
+        // A scan split by root answers its request with more than one run
+        Some(id) if id.starts_with("req-") => {
+            let requests: Vec<_> = all.iter().filter_map(|run| answered(run, id)).collect();
+            if requests.iter().any(|r| *r != requests[0]) {
+                return Err(OrchError::Usage(format!(
+                    "'{id}' matches more than one request; give more of the id."
+                )));
+            }
+            all.iter().position(|run| answered(run, id).is_some())
+        }
+        Some(id) => {
+            let matches: Vec<_> = (0..all.len())
+                .filter(|&i| all[i].id().starts_with(id))
+                .collect();
+            if matches.len() > 1 {
+                return Err(OrchError::Usage(format!(
+                    "'{id}' matches {} runs; give more of the id.",
+                    matches.len()
+                )));
+            }
+            matches.first().copied()
+        }
+    };
+    index.ok_or_else(|| match id {
+        Some(id) if id.starts_with("req-") => {
+            let log = runs::log_path(dir, id);
+            if log.exists() {
+                OrchError::NotFound(format!(
+                    "Scan {id} hasn't finished; its log is {}.",
+                    log.display()
+                ))
+            } else {
+                OrchError::NotFound(format!(
+                    "No run has answered {id} yet; the daemon may still have it queued."
+                ))
+            }
+        }
+        Some(id) => OrchError::NotFound(format!("No run '{id}' found.")),
+        None => OrchError::NotFound("No runs recorded yet.".into()),
+    })
+}
+
+/// One run in full; the latest when `id` is None. `id` may be a prefix.
+pub fn cmd_runs_show(
+    dir: &Path,
+    id: Option<&str>,
+    format: Option<Format>,
+) -> Result<(), OrchError> {
+    let mut all: Vec<_> = runs::iter(dir).collect();
+    let index = find(dir, &all, id)?;
+    let run = Run::new(all.swap_remove(index));
+    render::emit("runs show", &run, format.unwrap_or(Format::Human))
+}
+
+/// Lines of context around each change in [`unified`].
+const CONTEXT: usize = 3;
+
+/// Most lines times lines [`unified`] compares, to bound its table.
+const MAX_CELLS: usize = 16_000_000;
+
+/// `old` against `new` as a unified diff body (hunks only), by longest
+/// common subsequence of lines. None if they're too long to compare.
+fn unified(old: &str, new: &str) -> Option<String> {
+    let (a, b): (Vec<&str>, Vec<&str>) = (old.lines().collect(), new.lines().collect());
+    if a.len().saturating_mul(b.len()) > MAX_CELLS {
+        return None;
+    }
+    // lcs[i][j]: common lines of a[i..] and b[j..]
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    // (tag, line, old line number, new line number), from 0
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((' ', a[i], i, j));
+            (i, j) = (i + 1, j + 1);
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', a[i], i, j));
+            i += 1;
+        } else {
+            ops.push(('+', b[j], i, j));
+            j += 1;
+        }
+    }
+    let changed: Vec<usize> = (0..ops.len()).filter(|&k| ops[k].0 != ' ').collect();
+    let mut text = String::new();
+    let mut k = 0;
+    while k < changed.len() {
+        let start = changed[k].saturating_sub(CONTEXT);
+        let mut end = changed[k];
+        while k < changed.len() && changed[k] <= end + 2 * CONTEXT + 1 {
+            end = changed[k];
+            k += 1;
+        }
+        let end = (end + CONTEXT + 1).min(ops.len());
+        let hunk = &ops[start..end];
+        let old_len = hunk.iter().filter(|op| op.0 != '+').count();
+        let new_len = hunk.iter().filter(|op| op.0 != '-').count();
+        let (_, _, old_at, new_at) = hunk[0];
+        let _ = writeln!(
+            text,
+            "@@ -{},{old_len} +{},{new_len} @@",
+            old_at + usize::from(old_len > 0),
+            new_at + usize::from(new_len > 0)
+        );
+        for (tag, line, _, _) in hunk {
+            let _ = writeln!(text, "{tag}{line}");
+        }
+    }
+    Some(text)
+}
+
+/// A unified diff of two runs' outputs.
+pub fn cmd_runs_diff(dir: &Path, a: &str, b: &str) -> Result<(), OrchError> {
+    let all: Vec<_> = runs::iter(dir).collect();
+    let output = |id: &str| -> Result<(String, String), OrchError> {
+        let run = &all[find(dir, &all, Some(id))?];
+        let text = run
+            .output
+            .as_deref()
+            .and_then(|blob| blobs::get(dir, blob))
+            .ok_or_else(|| {
+                OrchError::NotFound(format!(
+                    "Run {}'s output isn't kept (only the newest `run_history` runs keep theirs).",
+                    run.id()
+                ))
+            })?;
+        Ok((run.id(), text))
+    };
+    let (a, old) = output(a)?;
+    let (b, new) = output(b)?;
+    if old == new {
+        outln!("Runs {a} and {b} printed the same.");
+        return Ok(());
+    }
+    let hunks = unified(&old, &new).ok_or_else(|| {
+        OrchError::Failed(format!("Runs {a} and {b} printed too much to diff here."))
+    })?;
+    outln!("--- run {a}");
+    outln!("+++ run {b}");
+    out!("{hunks}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STYLE: Style = Style {
+        color: false,
+        width: Some(80),
+        ascii: false,
+    };
+
+    /// `lines`, each ending in a newline: a rendering as it would print.
+    fn lines(lines: &[&str]) -> String {
+        lines.iter().map(|line| format!("{line}\n")).collect()
+    }
+
+    fn run(json: &str) -> Run {
+        Run::new(serde_json::from_str(json).expect("a runs.jsonl line"))
+    }
+
+    fn runs() -> Vec<Run> {
+        vec![
+            run(
+                r#"{"at":"2026-10-15T09:30:00+00:00","ok":true,"duration_secs":84.2,
+                "actions":[{"action":"started","task":"recon","session":"task-recon"},
+                           {"action":"blocked","task":"auth","reason":"needs a token lifetime"}],
+                "suspicious":["ran `curl` outside the tasks dir"],"cost_usd":0.1234,
+                "requests":["req-1a2b"]}"#,
+            ),
+            run(
+                r#"{"at":"2026-10-15T09:00:00+00:00","ok":false,"duration_secs":3.0,"offline":true}"#,
+            ),
+            run(
+                r#"{"at":"2026-10-15T08:30:00+00:00","ok":true,"duration_secs":12.5,"actions":[],
+                "no_effect":true}"#,
+            ),
+        ]
+    }
+
+    #[test]
+    fn list_snapshot() {
+        let list = RunList { runs: runs() };
+        // Ids are start times in the local zone
+        let [a, b, c] = [0, 1, 2].map(|i| list.runs[i].id.clone());
+        assert_eq!(
+            list.render(Format::Human, STYLE),
+            lines(&[
+                &format!("  {a}  ok            1m  1 started, 1 blocked  ⚠ 1 suspicious"),
+                &format!("  {b}  offline       3s  -"),
+                &format!("  {c}  no effect    12s  nothing"),
+            ])
+        );
+        assert_eq!(
+            list.render(Format::Porcelain, STYLE),
+            lines(&[
+                &format!("{a}\tok\t84\t1 started, 1 blocked\t1"),
+                &format!("{b}\toffline\t3\t-\t0"),
+                &format!("{c}\tno effect\t12\tnothing\t0"),
+            ])
+        );
+    }
+
+    #[test]
+    fn show_snapshot() {
+        let run = runs().remove(0);
+        let ago = fmt::age(run.record.at().expect("a timestamp"));
+        assert_eq!(
+            run.render(Format::Human, STYLE),
+            lines(&[
+                &format!("run {}", run.id),
+                &format!("  started:  2026-10-15T09:30:00+00:00 ({ago} ago)"),
+                "  result:   ok, took 1m",
+                "  cost:     $0.12",
+                "  answered: req-1a2b",
+                "  actions:",
+                "    started recon (task-recon)",
+                "    blocked auth: needs a token lifetime",
+                "  suspicious:",
+                "    ran `curl` outside the tasks dir",
+            ])
+        );
+    }
+
+    #[test]
+    fn empty_list_snapshot() {
+        let list = RunList { runs: Vec::new() };
+        assert_eq!(list.render(Format::Human, STYLE), "No runs recorded yet.\n");
+        assert_eq!(
+            list.render(Format::Csv, STYLE),
+            "id,result,duration_secs,actions,suspicious\n"
+        );
+    }
+}
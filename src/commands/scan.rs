@@ -0,0 +1,211 @@
+use std::{
+    fs::{self, File},
+    os::unix::process::CommandExt,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use chrono::{Local, Timelike};
+
+use crate::{
+    actions, budget, config::Config, daemon, error::OrchError, fmt, health, messages,
+    orchestrator::Claude, outln, paths, roots, runs, task, templates,
+};
+
+/// Longest `--wait` will wait for the daemon to pick up and finish the scan.
+const WAIT_LIMIT: Duration = Duration::from_secs(30 * 60);
+/// Longest `--async` waits for the daemon to take the request from the
+/// inbox.
+const ACK_WAIT: Duration = Duration::from_secs(15);
+
+/// The message asking for a scan of just the task at `path`. A held task or
+/// one over its budget is refused unless `force`, which starts its budget
+/// over (except in a `dry_run`) and marks the scan with
+/// [`daemon::force_line`] so the daemon doesn't skip it.
+pub(super) fn scoped_message(
+    dir: &Path,
+    config: &Config,
+    name: &str,
+    force: bool,
+    dry_run: bool,
+) -> Result<String, OrchError> {
+    let (name, path) = task::resolve(dir, name, config)?;
+    let name = name.as_str();
+    let content = task::read(&path, config)?;
+    let label = task::label(dir, &path);
+    let mut parked = task::held(&content) || task::start_after(&content).is_some();
+    if task::held(&content) && !force {
+        return Err(OrchError::Failed(format!(
+            "{name} is held; use --force to scan it anyway, or orch unhold {name}."
+        )));
+    }
+    if let Some((used, limit)) = budget::over(dir, name, &content) {
+        if !force {
+            return Err(OrchError::Failed(format!(
+                "{name} is over budget ({used} of {limit} scans); use --force to scan it anyway."
+            )));
+        }
+        if !dry_run {
+            budget::reset(dir, name);
+        }
+        parked = true;
+    }
+    let mut message = format!(
+        "Scan only {label}.md: check on its worker (spin one up if it has none) and update its \
+         status."
+    );
+    if force && parked {
+        message.push('\n');
+        message.push_str(&daemon::force_line(&label));
+    }
+    Ok(message)
+}
+
+/// Hands `message` to the daemon and waits for it to be taken, or without a
+/// daemon, starts a detached `orch _scan-run` logging to the run log.
+/// Prints the request id, and the log for a detached run.
+fn scan_async(dir: &Path, message: &str) -> Result<(), OrchError> {
+    let id = runs::request_id();
+    let message = runs::with_request(message, &id);
+    if health::load(dir).is_some_and(|h| h.alive()) {
+        let path = messages::spool(&paths::inbox_dir(dir), &message)?;
+        let mut waited = Duration::ZERO;
+        while path.exists() && waited < ACK_WAIT {
+            thread::sleep(Duration::from_millis(200));
+            waited += Duration::from_millis(200);
+        }
+        if path.exists() {
+            eprintln!("[orch] queued; the daemon is busy and will run it next");
+        } else {
+            eprintln!("[orch] the daemon took the scan");
+        }
+        outln!("{id}");
+        return Ok(());
+    }
+
+    // As the daemon would have it from the inbox
+    let message = format!("[message] {message}");
+    let log = runs::log_path(dir, &id);
+    let logs = paths::run_logs_dir(dir);
+    fs::create_dir_all(&logs).map_err(|e| OrchError::io(&logs, e))?;
+    let out = File::create(&log).map_err(|e| OrchError::io(&log, e))?;
+    let err = out.try_clone().map_err(|e| OrchError::io(&log, e))?;
+    let exe = std::env::current_exe()
+        .map_err(|e| OrchError::Failed(format!("can't find the orch binary: {e}")))?;
+    // Its own process group, so the terminal's Ctrl-C and hangup don't reach it
+    Command::new(exe)
+        .args(["_scan-run", &id, &message])
+        .stdin(Stdio::null())
+        .stdout(out)
+        .stderr(err)
+        .process_group(0)
+        .spawn()
+        .map_err(|e| OrchError::Failed(format!("failed to start the scan: {e}")))?;
+    eprintln!("[orch] no daemon running; scanning in the background");
+    outln!("{id}");
+    outln!("log: {}", log.display());
+    Ok(())
+}
+
+/// The detached half of `orch scan --async`: one scan as the daemon would
+/// run it, recorded under the request id `message` carries.
+pub fn cmd_scan_run(dir: &Path, config: &Config, id: &str, message: &str) -> Result<(), OrchError> {
+    eprintln!("[orch] scan {id} started");
+    let runner = Claude {
+        repo: paths::repo_dir()?,
+        tasks_dir: dir.to_path_buf(),
+        sandbox: config.sandbox,
+        prompt: roots::Prompt {
+            base: roots::global(config).1,
+            append: None,
+        },
+        report_actions: true,
+    };
+    if !daemon::scan_detached(config, dir, &runner, message) {
+        return Err(OrchError::Failed(format!("scan {id} failed")));
+    }
+    eprintln!("[orch] scan {id} finished");
+    Ok(())
+}
+
+pub fn cmd_scan(
+    dir: &Path,
+    config: &Config,
+    name: Option<&str>,
+    force: bool,
+    wait: bool,
+    dry_run: bool,
+    detach: bool,
+) -> Result<(), OrchError> {
+    let message = match name {
+        Some(name) => scoped_message(dir, config, name, force, dry_run)?,
+        None => templates::scan(config, dir),
+    };
+    if dry_run {
+        // The daemon gets it through the inbox, as any message
+        let runs = daemon::preview(config, dir, &format!("[message] {message}"));
+        for (i, (prompt, message)) in runs.iter().enumerate() {
+            if i > 0 {
+                outln!();
+            }
+            match runs.len() {
+                1 => outln!("# prompt: {}", prompt.describe()),
+                n => outln!("# run {} of {n}, prompt: {}", i + 1, prompt.describe()),
+            }
+            outln!("{message}");
+        }
+        return Ok(());
+    }
+    if detach {
+        return scan_async(dir, &message);
+    }
+    // daemon.json stamps are to the second
+    let requested = Local::now().with_nanosecond(0).unwrap_or_else(Local::now);
+    messages::write(&paths::inbox_dir(dir), &message)?;
+    eprintln!("[orch] scan triggered");
+    if !wait {
+        return Ok(());
+    }
+    if !health::load(dir).is_some_and(|h| h.alive()) {
+        return Err(OrchError::Failed(
+            "The daemon isn't running; the scan will run when it starts.".into(),
+        ));
+    }
+
+    let mut waited = Duration::ZERO;
+    let health = loop {
+        if let Some(h) = health::load(dir)
+            && h.scanning_since.is_none()
+            && h.last_scan().is_some_and(|t| t >= requested)
+        {
+            break h;
+        }
+        if waited >= WAIT_LIMIT {
+            return Err(OrchError::Failed(format!(
+                "Gave up waiting for the scan after {}.",
+                fmt::long(waited)
+            )));
+        }
+        thread::sleep(Duration::from_secs(1));
+        waited += Duration::from_secs(1);
+    };
+
+    let ok = health.last_scan_ok == Some(true);
+    match &health.last_scan_actions {
+        Some(actions) => {
+            outln!("## Scan actions: {}\n", actions::summary(actions));
+            for action in actions {
+                outln!("  {action}");
+            }
+        }
+        None => outln!("(the orchestrator didn't report its actions)"),
+    }
+    if !ok {
+        return Err(OrchError::Failed(
+            "The scan failed; see the daemon log.".into(),
+        ));
+    }
+    Ok(())
+}
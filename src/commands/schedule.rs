@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use chrono::Local;
+
+use crate::{config::Config, duration, edits, error::OrchError, fmt, frontmatter, outln, task};
+
+/// Sets `start_after:` from `--at` or `--in`, or clears it. The daemon holds
+/// the task back until then and scans it when the time comes.
+pub fn cmd_schedule(
+    dir: &Path,
+    config: &Config,
+    name: &str,
+    at: Option<&str>,
+    after: Option<&str>,
+) -> Result<(), OrchError> {
+    let when = match (at, after) {
+        (Some(at), _) => Some(duration::at(at).map_err(OrchError::Parse)?),
+        (None, Some(after)) => Some(duration::from_now(after).map_err(OrchError::Parse)?),
+        (None, None) => None,
+    };
+    let (name, path) = task::resolve(dir, name, config)?;
+    let start_after = when.map(task::timestamp);
+    edits::apply(dir, config, &path, |content| {
+        Some(frontmatter::set(
+            content,
+            "start_after",
+            start_after.as_deref(),
+        ))
+    })?;
+    match when {
+        None => outln!("{name}: unscheduled"),
+        Some(when) if when <= Local::now() => outln!(
+            "{name}: {} is already past, so it can start now",
+            fmt::time(when)
+        ),
+        Some(when) => outln!(
+            "{name}: starts {} (in {})",
+            fmt::time(when),
+            fmt::until(when)
+        ),
+    }
+    Ok(())
+}
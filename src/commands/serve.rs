@@ -0,0 +1,193 @@
+//! `orch serve`: read-only JSON over tasks, the inbox and daemon state, plus
+//! token-guarded POSTs that queue a scan or a message. Like the other
+//! commands it only touches files, so a request never waits on a scan.
+
+use std::{io::Read, path::Path, thread};
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use super::{scan::scoped_message, status::TaskStatus};
+use crate::{
+    config::Config, error::OrchError, health, inbox, messages, paths, runs, task, templates,
+};
+
+/// Requests handled at once.
+const WORKERS: usize = 4;
+/// Longest POST body read.
+const MAX_BODY: u64 = 64 * 1024;
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ScanBody {
+    task: Option<String>,
+    force: bool,
+}
+
+#[derive(Deserialize)]
+struct MessageBody {
+    message: String,
+}
+
+fn parse_body<T: for<'a> Deserialize<'a>>(body: &str) -> Result<T, OrchError> {
+    serde_json::from_str(body).map_err(|e| OrchError::Parse(format!("bad request body: {e}")))
+}
+
+fn get(dir: &Path, config: &Config, path: &str) -> Result<Value, OrchError> {
+    match path {
+        "/tasks" => Ok(task::list(dir, config)
+            .iter()
+            .map(|t| json!(TaskStatus::new(dir, config, t)))
+            .collect()),
+        "/inbox" => Ok(inbox::items(dir, config)
+            .iter()
+            .map(inbox::InboxItem::to_json)
+            .collect()),
+        "/runs" => Ok(json!(runs::iter(dir).collect::<Vec<_>>())),
+        "/health" => Ok(match health::load(dir) {
+            Some(h) => {
+                let mut value = json!(h);
+                value["alive"] = json!(h.alive());
+                value
+            }
+            None => json!({ "alive": false }),
+        }),
+        _ => {
+            let name = path
+                .strip_prefix("/tasks/")
+                .ok_or_else(|| OrchError::NotFound(format!("no endpoint {path}")))?;
+            task::list(dir, config)
+                .iter()
+                .find(|t| t.name == name || t.label == name)
+                .map(|t| json!(TaskStatus::new(dir, config, t).with_content(t)))
+                .ok_or_else(|| OrchError::no_task(name))
+        }
+    }
+}
+
+fn post(dir: &Path, config: &Config, path: &str, body: &str) -> Result<Value, OrchError> {
+    let message = match path {
+        "/scan" => {
+            let body: ScanBody = if body.trim().is_empty() {
+                ScanBody::default()
+            } else {
+                parse_body(body)?
+            };
+            match body.task {
+                Some(name) => scoped_message(dir, config, &name, body.force, false)?,
+                None => templates::scan(config, dir),
+            }
+        }
+        "/messages" => {
+            let body: MessageBody = parse_body(body)?;
+            if body.message.trim().is_empty() {
+                return Err(OrchError::Usage("No message given.".into()));
+            }
+            body.message
+        }
+        _ => return Err(OrchError::NotFound(format!("no endpoint {path}"))),
+    };
+    messages::write(&paths::inbox_dir(dir), &message)?;
+    Ok(json!({ "queued": true }))
+}
+
+/// `a == b` in time that depends only on their lengths, so a token can't be
+/// guessed a byte at a time from how fast wrong ones are turned away.
+fn same(a: &[u8], b: &[u8]) -> bool {
+    let diff = a
+        .iter()
+        .zip(b)
+        .fold(a.len() ^ b.len(), |diff, (x, y)| diff | usize::from(x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
+/// Whether the request may POST: a token is configured and it sent it.
+fn authorized(config: &Config, request: &Request) -> Result<(), (u16, String)> {
+    if config.read_only {
+        return Err((403, "orch is running read-only".into()));
+    }
+    let Some(token) = &config.serve.token else {
+        return Err((403, "set serve.token in the config to enable POST".into()));
+    };
+    let sent = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "));
+    if !sent.is_some_and(|sent| same(sent.as_bytes(), token.as_bytes())) {
+        return Err((401, "missing or wrong bearer token".into()));
+    }
+    Ok(())
+}
+
+fn status(e: &OrchError) -> u16 {
+    match e {
+        OrchError::NotFound(_) => 404,
+        OrchError::Usage(_) | OrchError::Parse(_) => 400,
+        _ => 500,
+    }
+}
+
+fn respond(dir: &Path, config: &Config, mut request: Request) {
+    let url = request.url().to_string();
+    let path = url
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('/');
+    let result = match request.method() {
+        Method::Get => get(dir, config, path).map(|v| (200, v)),
+        Method::Post => match authorized(config, &request) {
+            Ok(()) => {
+                let mut body = String::new();
+                let _ = request.as_reader().take(MAX_BODY).read_to_string(&mut body);
+                post(dir, config, path, &body).map(|v| (202, v))
+            }
+            Err((code, e)) => Ok((code, json!({ "error": e }))),
+        },
+        _ => Ok((405, json!({ "error": "only GET and POST" }))),
+    };
+    let (code, body) = result.unwrap_or_else(|e| (status(&e), json!({ "error": e.to_string() })));
+    if *request.method() == Method::Post {
+        eprintln!("[orch] POST {path} {code}");
+    }
+
+    let header = Header::from_bytes("Content-Type", "application/json").expect("static header");
+    let response = Response::from_string(body.to_string())
+        .with_status_code(code)
+        .with_header(header);
+    let _ = request.respond(response);
+}
+
+pub fn cmd_serve(dir: &Path, config: &Config, port: Option<u16>) -> Result<(), OrchError> {
+    let port = port.unwrap_or(config.serve.port);
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| OrchError::Failed(format!("failed to listen on 127.0.0.1:{port}: {e}")))?;
+    eprintln!("[orch] serving on http://127.0.0.1:{port}");
+    thread::scope(|s| {
+        for _ in 0..WORKERS {
+            s.spawn(|| {
+                for request in server.incoming_requests() {
+                    respond(dir, config, request);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_match_only_exactly() {
+        assert!(same(b"s3cret", b"s3cret"));
+        assert!(same(b"", b""));
+        assert!(!same(b"s3cret", b"s3creT"));
+        assert!(!same(b"s3cret", b"s3cret2"));
+        assert!(!same(b"s3cret", b"s3cre"));
+        assert!(!same(b"", b"s3cret"));
+    }
+}
@@ -0,0 +1,45 @@
+use std::path::Path;
+
+use crate::{
+    config::Config,
+    duration, edits,
+    error::OrchError,
+    fmt, frontmatter,
+    log::{self, Event},
+    outln, task,
+};
+
+/// Returns the task's name, as resolved from `name`.
+fn set_snooze(
+    dir: &Path,
+    config: &Config,
+    name: &str,
+    until: Option<&str>,
+) -> Result<String, OrchError> {
+    let (name, path) = task::resolve(dir, name, config)?;
+    edits::apply(dir, config, &path, |content| {
+        Some(frontmatter::set(content, "snoozed_until", until))
+    })?;
+    Ok(name)
+}
+
+pub fn cmd_snooze(
+    dir: &Path,
+    config: &Config,
+    name: &str,
+    duration: &str,
+) -> Result<(), OrchError> {
+    let until = duration::from_now(duration).map_err(OrchError::Parse)?;
+    let stamp = task::timestamp(until);
+    let name = set_snooze(dir, config, name, Some(&stamp))?;
+    log::record(dir, &name, Event::Snoozed { until: stamp });
+    outln!("{name}: snoozed until {}", fmt::time(until));
+    Ok(())
+}
+
+pub fn cmd_unsnooze(dir: &Path, config: &Config, name: &str) -> Result<(), OrchError> {
+    let name = set_snooze(dir, config, name, None)?;
+    log::record(dir, &name, Event::Unsnoozed);
+    outln!("{name}: unsnoozed");
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use std::{path::Path, thread, time::Duration};
+
+use chrono::Local;
+
+use crate::{
+    config::Config,
+    daemon, duration,
+    error::OrchError,
+    fmt, health, messages,
+    orchestrator::Claude,
+    outln, paths,
+    rollout::{self, Start},
+    roots,
+    task::{self, Task},
+    tmux,
+};
+
+/// Has the orchestrator start workers for `names`, or with None for every
+/// task without one, in [`rollout::plan`] order. With `stagger` they start
+/// one at a time that far apart: queued for the daemon when it's running,
+/// else here, going on past any that fail.
+pub fn cmd_spawn(
+    dir: &Path,
+    config: &Config,
+    names: Option<&[String]>,
+    stagger: Option<&str>,
+    dry_run: bool,
+) -> Result<(), OrchError> {
+    let gap = stagger
+        .map(duration::parse)
+        .transpose()
+        .map_err(OrchError::Usage)?
+        .unwrap_or_default();
+    let rollout::Plan { start, skipped } = rollout::plan(dir, config, names)?;
+    if dry_run {
+        for (i, task) in start.iter().enumerate() {
+            let offset = gap * i as u32;
+            if offset.is_zero() {
+                outln!("{:>3}. {}", i + 1, task.label);
+            } else {
+                outln!("{:>3}. {}  (+{})", i + 1, task.label, fmt::duration(offset));
+            }
+        }
+        for (label, why) in &skipped {
+            outln!("     {label}: skipped, {why}");
+        }
+        if start.is_empty() {
+            outln!("(nothing to start)");
+        }
+        return Ok(());
+    }
+    for (label, why) in &skipped {
+        eprintln!("Skipping {label}: {why}.");
+    }
+    if start.is_empty() {
+        outln!("Nothing to start.");
+        return Ok(());
+    }
+    if health::load(dir).is_some_and(|h| h.alive()) {
+        return hand_to_daemon(dir, &start, gap);
+    }
+    run_here(dir, config, &start, gap)
+}
+
+/// Asks the daemon for the first start now and queues the rest on the
+/// stagger, which it carries out even after this terminal closes.
+fn hand_to_daemon(dir: &Path, start: &[Task], gap: Duration) -> Result<(), OrchError> {
+    let labels: Vec<String> = start.iter().map(|t| t.label.clone()).collect();
+    let now_count = if gap.is_zero() { labels.len() } else { 1 };
+    let (now, later) = labels.split_at(now_count);
+    messages::write(&paths::inbox_dir(dir), &rollout::message(now))?;
+    let started = Local::now();
+    let queued: Vec<Start> = later
+        .iter()
+        .zip(1u32..)
+        .map(|(label, i)| Start {
+            label: label.clone(),
+            at: task::timestamp(started + gap * i),
+        })
+        .collect();
+    if !queued.is_empty() {
+        rollout::queue(dir, queued)?;
+    }
+    for (i, label) in labels.iter().enumerate() {
+        if i < now_count {
+            outln!("{:>3}. {label}  now", i + 1);
+        } else {
+            outln!(
+                "{:>3}. {label}  in {}",
+                i + 1,
+                fmt::duration(gap * i as u32)
+            );
+        }
+    }
+    match later.len() {
+        0 => outln!(
+            "Asked the daemon to start {}.",
+            fmt::count(labels.len(), "worker")
+        ),
+        n => outln!(
+            "Asked the daemon to start the first now; it starts the other {n} {} apart, \
+             whether or not this terminal stays open.",
+            fmt::duration(gap)
+        ),
+    }
+    Ok(())
+}
+
+/// Starts the workers through the orchestrator from here, waiting `gap`
+/// between them (all in one run without one).
+fn run_here(dir: &Path, config: &Config, start: &[Task], gap: Duration) -> Result<(), OrchError> {
+    let runner = Claude {
+        repo: paths::repo_dir()?,
+        tasks_dir: dir.to_path_buf(),
+        sandbox: config.sandbox,
+        prompt: roots::Prompt {
+            base: roots::global(config).1,
+            append: None,
+        },
+        report_actions: true,
+    };
+    let batches: Vec<&[Task]> = if gap.is_zero() {
+        vec![start]
+    } else {
+        start.chunks(1).collect()
+    };
+    let mut failed = Vec::new();
+    let mut done = 0;
+    for (i, batch) in batches.iter().enumerate() {
+        if i > 0 {
+            eprintln!("Waiting {}...", fmt::duration(gap));
+            thread::sleep(gap);
+        }
+        let labels: Vec<String> = batch.iter().map(|t| t.label.clone()).collect();
+        eprintln!(
+            "[{}/{}] starting {}...",
+            done + 1,
+            start.len(),
+            labels.join(", ")
+        );
+        let ok = daemon::scan_detached(config, dir, &runner, &rollout::message(&labels));
+        for task in *batch {
+            done += 1;
+            // The orchestrator may have recorded a session: of its own
+            let content = task::find(dir, &task.label, config)
+                .and_then(|path| task::read(&path, config).ok())
+                .unwrap_or_default();
+            let session = task::session(config, &task.label, &content);
+            let up = tmux::has_session(&session);
+            match (ok, up) {
+                (_, true) => outln!(
+                    "[{done}/{}] {}: started ({session})",
+                    start.len(),
+                    task.label
+                ),
+                (true, false) => {
+                    outln!("[{done}/{}] {}: no worker yet", start.len(), task.label);
+                    failed.push(task.label.clone());
+                }
+                (false, false) => {
+                    outln!("[{done}/{}] {}: the run failed", start.len(), task.label);
+                    failed.push(task.label.clone());
+                }
+            }
+        }
+    }
+    if !failed.is_empty() {
+        return Err(OrchError::Failed(format!(
+            "{} of {} didn't start: {}.",
+            failed.len(),
+            fmt::count(start.len(), "task"),
+            failed.join(", ")
+        )));
+    }
+    Ok(())
+}
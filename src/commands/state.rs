@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use crate::{config::Config, error::OrchError, outln, paths, state};
+
+/// Checks every file under `.orch/` and reports what's wrong; `repair` sets
+/// the damage aside (see [`state::repair`]). Fails if problems remain.
+pub fn cmd_state_check(dir: &Path, config: &Config, repair: bool) -> Result<(), OrchError> {
+    let state_dir = paths::state_dir(dir);
+    let short = |path: &Path| {
+        path.strip_prefix(&state_dir)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    let report = state::check(dir, config);
+    for (path, detail) in &report.ok {
+        outln!("ok    {}: {detail}", short(path));
+    }
+
+    let mut left = 0;
+    for problem in &report.problems {
+        let name = short(&problem.path);
+        if !repair || !problem.repairable() {
+            outln!("FAIL  {name}: {}", problem.what);
+            left += 1;
+            continue;
+        }
+        match state::repair(dir, config, problem) {
+            Ok(done) => outln!("fixed {name}: {}; {done}", problem.what),
+            Err(e) => {
+                outln!("FAIL  {name}: {}; repair failed: {e}", problem.what);
+                left += 1;
+            }
+        }
+    }
+
+    if left > 0 {
+        if !repair && report.problems.iter().any(state::Problem::repairable) {
+            eprintln!("Run `orch state check --repair` to set the damage aside.");
+        }
+        return Err(OrchError::Reported(1));
+    }
+    Ok(())
+}
@@ -0,0 +1,402 @@
+//! `orch stats`: throughput over time, from task `created:` stamps, their
+//! `## Status` entries, `done/` and `.orch/runs.jsonl`. A summary of the
+//! range, then the same counts bucketed by day, week or month.
+
+use std::{collections::BTreeMap, path::Path};
+
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    cli::Period, config::Config, costs, duration, error::OrchError, fmt, frontmatter, out, outln,
+    render, runs, task, tmux, warn,
+};
+
+/// First day of the bucket `date` falls in; weeks start on Monday.
+fn bucket(by: Period, date: NaiveDate) -> NaiveDate {
+    match by {
+        Period::Day => date,
+        Period::Week => date - Days::new(u64::from(date.weekday().num_days_from_monday())),
+        Period::Month => date.with_day(1).unwrap_or(date),
+    }
+}
+
+fn next(by: Period, start: NaiveDate) -> NaiveDate {
+    match by {
+        Period::Day => start + Days::new(1),
+        Period::Week => start + Days::new(7),
+        Period::Month => start + Months::new(1),
+    }
+}
+
+fn label(by: Period, start: NaiveDate) -> String {
+    match by {
+        Period::Month => start.format("%Y-%m").to_string(),
+        _ => start.format("%Y-%m-%d").to_string(),
+    }
+}
+
+#[derive(Default)]
+struct Row {
+    created: u64,
+    /// Of those, how many only have an approximate (mtime) creation time.
+    created_approx: u64,
+    completed: u64,
+    runs: u64,
+    failed_runs: u64,
+    /// Runs that succeeded without changing anything.
+    no_effect_runs: u64,
+    run_secs: f64,
+}
+
+const HEADER: [&str; 7] = [
+    "period",
+    "created",
+    "completed",
+    "runs",
+    "failed_runs",
+    "no_effect_runs",
+    "run_secs",
+];
+
+impl Row {
+    /// Rust's formatting ignores the locale, so decimals always use a dot.
+    fn cells(&self, period: String) -> [String; 7] {
+        [
+            period,
+            self.created.to_string(),
+            self.completed.to_string(),
+            self.runs.to_string(),
+            self.failed_runs.to_string(),
+            self.no_effect_runs.to_string(),
+            format!("{:.1}", self.run_secs),
+        ]
+    }
+}
+
+/// Totals over the whole range.
+#[derive(Serialize)]
+struct Summary {
+    since: String,
+    until: String,
+    created: u64,
+    /// Of those, how many only have an approximate (mtime) creation time.
+    created_approx: u64,
+    completed: u64,
+    /// Mean from `created:` to close, over tasks completed in the range that
+    /// have a `created:` stamp.
+    avg_secs_to_done: Option<f64>,
+    /// Open tasks now.
+    wip: usize,
+    /// Of those, how many have a live worker.
+    wip_running: usize,
+    /// Time spent blocked within the range, see [`blocked_secs`].
+    blocked_secs: i64,
+    /// Tasks closed in the range by `outcome:`; closes from before outcomes
+    /// were recorded count as "unrecorded".
+    outcomes: BTreeMap<String, u64>,
+    /// Share of recorded outcomes that are wontfix.
+    wontfix_rate: Option<f64>,
+    /// Median `active_secs:` of tasks closed as done in the range.
+    median_active_secs: Option<i64>,
+}
+
+/// A task still open or in `done/`, with what stats needs from it.
+struct Tracked {
+    created: Option<DateTime<Local>>,
+    /// `created` is the file's mtime, not a `created:` stamp.
+    created_approx: bool,
+    closed: Option<DateTime<Local>>,
+    /// Its worker session, while it's open.
+    session: Option<String>,
+    /// `outcome:` and `active_secs:`, stamped when it was closed.
+    outcome: Option<String>,
+    active_secs: Option<i64>,
+    content: String,
+}
+
+fn tracked(dir: &Path, config: &Config) -> Vec<Tracked> {
+    let open = task::list(dir, config).into_iter().map(|t| Tracked {
+        created: Some(t.created),
+        created_approx: t.created_approx,
+        closed: None,
+        session: Some(task::session(config, &t.label, &t.content)),
+        outcome: None,
+        active_secs: None,
+        content: t.content,
+    });
+    // An archived file that can't be read has nothing to count
+    let closed = task::archived(dir).into_iter().filter_map(|a| {
+        let content = task::read(&a.path, config).ok()?;
+        Some(Tracked {
+            created: frontmatter::get(&content, "created").and_then(|c| task::parse_timestamp(&c)),
+            created_approx: false,
+            closed: Some(a.archived),
+            session: None,
+            outcome: frontmatter::get(&content, "outcome"),
+            active_secs: frontmatter::get(&content, "active_secs").and_then(|s| s.parse().ok()),
+            content,
+        })
+    });
+    open.chain(closed).collect()
+}
+
+/// Seconds within `since..until` the task spent blocked: from each `## Status`
+/// entry that says "blocked" to the entry after it, or to its close (or now).
+fn blocked_secs(task: &Tracked, since: DateTime<Local>, until: DateTime<Local>) -> i64 {
+    let mut entries: Vec<_> = task::section(&task.content, "## Status")
+        .into_iter()
+        .filter_map(task::status_entry)
+        .map(|(time, _, text)| (time, text.to_lowercase()))
+        .collect();
+    entries.sort_by_key(|(time, _)| *time);
+    let end = task.closed.unwrap_or_else(Local::now);
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, text))| text.contains("blocked") && !text.contains("unblocked"))
+        .map(|(i, (from, _))| {
+            let to = entries.get(i + 1).map_or(end, |(next, _)| *next);
+            let (from, to) = ((*from).max(since), to.min(until));
+            (to - from).num_seconds().max(0)
+        })
+        .sum()
+}
+
+pub fn cmd_stats(
+    dir: &Path,
+    config: &Config,
+    by: Period,
+    since: &str,
+    until: Option<&str>,
+    csv: bool,
+    as_json: bool,
+) -> Result<(), OrchError> {
+    let since = duration::since(since).map_err(OrchError::Parse)?;
+    let until = until.map_or_else(|| Ok(Local::now()), duration::since);
+    let until = until.map_err(OrchError::Parse)?;
+    if since > until {
+        return Err(OrchError::Usage("--since is after --until.".into()));
+    }
+    let in_range = |t: &DateTime<Local>| *t >= since && *t <= until;
+
+    // Every bucket in the range, so a spreadsheet gets a continuous axis
+    let mut rows = BTreeMap::new();
+    let mut start = bucket(by, since.date_naive());
+    while start <= until.date_naive() {
+        rows.insert(start, Row::default());
+        start = next(by, start);
+    }
+    let key = |t: &DateTime<Local>| bucket(by, t.date_naive());
+
+    let tasks = tracked(dir, config);
+    for t in &tasks {
+        let Some(created) = t.created.filter(in_range) else {
+            continue;
+        };
+        if let Some(r) = rows.get_mut(&key(&created)) {
+            r.created += 1;
+            r.created_approx += u64::from(t.created_approx);
+        }
+    }
+    for t in tasks.iter().filter_map(|t| t.closed).filter(in_range) {
+        if let Some(r) = rows.get_mut(&key(&t)) {
+            r.completed += 1;
+        }
+    }
+    for run in runs::iter(dir) {
+        let Some(at) = run.at().filter(in_range) else {
+            continue;
+        };
+        if let Some(r) = rows.get_mut(&key(&at)) {
+            r.runs += 1;
+            r.failed_runs += u64::from(!run.ok);
+            r.no_effect_runs += u64::from(run.no_effect);
+            r.run_secs += run.duration_secs;
+        }
+    }
+
+    let to_done: Vec<i64> = tasks
+        .iter()
+        .filter(|t| !t.created_approx)
+        .filter_map(|t| Some((t.created?, t.closed.filter(in_range)?)))
+        .map(|(created, closed)| (closed - created).num_seconds().max(0))
+        .collect();
+    let closed: Vec<_> = tasks
+        .iter()
+        .filter(|t| t.closed.is_some_and(|c| in_range(&c)))
+        .collect();
+    let mut outcomes = BTreeMap::new();
+    for t in &closed {
+        let outcome = t.outcome.clone().unwrap_or_else(|| "unrecorded".into());
+        *outcomes.entry(outcome).or_insert(0) += 1;
+    }
+    let recorded = closed.iter().filter(|t| t.outcome.is_some()).count();
+    let wontfix = outcomes.get("wontfix").copied().unwrap_or(0);
+    let mut active: Vec<i64> = closed
+        .iter()
+        .filter(|t| t.outcome.as_deref() == Some("done"))
+        .filter_map(|t| t.active_secs)
+        .collect();
+    active.sort_unstable();
+    let open: Vec<_> = tasks.iter().filter(|t| t.closed.is_none()).collect();
+    let live = tmux::sessions();
+    let summary = Summary {
+        since: task::timestamp(since),
+        until: task::timestamp(until),
+        created: rows.values().map(|r| r.created).sum(),
+        created_approx: rows.values().map(|r| r.created_approx).sum(),
+        completed: rows.values().map(|r| r.completed).sum(),
+        avg_secs_to_done: (!to_done.is_empty())
+            .then(|| to_done.iter().sum::<i64>() as f64 / to_done.len() as f64),
+        wip: open.len(),
+        wip_running: open
+            .iter()
+            .filter(|t| t.session.as_ref().is_some_and(|s| live.contains(s)))
+            .count(),
+        blocked_secs: tasks.iter().map(|t| blocked_secs(t, since, until)).sum(),
+        outcomes,
+        wontfix_rate: (recorded > 0).then(|| wontfix as f64 / recorded as f64),
+        median_active_secs: active.get(active.len() / 2).copied(),
+    };
+
+    let table: Vec<_> = rows
+        .iter()
+        .map(|(start, r)| r.cells(label(by, *start)))
+        .collect();
+    if as_json {
+        let buckets: Vec<_> = table
+            .iter()
+            .zip(rows.values())
+            .map(|(cells, r)| {
+                json!({
+                    "period": cells[0],
+                    "created": r.created,
+                    "created_approx": r.created_approx,
+                    "completed": r.completed,
+                    "runs": r.runs,
+                    "failed_runs": r.failed_runs,
+                    "no_effect_runs": r.no_effect_runs,
+                    "run_secs": r.run_secs,
+                })
+            })
+            .collect();
+        let mut out = serde_json::to_value(&summary).unwrap_or_default();
+        out["buckets"] = json!(buckets);
+        let out = warn::attach(out);
+        outln!("{}", serde_json::to_string_pretty(&out).unwrap_or_default());
+        return Ok(());
+    }
+    if csv {
+        outln!("{}", HEADER.join(","));
+        for cells in &table {
+            outln!("{}", cells.join(","));
+        }
+        return Ok(());
+    }
+    let widths: Vec<usize> = (0..HEADER.len())
+        .map(|i| {
+            table
+                .iter()
+                .map(|c| c[i].len())
+                .chain([HEADER[i].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let line = |cells: &[&str]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(i, (c, w))| {
+                if i == 0 {
+                    format!("{c:<w$}")
+                } else {
+                    format!("{c:>w$}")
+                }
+            })
+            .collect();
+        outln!("  {}", padded.join("  "));
+    };
+    let approx = match summary.created_approx {
+        0 => String::new(),
+        n => format!(" ({n} approx)"),
+    };
+    outln!(
+        "created {}{approx}, completed {}, avg time to done {}",
+        summary.created,
+        summary.completed,
+        summary.avg_secs_to_done.map_or("-".into(), fmt::elapsed)
+    );
+    outln!(
+        "in progress {} ({} with a worker), blocked {}",
+        summary.wip,
+        summary.wip_running,
+        fmt::secs(summary.blocked_secs)
+    );
+    if !summary.outcomes.is_empty() {
+        let counts: Vec<_> = summary
+            .outcomes
+            .iter()
+            .map(|(outcome, n)| format!("{outcome} {n}"))
+            .collect();
+        outln!(
+            "outcomes: {}; wontfix rate {}, median cycle time {} active",
+            counts.join(", "),
+            summary
+                .wontfix_rate
+                .map_or("-".into(), |r| format!("{:.0}%", r * 100.0)),
+            summary.median_active_secs.map_or("-".into(), fmt::secs)
+        );
+    }
+    outln!();
+    line(&HEADER);
+    for cells in &table {
+        line(&cells.iter().map(String::as_str).collect::<Vec<_>>());
+    }
+    Ok(())
+}
+
+/// Spend per task from `.orch/costs.json`, most first, with runs that
+/// weren't about particular tasks last.
+pub fn cmd_stats_by_task(dir: &Path, csv: bool, as_json: bool) -> Result<(), OrchError> {
+    let mut totals = costs::load(dir);
+    let unattributed = totals.remove(costs::UNATTRIBUTED);
+    let mut rows: Vec<_> = totals.into_iter().collect();
+    rows.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    rows.extend(unattributed.map(|usd| (costs::UNATTRIBUTED.to_string(), usd)));
+    if as_json {
+        let tasks: Vec<_> = rows
+            .iter()
+            .map(|(task, usd)| json!({ "task": task, "cost_usd": usd }))
+            .collect();
+        let total: f64 = rows.iter().map(|(_, usd)| usd).sum();
+        let out = warn::attach(json!({ "total_usd": total, "tasks": tasks }));
+        outln!("{}", serde_json::to_string_pretty(&out).unwrap_or_default());
+        return Ok(());
+    }
+    if csv {
+        out!("{}", render::csv_row(&["task", "cost_usd"]));
+        for (task, usd) in &rows {
+            out!("{}", render::csv_row(&[task, &format!("{usd:.4}")]));
+        }
+        return Ok(());
+    }
+    if rows.is_empty() {
+        outln!("No run costs recorded yet.");
+        return Ok(());
+    }
+    let width = rows
+        .iter()
+        .map(|(task, _)| task.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (task, usd) in &rows {
+        outln!("  {task:<width$}  {:>8}", costs::format(*usd));
+    }
+    let total: f64 = rows.iter().map(|(_, usd)| usd).sum();
+    outln!("  {:<width$}  {:>8}", "total", costs::format(total));
+    Ok(())
+}
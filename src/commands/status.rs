@@ -0,0 +1,1411 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+    mem,
+    path::Path,
+    thread,
+};
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::{
+    budget,
+    cli::{GroupBy, StatusArgs},
+    config::{self, Config},
+    costs, duration, edits,
+    error::OrchError,
+    exits, fmt, frontmatter, heartbeat, inbox,
+    notify::InboxNotifier,
+    out, outln, output,
+    render::{self, Format, Report, Style},
+    session, task, tmux, transcript,
+};
+
+/// Which of a task's signals moved after `since`: its file, the newest
+/// `## Status` entry, or worker session activity.
+fn changes(task: &task::Task, session: &str, since: DateTime<Local>) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+    if task.modified >= since {
+        changed.push("file");
+    }
+    if task::latest_status(&task.content).is_some_and(|t| t >= since) {
+        changed.push("status");
+    }
+    if tmux::session_activity(session).is_some_and(|t| t >= since) {
+        changed.push("worker");
+    }
+    changed
+}
+
+/// `--sessions`: lists tasks whose `status:` says a worker is on them
+/// while their session isn't running, and fails if there are any.
+fn check_sessions(dir: &Path, config: &Config) -> Result<(), OrchError> {
+    let missing: Vec<_> = task::list(dir, config)
+        .into_iter()
+        .filter(|t| task::claims_running(&t.content))
+        .filter_map(|t| {
+            let session = task::session(config, &t.label, &t.content);
+            (!tmux::has_session(&session)).then_some((t, session))
+        })
+        .collect();
+    if missing.is_empty() {
+        outln!("Every task whose status says running has a worker.");
+        return Ok(());
+    }
+    for (task, session) in &missing {
+        let status = frontmatter::get(&task.content, "status").unwrap_or_default();
+        outln!(
+            "{}: {}, status says '{status}' but {session} isn't running; `orch jump {} --spawn`",
+            task.label,
+            Style::detect().red("worker missing!"),
+            name(task)
+        );
+    }
+    Err(OrchError::Reported(1))
+}
+
+/// The task's file name, without its project.
+fn name(task: &task::Task) -> &str {
+    task.label.rsplit('/').next().unwrap_or(&task.label)
+}
+
+const COMPACT_STATUS_WIDTH: usize = 50;
+
+/// A glyph of the narrow `--compact` layout: (glyph, ASCII, meaning).
+type Glyph = (&'static str, &'static str, &'static str);
+
+const RUNNING: Glyph = ("●", "*", "running");
+const HUNG: Glyph = ("◐", "~", "hung");
+const MISSING: Glyph = ("✗", "!", "missing");
+const EXITED: Glyph = ("■", "x", "exited");
+const IDLE: Glyph = ("○", "-", "no worker");
+const PINNED: Glyph = (render::PIN, "^", "pinned");
+const HELD: Glyph = ("⏸", "=", "held");
+const SNOOZED: Glyph = ("☾", "z", "snoozed");
+const AGE: Glyph = ("⏱", "@", "since last change");
+
+/// Summary lines, or else the first line of the body.
+fn description(content: &str) -> Vec<&str> {
+    let summary = task::section(content, "## Summary");
+    if !summary.is_empty() {
+        return summary;
+    }
+    let desc = frontmatter::split(content)
+        .1
+        .lines()
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or("")
+        .trim()
+        .trim_start_matches('#')
+        .trim();
+    vec![desc]
+}
+
+/// The latest status entry, else the first line of the description.
+fn status_line(content: &str) -> String {
+    let line = task::section(content, "## Status")
+        .last()
+        .copied()
+        .unwrap_or_else(|| description(content).first().copied().unwrap_or(""));
+    line.trim().trim_start_matches("- ").to_string()
+}
+
+fn parse_when(when: &str) -> Result<DateTime<Local>, OrchError> {
+    duration::since(when).map_err(OrchError::Parse)
+}
+
+/// A task as `--json`, `--json-lines` and `orch serve` report it.
+#[derive(Serialize)]
+pub(super) struct TaskStatus<'a> {
+    name: &'a str,
+    label: &'a str,
+    session: String,
+    running: bool,
+    priority: Option<String>,
+    held: bool,
+    pinned: bool,
+    /// `status:` says a worker is on it, but its session isn't running.
+    worker_missing: bool,
+    /// How its worker exited, until the task moves on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    worker_exit: Option<exits::Exit>,
+    snoozed_until: Option<String>,
+    /// `start_after:` from `orch schedule`, while it's ahead.
+    start_after: Option<String>,
+    created: String,
+    created_approx: bool,
+    modified: String,
+    /// The latest `## Status` entry.
+    status: Option<&'a str>,
+    unreadable: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    warnings: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<&'a str>,
+    /// With `--cost`, dollars its runs have cost so far.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost_usd: Option<f64>,
+}
+
+impl<'a> TaskStatus<'a> {
+    pub(super) fn new(dir: &Path, config: &Config, task: &'a task::Task) -> Self {
+        let session = task::session(config, &task.label, &task.content);
+        let running = tmux::has_session(&session);
+        TaskStatus {
+            name: &task.name,
+            label: &task.label,
+            running,
+            worker_missing: !running && task::claims_running(&task.content),
+            worker_exit: exits::current(dir, &task.name, &task.content, running),
+            session,
+            priority: frontmatter::get(&task.content, "priority"),
+            held: task::held(&task.content),
+            pinned: task::pinned(&task.content),
+            snoozed_until: task::snoozed_until(&task.content).map(task::timestamp),
+            start_after: task::start_after(&task.content).map(task::timestamp),
+            created: task::timestamp(task.created),
+            created_approx: task.created_approx,
+            modified: task::timestamp(task.modified),
+            status: task::section(&task.content, "## Status")
+                .last()
+                .map(|l| l.trim().trim_start_matches("- ")),
+            unreadable: task.unreadable.as_deref(),
+            warnings: &task.warnings,
+            content: None,
+            cost_usd: None,
+        }
+    }
+
+    fn with_cost(mut self, usd: Option<f64>) -> Self {
+        self.cost_usd = usd;
+        self
+    }
+
+    /// Includes the file's content.
+    pub(super) fn with_content(mut self, task: &'a task::Task) -> Self {
+        self.content = Some(&task.content);
+        self
+    }
+}
+
+/// A task's worker, as the text renderers word it.
+enum Worker {
+    /// `status:` says running, but its session isn't.
+    Missing,
+    /// Its session ended with the hook's report: how, and how long ago.
+    Exited(String, String),
+    Idle,
+    /// Session and how long its pane has been quiet.
+    Hung(String, String),
+    Running(String),
+}
+
+impl Worker {
+    /// One word for porcelain and csv.
+    fn state(&self) -> &'static str {
+        match self {
+            Worker::Missing => "missing",
+            Worker::Exited(..) => "exited",
+            Worker::Idle => "none",
+            Worker::Hung(..) => "hung",
+            Worker::Running(_) => "running",
+        }
+    }
+
+    fn glyph(&self) -> Glyph {
+        match self {
+            Worker::Missing => MISSING,
+            Worker::Exited(..) => EXITED,
+            Worker::Idle => IDLE,
+            Worker::Hung(..) => HUNG,
+            Worker::Running(_) => RUNNING,
+        }
+    }
+}
+
+/// A task as the text renderers show it.
+struct Entry {
+    project: Option<String>,
+    progress: task::Progress,
+    running: bool,
+    blocked: bool,
+    pinned: bool,
+    held: bool,
+    snoozed: bool,
+    name: String,
+    label: String,
+    session: String,
+    worker: Worker,
+    /// When its file last changed.
+    modified: DateTime<Local>,
+    /// Pinned, held, snoozed and scheduled marks after the label.
+    marks: String,
+    /// The same for `--compact`, plus budget and recording.
+    compact_marks: String,
+    /// The `[...]` tags after the worker.
+    tags: String,
+    /// Latest status entry, else the first line of the description.
+    status_line: String,
+    /// Summary lines or the first line of the body; none if unreadable.
+    description: Vec<String>,
+    /// With `--cost`, what its runs cost so far.
+    cost: Option<String>,
+}
+
+impl Entry {
+    fn fields(&self) -> [&str; 5] {
+        [
+            &self.label,
+            self.progress.label(),
+            self.worker.state(),
+            &self.session,
+            &self.status_line,
+        ]
+    }
+
+    fn compact_label(&self) -> String {
+        format!("{}{}", self.label, self.compact_marks)
+    }
+
+    fn compact_worker(&self, style: Style) -> String {
+        match &self.worker {
+            Worker::Missing => style.red("missing!"),
+            Worker::Exited(how, _) => how.clone(),
+            Worker::Idle => "-".into(),
+            Worker::Hung(session, since) => format!("{session} (hung {since})"),
+            Worker::Running(session) => session.clone(),
+        }
+    }
+
+    fn worker(&self, style: Style) -> String {
+        match &self.worker {
+            Worker::Missing => style.red(&format!(
+                "missing! status says running; `orch jump {} --spawn`",
+                self.name
+            )),
+            Worker::Exited(how, ago) => format!("{how}, {ago} ago"),
+            Worker::Idle => "none".into(),
+            Worker::Hung(session, since) => {
+                format!("running ({session}), possibly hung, no output {since}")
+            }
+            Worker::Running(session) => format!("running ({session})"),
+        }
+    }
+}
+
+/// A closed task under `## Archived` or `## Recently archived`.
+struct Archived {
+    name: String,
+    at: DateTime<Local>,
+    description: Vec<String>,
+}
+
+/// Everything `orch status` shows besides the tasks' JSON.
+struct Board {
+    since: Option<DateTime<Local>>,
+    missing_dir: bool,
+    group_by: Option<GroupBy>,
+    show_done: bool,
+    entries: Vec<Entry>,
+    /// Name, task, windows, created, active; none with `--changed-since`.
+    workers: Vec<[String; 5]>,
+    /// `--include-archive`: every archived task, newest first.
+    archive: Option<Vec<Archived>>,
+    /// With `--changed-since`, tasks archived since then.
+    recently_archived: Vec<Archived>,
+}
+
+/// `orch status`, in any format. As JSON it's the task list, one
+/// [`TaskStatus`] each; JSON lines are streamed by [`show`] instead.
+#[derive(Serialize)]
+#[serde(transparent)]
+struct StatusReport<'a> {
+    tasks: Vec<TaskStatus<'a>>,
+    #[serde(skip)]
+    board: Board,
+}
+
+impl Report for StatusReport<'_> {
+    const FORMATS: &'static [Format] = &[Format::Compact, Format::Porcelain, Format::Csv];
+
+    fn render(&self, format: Format, style: Style) -> String {
+        let mut text = String::new();
+        match format {
+            Format::Porcelain => {
+                for entry in &self.board.entries {
+                    text.push_str(&render::porcelain_row(&entry.fields()));
+                }
+            }
+            Format::Csv => {
+                text.push_str(&render::csv_row(&[
+                    "label", "progress", "worker", "session", "status",
+                ]));
+                for entry in &self.board.entries {
+                    text.push_str(&render::csv_row(&entry.fields()));
+                }
+            }
+            _ => self.board.text(&mut text, format == Format::Compact, style),
+        }
+        text
+    }
+}
+
+/// `orch show`: one task in full. As JSON it's its [`TaskStatus`] with the
+/// file's content, as `orch serve` has it.
+#[derive(Serialize)]
+#[serde(transparent)]
+struct TaskReport<'a> {
+    task: TaskStatus<'a>,
+    #[serde(skip)]
+    entry: Entry,
+    #[serde(skip)]
+    path: String,
+    /// The newest `## Status` entries, oldest first.
+    #[serde(skip)]
+    log: Vec<String>,
+    /// Entries older than those in `log`.
+    #[serde(skip)]
+    earlier: usize,
+}
+
+/// Status entries `orch show` lists.
+const SHOW_LOG: usize = 10;
+
+impl Report for TaskReport<'_> {
+    const FORMATS: &'static [Format] = &[Format::Porcelain];
+
+    fn render(&self, format: Format, style: Style) -> String {
+        let entry = &self.entry;
+        if format == Format::Porcelain {
+            return render::porcelain_row(&entry.fields());
+        }
+        // Long lines are cut to the terminal, indent included
+        let fit = |line: &str, indent: usize| match style.width {
+            Some(width) => clip(line, width.saturating_sub(indent).max(12)),
+            None => line.to_string(),
+        };
+        let mut text = String::new();
+        let _ = writeln!(text, "{}{}", entry.label, entry.marks);
+        let _ = writeln!(text, "  file:     {}", fit(&self.path, 12));
+        let _ = writeln!(text, "  worker:   {}", entry.worker(style));
+        let _ = writeln!(text, "  progress: {}", entry.progress.label());
+        if let Some(cost) = &entry.cost {
+            let _ = writeln!(text, "  cost:     {cost}");
+        }
+        let tags = entry.tags.trim_start();
+        if !tags.is_empty() {
+            let _ = writeln!(text, "  {}", fit(tags, 2));
+        }
+        if !entry.description.is_empty() {
+            text.push('\n');
+        }
+        for line in &entry.description {
+            let _ = writeln!(text, "  {}", fit(line, 2));
+        }
+        text.push_str("\n## Status\n\n");
+        if self.log.is_empty() {
+            text.push_str("  (no entries)\n");
+        }
+        if self.earlier > 0 {
+            let _ = writeln!(text, "  ({} earlier)", self.earlier);
+        }
+        for line in &self.log {
+            let _ = writeln!(text, "  {}", fit(line, 2));
+        }
+        text
+    }
+}
+
+/// "3 tasks, 1 blocked, 2 running"
+fn tally<'a>(entries: impl Iterator<Item = &'a Entry>) -> String {
+    let (mut tasks, mut blocked, mut running) = (0, 0, 0);
+    for e in entries {
+        tasks += 1;
+        blocked += usize::from(e.blocked);
+        running += usize::from(e.running);
+    }
+    format!(
+        "{}, {blocked} blocked, {running} running",
+        fmt::count(tasks, "task")
+    )
+}
+
+/// `line` cut to `width` characters, ending in `…` if it was longer.
+fn clip(line: &str, width: usize) -> String {
+    if line.chars().count() <= width {
+        return line.to_string();
+    }
+    let mut clipped: String = line.chars().take(width.saturating_sub(1)).collect();
+    clipped.push('…');
+    clipped
+}
+
+impl Board {
+    /// The human or `--compact` rendering.
+    fn text(&self, text: &mut String, compact: bool, style: Style) {
+        let (pinned, entries): (Vec<&Entry>, Vec<&Entry>) =
+            self.entries.iter().partition(|e| e.pinned);
+        if !pinned.is_empty() {
+            text.push_str("## Pinned\n\n");
+            self.entries_text(text, &pinned, compact, style);
+        }
+        match self.since {
+            Some(since) => {
+                let _ = writeln!(text, "## Tasks changed since {}\n", fmt::time(since));
+            }
+            None => text.push_str("## Tasks\n\n"),
+        }
+        if self.missing_dir {
+            text.push_str("  ~/tasks/ not found\n");
+            return;
+        }
+
+        let found = !self.entries.is_empty();
+        if found && entries.is_empty() {
+            text.push_str("  (all pinned)\n\n");
+        }
+        match self.group_by {
+            None | Some(GroupBy::Ungrouped) => {
+                self.entries_text(text, &entries, compact, style);
+            }
+            Some(GroupBy::Project) => {
+                // Ungrouped first; stable, so tasks keep their order in a project
+                let mut sorted = entries.clone();
+                sorted.sort_by(|a, b| a.project.cmp(&b.project));
+                for group in sorted.chunk_by(|a, b| a.project == b.project) {
+                    let project = group[0].project.as_deref().unwrap_or("(ungrouped)");
+                    let _ = writeln!(text, "### {project}  ({})\n", tally(group.iter().copied()));
+                    self.entries_text(text, group, compact, style);
+                }
+            }
+            Some(GroupBy::Status) => {
+                // Board order; stable, so tasks keep their order in a column
+                let mut sorted = entries.clone();
+                sorted.sort_by(|a, b| a.progress.cmp(&b.progress));
+                let column = |e: &Entry| mem::discriminant(&e.progress);
+                for group in sorted.chunk_by(|a, b| column(a) == column(b)) {
+                    let progress = &group[0].progress;
+                    let mut header = format!(
+                        "### {}  ({})",
+                        progress.label(),
+                        tally(group.iter().copied())
+                    );
+                    if let task::Progress::Other(_) = progress {
+                        let mut raw: Vec<_> = group
+                            .iter()
+                            .filter_map(|e| match &e.progress {
+                                task::Progress::Other(s) => Some(s.as_str()),
+                                _ => None,
+                            })
+                            .collect();
+                        raw.dedup();
+                        header.push_str(&format!("  [status: {}]", raw.join(", ")));
+                    }
+                    let _ = writeln!(text, "{header}\n");
+                    if *progress == task::Progress::Done && !self.show_done {
+                        text.push_str("  (--show-done to list them)\n\n");
+                        continue;
+                    }
+                    self.entries_text(text, group, compact, style);
+                }
+            }
+        }
+        if found && matches!(self.group_by, Some(GroupBy::Project | GroupBy::Status)) {
+            let _ = writeln!(text, "Total: {}\n", tally(self.entries.iter()));
+        }
+        if !found {
+            text.push_str("  (no tasks)\n");
+        }
+        if compact && style.narrow() {
+            self.legend_text(text, style);
+        } else {
+            self.workers_text(text);
+        }
+        self.archive_text(text, found);
+    }
+
+    /// The narrow `--compact` lines: `● recon [blocked] ⏱2h`. Names are
+    /// shortened to fit, keeping what sets apart ones with a shared start.
+    fn narrow_text(&self, text: &mut String, entries: &[&Entry], style: Style) {
+        let width = style.width.unwrap_or(render::NARROW);
+        let labels: Vec<&str> = self.entries.iter().map(|e| e.label.as_str()).collect();
+        for entry in entries {
+            let mut marks = String::new();
+            for (on, glyph) in [
+                (entry.pinned, PINNED),
+                (entry.held, HELD),
+                (entry.snoozed, SNOOZED),
+            ] {
+                if on {
+                    marks.push(' ');
+                    marks.push_str(style.glyph(glyph.0, glyph.1));
+                }
+            }
+            match &entry.progress {
+                task::Progress::New | task::Progress::InProgress => {}
+                task::Progress::Other(raw) => marks.push_str(&format!(" [{raw}]")),
+                progress => marks.push_str(&format!(" [{}]", progress.label())),
+            }
+            marks.push_str(&format!(
+                " {}{}",
+                style.glyph(AGE.0, AGE.1),
+                fmt::age(entry.modified)
+            ));
+            let glyph = entry.worker.glyph();
+            let room = width.saturating_sub(render::width(&marks) + 2).max(8);
+            let _ = writeln!(
+                text,
+                "{} {}{marks}",
+                style.glyph(glyph.0, glyph.1),
+                render::shorten(&entry.label, &labels, room)
+            );
+        }
+        text.push('\n');
+    }
+
+    /// What the narrow `--compact` glyphs mean, the ones shown anyway.
+    fn legend_text(&self, text: &mut String, style: Style) {
+        let mut shown: Vec<Glyph> = Vec::new();
+        for entry in &self.entries {
+            let glyph = entry.worker.glyph();
+            let marks = [
+                (entry.pinned, PINNED),
+                (entry.held, HELD),
+                (entry.snoozed, SNOOZED),
+            ];
+            let used = std::iter::once(glyph)
+                .chain(marks.into_iter().filter_map(|(on, g)| on.then_some(g)));
+            for glyph in used {
+                if !shown.contains(&glyph) {
+                    shown.push(glyph);
+                }
+            }
+        }
+        if !shown.is_empty() {
+            shown.push(AGE);
+            let _ = writeln!(text, "{}", style.legend(&shown));
+        }
+    }
+
+    /// `entries`, with `--compact` columns as wide as they need to be for
+    /// every task, so groups line up.
+    fn entries_text(&self, text: &mut String, entries: &[&Entry], compact: bool, style: Style) {
+        if compact && style.narrow() {
+            self.narrow_text(text, entries, style);
+            return;
+        }
+        if !compact {
+            for entry in entries {
+                let _ = writeln!(
+                    text,
+                    "  {}{}  [worker: {}]{}",
+                    entry.label,
+                    entry.marks,
+                    entry.worker(style),
+                    entry.tags
+                );
+                for line in &entry.description {
+                    let _ = writeln!(text, "    {line}");
+                }
+                text.push('\n');
+            }
+            return;
+        }
+        let all = &self.entries;
+        let name_w = all
+            .iter()
+            .map(|e| render::width(&e.compact_label()))
+            .max()
+            .unwrap_or(0);
+        let worker_w = all
+            .iter()
+            .map(|e| {
+                e.compact_worker(Style {
+                    color: false,
+                    ..style
+                })
+                .chars()
+                .count()
+            })
+            .max()
+            .unwrap_or(0);
+        let cost_w = all
+            .iter()
+            .filter_map(|e| e.cost.as_ref())
+            .map(|c| c.len() + 2)
+            .max()
+            .unwrap_or(0);
+        // Narrower than usual if the terminal can't fit the whole line
+        let clip_at = style.width.map_or(COMPACT_STATUS_WIDTH, |w| {
+            w.saturating_sub(name_w + cost_w + worker_w + 6)
+                .clamp(12, COMPACT_STATUS_WIDTH)
+        });
+        let status_w = all
+            .iter()
+            .map(|e| clip(&e.status_line, clip_at).chars().count())
+            .max()
+            .unwrap_or(0);
+        for entry in entries {
+            let cost = entry
+                .cost
+                .as_ref()
+                .map(|c| format!("{c:>w$}  ", w = cost_w - 2))
+                .unwrap_or_default();
+            let _ = writeln!(
+                text,
+                "  {}  {cost}{:<status_w$}  {}",
+                render::pad(&entry.compact_label(), name_w),
+                clip(&entry.status_line, clip_at),
+                entry.compact_worker(style)
+            );
+        }
+        text.push('\n');
+    }
+
+    /// The Workers section.
+    fn workers_text(&self, text: &mut String) {
+        let rows = &self.workers;
+        if rows.is_empty() {
+            return;
+        }
+        text.push_str("## Workers\n\n");
+        let width = |i: usize| rows.iter().map(|r| r[i].chars().count()).max().unwrap_or(0);
+        let (w0, w1, w2, w3) = (width(0), width(1), width(2), width(3));
+        for [name, task, windows, created, active] in rows {
+            let _ = writeln!(
+                text,
+                "  {name:<w0$}  {task:<w1$}  {windows:<w2$}  {created:<w3$}  {active}"
+            );
+        }
+        text.push('\n');
+    }
+
+    /// `## Archived` for `--include-archive`, else `## Recently archived`
+    /// with `--changed-since`.
+    fn archive_text(&self, text: &mut String, found: bool) {
+        if let Some(archive) = &self.archive {
+            if !found {
+                text.push('\n');
+            }
+            text.push_str("## Archived\n\n");
+            if archive.is_empty() {
+                text.push_str("  (none)\n");
+            }
+            for a in archive {
+                let _ = writeln!(
+                    text,
+                    "  {} (archived)  [archived {}, {} ago]",
+                    a.name,
+                    fmt::time(a.at),
+                    fmt::age(a.at)
+                );
+                for line in &a.description {
+                    let _ = writeln!(text, "    {line}");
+                }
+                text.push('\n');
+            }
+        } else if !self.recently_archived.is_empty() {
+            if !found {
+                text.push('\n');
+            }
+            text.push_str("## Recently archived\n\n");
+            for a in &self.recently_archived {
+                let _ = writeln!(text, "  {}  [archived {} ago]", a.name, fmt::age(a.at));
+            }
+        }
+    }
+}
+
+/// Every session named like a worker, with its task or "(no task file)",
+/// as name, task, windows, created and active columns.
+fn worker_rows(config: &Config, tasks: &HashMap<String, String>) -> Vec<[String; 5]> {
+    let prefix = session::prefix(config);
+    tmux::list()
+        .into_iter()
+        .filter(|s| s.name != session::CONSOLE)
+        .filter(|s| s.name.starts_with(prefix) || tasks.contains_key(&s.name))
+        .map(|s| {
+            let task = tasks
+                .get(&s.name)
+                .cloned()
+                .unwrap_or_else(|| "(no task file)".into());
+            let windows = match s.windows {
+                1 => "1 window".to_string(),
+                n => format!("{n} windows"),
+            };
+            let age = |t: Option<DateTime<Local>>| t.map_or("?".into(), fmt::age);
+            let mut active = format!("active {} ago", age(s.activity));
+            if s.attached {
+                active.push_str(", attached");
+            }
+            [
+                s.name,
+                task,
+                windows,
+                format!("created {} ago", age(s.created)),
+                active,
+            ]
+        })
+        .collect()
+}
+
+/// Points each task's `session:` line at a live session: the recorded one if
+/// it's running, else the default name if that is, else no line at all.
+/// Prints each correction; returns whether every write worked.
+fn refresh_sessions(dir: &Path, config: &Config) -> bool {
+    let live = tmux::sessions();
+    let mut ok = true;
+    let mut fixed = 0;
+    outln!("## Sessions\n");
+    for path in task::files(dir, config) {
+        let Ok(content) = task::read_checked(&path, config.max_task_bytes) else {
+            continue;
+        };
+        let label = task::label(dir, &path);
+        let recorded = task::session(config, &label, &content);
+        let default = session::name(config, &label);
+        if live.contains(&recorded) || recorded == default {
+            continue;
+        }
+        let (adopt, note) = if live.contains(&default) {
+            (Some(default.as_str()), format!("adopted {default}"))
+        } else {
+            (None, "cleared".to_string())
+        };
+        if task::set_session(&content, adopt).is_none() {
+            continue;
+        }
+        let hash = config::fnv(content.as_bytes());
+        match edits::edit_planned(dir, config, &path, hash, |c| task::set_session(c, adopt)) {
+            Ok(edits::Outcome::Dropped) => {
+                outln!("  {label}: changed while orch read it, left alone");
+            }
+            Ok(_) => {
+                fixed += 1;
+                outln!("  {label}: {recorded} isn't running, {note}");
+            }
+            Err(e) => {
+                ok = false;
+                eprintln!("{label}: failed to update {}: {e}", path.display());
+            }
+        }
+    }
+    if fixed == 0 {
+        outln!("  (all recorded sessions are running)");
+    }
+    outln!();
+    ok
+}
+
+pub fn cmd_status(
+    dir: &Path,
+    config: &Config,
+    args: &StatusArgs,
+    format: Option<Format>,
+) -> Result<(), OrchError> {
+    let format = if args.json {
+        Format::Json
+    } else if args.json_lines {
+        Format::Jsonl
+    } else if args.compact {
+        Format::Compact
+    } else {
+        format.unwrap_or_else(render::default_format)
+    };
+    if !args.watch {
+        return show(dir, config, args, format);
+    }
+    let interval = duration::parse(&args.interval).map_err(OrchError::Parse)?;
+    // Starts from the current inbox, so only new arrivals are announced
+    let mut notifier = args.notify.then(|| InboxNotifier::new(config, dir));
+    loop {
+        if output::is_terminal() {
+            out!("\x1b[H\x1b[2J");
+        }
+        show(dir, config, args, format)?;
+        outln!("Every {}, Ctrl-C to stop.", args.interval);
+        if let Some(notifier) = &mut notifier {
+            notifier.check(config, dir);
+            notifier.flush();
+        }
+        thread::sleep(interval);
+    }
+}
+
+fn show(dir: &Path, config: &Config, args: &StatusArgs, format: Format) -> Result<(), OrchError> {
+    if args.sessions {
+        return check_sessions(dir, config);
+    }
+    let refreshed = !args.refresh_sessions || !dir.is_dir() || refresh_sessions(dir, config);
+    let since = args.changed_since.as_deref().map(parse_when).transpose()?;
+    let archived_since = args.since.as_deref().map(parse_when).transpose()?;
+
+    let mut list = if dir.is_dir() {
+        task::list(dir, config)
+    } else {
+        Vec::new()
+    };
+    // Pinned tasks first in every format; stable, so the rest keep their order
+    list.sort_by_key(|t| !task::pinned(&t.content));
+    let group_by = args.group_by.or(config.status_group_by);
+    let blocked: HashSet<String> = match group_by {
+        Some(GroupBy::Project | GroupBy::Status) => inbox::items(dir, config)
+            .into_iter()
+            .map(|item| item.label)
+            .collect(),
+        _ => HashSet::new(),
+    };
+    let costs = args.cost.then(|| costs::load(dir));
+    let mut tasks = Vec::new();
+    let mut entries = Vec::new();
+    let mut workers = HashMap::new();
+    for task in &list {
+        let content = &task.content;
+        let session = task::session(config, &task.label, content);
+        workers.insert(session.clone(), task.label.clone());
+
+        let changed = since.map(|since| changes(task, &session, since));
+        if changed.as_ref().is_some_and(Vec::is_empty) {
+            continue;
+        }
+        let cost = costs
+            .as_ref()
+            .map(|c| c.get(name(task)).copied().unwrap_or(0.0));
+        let status = TaskStatus::new(dir, config, task).with_cost(cost);
+        // JSON lines go out as each task is read, for consumers reading along
+        if format == Format::Jsonl {
+            render::line(&status)?;
+            continue;
+        }
+        tasks.push(status);
+        let mut entry = entry(dir, config, task, session, changed, &blocked);
+        if let Some(usd) = cost {
+            entry
+                .tags
+                .push_str(&format!(" [cost so far: {}]", costs::format(usd)));
+            entry.cost = Some(costs::format(usd));
+        }
+        entries.push(entry);
+    }
+
+    if format == Format::Jsonl {
+        return if refreshed {
+            Ok(())
+        } else {
+            Err(OrchError::Reported(1))
+        };
+    }
+
+    let archived = |after: Option<DateTime<Local>>, full: bool| {
+        let mut archived: Vec<_> = task::archived(dir)
+            .into_iter()
+            .filter(|a| after.is_none_or(|t| a.archived >= t))
+            .map(|a| Archived {
+                description: if full {
+                    match task::read_checked(&a.path, config.max_task_bytes) {
+                        Ok(content) => description(&content)
+                            .into_iter()
+                            .map(str::to_string)
+                            .collect(),
+                        Err(reason) => vec![format!("[unreadable: {reason}]")],
+                    }
+                } else {
+                    Vec::new()
+                },
+                name: a.name,
+                at: a.archived,
+            })
+            .collect();
+        archived.sort_by_key(|a| std::cmp::Reverse(a.at));
+        archived
+    };
+    let board = Board {
+        since,
+        missing_dir: !dir.is_dir(),
+        group_by,
+        show_done: args.show_done,
+        entries,
+        workers: if since.is_none() {
+            worker_rows(config, &workers)
+        } else {
+            Vec::new()
+        },
+        archive: args.include_archive.then(|| archived(archived_since, true)),
+        recently_archived: match since {
+            Some(since) if !args.include_archive => archived(Some(since), false),
+            _ => Vec::new(),
+        },
+    };
+    render::emit("status", &StatusReport { tasks, board }, format)?;
+    if !refreshed {
+        return Err(OrchError::Reported(1));
+    }
+    Ok(())
+}
+
+/// One task in full: what status shows for it, plus its file and the tail
+/// of its status log.
+pub fn cmd_show(
+    dir: &Path,
+    config: &Config,
+    name: &str,
+    json: bool,
+    format: Option<Format>,
+) -> Result<(), OrchError> {
+    let format = if json {
+        Format::Json
+    } else {
+        format.unwrap_or(Format::Human)
+    };
+    let (_, path) = task::resolve(dir, name, config)?;
+    let task = task::load(dir, &path, config);
+    let session = task::session(config, &task.label, &task.content);
+    let cost = costs::load(dir).get(&task.name).copied();
+    let mut entry = entry(dir, config, &task, session, None, &HashSet::new());
+    entry.cost = cost.map(costs::format);
+    let log: Vec<String> = task::section(&task.content, "## Status")
+        .into_iter()
+        .map(|l| l.trim().trim_start_matches("- ").to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+    let earlier = log.len().saturating_sub(SHOW_LOG);
+    let report = TaskReport {
+        task: TaskStatus::new(dir, config, &task)
+            .with_cost(cost)
+            .with_content(&task),
+        entry,
+        path: path.display().to_string(),
+        log: log[earlier..].to_vec(),
+        earlier,
+    };
+    render::emit("show", &report, format)
+}
+
+/// How the text renderers show `task`; `changed` is what moved since
+/// `--changed-since`.
+fn entry(
+    dir: &Path,
+    config: &Config,
+    task: &task::Task,
+    session: String,
+    changed: Option<Vec<&str>>,
+    blocked: &HashSet<String>,
+) -> Entry {
+    let content = &task.content;
+    let live = tmux::has_session(&session);
+    let worker = match exits::current(dir, name(task), content, live) {
+        Some(exit) => Worker::Exited(
+            exit.describe(),
+            task::parse_timestamp(&exit.at).map_or_else(|| "?".into(), fmt::age),
+        ),
+        None if !live && task::claims_running(content) => Worker::Missing,
+        None if !live => Worker::Idle,
+        None => match heartbeat::hung_since(dir, config, &session) {
+            Some(since) => Worker::Hung(session.clone(), fmt::age(since)),
+            None => Worker::Running(session.clone()),
+        },
+    };
+    let snoozed = task::snoozed_until(content).is_some();
+    let held = task::held(content);
+    let pinned = task::pinned(content);
+    let start_after = task::start_after(content);
+
+    let mut compact_marks = String::new();
+    if pinned {
+        compact_marks.push_str(&format!(" {}", render::PIN));
+    }
+    if snoozed {
+        compact_marks.push_str(" (snoozed)");
+    }
+    if held {
+        compact_marks.push_str(" ⏸");
+    }
+    if let Some(at) = start_after {
+        compact_marks.push_str(&format!(" (starts in {})", fmt::until(at)));
+    }
+    if budget::over(dir, name(task), content).is_some() {
+        compact_marks.push_str(" (over budget)");
+    }
+    let recording = live && transcript::recording(&session);
+    if recording {
+        compact_marks.push_str(" ●");
+    }
+
+    let mut marks = String::new();
+    if pinned {
+        marks.push_str(&format!(" {}", render::PIN));
+    }
+    if held {
+        marks.push_str(" ⏸");
+    }
+    if snoozed {
+        marks.push_str(" (snoozed)");
+    }
+    if let Some(at) = start_after {
+        marks.push_str(&format!(" (scheduled: starts in {})", fmt::until(at)));
+    }
+
+    let mut tags = String::new();
+    if recording {
+        tags.push_str(" [● recording]");
+    }
+    if let Some(size) = transcript::oversized(dir, config, name(task)) {
+        tags.push_str(&format!(" [transcript: {}]", fmt::bytes(size)));
+    }
+    if let Some(reason) = &task.unreadable {
+        tags.push_str(&format!(" [unreadable: {reason}]"));
+    }
+    for warning in &task.warnings {
+        tags.push_str(&format!(" [warning: {warning}]"));
+    }
+    if let Some(p) = frontmatter::get(content, "priority") {
+        tags.push_str(&format!(" [priority: {p}]"));
+    }
+    if let Some(limit) = budget::limit(content) {
+        let used = budget::used(dir, name(task));
+        let over = if used >= limit {
+            "over budget"
+        } else {
+            "budget"
+        };
+        tags.push_str(&format!(" [{over}: {used}/{limit} scans]"));
+    }
+    if let Some(changed) = changed {
+        tags.push_str(&format!(" [changed: {}]", changed.join(", ")));
+    }
+
+    Entry {
+        project: task.label.rsplit_once('/').map(|(p, _)| p.to_string()),
+        progress: task::progress(content),
+        running: live,
+        blocked: blocked.contains(&task.label),
+        pinned,
+        held,
+        snoozed,
+        name: name(task).to_string(),
+        label: task.label.clone(),
+        session,
+        worker,
+        modified: task.modified,
+        marks,
+        compact_marks,
+        tags,
+        status_line: match &task.unreadable {
+            Some(reason) => format!("unreadable: {reason}"),
+            None => status_line(content),
+        },
+        description: if task.unreadable.is_none() {
+            description(content)
+                .into_iter()
+                .map(str::to_string)
+                .collect()
+        } else {
+            Vec::new()
+        },
+        cost: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    /// `lines`, each ending in a newline: a rendering as it would print.
+    fn lines(lines: &[&str]) -> String {
+        lines.iter().map(|line| format!("{line}\n")).collect()
+    }
+
+    fn style(width: usize, ascii: bool) -> Style {
+        Style {
+            color: false,
+            width: Some(width),
+            ascii,
+        }
+    }
+
+    fn fixture(label: &str, worker: Worker, progress: task::Progress, status: &str) -> Entry {
+        let (project, name) = match label.rsplit_once('/') {
+            Some((project, name)) => (Some(project.to_string()), name),
+            None => (None, label),
+        };
+        Entry {
+            project,
+            running: matches!(worker, Worker::Running(_) | Worker::Hung(..)),
+            progress,
+            blocked: false,
+            pinned: false,
+            held: false,
+            snoozed: false,
+            name: name.to_string(),
+            label: label.to_string(),
+            session: format!("task-{name}"),
+            worker,
+            modified: Local::now() - Duration::hours(2),
+            marks: String::new(),
+            compact_marks: String::new(),
+            tags: String::new(),
+            status_line: status.to_string(),
+            description: Vec::new(),
+            cost: None,
+        }
+    }
+
+    fn entries() -> Vec<Entry> {
+        let mut recon = fixture(
+            "recon",
+            Worker::Running("task-recon".into()),
+            task::Progress::InProgress,
+            "Waiting on API access from the platform team, pinged them twice already",
+        );
+        recon.pinned = true;
+        recon.marks = format!(" {}", render::PIN);
+        recon.compact_marks = recon.marks.clone();
+        recon.tags = " [priority: high] [created 3h ago]".into();
+        recon.description = vec!["Look at the API and write it up.".into()];
+        let mut auth = fixture(
+            "backend/auth",
+            Worker::Idle,
+            task::Progress::Blocked,
+            "needs a decision on token lifetime",
+        );
+        auth.held = true;
+        auth.blocked = true;
+        auth.marks = " ⏸".into();
+        auth.compact_marks = " ⏸".into();
+        auth.tags = " [created 1d ago]".into();
+        let mut tests = fixture(
+            "backend/auth-tests",
+            Worker::Missing,
+            task::Progress::New,
+            "",
+        );
+        tests.tags = " [created 5m ago]".into();
+        tests.description = vec!["Cover the refresh path.".into()];
+        vec![recon, auth, tests]
+    }
+
+    fn board(entries: Vec<Entry>) -> StatusReport<'static> {
+        StatusReport {
+            tasks: Vec::new(),
+            board: Board {
+                since: None,
+                missing_dir: false,
+                group_by: None,
+                show_done: false,
+                entries,
+                workers: vec![[
+                    "task-recon".into(),
+                    "recon".into(),
+                    "1 window".into(),
+                    "created 3h ago".into(),
+                    "active 2m ago".into(),
+                ]],
+                archive: None,
+                recently_archived: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn human_snapshot() {
+        let text = board(entries()).render(Format::Human, style(80, false));
+        assert_eq!(
+            text,
+            lines(&[
+                "## Pinned",
+                "",
+                "  recon 📌  [worker: running (task-recon)] [priority: high] [created 3h ago]",
+                "    Look at the API and write it up.",
+                "",
+                "## Tasks",
+                "",
+                "  backend/auth ⏸  [worker: none] [created 1d ago]",
+                "",
+                "  backend/auth-tests  [worker: missing! status says running; `orch jump auth-tests --spawn`] [created 5m ago]",
+                "    Cover the refresh path.",
+                "",
+                "## Workers",
+                "",
+                "  task-recon  recon  1 window  created 3h ago  active 2m ago",
+                "",
+            ])
+        );
+    }
+
+    #[test]
+    fn compact_snapshot_at_80_columns() {
+        let text = board(entries()).render(Format::Compact, style(80, false));
+        assert_eq!(
+            text,
+            lines(&[
+                "## Pinned",
+                "",
+                "  recon 📌            Waiting on API access from the platform team,…  task-recon",
+                "",
+                "## Tasks",
+                "",
+                "  backend/auth ⏸      needs a decision on token lifetime              -",
+                "  backend/auth-tests                                                  missing!",
+                "",
+                "## Workers",
+                "",
+                "  task-recon  recon  1 window  created 3h ago  active 2m ago",
+                "",
+            ])
+        );
+    }
+
+    #[test]
+    fn compact_snapshot_at_40_columns() {
+        let text = board(entries()).render(Format::Compact, style(40, true));
+        assert_eq!(
+            text,
+            lines(&[
+                "## Pinned",
+                "",
+                "* recon ^ @2h",
+                "",
+                "## Tasks",
+                "",
+                "- backend/auth = [blocked] @2h",
+                "! backend/auth-tests @2h",
+                "",
+                "* running  ^ pinned  - no worker  = held",
+                "! missing  @ since last change",
+            ])
+        );
+    }
+
+    #[test]
+    fn grouped_snapshot() {
+        let mut report = board(entries());
+        report.board.group_by = Some(GroupBy::Project);
+        let text = report.render(Format::Human, style(80, false));
+        assert_eq!(
+            text,
+            lines(&[
+                "## Pinned",
+                "",
+                "  recon 📌  [worker: running (task-recon)] [priority: high] [created 3h ago]",
+                "    Look at the API and write it up.",
+                "",
+                "## Tasks",
+                "",
+                "### backend  (2 tasks, 1 blocked, 0 running)",
+                "",
+                "  backend/auth ⏸  [worker: none] [created 1d ago]",
+                "",
+                "  backend/auth-tests  [worker: missing! status says running; `orch jump auth-tests --spawn`] [created 5m ago]",
+                "    Cover the refresh path.",
+                "",
+                "Total: 3 tasks, 1 blocked, 1 running",
+                "",
+                "## Workers",
+                "",
+                "  task-recon  recon  1 window  created 3h ago  active 2m ago",
+                "",
+            ])
+        );
+    }
+
+    fn show_report(task: &task::Task) -> TaskReport<'_> {
+        let mut entry = entries().remove(0);
+        entry.cost = Some("$0.42".into());
+        TaskReport {
+            task: TaskStatus {
+                name: &task.name,
+                label: &task.label,
+                session: "task-recon".into(),
+                running: true,
+                priority: Some("high".into()),
+                held: false,
+                pinned: true,
+                worker_missing: false,
+                worker_exit: None,
+                snoozed_until: None,
+                start_after: None,
+                created: task::timestamp(task.created),
+                created_approx: false,
+                modified: task::timestamp(task.modified),
+                status: None,
+                unreadable: None,
+                warnings: &task.warnings,
+                content: Some(&task.content),
+                cost_usd: Some(0.42),
+            },
+            entry,
+            path: "/home/me/tasks/recon.md".into(),
+            log: vec![
+                "2026-10-14 09:12 started on the API survey".into(),
+                "2026-10-15 10:40 Waiting on API access from the platform team, pinged them twice already".into(),
+            ],
+            earlier: 3,
+        }
+    }
+
+    fn task() -> task::Task {
+        task::Task {
+            name: "recon".into(),
+            label: "recon".into(),
+            content: "# Recon\n".into(),
+            created: Local::now(),
+            created_approx: false,
+            modified: Local::now(),
+            unreadable: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn show_snapshot_at_80_columns() {
+        let task = task();
+        let text = show_report(&task).render(Format::Human, style(80, false));
+        assert_eq!(
+            text,
+            lines(&[
+                "recon 📌",
+                "  file:     /home/me/tasks/recon.md",
+                "  worker:   running (task-recon)",
+                "  progress: in progress",
+                "  cost:     $0.42",
+                "  [priority: high] [created 3h ago]",
+                "",
+                "  Look at the API and write it up.",
+                "",
+                "## Status",
+                "",
+                "  (3 earlier)",
+                "  2026-10-14 09:12 started on the API survey",
+                "  2026-10-15 10:40 Waiting on API access from the platform team, pinged them tw…",
+            ])
+        );
+    }
+
+    #[test]
+    fn show_snapshot_at_40_columns() {
+        let task = task();
+        let text = show_report(&task).render(Format::Human, style(40, false));
+        assert_eq!(
+            text,
+            lines(&[
+                "recon 📌",
+                "  file:     /home/me/tasks/recon.md",
+                "  worker:   running (task-recon)",
+                "  progress: in progress",
+                "  cost:     $0.42",
+                "  [priority: high] [created 3h ago]",
+                "",
+                "  Look at the API and write it up.",
+                "",
+                "## Status",
+                "",
+                "  (3 earlier)",
+                "  2026-10-14 09:12 started on the API s…",
+                "  2026-10-15 10:40 Waiting on API acces…",
+            ])
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! `orch summary`: a popup-sized overview. Kept fast enough for a tmux
+//! keybinding: no claude, no pane captures, one tmux call.
+
+use std::path::Path;
+
+use super::cmd_jump;
+use crate::{
+    cli::JumpArgs, config::Config, error::OrchError, fmt, frontmatter, health, inbox, outln,
+    render, task, tmux,
+};
+
+struct Entry {
+    name: String,
+    label: String,
+    note: String,
+    /// `!` inbox, `*` running, else blank.
+    marker: &'static str,
+    pinned: bool,
+}
+
+/// Pinned tasks, then inbox tasks, running workers and idle tasks, each by
+/// priority. Pinned ones are in the same order among themselves.
+fn entries(dir: &Path, config: &Config) -> (Vec<Entry>, usize, usize) {
+    let live = tmux::sessions();
+    let patterns = inbox::patterns(config);
+    let mut groups: [Vec<(i64, Entry)>; 3] = Default::default();
+    for task in task::list(dir, config) {
+        let priority = frontmatter::get(&task.content, "priority")
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(0);
+        let session = task::session(config, &task.label, &task.content);
+        let snoozed = task::snoozed_until(&task.content).is_some();
+        let waiting = (!snoozed)
+            .then(|| inbox::waiting(&patterns, &task.content, None))
+            .flatten();
+        let (group, note) = match waiting {
+            Some(reason) => (0, reason),
+            None if live.contains(&session) => (1, session),
+            None => (2, String::new()),
+        };
+        let entry = Entry {
+            marker: ["!", "*", " "][group],
+            pinned: task::pinned(&task.content),
+            name: task.name,
+            label: task.label,
+            note,
+        };
+        groups[group].push((priority, entry));
+    }
+    let counts = (groups[0].len(), groups[1].len());
+    let mut entries: Vec<_> = groups
+        .into_iter()
+        .flat_map(|mut group| {
+            group.sort_by(|(pa, a), (pb, b)| pb.cmp(pa).then_with(|| a.label.cmp(&b.label)));
+            group.into_iter().map(|(_, e)| e)
+        })
+        .collect();
+    entries.sort_by_key(|e| !e.pinned);
+    (entries, counts.0, counts.1)
+}
+
+fn clip(line: &str, width: usize) -> String {
+    if line.chars().count() <= width {
+        return line.to_string();
+    }
+    let mut clipped: String = line.chars().take(width.saturating_sub(1)).collect();
+    clipped.push('…');
+    clipped
+}
+
+pub fn cmd_summary(
+    dir: &Path,
+    config: &Config,
+    pick: Option<usize>,
+    tmux_bind: bool,
+) -> Result<(), OrchError> {
+    let limits = &config.summary;
+    if tmux_bind {
+        outln!(
+            "bind-key O display-popup -E -w {} -h {} \"orch summary; printf 'jump to: '; read n; [ -n \\\"\\$n\\\" ] && orch summary --pick \\\"\\$n\\\"\"",
+            limits.width + 4,
+            limits.max_lines + 4
+        );
+        return Ok(());
+    }
+
+    let (entries, inbox_count, running) = entries(dir, config);
+    if let Some(n) = pick {
+        let entry = n
+            .checked_sub(1)
+            .and_then(|i| entries.get(i))
+            .ok_or_else(|| OrchError::NotFound(format!("No task #{n} in the summary.")))?;
+        return cmd_jump(
+            dir,
+            config,
+            &JumpArgs {
+                name: Some(entry.name.clone()),
+                ..JumpArgs::default()
+            },
+        );
+    }
+
+    let daemon = match health::load(dir) {
+        Some(h) if h.alive() => match h.last_scan() {
+            Some(t) if h.last_scan_ok == Some(false) => {
+                format!("daemon ok, last scan failed {} ago", fmt::age(t))
+            }
+            Some(t) => format!("daemon ok, scan {} ago", fmt::age(t)),
+            None => "daemon ok".into(),
+        },
+        _ => "daemon not running".into(),
+    };
+    let mut lines = vec![format!(
+        "{} tasks · {inbox_count} inbox · {running} running · {daemon}",
+        entries.len()
+    )];
+
+    // Room for the header, the shortcut line and maybe "… N more"
+    let mut room = limits.max_lines.saturating_sub(2).max(1);
+    if entries.len() > room {
+        room = room.saturating_sub(1).max(1);
+    }
+    let shown = entries.len().min(room);
+    let name_width = entries[..shown]
+        .iter()
+        .map(|e| e.label.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (i, entry) in entries[..shown].iter().enumerate() {
+        let pin = if entry.pinned {
+            format!(" {}", render::PIN)
+        } else {
+            String::new()
+        };
+        lines.push(format!(
+            "{:>2}{} {:<name_width$}  {}{pin}",
+            i + 1,
+            entry.marker,
+            entry.label,
+            entry.note
+        ));
+    }
+    if entries.len() > shown {
+        lines.push(format!("   … {} more", entries.len() - shown));
+    }
+    if shown > 0 {
+        lines.push(format!("1-{shown}: jump (! inbox, * running)"));
+    }
+
+    for line in lines.iter().take(limits.max_lines.max(1)) {
+        outln!("{}", clip(line.trim_end(), limits.width));
+    }
+    Ok(())
+}
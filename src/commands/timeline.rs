@@ -0,0 +1,213 @@
+use std::{iter::Peekable, path::Path};
+
+use chrono::{DateTime, Local};
+use serde_json::json;
+
+use crate::{
+    actions::ActionKind, config::Config, duration, error::OrchError, fmt, frontmatter, log, outln,
+    output, runs, task,
+};
+
+/// One thing that happened to a task.
+struct Event {
+    at: DateTime<Local>,
+    /// Only the day is known (legacy date-only status lines, backfilled
+    /// `created:`).
+    approx: bool,
+    task: String,
+    kind: String,
+    detail: String,
+}
+
+/// Created, status entries and closed (with its outcome), from live and
+/// archived task files, plus what each task's log has.
+fn task_events(dir: &Path, config: &Config) -> Vec<Event> {
+    let mut files: Vec<_> = task::list(dir, config)
+        .into_iter()
+        .map(|t| {
+            let name = t.label.rsplit('/').next().unwrap_or(&t.label).to_string();
+            (name, t.content, None)
+        })
+        .collect();
+    for a in task::archived(dir) {
+        let Ok(content) = task::read(&a.path, config) else {
+            continue;
+        };
+        files.push((a.name, content, Some(a.archived)));
+    }
+
+    let mut events = Vec::new();
+    for (name, content, archived) in files {
+        let event = |at, approx, kind: &str, detail: &str| Event {
+            at,
+            approx,
+            task: name.clone(),
+            kind: kind.to_string(),
+            detail: detail.to_string(),
+        };
+        if let Some(created) =
+            frontmatter::get(&content, "created").and_then(|c| task::parse_timestamp(&c))
+        {
+            events.push(event(created, false, "created", ""));
+        }
+        for line in task::section(&content, "## Status") {
+            if let Some((at, date_only, text)) = task::status_entry(line) {
+                events.push(event(at, date_only, "status", text));
+            }
+        }
+        if let Some(at) = archived {
+            let at = frontmatter::get(&content, "closed")
+                .and_then(|c| task::parse_timestamp(&c))
+                .unwrap_or(at);
+            let outcome = frontmatter::get(&content, "outcome").unwrap_or_default();
+            events.push(event(at, false, "closed", &outcome));
+        }
+    }
+    for name in log::tasks(dir) {
+        for entry in log::entries(dir, &name) {
+            // Closes come from the archived file, above
+            if let (Some(at), false) =
+                (entry.at(), matches!(entry.event, log::Event::Closed { .. }))
+            {
+                events.push(Event {
+                    at,
+                    approx: false,
+                    task: name.clone(),
+                    kind: entry.event.kind().into(),
+                    detail: entry.event.detail(),
+                });
+            }
+        }
+    }
+    events.sort_by_key(|e| e.at);
+    events
+}
+
+/// What the orchestrator reported doing in each run, plus failed runs,
+/// read from the run log as it goes.
+fn run_events(dir: &Path) -> impl Iterator<Item = Event> {
+    runs::iter(dir).flat_map(|run| {
+        let at = run.at().unwrap_or_else(Local::now);
+        let mut events = Vec::new();
+        if !run.ok {
+            events.push(Event {
+                at,
+                approx: false,
+                task: "-".into(),
+                kind: "scan".into(),
+                detail: "failed".into(),
+            });
+        }
+        for action in run.actions.unwrap_or_default() {
+            let detail = match action.kind {
+                ActionKind::Blocked => action.reason,
+                _ => action.session,
+            };
+            events.push(Event {
+                at,
+                approx: false,
+                task: action.task,
+                kind: action.kind.to_string(),
+                detail: detail.unwrap_or_default(),
+            });
+        }
+        events
+    })
+}
+
+/// Merges two time-ordered streams.
+struct Merge<A: Iterator<Item = Event>, B: Iterator<Item = Event>> {
+    a: Peekable<A>,
+    b: Peekable<B>,
+}
+
+impl<A: Iterator<Item = Event>, B: Iterator<Item = Event>> Iterator for Merge<A, B> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        match (self.a.peek(), self.b.peek()) {
+            (Some(a), Some(b)) if b.at < a.at => self.b.next(),
+            (Some(_), _) => self.a.next(),
+            (None, _) => self.b.next(),
+        }
+    }
+}
+
+/// A stable color per task name, so one task's lines are easy to follow.
+fn paint(name: &str) -> String {
+    if !output::is_terminal() || name == "-" {
+        return name.to_string();
+    }
+    let hash = name
+        .bytes()
+        .fold(0u32, |h, b| h.wrapping_mul(31).wrapping_add(b.into()));
+    format!("\x1b[{}m{name}\x1b[0m", 31 + hash % 6)
+}
+
+fn parse_when(when: &str) -> Result<DateTime<Local>, OrchError> {
+    duration::since(when).map_err(OrchError::Parse)
+}
+
+pub fn cmd_timeline(
+    dir: &Path,
+    config: &Config,
+    since: &str,
+    until: Option<&str>,
+    only: Option<&str>,
+    json: bool,
+) -> Result<(), OrchError> {
+    let since = parse_when(since)?;
+    let until = until.map_or_else(|| Ok(Local::now()), parse_when)?;
+
+    let events = Merge {
+        a: task_events(dir, config).into_iter().peekable(),
+        b: run_events(dir).peekable(),
+    }
+    .filter(|e| e.at >= since && e.at <= until)
+    .filter(|e| only.is_none_or(|name| e.task == name));
+
+    let mut day = None;
+    for e in events {
+        if json {
+            outln!(
+                "{}",
+                json!({
+                    "at": task::timestamp(e.at),
+                    "approximate": e.approx,
+                    "task": e.task,
+                    "event": e.kind,
+                    "detail": e.detail,
+                })
+            );
+            continue;
+        }
+        // By the day shown, which `--utc` can move
+        let heading = fmt::day(e.at);
+        if day.as_ref() != Some(&heading) {
+            if day.is_some() {
+                outln!();
+            }
+            outln!("## {heading}\n");
+            day = Some(heading);
+        }
+        let time = if e.approx {
+            "(day)".to_string()
+        } else {
+            fmt::clock(e.at)
+        };
+        let detail = if e.detail.is_empty() {
+            String::new()
+        } else {
+            format!("  {}", e.detail)
+        };
+        outln!("  {time:>5}  {:<8} {}{detail}", e.kind, paint(&e.task));
+    }
+    if day.is_none() && !json {
+        outln!(
+            "(nothing between {} and {})",
+            fmt::time(since),
+            fmt::time(until)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use std::path::Path;
+
+use chrono::Local;
+
+use crate::{config::Config, edits, error::OrchError, frontmatter, health, outln, task};
+
+/// Stamps `touched_at:` on the task, which the daemon takes as a request to
+/// reconsider it.
+pub fn cmd_touch(dir: &Path, config: &Config, name: &str) -> Result<(), OrchError> {
+    let (_, path) = task::resolve(dir, name, config)?;
+    let now = task::timestamp(Local::now());
+    edits::apply(dir, config, &path, |content| {
+        Some(frontmatter::set(content, "touched_at", Some(&now)))
+    })?;
+    outln!("touched {} (touched_at: {now})", path.display());
+    if !health::load(dir).is_some_and(|h| h.alive()) {
+        eprintln!("The daemon isn't running; it'll see this at its next scan.");
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use crate::{
+    config::{self, Config},
+    edits,
+    error::OrchError,
+    fmt, outln, task,
+};
+
+/// Merges the repeated `## Status` sections of the task at `path`, read as
+/// `content` (see [`task::merge_status`]). The merged content, or None if
+/// there was nothing to merge.
+pub(super) fn merge_status(
+    dir: &Path,
+    config: &Config,
+    path: &Path,
+    content: &str,
+) -> Result<Option<String>, String> {
+    if task::merge_status(content).is_none() {
+        return Ok(None);
+    }
+    let hash = config::fnv(content.as_bytes());
+    match edits::edit_planned(dir, config, path, hash, task::merge_status) {
+        Ok(edits::Outcome::Written(merged)) => Ok(Some(merged)),
+        Ok(_) => Err("changed while orch read it; run it again".into()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Checks every task file's structure: readable, one `## Status` section,
+/// no repeated frontmatter keys ([`task::warnings`]). `fix` merges repeated
+/// sections first. Fails if problems remain.
+pub fn cmd_validate(dir: &Path, config: &Config, fix: bool) -> Result<(), OrchError> {
+    let mut files = task::files(dir, config);
+    files.sort();
+    let mut left = 0;
+    let mut mergeable = false;
+    for path in &files {
+        let name = format!("{}.md", task::label(dir, path));
+        let mut content = match task::read_checked(path, config.max_task_bytes) {
+            Ok(content) => content,
+            Err(reason) => {
+                outln!("FAIL  {name}: unreadable, {reason}");
+                left += 1;
+                continue;
+            }
+        };
+        if fix {
+            match merge_status(dir, config, path, &content) {
+                Ok(Some(merged)) => {
+                    outln!("fixed {name}: merged its ## Status sections");
+                    content = merged;
+                }
+                Ok(None) => {}
+                Err(e) => outln!("FAIL  {name}: merging ## Status failed: {e}"),
+            }
+        }
+        mergeable |= task::merge_status(&content).is_some();
+        for warning in task::warnings(&content) {
+            outln!("FAIL  {name}: {warning}");
+            left += 1;
+        }
+    }
+
+    if left > 0 {
+        if !fix && mergeable {
+            eprintln!("Run `orch validate --fix` to merge repeated ## Status sections.");
+        }
+        return Err(OrchError::Reported(1));
+    }
+    outln!("{} checked, no problems.", fmt::count(files.len(), "task"));
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::{error::OrchError, outln, paths};
+
+/// First line of `<program> <arg>`'s output, or "not found".
+pub(super) fn tool_version(program: &str, arg: &str) -> String {
+    match Command::new(program)
+        .arg(arg)
+        .stderr(Stdio::null())
+        .output()
+    {
+        Ok(out) => String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .next()
+            .unwrap_or("unknown")
+            .trim()
+            .to_string(),
+        Err(_) => "not found".into(),
+    }
+}
+
+pub fn cmd_version(dir: &Path, config_path: Option<&Path>, verbose: bool) -> Result<(), OrchError> {
+    outln!("orch {}", env!("CARGO_PKG_VERSION"));
+    if !verbose {
+        return Ok(());
+    }
+
+    let config = match config_path {
+        Some(path) if path.exists() => path.display().to_string(),
+        Some(path) => format!("{} (not found)", path.display()),
+        None => paths::NO_CONFIG.into(),
+    };
+    outln!("tmux: {}", tool_version("tmux", "-V"));
+    outln!("claude: {}", tool_version("claude", "--version"));
+    outln!("config: {config}");
+    outln!("tasks dir: {}", dir.display());
+    if let Some(project) = paths::project_dir() {
+        outln!("project: {}", project.display());
+    }
+    outln!(
+        "ORCH_REPO: {}",
+        std::env::var("ORCH_REPO").unwrap_or_else(|_| "(unset)".into())
+    );
+    outln!("os: {} {}", std::env::consts::OS, std::env::consts::ARCH);
+    Ok(())
+}
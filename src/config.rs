@@ -0,0 +1,456 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{cli::GroupBy, duration, sandbox::SandboxMode, session, task, templates, transcript};
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Event name -> shell command, see `HookEvent`.
+    pub hooks: HashMap<String, String>,
+    /// Treat symlinked `.md` files in ~/tasks as tasks. Symlinked
+    /// directories are never descended.
+    pub follow_symlinks: bool,
+    /// Have the daemon also watch the dirs symlinked tasks resolve into, so
+    /// edits made through the target are seen without waiting for a scan.
+    pub watch_link_targets: bool,
+    /// Write a `created:` field into task files that lack one, the first
+    /// time they're loaded.
+    pub backfill_created: bool,
+    pub notifications: NotificationsConfig,
+    /// Extra case-insensitive substrings that put a task in the inbox.
+    /// Matched literally: there's no regex syntax, so list each phrasing.
+    pub inbox_patterns: Vec<String>,
+    /// Whether `inbox_patterns` adds to or replaces the built-in set.
+    pub inbox_patterns_mode: PatternsMode,
+    pub inbox: InboxConfig,
+    pub escalation: EscalationConfig,
+    /// Check the orchestrator's `orch-actions` block against tmux and the
+    /// tasks dir after each run.
+    pub reconcile_actions: bool,
+    /// Worker session names, see [`crate::session`].
+    pub session_format: String,
+    /// Shorthand for `session_format = "<prefix>{{name}}"`, to keep two orch
+    /// setups on one tmux server apart.
+    pub session_prefix: Option<String>,
+    /// A live worker whose pane hasn't changed for this long is flagged as
+    /// possibly hung.
+    pub hung_after: String,
+    /// How many trailing pane lines count as a worker's output.
+    pub hung_lines: usize,
+    /// Press Enter in a hung worker's pane once when it's flagged.
+    pub nudge_hung: bool,
+    /// Kill a hung worker's session and ask the orchestrator for a new one.
+    pub auto_restart_hung: bool,
+    /// Hold `orch -` messages back while claude can't reach its API, and
+    /// send them once it can (also `orch - --queue-on-failure`).
+    pub queue_on_failure: bool,
+    /// Watch ~/tasks by polling mtimes instead of filesystem events, for
+    /// network filesystems (also `orch daemon --poll-watch`).
+    pub poll_watch: bool,
+    pub poll_interval: String,
+    /// A wait that overruns its timeout by this much means the machine
+    /// slept; the daemon then re-checks its watch and runs a wake-up scan.
+    /// `"0s"` turns this off.
+    pub wake_gap: String,
+    pub summary: SummaryConfig,
+    /// Task files bigger than this are reported as unreadable, not loaded.
+    pub max_task_bytes: u64,
+    /// File holding the prompt for `orch new --ask`, instead of the built-in
+    /// one.
+    pub draft_prompt: Option<PathBuf>,
+    /// File holding the prompt for `orch review`, instead of the built-in
+    /// one.
+    pub review_prompt: Option<PathBuf>,
+    pub serve: ServeConfig,
+    /// `orch status` flags transcripts bigger than this.
+    pub transcript_warn_bytes: u64,
+    /// Run the orchestrator with this prompt file, `@include`s expanded,
+    /// instead of the installed `orchestrator` agent.
+    pub orchestrator_prompt: Option<PathBuf>,
+    /// Prompt files kept inside the tasks dir, see [`crate::roots`].
+    pub root_prompts: RootPromptsConfig,
+    pub messages: MessagesConfig,
+    /// `--prompt` from the command line, which beats every other source.
+    #[serde(skip)]
+    pub prompt_flag: Option<PathBuf>,
+    pub audit_log: AuditLogConfig,
+    /// Only take exact task names, no prefix or substring matches (also
+    /// `--exact`).
+    pub exact_names: bool,
+    /// How far claude is trusted when running the orchestrator, see
+    /// [`crate::sandbox`].
+    pub sandbox: SandboxMode,
+    /// Git work trees the orchestrator has no business changing; any change
+    /// to their `git status` during a run is reported.
+    pub sandbox_repos: Vec<PathBuf>,
+    /// After this many orchestrator runs in a row that changed nothing, the
+    /// daemon warns and stretches its periodic scan interval until one
+    /// does (0 turns both off).
+    pub no_effect_warn_after: u32,
+    /// How many of the newest runs keep their prompt and output in
+    /// `.orch/blobs/`, for `orch replay` and `orch runs diff`.
+    pub run_history: usize,
+    /// How plain `orch status` groups tasks when `--group-by` isn't given.
+    pub status_group_by: Option<GroupBy>,
+    /// Most workers `orch spawn` has running at once; unset for no cap.
+    pub max_workers: Option<usize>,
+    /// Refuse every command that would change anything, and skip incidental
+    /// writes (also `--read-only` and `ORCH_READ_ONLY=1`).
+    pub read_only: bool,
+}
+
+/// Size limits for `orch summary`, to fit a tmux popup.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct SummaryConfig {
+    pub max_lines: usize,
+    pub width: usize,
+}
+
+impl Default for SummaryConfig {
+    fn default() -> Self {
+        SummaryConfig {
+            max_lines: 15,
+            width: 60,
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternsMode {
+    #[default]
+    Extend,
+    Replace,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct InboxConfig {
+    pub scoring: ScoringConfig,
+}
+
+/// Weights for ordering the inbox, see [`crate::inbox::Score`].
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct ScoringConfig {
+    /// Per point of `priority:`.
+    pub priority: f64,
+    /// Per hour in the inbox.
+    pub blocked_per_hour: f64,
+    /// At or past the `due:` date, tapering off further out.
+    pub due: f64,
+    /// When a live worker is waiting at a prompt.
+    pub waiting: f64,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig {
+            priority: 10.0,
+            blocked_per_hour: 1.0,
+            due: 30.0,
+            waiting: 20.0,
+        }
+    }
+}
+
+/// When the daemon escalates inbox items left unresolved, see
+/// [`crate::escalation`]. Each threshold is a duration like `4h`; unset
+/// ones are off.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct EscalationConfig {
+    /// Notify once an item has been in the inbox this long.
+    pub notify_after: Option<String>,
+    /// Then notify again this often while it stays.
+    pub renotify_every: Option<String>,
+    /// Bump the task's `priority:` once it's been in the inbox this long.
+    pub escalate_priority_after: Option<String>,
+}
+
+/// The record of orch invocations, see [`crate::audit`].
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct AuditLogConfig {
+    pub enabled: bool,
+    /// Where to append; `.orch/audit.jsonl` when unset.
+    pub path: Option<PathBuf>,
+    /// Extra substrings that mark an argument as a secret.
+    pub redact: Vec<String>,
+}
+
+/// A prompt file at the top of the tasks dir or of a project folder, for
+/// the scans that cover its tasks.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct RootPromptsConfig {
+    /// File name looked for; it's never listed as a task.
+    pub file: String,
+    /// Whether it's added to the global prompt or used instead of it.
+    pub mode: RootPromptMode,
+}
+
+impl Default for RootPromptsConfig {
+    fn default() -> Self {
+        RootPromptsConfig {
+            file: "orchestrator.md".into(),
+            mode: RootPromptMode::default(),
+        }
+    }
+}
+
+/// What the daemon tells the orchestrator, as [`crate::templates`].
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct MessagesConfig {
+    /// A full scan: at startup, on `orch scan`, after the tasks dir is
+    /// replaced. Has `{{tasks_dir}}`.
+    pub scan: String,
+    /// The hourly scan. Has `{{tasks_dir}}`.
+    pub periodic: String,
+    /// Leads a scan after the machine slept, before `scan`. Has
+    /// `{{tasks_dir}}`, `{{asleep}}`, `{{session}}` (sessions that
+    /// disappeared) and `{{changed_files}}`.
+    pub changed: String,
+    /// One line per worker whose task says it's running but whose session
+    /// is gone. Has `{{tasks_dir}}`, `{{task}}`, `{{session}}` and
+    /// `{{snapshot}}` (the end of its transcript, if it was recorded).
+    pub worker_exit: String,
+}
+
+impl Default for MessagesConfig {
+    fn default() -> Self {
+        MessagesConfig {
+            scan: templates::SCAN.into(),
+            periodic: templates::SCAN.into(),
+            changed: templates::WAKE.into(),
+            worker_exit: templates::WORKER_EXIT.into(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RootPromptMode {
+    #[default]
+    Append,
+    Replace,
+}
+
+/// `orch serve`, the local HTTP API.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct ServeConfig {
+    /// Port on 127.0.0.1 (also `orch serve --port`).
+    pub port: u16,
+    /// Bearer token for the POST endpoints; they're refused when unset.
+    pub token: Option<String>,
+}
+
+impl Default for ServeConfig {
+    fn default() -> Self {
+        ServeConfig {
+            port: 7370,
+            token: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    pub backend: BackendKind,
+    /// Several backends at once, each sent every notification; replaces
+    /// `backend` when set.
+    pub notifiers: Vec<BackendKind>,
+    /// URL the `webhook` backend POSTs `{event, title, body, task}` to.
+    pub webhook: Option<String>,
+    /// Program for the `command` backend, run like a hook with
+    /// `{title, body, task}` on stdin.
+    pub command: Option<String>,
+    /// Where the `terminal` backend writes; the controlling terminal when
+    /// unset.
+    pub tty: Option<PathBuf>,
+    /// Escape sequences the `terminal` backend writes, since terminals
+    /// support different ones.
+    pub escapes: Vec<Escape>,
+    /// More notifications than this within `breaker_window` and the rest
+    /// are held back for a digest, see [`crate::notify::Breaker`]. `0`
+    /// turns this off.
+    pub breaker_max: usize,
+    pub breaker_window: String,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        NotificationsConfig {
+            backend: BackendKind::default(),
+            notifiers: Vec::new(),
+            webhook: None,
+            command: None,
+            tty: None,
+            escapes: vec![Escape::Bel, Escape::Osc9],
+            breaker_max: 10,
+            breaker_window: "10m".into(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Escape {
+    /// Plain bell
+    Bel,
+    /// iTerm2, Windows Terminal, kitty, ...
+    Osc9,
+    /// urxvt, foot, VTE terminals
+    Osc777,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// Desktop notifications if available, else `command` if configured
+    #[default]
+    Auto,
+    Desktop,
+    Command,
+    /// JSON POSTed to `webhook`
+    Webhook,
+    /// Bell/OSC escape sequences to a tty, for remote sessions
+    Terminal,
+    None,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            hooks: HashMap::new(),
+            follow_symlinks: true,
+            watch_link_targets: false,
+            backfill_created: false,
+            notifications: NotificationsConfig::default(),
+            inbox_patterns: Vec::new(),
+            inbox_patterns_mode: PatternsMode::default(),
+            inbox: InboxConfig::default(),
+            escalation: EscalationConfig::default(),
+            reconcile_actions: false,
+            session_format: session::DEFAULT_FORMAT.into(),
+            session_prefix: None,
+            hung_after: "60m".into(),
+            hung_lines: 20,
+            nudge_hung: false,
+            auto_restart_hung: false,
+            queue_on_failure: false,
+            poll_watch: false,
+            poll_interval: "5s".into(),
+            wake_gap: "5m".into(),
+            summary: SummaryConfig::default(),
+            max_task_bytes: task::DEFAULT_MAX_BYTES,
+            draft_prompt: None,
+            review_prompt: None,
+            serve: ServeConfig::default(),
+            transcript_warn_bytes: transcript::DEFAULT_WARN_BYTES,
+            orchestrator_prompt: None,
+            root_prompts: RootPromptsConfig::default(),
+            messages: MessagesConfig::default(),
+            prompt_flag: None,
+            audit_log: AuditLogConfig::default(),
+            exact_names: false,
+            sandbox: SandboxMode::default(),
+            sandbox_repos: Vec::new(),
+            no_effect_warn_after: 3,
+            run_history: 200,
+            status_group_by: None,
+            max_workers: None,
+            read_only: false,
+        }
+    }
+}
+
+/// A short fingerprint of the config file's bytes (FNV-1a), to tell whether
+/// it changed since the daemon loaded it. None if it can't be read.
+pub fn hash(path: &Path) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    Some(format!("{:016x}", fnv(&bytes)))
+}
+
+/// FNV-1a of `bytes`: quick, and enough to tell versions of a file apart.
+pub fn fnv(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+        (h ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Loads the config at `path`. A missing file means defaults; an invalid one
+/// is reported and ignored.
+pub fn load(path: &Path) -> Config {
+    let Ok(text) = fs::read_to_string(path) else {
+        return Config::default();
+    };
+    let mut config: Config = toml::from_str(&text).unwrap_or_else(|e| {
+        crate::warn!(ConfigInvalid, "ignoring {}: {e}", path.display());
+        Config::default()
+    });
+    if let Some(prefix) = &config.session_prefix {
+        if config.session_format == session::DEFAULT_FORMAT {
+            config.session_format = format!("{prefix}{{{{name}}}}");
+        } else {
+            crate::warn!(
+                ConfigConflict,
+                "both session_prefix and session_format set, using session_format"
+            );
+        }
+    }
+    let defaults = MessagesConfig::default();
+    let messages = &mut config.messages;
+    for (name, template, default) in [
+        ("scan", &mut messages.scan, defaults.scan),
+        ("periodic", &mut messages.periodic, defaults.periodic),
+        ("changed", &mut messages.changed, defaults.changed),
+        (
+            "worker_exit",
+            &mut messages.worker_exit,
+            defaults.worker_exit,
+        ),
+    ] {
+        if let Err(e) = templates::check(template) {
+            crate::warn!(ConfigTemplate, "ignoring messages.{name}: {e}");
+            *template = default;
+        }
+    }
+    let escalation = &mut config.escalation;
+    for (name, threshold) in [
+        ("notify_after", &mut escalation.notify_after),
+        ("renotify_every", &mut escalation.renotify_every),
+        (
+            "escalate_priority_after",
+            &mut escalation.escalate_priority_after,
+        ),
+    ] {
+        if let Some(Err(e)) = threshold.as_deref().map(duration::parse) {
+            crate::warn!(ConfigInvalid, "ignoring escalation.{name}: {e}");
+            *threshold = None;
+        }
+    }
+    if let Err(e) = duration::parse(&config.notifications.breaker_window) {
+        crate::warn!(ConfigInvalid, "ignoring notifications.breaker_window: {e}");
+        config.notifications.breaker_window = NotificationsConfig::default().breaker_window;
+    }
+    if let Err(e) = session::validate(&config.session_format) {
+        crate::warn!(
+            ConfigSessionFormat,
+            "ignoring session_format '{}': {e}",
+            config.session_format
+        );
+        config.session_format = session::DEFAULT_FORMAT.into();
+    }
+    config
+}
@@ -0,0 +1,44 @@
+//! What the orchestrator's runs cost, by task, in `.orch/costs.json`. A
+//! run's cost is split evenly across the tasks its trigger names (a scoped
+//! scan, a message about a task, a worker event); one that names none, like
+//! a full scan, goes to [`UNATTRIBUTED`] rather than being spread over
+//! every task. Keyed by task name, so moving a task keeps its total.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::{paths, state, task};
+
+/// The bucket for runs that weren't about particular tasks.
+pub const UNATTRIBUTED: &str = "(unattributed)";
+
+/// Dollars spent per task name, plus [`UNATTRIBUTED`].
+pub fn load(dir: &Path) -> BTreeMap<String, f64> {
+    state::load(&paths::costs_path(dir)).unwrap_or_default()
+}
+
+/// Adds a run's `usd` to `tasks`, or to [`UNATTRIBUTED`] if there are none.
+pub fn attribute(dir: &Path, usd: f64, tasks: &[String]) {
+    let mut totals = load(dir);
+    if tasks.is_empty() {
+        *totals.entry(UNATTRIBUTED.into()).or_default() += usd;
+    }
+    for name in tasks {
+        *totals.entry(name.clone()).or_default() += usd / tasks.len() as f64;
+    }
+    let path = paths::costs_path(dir);
+    let json = serde_json::to_string_pretty(&totals).unwrap_or_default();
+    let result =
+        fs::create_dir_all(paths::state_dir(dir)).and_then(|_| task::write_atomic(&path, &json));
+    if let Err(e) = result {
+        eprintln!("[orch] failed to save {}: {e}", path.display());
+    }
+}
+
+/// "$1.82", or "$0.004" below a cent.
+pub fn format(usd: f64) -> String {
+    if usd > 0.0 && usd < 0.01 {
+        format!("${usd:.3}")
+    } else {
+        format!("${usd:.2}")
+    }
+}
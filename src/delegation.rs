@@ -0,0 +1,405 @@
+//! Handing work from one task to another. `orch delegate` does it directly;
+//! a worker that can't run orch prints a [`MARKER`] line instead, which the
+//! daemon finds in its pane. Either way the receiving task gets a
+//! `Delegated from` entry under `## Instructions`, the giving one a
+//! `Delegated to` status entry, both get a `delegated_from:` /
+//! `delegated_to:` link in their frontmatter, and the orchestrator is asked
+//! to look at both. Each delegation is noted in both tasks' [`crate::log`].
+
+use std::{collections::HashSet, path::Path};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    edits,
+    error::OrchError,
+    frontmatter,
+    log::{self, Event},
+    messages, paths, session, task, tmux,
+};
+
+/// Starts a delegation request in a worker's pane:
+/// `[orch-delegate to=<task>] <what to do>`.
+pub const MARKER: &str = "[orch-delegate to=";
+
+/// Appended to the orchestrator's system prompt on daemon runs.
+pub const PROMPT: &str = "\
+    Workers can hand work another task should own to that task. From a shell: \
+    `orch delegate <their task> --to <other task> \"<what to do>\"` (add --create if the \
+    other task doesn't exist yet). Or by printing one line of its own: \
+    `[orch-delegate to=<other task>] <what to do>`, which orch picks up from the pane. \
+    Tell workers this when you start them. A [delegated] message means the other task \
+    has the work under its ## Instructions; make sure it has a worker.";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Via {
+    Command,
+    Pane,
+}
+
+/// A delegation that was carried out.
+pub struct Delegation {
+    pub from: String,
+    pub to: String,
+    pub text: String,
+    /// `to` was created for it.
+    pub created: bool,
+}
+
+/// `content` with `name` added to the comma-separated list in `key`.
+fn link(content: &str, key: &str, name: &str) -> String {
+    let mut names: Vec<String> = frontmatter::get(content, key)
+        .map(|v| v.split(',').map(|n| n.trim().to_string()).collect())
+        .unwrap_or_default();
+    names.retain(|n| !n.is_empty());
+    if !names.iter().any(|n| n == name) {
+        names.push(name.to_string());
+    }
+    frontmatter::set(content, key, Some(&names.join(", ")))
+}
+
+/// Hands `text` from task `from` to task `to`. With `create`, a missing
+/// `to` is made the way `orch new` makes one, with `text` as its
+/// description, once the giving task has taken its edit: a delegation that
+/// fails leaves no new file behind.
+pub fn delegate(
+    dir: &Path,
+    config: &Config,
+    from: &str,
+    to: &str,
+    text: &str,
+    create: bool,
+    via: Via,
+) -> Result<Delegation, OrchError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(OrchError::Usage("Nothing to delegate given.".into()));
+    }
+    let (from, from_path) = task::resolve_open(dir, from, config, "delegated from")?;
+    let found = match task::resolve_open(dir, to, config, "delegated to") {
+        Err(OrchError::NotFound(_)) if create => {
+            task::check_new(dir, config, to)?;
+            None
+        }
+        Err(OrchError::NotFound(_)) => {
+            return Err(OrchError::NotFound(format!(
+                "No task '{to}' found; pass --create to create it."
+            )));
+        }
+        found => Some(found?),
+    };
+    let to = found.as_ref().map_or(to, |(name, _)| name).to_string();
+    if from == to {
+        return Err(OrchError::Usage(format!(
+            "{from} can't delegate to itself."
+        )));
+    }
+
+    let now = Local::now();
+    let first = text.lines().next().unwrap_or_default();
+    let receive = |content: &str| {
+        let content =
+            task::add_instruction(content, now, &format!("Delegated from {from}: {text}"));
+        link(&content, "delegated_from", &from)
+    };
+    if let Some((_, to_path)) = &found {
+        edits::apply(dir, config, to_path, |content| Some(receive(content)))?;
+    }
+    edits::apply(dir, config, &from_path, |content| {
+        let content = task::add_status(content, now, &format!("Delegated to {to}: {first}"));
+        Some(link(&content, "delegated_to", &to))
+    })?;
+    let created = found.is_none();
+    let to_path = match found {
+        Some((_, path)) => path,
+        None => task::create(dir, &to, &receive(text))
+            .map_err(|e| OrchError::io(dir.join(format!("{to}.md")), e))?,
+    };
+
+    let (from_label, to_label) = (task::label(dir, &from_path), task::label(dir, &to_path));
+    messages::write(
+        &paths::inbox_dir(dir),
+        &format!(
+            "[delegated] {from_label}.md handed work to {to_label}.md: {first}\n\
+             It's under ## Instructions in {to_label}.md. Make sure that task has a worker on it."
+        ),
+    )?;
+    log::record(
+        dir,
+        &from,
+        Event::DelegatedTo {
+            to: to.clone(),
+            via,
+            text: text.to_string(),
+            created,
+        },
+    );
+    log::record(
+        dir,
+        &to,
+        Event::DelegatedFrom {
+            from: from.clone(),
+            via,
+            text: text.to_string(),
+            created,
+        },
+    );
+    Ok(Delegation {
+        from,
+        to,
+        text: text.to_string(),
+        created,
+    })
+}
+
+/// The `(to, text)` of each [`MARKER`] line in `pane`. The marker may follow
+/// a bullet or prompt. A target with spaces or angle brackets (like the
+/// `<other task>` of the prompt explaining it) doesn't count.
+pub fn requests(pane: &str) -> Vec<(String, String)> {
+    pane.lines()
+        .filter_map(|line| {
+            let rest = &line[line.find(MARKER)? + MARKER.len()..];
+            let (to, text) = rest.split_once(']')?;
+            let (to, text) = (to.trim(), text.trim());
+            let placeholder = to.contains(|c: char| c.is_whitespace() || c == '<' || c == '>');
+            (!placeholder && task::validate_name(to).is_ok() && !text.is_empty())
+                .then(|| (to.to_string(), text.to_string()))
+        })
+        .collect()
+}
+
+/// Carries out the requests in live workers' panes that haven't been yet.
+/// Requests that fail are logged once and remembered in `refused`, so a
+/// line still on screen isn't retried every tick.
+pub fn from_panes(
+    dir: &Path,
+    config: &Config,
+    refused: &mut HashSet<(String, String, String)>,
+) -> Vec<Delegation> {
+    let mut delegated = Vec::new();
+    for task in task::list(dir, config) {
+        let session = task::session(config, &task.label, &task.content);
+        if session == session::CONSOLE || !tmux::has_session(&session) {
+            continue;
+        }
+        let mut done: HashSet<_> = log::entries(dir, &task.name)
+            .filter_map(|e| match e.event {
+                Event::DelegatedTo {
+                    to,
+                    via: Via::Pane,
+                    text,
+                    ..
+                } => Some((task.name.clone(), to, text)),
+                _ => None,
+            })
+            .collect();
+        for (to, text) in requests(&tmux::capture_pane_joined(&session)) {
+            let key = (task.name.clone(), to, text);
+            if done.contains(&key) || refused.contains(&key) {
+                continue;
+            }
+            match delegate(dir, config, &key.0, &key.1, &key.2, false, Via::Pane) {
+                Ok(d) => {
+                    done.insert(key);
+                    delegated.push(d);
+                }
+                Err(e) => {
+                    eprintln!("[orch] {} asked to delegate to {}: {e}", key.0, key.1);
+                    refused.insert(key);
+                }
+            }
+        }
+    }
+    delegated
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// A tasks dir with `recon` and `infra`.
+    fn tasks() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("recon.md"), "# Recon\n").unwrap();
+        fs::write(tmp.path().join("infra.md"), "# Infra\n").unwrap();
+        tmp
+    }
+
+    fn read(dir: &Path, name: &str) -> String {
+        fs::read_to_string(dir.join(format!("{name}.md"))).unwrap()
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .filter(|n| n.ends_with(".md"))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn delegating_to_an_existing_task() {
+        let tmp = tasks();
+        let dir = tmp.path();
+        let config = Config::default();
+        let d = delegate(
+            dir,
+            &config,
+            "recon",
+            "infra",
+            "Rotate the staging certs.",
+            false,
+            Via::Command,
+        )
+        .unwrap();
+        assert!(!d.created);
+        let infra = read(dir, "infra");
+        assert!(infra.contains("Delegated from recon: Rotate the staging certs."));
+        assert_eq!(
+            frontmatter::get(&infra, "delegated_from").as_deref(),
+            Some("recon")
+        );
+        let recon = read(dir, "recon");
+        assert!(recon.contains("Delegated to infra: Rotate the staging certs."));
+        assert_eq!(
+            frontmatter::get(&recon, "delegated_to").as_deref(),
+            Some("infra")
+        );
+        assert_eq!(messages::pending(&paths::inbox_dir(dir)), 1);
+        let details = |name| {
+            log::entries(dir, name)
+                .map(|e| e.event.detail())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(details("recon"), ["to infra: Rotate the staging certs."]);
+        assert_eq!(details("infra"), ["from recon: Rotate the staging certs."]);
+    }
+
+    #[test]
+    fn create_makes_the_task_like_orch_new() {
+        let tmp = tasks();
+        let dir = tmp.path();
+        let d = delegate(
+            dir,
+            &Config::default(),
+            "recon",
+            "certs",
+            "Rotate the staging certs.",
+            true,
+            Via::Command,
+        )
+        .unwrap();
+        assert!(d.created);
+        let certs = read(dir, "certs");
+        assert!(frontmatter::get(&certs, "created").is_some(), "{certs}");
+        assert_eq!(
+            frontmatter::get(&certs, "delegated_from").as_deref(),
+            Some("recon")
+        );
+        let body = frontmatter::split(&certs).1;
+        assert!(body.starts_with("Rotate the staging certs.\n"), "{certs}");
+        assert!(body.contains("Delegated from recon: Rotate the staging certs."));
+        assert!(!certs.contains("# certs"), "{certs}");
+    }
+
+    #[test]
+    fn create_refuses_what_orch_new_refuses() {
+        let tmp = tasks();
+        let dir = tmp.path();
+        for to in ["../escape", "orch-console"] {
+            let config = Config {
+                session_format: "{{name}}".into(),
+                ..Config::default()
+            };
+            let e = delegate(dir, &config, "recon", to, "x", true, Via::Command)
+                .err()
+                .unwrap();
+            assert_eq!(e.exit_code(), 2, "{to}: {e}");
+        }
+        assert_eq!(names(dir), ["infra.md", "recon.md"]);
+        assert_eq!(read(dir, "recon"), "# Recon\n");
+    }
+
+    #[test]
+    fn a_failed_delegation_creates_nothing() {
+        let tmp = tasks();
+        let dir = tmp.path();
+        // recon.md can't be rewritten: its temp file's name is taken
+        fs::create_dir(dir.join(".recon.md.tmp")).unwrap();
+        let e = delegate(
+            dir,
+            &Config::default(),
+            "recon",
+            "certs",
+            "Rotate the staging certs.",
+            true,
+            Via::Command,
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(e, OrchError::Io { .. }), "{e}");
+        assert_eq!(names(dir), ["infra.md", "recon.md"]);
+        assert_eq!(messages::pending(&paths::inbox_dir(dir)), 0);
+        assert!(!paths::log_dir(dir).exists());
+    }
+
+    #[test]
+    fn missing_tasks_need_create() {
+        let tmp = tasks();
+        let dir = tmp.path();
+        let e = delegate(
+            dir,
+            &Config::default(),
+            "recon",
+            "certs",
+            "x",
+            false,
+            Via::Pane,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            e.to_string(),
+            "No task 'certs' found; pass --create to create it."
+        );
+        let e = delegate(
+            dir,
+            &Config::default(),
+            "recon",
+            "recon",
+            "x",
+            true,
+            Via::Command,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(e.to_string(), "recon can't delegate to itself.");
+        assert_eq!(read(dir, "recon"), "# Recon\n");
+    }
+
+    #[test]
+    fn pane_requests() {
+        let pane = "\
+            $ make\n\
+            [orch-delegate to=infra] Rotate the staging certs.\n\
+            - [orch-delegate to=api] Add the endpoint\n\
+            [orch-delegate to=<other task>] <what to do>\n\
+            [orch-delegate to=infra]\n\
+            [orch-delegate to=../x] escape\n";
+        assert_eq!(
+            requests(pane),
+            [
+                ("infra".to_string(), "Rotate the staging certs.".to_string()),
+                ("api".to_string(), "Add the endpoint".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+
+use crate::task;
+
+/// Parses durations like `90s`, `30m`, `2d`, `1w`, and compounds like
+/// `1h30m` or `1d 12h`. Every number needs a unit.
+pub fn parse(s: &str) -> Result<Duration, String> {
+    let invalid =
+        |why: &str| format!("invalid duration '{s}': {why} (expected e.g. 30m, 2h, 1h30m)");
+    let rest = s.trim();
+    if rest.is_empty() {
+        return Err(invalid("empty"));
+    }
+    if rest.starts_with('-') {
+        return Err(invalid("negative"));
+    }
+
+    let mut total: u64 = 0;
+    let mut rest = rest;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return Err(invalid("expected a number"));
+        }
+        let (num, tail) = rest.split_at(digits);
+        let unit_len = tail
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        let secs = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            "d" => 86400,
+            "w" => 7 * 86400,
+            "" => return Err(invalid(&format!("missing unit after {num}"))),
+            _ => return Err(invalid(&format!("unknown unit '{unit}'"))),
+        };
+        total = num
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(secs))
+            .and_then(|n| total.checked_add(n))
+            .ok_or_else(|| invalid("too large"))?;
+        rest = tail.trim_start();
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// `now + parse(s)`, for deadlines like `--for 2h`.
+pub fn from_now(s: &str) -> Result<DateTime<Local>, String> {
+    let d = parse(s)?;
+    chrono::Duration::from_std(d)
+        .ok()
+        .and_then(|d| Local::now().checked_add_signed(d))
+        .ok_or_else(|| format!("duration '{s}' is too long"))
+}
+
+/// A point in time given as a duration ago (`3h`) or a timestamp (RFC 3339,
+/// or local `2026-03-02 14:00` / `2026-03-02T14:00` / `2026-03-02`).
+pub fn since(s: &str) -> Result<DateTime<Local>, String> {
+    if let Ok(ago) = parse(s) {
+        return chrono::Duration::from_std(ago)
+            .ok()
+            .and_then(|ago| Local::now().checked_sub_signed(ago))
+            .ok_or_else(|| format!("duration '{s}' is too long"));
+    }
+    local_time(s)
+        .ok_or_else(|| format!("invalid time '{s}' (expected e.g. 3h or 2026-03-02T14:00)"))
+}
+
+/// A point in time given as a timestamp, in the forms [`since`] takes.
+pub fn at(s: &str) -> Result<DateTime<Local>, String> {
+    local_time(s).ok_or_else(|| format!("invalid time '{s}' (expected e.g. 2026-03-02 14:00)"))
+}
+
+/// RFC 3339, or local `2026-03-02 14:00` / `2026-03-02T14:00` / `2026-03-02`.
+fn local_time(s: &str) -> Option<DateTime<Local>> {
+    let s = s.trim();
+    task::parse_timestamp(s).or_else(|| {
+        let naive = ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S"]
+            .iter()
+            .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+            .or_else(|| {
+                NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    .ok()?
+                    .and_hms_opt(0, 0, 0)
+            })?;
+        naive.and_local_timezone(Local).single()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: &str) -> u64 {
+        parse(s).unwrap().as_secs()
+    }
+
+    /// The reason `parse(s)` gives, between the input and the examples.
+    fn why(s: &str) -> String {
+        let err = parse(s).unwrap_err();
+        assert!(err.ends_with("(expected e.g. 30m, 2h, 1h30m)"), "{err}");
+        err.split_once("': ")
+            .unwrap()
+            .1
+            .split(" (")
+            .next()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn single_units() {
+        assert_eq!(secs("90s"), 90);
+        assert_eq!(secs("30m"), 30 * 60);
+        assert_eq!(secs("2h"), 2 * 3600);
+        assert_eq!(secs("2d"), 2 * 86400);
+        assert_eq!(secs("1w"), 7 * 86400);
+        assert_eq!(secs("0s"), 0);
+    }
+
+    #[test]
+    fn compounds() {
+        assert_eq!(secs("1h30m"), 5400);
+        assert_eq!(secs("1d 12h"), 36 * 3600);
+        assert_eq!(secs("  1h  30m  "), 5400);
+        assert_eq!(secs("30m1h"), 5400);
+        assert_eq!(secs("1m1m"), 120);
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(why(""), "empty");
+        assert_eq!(why("   "), "empty");
+    }
+
+    #[test]
+    fn negative() {
+        assert_eq!(why("-5m"), "negative");
+        assert_eq!(why(" -1h"), "negative");
+        assert_eq!(why("1h -5m"), "expected a number");
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(secs("18446744073709551615s"), u64::MAX);
+        assert_eq!(why("18446744073709551616s"), "too large");
+        assert_eq!(why("18446744073709551615m"), "too large");
+        assert_eq!(why("18446744073709551615s 1s"), "too large");
+        assert_eq!(why("99999999999999999999999w"), "too large");
+    }
+
+    #[test]
+    fn overflow_past_what_a_time_can_hold() {
+        assert!(parse("9999999999w").is_ok());
+        assert_eq!(
+            from_now("9999999999w").unwrap_err(),
+            "duration '9999999999w' is too long"
+        );
+        assert_eq!(
+            since("9999999999w").unwrap_err(),
+            "duration '9999999999w' is too long"
+        );
+    }
+
+    #[test]
+    fn missing_unit() {
+        assert_eq!(why("30"), "missing unit after 30");
+        assert_eq!(why("1h30"), "missing unit after 30");
+        assert_eq!(why("10 m"), "missing unit after 10");
+        assert_eq!(why("1.5h"), "missing unit after 1");
+    }
+
+    #[test]
+    fn unknown_units_and_stray_text() {
+        assert_eq!(why("5min"), "unknown unit 'min'");
+        assert_eq!(why("2H"), "unknown unit 'H'");
+        assert_eq!(why("h"), "expected a number");
+        assert_eq!(why("+5m"), "expected a number");
+        assert_eq!(why("5m!"), "expected a number");
+    }
+
+    #[test]
+    fn since_takes_durations_and_timestamps() {
+        let ago = Local::now() - since("2h").unwrap();
+        assert!((ago.num_seconds() - 7200).abs() <= 1);
+        let day = since("2026-03-02").unwrap();
+        assert_eq!(day.format("%Y-%m-%d %H:%M").to_string(), "2026-03-02 00:00");
+        assert_eq!(
+            since("2026-03-02 14:00").unwrap(),
+            at("2026-03-02T14:00").unwrap()
+        );
+        assert!(since("yesterday").is_err());
+        assert!(at("2h").is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! Read-modify-write of task files that orch itself makes, while workers and
+//! the orchestrator may be editing the same files. Edits to one file are
+//! serialized by a lock file per task name under `.orch/locks/`, so two of
+//! orch's, in one process or several, never interleave; each rewrites the
+//! whole file atomically, and only if the file still hashes the same as when
+//! it was read. Someone else's write in between makes the edit start over on
+//! the new content ([`OnConflict::Reapply`]) or give up
+//! ([`OnConflict::Drop`]).
+//!
+//! The hash check narrows the window for outside writers but can't close
+//! it: there's no lock they'd honor.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::{self, Config},
+    error::OrchError,
+    paths, task,
+};
+
+/// What an edit does when the file changed after it was read.
+#[derive(Clone, Copy, PartialEq)]
+pub enum OnConflict {
+    /// Read it again and redo the edit on what's there now. For edits that
+    /// must land and can be computed from any version of the file.
+    Reapply,
+    /// Leave the file alone. For edits only right against what was read.
+    Drop,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    /// The new content, now on disk.
+    Written(String),
+    /// The edit had nothing to change.
+    Unchanged,
+    /// The file kept changing underneath (or changed once, with
+    /// [`OnConflict::Drop`]), or wasn't what [`edit_planned`] expected.
+    Dropped,
+}
+
+/// Attempts before a [`OnConflict::Reapply`] edit gives up.
+const TRIES: usize = 5;
+
+/// The lock file for edits to `path`, named after the file it resolves to
+/// so a symlink and its target share one. Tasks of the same name in two
+/// projects share one too, which only makes them take turns.
+fn lock_path(dir: &Path, path: &Path) -> PathBuf {
+    let real = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    paths::locks_dir(dir).join(real.file_stem().unwrap_or_default())
+}
+
+/// Takes the lock for edits to `path`, waiting for whoever holds it. It's
+/// released when the file is dropped.
+fn lock(dir: &Path, path: &Path) -> Result<File, OrchError> {
+    let lock = lock_path(dir, path);
+    let file = lock
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| {
+            OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&lock)
+        })
+        .map_err(|e| OrchError::io(&lock, e))?;
+    file.lock().map_err(|e| OrchError::io(&lock, e))?;
+    Ok(file)
+}
+
+/// Applies `change` to the task file at `path` in the tasks dir `dir`: it
+/// gets the current content and returns the new one, or None to leave the
+/// file as is. `change` may run more than once, on different content. A
+/// file that can't be read (see [`task::read`]) fails the edit.
+pub fn edit(
+    dir: &Path,
+    config: &Config,
+    path: &Path,
+    on_conflict: OnConflict,
+    change: impl Fn(&str) -> Option<String>,
+) -> Result<Outcome, OrchError> {
+    run(dir, config, path, None, on_conflict, change)
+}
+
+/// [`edit`] for a change worked out from an earlier read of the file, whose
+/// content hashed to `hash` ([`config::fnv`]). A file that's changed since
+/// is left alone, as with [`OnConflict::Drop`].
+pub fn edit_planned(
+    dir: &Path,
+    config: &Config,
+    path: &Path,
+    hash: u64,
+    change: impl Fn(&str) -> Option<String>,
+) -> Result<Outcome, OrchError> {
+    run(dir, config, path, Some(hash), OnConflict::Drop, change)
+}
+
+/// [`edit`] that has to land, for commands: a file that keeps changing
+/// fails it. The new content, or None if there was nothing to change.
+pub fn apply(
+    dir: &Path,
+    config: &Config,
+    path: &Path,
+    change: impl Fn(&str) -> Option<String>,
+) -> Result<Option<String>, OrchError> {
+    match edit(dir, config, path, OnConflict::Reapply, change)? {
+        Outcome::Written(content) => Ok(Some(content)),
+        Outcome::Unchanged => Ok(None),
+        Outcome::Dropped => Err(OrchError::Failed(format!(
+            "{} kept changing while orch tried to edit it; try again.",
+            path.display()
+        ))),
+    }
+}
+
+fn run(
+    dir: &Path,
+    config: &Config,
+    path: &Path,
+    expected: Option<u64>,
+    on_conflict: OnConflict,
+    change: impl Fn(&str) -> Option<String>,
+) -> Result<Outcome, OrchError> {
+    let _lock = lock(dir, path)?;
+    for _ in 0..TRIES {
+        let before = task::read(path, config)?;
+        if expected.is_some_and(|hash| hash != config::fnv(before.as_bytes())) {
+            return Ok(Outcome::Dropped);
+        }
+        let Some(after) = change(&before) else {
+            return Ok(Outcome::Unchanged);
+        };
+        if after == before {
+            return Ok(Outcome::Unchanged);
+        }
+        let now = fs::read(path).map_err(|e| OrchError::io(path, e))?;
+        if config::fnv(&now) != config::fnv(before.as_bytes()) {
+            match on_conflict {
+                OnConflict::Reapply => continue,
+                OnConflict::Drop => return Ok(Outcome::Dropped),
+            }
+        }
+        task::write_atomic(path, &after).map_err(|e| OrchError::io(path, e))?;
+        return Ok(Outcome::Written(after));
+    }
+    Ok(Outcome::Dropped)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, thread};
+
+    use super::*;
+
+    const THREADS: usize = 16;
+    const EDITS: usize = 25;
+
+    fn append(line: String) -> impl Fn(&str) -> Option<String> {
+        move |content| Some(format!("{content}{line}\n"))
+    }
+
+    #[test]
+    fn concurrent_edits_land_exactly_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("task.md");
+        fs::write(&path, "# Task\n\n## Status\n").unwrap();
+        let config = Config::default();
+        thread::scope(|scope| {
+            for t in 0..THREADS {
+                let (dir, path, config) = (dir.path(), &path, &config);
+                scope.spawn(move || {
+                    for i in 0..EDITS {
+                        let outcome = edit(
+                            dir,
+                            config,
+                            path,
+                            OnConflict::Reapply,
+                            append(format!("- entry {t}-{i}")),
+                        )
+                        .unwrap();
+                        assert!(matches!(outcome, Outcome::Written(_)));
+                    }
+                });
+            }
+        });
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# Task\n\n## Status\n"));
+        for t in 0..THREADS {
+            for i in 0..EDITS {
+                let line = format!("- entry {t}-{i}\n");
+                assert_eq!(content.matches(&line).count(), 1, "{line:?}");
+            }
+        }
+        assert_eq!(content.lines().count(), 3 + THREADS * EDITS);
+    }
+
+    #[test]
+    fn an_outside_write_is_reapplied_onto() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("task.md");
+        fs::write(&path, "# Task\n").unwrap();
+        let first = Cell::new(true);
+        let outcome = edit(
+            dir.path(),
+            &Config::default(),
+            &path,
+            OnConflict::Reapply,
+            |content| {
+                // A worker writes between orch's read and its write
+                if first.replace(false) {
+                    fs::write(&path, "# Task\nworker\n").unwrap();
+                }
+                Some(format!("{content}orch\n"))
+            },
+        )
+        .unwrap();
+        assert_eq!(outcome, Outcome::Written("# Task\nworker\norch\n".into()));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# Task\nworker\norch\n");
+    }
+
+    #[test]
+    fn an_outside_write_drops_a_drop_edit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("task.md");
+        fs::write(&path, "# Task\n").unwrap();
+        let outcome = edit(
+            dir.path(),
+            &Config::default(),
+            &path,
+            OnConflict::Drop,
+            |content| {
+                fs::write(&path, "# Task\nworker\n").unwrap();
+                Some(format!("{content}orch\n"))
+            },
+        )
+        .unwrap();
+        assert_eq!(outcome, Outcome::Dropped);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# Task\nworker\n");
+    }
+
+    #[test]
+    fn a_planned_edit_of_a_changed_file_is_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("task.md");
+        fs::write(&path, "# Task\nworker\n").unwrap();
+        let config = Config::default();
+        let planned = config::fnv(b"# Task\n");
+        let outcome = edit_planned(dir.path(), &config, &path, planned, |content| {
+            Some(format!("{content}orch\n"))
+        })
+        .unwrap();
+        assert_eq!(outcome, Outcome::Dropped);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# Task\nworker\n");
+
+        let planned = config::fnv(b"# Task\nworker\n");
+        let outcome = edit_planned(dir.path(), &config, &path, planned, |content| {
+            Some(format!("{content}orch\n"))
+        })
+        .unwrap();
+        assert_eq!(outcome, Outcome::Written("# Task\nworker\norch\n".into()));
+    }
+
+    #[test]
+    fn an_unreadable_file_fails_the_edit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("task.md");
+        fs::write(&path, "x".repeat(100)).unwrap();
+        let config = Config {
+            max_task_bytes: 64,
+            ..Config::default()
+        };
+        let err = apply(dir.path(), &config, &path, |_| Some(String::new())).unwrap_err();
+        assert!(err.to_string().contains("unreadable, too large"), "{err}");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "x".repeat(100));
+    }
+
+    #[test]
+    fn a_symlink_shares_its_targets_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("elsewhere").join("task.md");
+        let link = dir.path().join("link.md");
+        fs::create_dir(path.parent().unwrap()).unwrap();
+        fs::write(&path, "").unwrap();
+        std::os::unix::fs::symlink(&path, &link).unwrap();
+        assert_eq!(lock_path(dir.path(), &link), lock_path(dir.path(), &path));
+        assert_eq!(
+            lock_path(dir.path(), &link),
+            dir.path().join(".orch/locks/task")
+        );
+    }
+}
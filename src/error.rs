@@ -0,0 +1,111 @@
+//! The error type commands return; `main` prints it and exits with
+//! [`OrchError::exit_code`].
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum OrchError {
+    /// Bad arguments or input: exit 2.
+    #[error("{0}")]
+    Usage(String),
+    #[error("{}", describe_io(path, source))]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("{0}")]
+    Config(String),
+    #[error("{0}")]
+    Tmux(String),
+    #[error("{0}")]
+    Claude(String),
+    /// A duration, timestamp or payload that didn't parse: exit 2.
+    #[error("{0}")]
+    Parse(String),
+    /// No such task, session, or recorded prompt.
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Failed(String),
+    /// Details are already on stderr (e.g. per-task failures in a batch);
+    /// just exit with this code.
+    #[error("")]
+    Reported(i32),
+}
+
+/// Whether `e` is a read-only mount (a container's tasks, say) or a dir
+/// orch's user can't write to refusing a write.
+pub fn read_only(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::ReadOnlyFilesystem | io::ErrorKind::PermissionDenied
+    )
+}
+
+/// `path: source`, explained when it's [`read_only`].
+fn describe_io(path: &Path, source: &io::Error) -> String {
+    if !read_only(source) {
+        return format!("{}: {source}", path.display());
+    }
+    format!(
+        "{}: {source}. Read commands like status and inbox work on a read-only \
+         tasks dir, but this one has to write there. Point ORCH_TASKS_DIR and \
+         ORCH_STATE_DIR at a writable copy to change things, or pass --read-only \
+         to only look.",
+        path.display()
+    )
+}
+
+impl OrchError {
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> OrchError {
+        OrchError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn no_task(name: &str) -> OrchError {
+        OrchError::NotFound(format!("No task '{name}' found."))
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            OrchError::Usage(_) | OrchError::Parse(_) => 2,
+            OrchError::Reported(code) => *code,
+            _ => 1,
+        }
+    }
+}
+
+pub type Result<T = (), E = OrchError> = std::result::Result<T, E>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_only_writes_are_explained() {
+        for kind in [
+            io::ErrorKind::ReadOnlyFilesystem,
+            io::ErrorKind::PermissionDenied,
+        ] {
+            let e = OrchError::io("/tasks/a.md", io::Error::from(kind));
+            let message = e.to_string();
+            assert!(message.starts_with("/tasks/a.md: "), "{message}");
+            assert!(message.contains("ORCH_STATE_DIR"), "{message}");
+            assert_eq!(e.exit_code(), 1);
+        }
+    }
+
+    #[test]
+    fn other_io_errors_are_plain() {
+        let e = OrchError::io("/tasks/a.md", io::Error::from(io::ErrorKind::NotFound));
+        assert_eq!(e.to_string(), "/tasks/a.md: entity not found");
+    }
+}
@@ -0,0 +1,201 @@
+//! Escalating inbox items nobody has answered, per `[escalation]`: a
+//! notification once an item has been in the inbox for `notify_after`,
+//! another every `renotify_every`, and a `priority:` bump past
+//! `escalate_priority_after`. Time in the inbox is counted from when
+//! `.orch/inbox.json` first saw the item, and what was already done for it is
+//! kept in `.orch/escalation.json`, so a restarted daemon doesn't repeat
+//! itself. Snoozed and held tasks aren't in the inbox, so they're exempt.
+
+use std::{cell::Cell, collections::BTreeMap, fs, path::Path, time::Duration};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Config, EscalationConfig},
+    duration,
+    edits::{self, OnConflict, Outcome},
+    fmt,
+    inbox::{self, InboxItem},
+    log::{self, Event},
+    paths, state, task,
+};
+
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) struct Escalation {
+    /// RFC 3339, when the item entered the inbox; a new stay starts over.
+    since: String,
+    /// RFC 3339, when it was last notified about.
+    #[serde(default)]
+    notified: Option<String>,
+    /// Its priority was bumped during this stay.
+    #[serde(default)]
+    bumped: bool,
+}
+
+/// How far along an item is, for coloring its age.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Fresh,
+    /// Past `notify_after`.
+    Overdue,
+    /// Past `escalate_priority_after`.
+    Escalated,
+}
+
+/// What was done about an item on this check.
+#[derive(Clone, Copy)]
+pub enum Step {
+    /// Its first notification.
+    Notify,
+    /// Another one, `renotify_every` after the last.
+    Renotify,
+    /// Its priority was bumped to this.
+    Priority(i64),
+}
+
+impl Step {
+    pub fn name(self) -> &'static str {
+        match self {
+            Step::Notify => "notify",
+            Step::Renotify => "renotify",
+            Step::Priority(_) => "priority",
+        }
+    }
+}
+
+fn threshold(value: &Option<String>) -> Option<Duration> {
+    // Invalid values were dropped with a warning when the config loaded
+    duration::parse(value.as_deref()?).ok()
+}
+
+fn load(dir: &Path) -> BTreeMap<String, Escalation> {
+    state::load(&paths::escalation_path(dir)).unwrap_or_default()
+}
+
+fn save(dir: &Path, escalations: &BTreeMap<String, Escalation>) {
+    let path = paths::escalation_path(dir);
+    let result = fs::create_dir_all(paths::state_dir(dir)).and_then(|_| {
+        let json = serde_json::to_string_pretty(escalations).unwrap_or_default();
+        task::write_atomic(&path, &json)
+    });
+    if let Err(e) = result {
+        eprintln!("[orch] failed to save {}: {e}", path.display());
+    }
+}
+
+/// Carries what was done for a task over to its new label after a move,
+/// as [`inbox::relabel`] does its time in the inbox.
+pub fn relabel(dir: &Path, from: &str, to: &str) {
+    let mut escalations = load(dir);
+    if let Some(escalation) = escalations.remove(from) {
+        escalations.insert(to.to_string(), escalation);
+        save(dir, &escalations);
+    }
+}
+
+/// How far an item in the inbox since `since` is past the thresholds.
+pub fn severity(config: &EscalationConfig, since: DateTime<Local>) -> Severity {
+    let age = (Local::now() - since).to_std().unwrap_or_default();
+    let past = |value: &Option<String>| threshold(value).is_some_and(|t| age >= t);
+    if past(&config.escalate_priority_after) {
+        Severity::Escalated
+    } else if past(&config.notify_after) {
+        Severity::Overdue
+    } else {
+        Severity::Fresh
+    }
+}
+
+/// Bumps the priority of the item's task and notes why in its status log
+/// and its [`log`]. The entry quotes the item's reason so the task stays in
+/// the inbox.
+fn bump(dir: &Path, config: &Config, item: &InboxItem) -> Option<i64> {
+    let (name, path) = task::resolve(dir, &item.label, config).ok()?;
+    let now = Local::now();
+    let age = fmt::long((now - item.since).to_std().unwrap_or_default());
+    let bumped = Cell::new(None);
+    let result = edits::edit(dir, config, &path, OnConflict::Reapply, |content| {
+        let (content, priority) = task::bump_priority(content);
+        bumped.set(Some(priority));
+        Some(task::add_status(
+            &content,
+            now,
+            &format!(
+                "In the inbox for {age}, priority raised to {priority} ({})",
+                item.reason
+            ),
+        ))
+    });
+    match result {
+        Ok(Outcome::Written(_)) => {
+            let priority = bumped.get()?;
+            log::record(dir, &name, Event::Escalated { priority, age });
+            Some(priority)
+        }
+        Ok(_) => None,
+        Err(e) => {
+            eprintln!("[orch] failed to bump {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+/// Takes the inbox's items past a threshold a step further, returning each
+/// with what was done. Nothing happens twice for one stay in the inbox.
+pub fn check(dir: &Path, config: &Config) -> Vec<(InboxItem, Step)> {
+    let thresholds = &config.escalation;
+    let notify_after = threshold(&thresholds.notify_after);
+    let renotify_every = threshold(&thresholds.renotify_every);
+    let escalate_after = threshold(&thresholds.escalate_priority_after);
+    if notify_after.is_none() && escalate_after.is_none() {
+        return Vec::new();
+    }
+    let old = load(dir);
+    let mut escalations = BTreeMap::new();
+    let mut steps = Vec::new();
+    let now = Local::now();
+    for item in inbox::items(dir, config) {
+        let since = task::timestamp(item.since);
+        let mut escalation = old
+            .get(&item.label)
+            .filter(|e| e.since == since)
+            .cloned()
+            .unwrap_or(Escalation {
+                since,
+                notified: None,
+                bumped: false,
+            });
+        let age = (now - item.since).to_std().unwrap_or_default();
+        let mut step = None;
+        if escalate_after.is_some_and(|t| age >= t) && !escalation.bumped && !config.read_only {
+            escalation.bumped = true;
+            step = bump(dir, config, &item).map(Step::Priority);
+        }
+        if notify_after.is_some_and(|t| age >= t) {
+            let last = escalation
+                .notified
+                .as_deref()
+                .and_then(task::parse_timestamp);
+            let due = match last {
+                None => Some(Step::Notify),
+                Some(last) => renotify_every
+                    .filter(|every| (now - last).to_std().unwrap_or_default() >= *every)
+                    .map(|_| Step::Renotify),
+            };
+            if let Some(due) = due {
+                escalation.notified = Some(task::timestamp(now));
+                // A bump on the same check says more than the notification
+                step = step.or(Some(due));
+            }
+        }
+        escalations.insert(item.label.clone(), escalation);
+        if let Some(step) = step {
+            steps.push((item, step));
+        }
+    }
+    if escalations != old && !config.read_only {
+        save(dir, &escalations);
+    }
+    steps
+}
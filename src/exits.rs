@@ -0,0 +1,268 @@
+//! Hearing about workers that exit as it happens, rather than from the
+//! daemon's next look at tmux. The daemon hooks the first pane of every
+//! worker session it sees: when the worker's command exits, tmux runs
+//! `orch _worker-exited <task> <code>` and closes the pane. That adds a
+//! status entry, keeps the exit in `.orch/exits.json` and spools a
+//! `[worker-exited]` message for the daemon. If orch has since moved or been
+//! uninstalled the hook fails quietly, and the daemon's polling still finds
+//! the session gone.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, edits, error::OrchError, messages, paths, session, state, task, tmux};
+
+/// How a worker's session ended.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Exit {
+    /// RFC 3339.
+    pub at: String,
+    pub session: String,
+    /// None when it was killed by a signal.
+    pub code: Option<i32>,
+}
+
+impl Exit {
+    /// "exited 0", or "exited" without a code.
+    pub fn describe(&self) -> String {
+        match self.code {
+            Some(code) => format!("exited {code}"),
+            None => "exited".into(),
+        }
+    }
+
+    /// The `## Status` entry `_worker-exited` adds.
+    fn status_text(&self) -> String {
+        format!("Worker {} {}", self.session, self.describe())
+    }
+}
+
+/// The latest exit of each task's worker, by task name.
+pub fn load(dir: &Path) -> BTreeMap<String, Exit> {
+    state::load(&paths::exits_path(dir)).unwrap_or_default()
+}
+
+/// The exit of `name`'s worker, while nothing has happened since: its
+/// session is still gone and the task's latest status entry is the one the
+/// exit added.
+pub fn current(dir: &Path, name: &str, content: &str, live: bool) -> Option<Exit> {
+    if live {
+        return None;
+    }
+    let exit = load(dir).remove(name)?;
+    let latest = task::section(content, "## Status").last()?.trim_end();
+    latest.ends_with(&exit.status_text()).then_some(exit)
+}
+
+/// Records that `name`'s worker exited with `code`, under `## Status` and
+/// in `.orch/exits.json`, and tells the daemon. A task that's gone by now
+/// (closed, say) is left alone.
+pub fn exited(dir: &Path, config: &Config, name: &str, code: Option<i32>) -> Result<(), OrchError> {
+    let Some(path) = task::find(dir, name, config) else {
+        return Ok(());
+    };
+    let label = task::label(dir, &path);
+    let now = Local::now();
+    let exit = Exit {
+        at: task::timestamp(now),
+        session: task::session(config, &label, &task::read(&path, config)?),
+        code,
+    };
+    edits::apply(dir, config, &path, |content| {
+        Some(task::add_status(content, now, &exit.status_text()))
+    })?;
+
+    let mut exits = load(dir);
+    let name = path.file_stem().unwrap_or_default().to_string_lossy();
+    exits.insert(name.into_owned(), exit.clone());
+    let exits_path = paths::exits_path(dir);
+    let json = serde_json::to_string_pretty(&exits).unwrap_or_default();
+    fs::create_dir_all(paths::state_dir(dir))
+        .and_then(|_| task::write_atomic(&exits_path, &json))
+        .map_err(|e| OrchError::io(&exits_path, e))?;
+
+    messages::write(
+        &paths::inbox_dir(dir),
+        &format!(
+            "[worker-exited] {label}.md: {} {}",
+            exit.session,
+            exit.describe()
+        ),
+    )
+}
+
+/// `text` escaped for a double-quoted tmux string, with `#` kept from being
+/// read as a format. Formats copy a run of `#`s before `[` as is (it starts
+/// a style), so only other `#`s are doubled.
+fn tmux_escape(text: &str) -> String {
+    let text = text
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('$', r"\$");
+    let mut escaped = String::with_capacity(text.len());
+    let mut hashes = 0;
+    for c in text.chars() {
+        if c == '#' {
+            hashes += 1;
+            continue;
+        }
+        let n = if c == '[' { hashes } else { hashes * 2 };
+        escaped.extend(std::iter::repeat_n('#', n));
+        escaped.push(c);
+        hashes = 0;
+    }
+    escaped.extend(std::iter::repeat_n('#', hashes * 2));
+    escaped
+}
+
+/// The shell command a worker's pane runs when it dies: this orch, from the
+/// daemon's directory, tasks dir, state dir and config, reporting the pane's
+/// exit status.
+fn report_command(dir: &Path, name: &str) -> Option<String> {
+    let exe = std::env::current_exe().ok()?;
+    let cwd = std::env::current_dir().ok()?;
+    let mut env = vec![
+        ("ORCH_TASKS_DIR", dir.to_path_buf()),
+        ("ORCH_STATE_DIR", paths::state_dir(dir)),
+    ];
+    if let Some(config) = paths::config_path() {
+        env.push(("ORCH_CONFIG", config));
+    }
+    Some(exit_command(&cwd, &exe, &env, name))
+}
+
+/// [`report_command`] for `exe` run in `cwd` with `env`, as a double-quoted
+/// tmux string.
+fn exit_command(cwd: &Path, exe: &Path, env: &[(&str, PathBuf)], name: &str) -> String {
+    let env: String = env
+        .iter()
+        .map(|(var, path)| format!("{var}={} ", tmux::quote(&path.to_string_lossy())))
+        .collect();
+    let command = format!(
+        "cd {} && {env}{} _worker-exited {} ",
+        tmux::quote(&cwd.to_string_lossy()),
+        tmux::quote(&exe.to_string_lossy()),
+        tmux::quote(name)
+    );
+    format!(
+        "\"{}#{{pane_dead_status}} >/dev/null 2>&1 || true\"",
+        tmux_escape(&command)
+    )
+}
+
+/// Hooks the first pane of each live worker session not yet in `hooked`
+/// (pane ids, kept by the daemon across ticks). Failures are logged once.
+pub fn hook_workers(dir: &Path, config: &Config, hooked: &mut HashSet<String>) {
+    let live = tmux::sessions();
+    for task in task::list(dir, config) {
+        let session = task::session(config, &task.label, &task.content);
+        if session == session::CONSOLE || !live.contains(&session) {
+            continue;
+        }
+        let Some(pane) = tmux::first_pane(&session) else {
+            continue;
+        };
+        if !hooked.insert(pane.clone()) {
+            continue;
+        }
+        let Some(command) = report_command(dir, &task.name) else {
+            continue;
+        };
+        if let Err(e) = tmux::on_pane_died(&pane, &format!("run-shell -b {command}")) {
+            eprintln!("[orch] couldn't hook {session}'s exit, polling for it instead: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{os::unix::fs::PermissionsExt, process::Command};
+
+    use super::*;
+
+    /// Task names and paths that tmux or a shell would do something with.
+    const PAYLOADS: &[&str] = &[
+        "x'; touch pwned; echo '",
+        "$(touch pwned) `touch pwned` $HOME",
+        "#{pane_id} #[fg=red] ##[x] ###",
+        "#, #",
+        "\\\" ; kill-server ; \\",
+        "it's \"quoted\"",
+        "plain-name",
+    ];
+
+    #[test]
+    fn escaping_keeps_quotes_variables_and_formats_literal() {
+        assert_eq!(
+            tmux_escape(r#"say "hi" to $USER\ #{pane_id} # #, ##"#),
+            r#"say \"hi\" to \$USER\\ ##{pane_id} ## ##, ####"#
+        );
+        assert_eq!(tmux_escape("#[fg=red] ##[x] a#"), "#[fg=red] ##[x] a##");
+    }
+
+    /// `tmux args` on a server of the test's own at `socket`; None without
+    /// tmux installed.
+    fn tmux(socket: &Path, args: &[&str]) -> Option<String> {
+        let out = Command::new("tmux")
+            .arg("-S")
+            .arg(socket)
+            .args(["-f", "/dev/null"])
+            .args(args)
+            .env_remove("TMUX")
+            .output()
+            .ok()?;
+        Some(String::from_utf8_lossy(&out.stdout).trim().to_string())
+    }
+
+    /// Run the way the hook runs it, orch gets the task name and paths as
+    /// single words, however they're spelled. (The pane here is alive, so
+    /// its exit status expands to nothing.)
+    #[test]
+    fn the_exit_command_passes_payloads_through_as_words() {
+        let tmp = tempfile::tempdir().unwrap();
+        let socket = tmp.path().join("socket");
+        if tmux(&socket, &["new-session", "-d", "-s", "w", "sleep 60"]).is_none() {
+            eprintln!("skipped: no tmux to run the command");
+            return;
+        }
+        let pane = tmux(&socket, &["list-panes", "-t", "=w:", "-F", "#{pane_id}"]).unwrap();
+        for (i, payload) in PAYLOADS.iter().enumerate() {
+            let base = tmp.path().join(i.to_string());
+            let (cwd, tasks) = (base.join(payload), base.join(format!("tasks {payload}")));
+            fs::create_dir_all(&cwd).unwrap();
+            let exe = base.join(format!("orch {payload}"));
+            fs::write(
+                &exe,
+                "#!/bin/sh\nprintf '%s\\0' \"$PWD\" \"$ORCH_TASKS_DIR\" \"$@\" > \"$(dirname \"$0\")/out\"\n",
+            )
+            .unwrap();
+            fs::set_permissions(&exe, fs::Permissions::from_mode(0o755)).unwrap();
+            let command = exit_command(&cwd, &exe, &[("ORCH_TASKS_DIR", tasks.clone())], payload);
+
+            // Hooks are parsed like a config file
+            let conf = base.join("hook.conf");
+            fs::write(&conf, format!("run-shell -t {pane} {command}\n")).unwrap();
+            tmux(&socket, &["source-file", &conf.to_string_lossy()]);
+            let words = fs::read_to_string(base.join("out")).unwrap_or_default();
+            let words: Vec<_> = words.split_terminator('\0').collect();
+            assert_eq!(
+                words,
+                [
+                    &*cwd.to_string_lossy(),
+                    &*tasks.to_string_lossy(),
+                    "_worker-exited",
+                    payload,
+                ],
+                "{command}"
+            );
+        }
+        tmux(&socket, &["kill-server"]);
+        assert!(!tmp.path().join("pwned").exists());
+    }
+}
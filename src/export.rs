@@ -0,0 +1,502 @@
+//! `orch export`: tasks mirrored into other tools, through an [`Exporter`]
+//! per format. Each task gets a stable id, kept by label in
+//! `.orch/export.json` and written into the export, so `--sync` can find
+//! what an earlier export wrote for it and update that in place. Task files
+//! are only ever read.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{Local, NaiveDate};
+use clap::ValueEnum;
+
+use crate::{
+    config,
+    error::{self, OrchError},
+    frontmatter, paths, state, task,
+};
+
+/// The formats `orch export` writes.
+#[derive(Clone, Copy, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum ExportFormat {
+    /// One note per task, for an Obsidian vault
+    Obsidian,
+    /// One todo.txt file
+    TodoTxt,
+}
+
+impl ExportFormat {
+    pub fn exporter(self) -> Box<dyn Exporter> {
+        match self {
+            ExportFormat::Obsidian => Box::new(Obsidian),
+            ExportFormat::TodoTxt => Box::new(TodoTxt),
+        }
+    }
+}
+
+/// A task as exporters see it.
+pub struct Entry {
+    pub id: String,
+    pub name: String,
+    pub label: String,
+    pub content: String,
+}
+
+impl Entry {
+    /// The task's `# ` heading, else its name.
+    fn title(&self) -> String {
+        frontmatter::split(&self.content)
+            .1
+            .lines()
+            .find_map(|l| l.trim().strip_prefix("# "))
+            .unwrap_or(&self.name)
+            .trim()
+            .to_string()
+    }
+
+    /// The project folder, if it's in one.
+    fn project(&self) -> Option<&str> {
+        self.label.rsplit_once('/').map(|(project, _)| project)
+    }
+}
+
+/// A task found in an earlier export.
+pub struct Exported {
+    pub id: String,
+    pub label: String,
+    pub path: PathBuf,
+}
+
+/// What an export did.
+#[derive(Default)]
+pub struct Written {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    /// Exported earlier for tasks that are gone; left as they were.
+    pub orphaned: usize,
+}
+
+/// A format `orch export` writes.
+pub trait Exporter {
+    /// What an earlier export left at `out`.
+    fn exported(&self, out: &Path) -> Vec<Exported>;
+    /// Writes `entries` to `out`. Without `sync`, refuses to touch anything
+    /// already there; with it, updates what [`Exporter::exported`] finds.
+    fn write(&self, entries: &[Entry], out: &Path, sync: bool) -> Result<Written, OrchError>;
+}
+
+/// Each task's export id, by label.
+fn load_ids(dir: &Path) -> BTreeMap<String, String> {
+    state::load(&paths::export_ids_path(dir)).unwrap_or_default()
+}
+
+fn save_ids(dir: &Path, ids: &BTreeMap<String, String>) {
+    let path = paths::export_ids_path(dir);
+    let result = fs::create_dir_all(paths::state_dir(dir)).and_then(|_| {
+        let json = serde_json::to_string_pretty(ids).unwrap_or_default();
+        task::write_atomic(&path, &json)
+    });
+    match result {
+        Ok(()) => {}
+        // Read-only tasks can still be exported: a later export finds the
+        // ids in what this one wrote
+        Err(e) if error::read_only(&e) => {}
+        Err(e) => eprintln!("[orch] failed to save {}: {e}", path.display()),
+    }
+}
+
+/// Carries a task's export id over to its new label after a move.
+pub fn relabel(dir: &Path, from: &str, to: &str) {
+    let mut ids = load_ids(dir);
+    if let Some(id) = ids.remove(from) {
+        ids.insert(to.to_string(), id);
+        save_ids(dir, &ids);
+    }
+}
+
+/// The tasks to export, each with its id: the recorded one, else the one an
+/// earlier export in `earlier` gave its label, else a new one.
+pub fn entries(dir: &Path, config: &config::Config, earlier: &[Exported]) -> Vec<Entry> {
+    let old = load_ids(dir);
+    let mut ids = old.clone();
+    let now = Local::now().timestamp_nanos_opt().unwrap_or_default();
+    let entries: Vec<Entry> = task::list(dir, config)
+        .into_iter()
+        .filter(|t| t.unreadable.is_none())
+        .map(|t| {
+            let id = ids
+                .get(&t.label)
+                .cloned()
+                .or_else(|| {
+                    earlier
+                        .iter()
+                        .find(|e| e.label == t.label)
+                        .map(|e| e.id.clone())
+                })
+                .unwrap_or_else(|| {
+                    let seed = format!("{}\n{}\n{now}", t.label, task::timestamp(t.created));
+                    format!("{:016x}", config::fnv(seed.as_bytes()))
+                });
+            ids.insert(t.label.clone(), id.clone());
+            Entry {
+                id,
+                name: t.name,
+                label: t.label,
+                content: t.content,
+            }
+        })
+        .collect();
+    if ids != old && !config.read_only {
+        save_ids(dir, &ids);
+    }
+    entries
+}
+
+/// Why an export without `--sync` won't write over `path` and `more` others.
+fn refuse(path: &Path, more: usize) -> OrchError {
+    let more = match more {
+        0 => String::new(),
+        n => format!(" (and {n} more)"),
+    };
+    OrchError::Usage(format!(
+        "{} already exists{more}; pass --sync to update an earlier export.",
+        path.display()
+    ))
+}
+
+/// Writes `content` to `path` unless it's already that. True if it wrote.
+fn put(path: &Path, content: &str) -> Result<bool, OrchError> {
+    if fs::read_to_string(path).is_ok_and(|old| old == content) {
+        return Ok(false);
+    }
+    path.parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| task::write_atomic(path, content))
+        .map_err(|e| OrchError::io(path, e))?;
+    Ok(true)
+}
+
+/// The comma-separated names in the frontmatter `key`.
+fn names(content: &str, key: &str) -> Vec<String> {
+    frontmatter::get(content, key)
+        .map(|v| {
+            v.split(',')
+                .map(|n| n.trim().to_string())
+                .filter(|n| !n.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `due:` as a date: a date, or the day of a timestamp.
+fn due_date(content: &str) -> Option<NaiveDate> {
+    let due = frontmatter::get(content, "due")?;
+    NaiveDate::parse_from_str(due.trim(), "%Y-%m-%d")
+        .ok()
+        .or_else(|| task::parse_timestamp(&due).map(|t| t.date_naive()))
+}
+
+/// Notes in an Obsidian vault folder: `<label>.md` per task, its frontmatter
+/// as properties and its delegations as wiki-links.
+pub struct Obsidian;
+
+/// Frontmatter keys given their own property rather than passed through.
+const MAPPED: &[&str] = &[
+    "title",
+    "tags",
+    "status",
+    "priority",
+    "due",
+    "created",
+    "delegated_to",
+    "delegated_from",
+    "orch_id",
+    "orch_task",
+];
+
+/// A YAML double-quoted string; JSON's quoting is valid YAML.
+fn quoted(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+impl Obsidian {
+    /// The note for `entry`, with `links` from task names and labels to the
+    /// wiki-link targets of their notes.
+    fn note(entry: &Entry, links: &HashMap<&str, String>) -> String {
+        let content = &entry.content;
+        let mut fm = vec![
+            format!("orch_id: {}", entry.id),
+            format!("orch_task: {}", quoted(&entry.label)),
+            format!("title: {}", quoted(&entry.title())),
+        ];
+        if let Some(status) = frontmatter::get(content, "status").filter(|s| !s.is_empty()) {
+            fm.push(format!("status: {}", quoted(&status)));
+        }
+        if let Some(priority) = frontmatter::get(content, "priority") {
+            match priority.parse::<f64>() {
+                Ok(_) => fm.push(format!("priority: {priority}")),
+                Err(_) => fm.push(format!("priority: {}", quoted(&priority))),
+            }
+        }
+        if let Some(due) = due_date(content) {
+            fm.push(format!("due: {}", due.format("%Y-%m-%d")));
+        }
+        if let Some(created) =
+            frontmatter::get(content, "created").and_then(|c| task::parse_timestamp(&c))
+        {
+            fm.push(format!("created: {}", created.format("%Y-%m-%dT%H:%M:%S")));
+        }
+        let mut tags = vec!["orch".to_string()];
+        tags.extend(entry.project().map(|p| p.replace(' ', "-")));
+        fm.push("tags:".into());
+        fm.extend(tags.iter().map(|t| format!("  - {}", quoted(t))));
+        for key in ["delegated_to", "delegated_from"] {
+            let names = names(content, key);
+            if names.is_empty() {
+                continue;
+            }
+            fm.push(format!("{key}:"));
+            for name in names {
+                let target = links.get(name.as_str()).cloned().unwrap_or(name);
+                fm.push(format!("  - {}", quoted(&format!("[[{target}]]"))));
+            }
+        }
+        let (own, body) = frontmatter::split(content);
+        for line in own.unwrap_or("").lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim();
+            if !key.is_empty() && !MAPPED.contains(&key) {
+                fm.push(format!("{key}: {}", quoted(value.trim())));
+            }
+        }
+        format!(
+            "---\n{}\n---\n{}",
+            fm.join("\n"),
+            body.trim_start_matches('\n')
+        )
+    }
+}
+
+impl Exporter for Obsidian {
+    fn exported(&self, out: &Path) -> Vec<Exported> {
+        fn walk(dir: &Path, found: &mut Vec<Exported>) {
+            let Ok(entries) = fs::read_dir(dir) else {
+                return;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                if hidden {
+                    continue;
+                }
+                if path.is_dir() {
+                    walk(&path, found);
+                } else if path.extension().is_some_and(|e| e == "md") {
+                    let content = fs::read_to_string(&path).unwrap_or_default();
+                    if let Some(id) = frontmatter::get(&content, "orch_id") {
+                        let label = frontmatter::get(&content, "orch_task")
+                            .map(|l| serde_json::from_str(&l).unwrap_or(l))
+                            .unwrap_or_default();
+                        found.push(Exported { id, label, path });
+                    }
+                }
+            }
+        }
+        let mut found = Vec::new();
+        walk(out, &mut found);
+        found
+    }
+
+    fn write(&self, entries: &[Entry], out: &Path, sync: bool) -> Result<Written, OrchError> {
+        let earlier = self.exported(out);
+        // A wiki-link by the note's name unless another task shares it
+        let mut by_name: HashMap<&str, usize> = HashMap::new();
+        for entry in entries {
+            *by_name.entry(entry.name.as_str()).or_default() += 1;
+        }
+        let mut links = HashMap::new();
+        for entry in entries {
+            let target = if by_name[entry.name.as_str()] == 1 {
+                entry.name.clone()
+            } else {
+                entry.label.clone()
+            };
+            links.insert(entry.label.as_str(), target.clone());
+            links.entry(entry.name.as_str()).or_insert(target);
+        }
+
+        let plan: Vec<(PathBuf, Option<&PathBuf>, &Entry)> = entries
+            .iter()
+            .map(|entry| {
+                let path = out.join(format!("{}.md", entry.label));
+                let old = earlier.iter().find(|e| e.id == entry.id).map(|e| &e.path);
+                (path, old, entry)
+            })
+            .collect();
+        if !sync {
+            let mut existing: Vec<PathBuf> = plan
+                .iter()
+                .filter(|(path, ..)| path.exists())
+                .map(|(path, ..)| path.clone())
+                .chain(earlier.iter().map(|e| e.path.clone()))
+                .collect();
+            existing.sort();
+            existing.dedup();
+            if let Some(first) = existing.first() {
+                return Err(refuse(first, existing.len() - 1));
+            }
+        }
+
+        let mut written = Written::default();
+        for (path, old, entry) in &plan {
+            let existed = path.exists();
+            if !put(path, &Self::note(entry, &links))? {
+                written.unchanged += 1;
+            } else if existed || old.is_some() {
+                written.updated += 1;
+            } else {
+                written.created += 1;
+            }
+            // Renamed since: the note moves along instead of leaving a copy
+            if let Some(old) = old
+                && *old != path
+                && let Err(e) = fs::remove_file(old)
+                && e.kind() != io::ErrorKind::NotFound
+            {
+                return Err(OrchError::io(old, e));
+            }
+        }
+        written.orphaned = earlier
+            .iter()
+            .filter(|e| !entries.iter().any(|entry| entry.id == e.id))
+            .count();
+        Ok(written)
+    }
+}
+
+/// A todo.txt file: a line per task with its priority letter, `+project`,
+/// `due:`, `status:` and the `orch:` id. A done task is marked `x`.
+pub struct TodoTxt;
+
+/// The `key:value` on a todo.txt line, if any.
+fn tag<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    line.split_whitespace()
+        .find_map(|word| word.strip_prefix(key)?.strip_prefix(':'))
+}
+
+impl TodoTxt {
+    /// `(A)` for priority 3 and up, `(B)` for 2, `(C)` for 1.
+    fn letter(content: &str) -> Option<char> {
+        let priority = frontmatter::get(content, "priority")?.parse::<f64>().ok()?;
+        match priority {
+            p if p >= 3.0 => Some('A'),
+            p if p >= 2.0 => Some('B'),
+            p if p >= 1.0 => Some('C'),
+            _ => None,
+        }
+    }
+
+    fn line(entry: &Entry) -> String {
+        let content = &entry.content;
+        let mut words = Vec::new();
+        let done = task::progress(content) == task::Progress::Done;
+        if done {
+            words.push("x".to_string());
+        } else if let Some(letter) = Self::letter(content) {
+            words.push(format!("({letter})"));
+        }
+        if let Some(created) =
+            frontmatter::get(content, "created").and_then(|c| task::parse_timestamp(&c))
+        {
+            words.push(created.format("%Y-%m-%d").to_string());
+        }
+        words.push(entry.title().replace('\n', " "));
+        if let Some(project) = entry.project() {
+            words.push(format!("+{}", project.replace(' ', "-")));
+        }
+        if let Some(due) = due_date(content) {
+            words.push(format!("due:{}", due.format("%Y-%m-%d")));
+        }
+        if let Some(status) = frontmatter::get(content, "status").filter(|s| !s.is_empty()) {
+            words.push(format!("status:{}", status.replace(' ', "-")));
+        }
+        if done && let Some(letter) = Self::letter(content) {
+            words.push(format!("pri:{letter}"));
+        }
+        words.push(format!("task:{}", entry.label.replace(' ', "-")));
+        words.push(format!("orch:{}", entry.id));
+        words.join(" ")
+    }
+}
+
+impl Exporter for TodoTxt {
+    fn exported(&self, out: &Path) -> Vec<Exported> {
+        let text = fs::read_to_string(out).unwrap_or_default();
+        text.lines()
+            .filter_map(|line| {
+                Some(Exported {
+                    id: tag(line, "orch")?.to_string(),
+                    label: tag(line, "task").unwrap_or_default().to_string(),
+                    path: out.to_path_buf(),
+                })
+            })
+            .collect()
+    }
+
+    fn write(&self, entries: &[Entry], out: &Path, sync: bool) -> Result<Written, OrchError> {
+        let mut written = Written::default();
+        let old = match fs::read_to_string(out) {
+            Ok(_) if !sync => return Err(refuse(out, 0)),
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(OrchError::io(out, e)),
+        };
+        let mut lines: Vec<String> = Vec::new();
+        let mut placed = Vec::new();
+        // Lines of an earlier export are updated where they are; the user's
+        // own lines and those of tasks that are gone stay as they were
+        for line in old.lines() {
+            let entry = tag(line, "orch").and_then(|id| entries.iter().find(|e| e.id == id));
+            match entry {
+                Some(entry) if !placed.contains(&entry.id) => {
+                    let new = Self::line(entry);
+                    if new == line {
+                        written.unchanged += 1;
+                    } else {
+                        written.updated += 1;
+                    }
+                    placed.push(entry.id.clone());
+                    lines.push(new);
+                }
+                Some(_) => {}
+                None => {
+                    if tag(line, "orch").is_some() {
+                        written.orphaned += 1;
+                    }
+                    lines.push(line.to_string());
+                }
+            }
+        }
+        for entry in entries.iter().filter(|e| !placed.contains(&e.id)) {
+            written.created += 1;
+            lines.push(Self::line(entry));
+        }
+        let mut text = lines.join("\n");
+        text.push('\n');
+        if text != old {
+            out.parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| task::write_atomic(out, &text))
+                .map_err(|e| OrchError::io(out, e))?;
+        }
+        Ok(written)
+    }
+}
@@ -0,0 +1,334 @@
+//! How durations, sizes, counts and times look in output, so every command
+//! says `12m`, `2.3 MiB` and `3 tasks` the same way. Times are local unless
+//! `--utc` ([`set_utc`]), for output pasted where readers are elsewhere.
+//! Timestamps written into files (status entries, run ids, JSON) aren't
+//! display and don't come through here.
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local, Utc};
+
+static UTC: AtomicBool = AtomicBool::new(false);
+
+/// Renders times in UTC from now on, marked as such.
+pub fn set_utc(utc: bool) {
+    UTC.store(utc, Ordering::Relaxed);
+}
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+const YEAR: i64 = 365 * DAY;
+
+/// Units from largest, as (seconds, compact suffix, long name).
+const UNITS: &[(i64, &str, &str)] = &[
+    (YEAR, "y", "year"),
+    (DAY, "d", "day"),
+    (HOUR, "h", "hour"),
+    (MINUTE, "m", "minute"),
+    (1, "s", "second"),
+];
+
+/// A span as its largest whole unit: `45s`, `12m`, `3h`, `2d`, `1y`.
+/// Negative spans count as none.
+pub fn secs(secs: i64) -> String {
+    let secs = secs.max(0);
+    let &(unit, suffix, _) = UNITS
+        .iter()
+        .find(|(unit, ..)| secs >= *unit)
+        .unwrap_or(&UNITS[UNITS.len() - 1]);
+    format!("{}{suffix}", secs / unit)
+}
+
+/// [`secs`] of `d`, with a span under a second in milliseconds: `350ms`.
+pub fn duration(d: Duration) -> String {
+    if !d.is_zero() && d < Duration::from_secs(1) {
+        return format!("{}ms", d.as_millis());
+    }
+    secs(i64::try_from(d.as_secs()).unwrap_or(i64::MAX))
+}
+
+/// A duration recorded as (fractional) seconds, as [`duration`].
+pub fn elapsed(secs: f64) -> String {
+    duration(Duration::try_from_secs_f64(secs).unwrap_or_default())
+}
+
+/// `d` in words, by its two largest units: `45 seconds`, `1 hour 5
+/// minutes`, `2 days`, `1 year 3 days`.
+pub fn long(d: Duration) -> String {
+    if d.is_zero() {
+        return "0 seconds".into();
+    }
+    if d < Duration::from_secs(1) {
+        return count(d.as_millis() as usize, "millisecond");
+    }
+    let mut left = i64::try_from(d.as_secs()).unwrap_or(i64::MAX);
+    let mut parts = Vec::new();
+    for &(unit, _, name) in UNITS {
+        if left >= unit {
+            parts.push(count((left / unit) as usize, name));
+            left %= unit;
+        } else if !parts.is_empty() {
+            // `1 hour 5 seconds` would skip a unit; stop instead
+            break;
+        }
+        if parts.len() == 2 {
+            break;
+        }
+    }
+    parts.join(" ")
+}
+
+/// How long ago `since` was, compactly: `12m`.
+pub fn age(since: DateTime<Local>) -> String {
+    secs((Local::now() - since).num_seconds())
+}
+
+/// How long until `at`, compactly; `0s` once it's past.
+pub fn until(at: DateTime<Local>) -> String {
+    secs((at - Local::now()).num_seconds())
+}
+
+/// `512 B`, `2.3 KiB`, `63 MiB`: one decimal below 10 of a unit.
+pub fn bytes(n: u64) -> String {
+    const SIZES: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+    if n < 1024 {
+        return format!("{n} B");
+    }
+    let mut value = n as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < SIZES.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 {
+        format!("{value:.1} {}", SIZES[unit])
+    } else {
+        format!("{} {}", value as u64, SIZES[unit])
+    }
+}
+
+/// `noun` for `n` of them: `task`, `tasks`, `entries`, `matches`.
+pub fn plural(n: usize, noun: &str) -> String {
+    if n == 1 {
+        return noun.to_string();
+    }
+    let consonant_y = noun.strip_suffix('y').is_some_and(|stem| {
+        stem.ends_with(|c: char| c.is_ascii_alphabetic() && !"aeiou".contains(c))
+    });
+    if consonant_y {
+        format!("{}ies", &noun[..noun.len() - 1])
+    } else if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|end| noun.ends_with(end))
+    {
+        format!("{noun}es")
+    } else {
+        format!("{noun}s")
+    }
+}
+
+/// `1 task`, `3 tasks`, `0 entries`.
+pub fn count(n: usize, noun: &str) -> String {
+    format!("{n} {}", plural(n, noun))
+}
+
+/// `t` in `format`, in UTC under `--utc`.
+fn render(t: DateTime<Local>, format: &str) -> String {
+    if UTC.load(Ordering::Relaxed) {
+        t.with_timezone(&Utc).format(format).to_string()
+    } else {
+        t.format(format).to_string()
+    }
+}
+
+/// ` UTC` under `--utc`, for times that stand alone.
+fn zone() -> &'static str {
+    if UTC.load(Ordering::Relaxed) {
+        " UTC"
+    } else {
+        ""
+    }
+}
+
+/// `Tue Oct 15 14:02`.
+pub fn time(t: DateTime<Local>) -> String {
+    format!("{}{}", render(t, "%a %b %-d %H:%M"), zone())
+}
+
+/// `2026-10-15 14:02`, where the year matters.
+pub fn date_time(t: DateTime<Local>) -> String {
+    format!("{}{}", render(t, "%Y-%m-%d %H:%M"), zone())
+}
+
+/// `Tue Oct 15`, heading times shown with [`clock`].
+pub fn day(t: DateTime<Local>) -> String {
+    render(t, "%a %b %-d")
+}
+
+/// `14:02`, under a [`day`] or next to a [`time`].
+pub fn clock(t: DateTime<Local>) -> String {
+    render(t, "%H:%M")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn secs_picks_the_largest_whole_unit() {
+        let cases = [
+            (0, "0s"),
+            (1, "1s"),
+            (59, "59s"),
+            (MINUTE, "1m"),
+            (HOUR - 1, "59m"),
+            (HOUR, "1h"),
+            (DAY - 1, "23h"),
+            (DAY, "1d"),
+            (YEAR - 1, "364d"),
+            (YEAR, "1y"),
+            (3 * YEAR + 200 * DAY, "3y"),
+        ];
+        for (n, want) in cases {
+            assert_eq!(secs(n), want, "{n}");
+        }
+        assert_eq!(secs(i64::MAX), format!("{}y", i64::MAX / YEAR));
+    }
+
+    #[test]
+    fn negative_spans_are_none() {
+        assert_eq!(secs(-1), "0s");
+        assert_eq!(secs(i64::MIN), "0s");
+        assert_eq!(elapsed(-3.0), "0s");
+    }
+
+    #[test]
+    fn duration_shows_sub_second_spans_in_milliseconds() {
+        assert_eq!(duration(Duration::ZERO), "0s");
+        assert_eq!(duration(Duration::from_nanos(1)), "0ms");
+        assert_eq!(duration(ms(1)), "1ms");
+        assert_eq!(duration(ms(350)), "350ms");
+        assert_eq!(duration(ms(999)), "999ms");
+        assert_eq!(duration(ms(1000)), "1s");
+        assert_eq!(duration(ms(1500)), "1s");
+        assert_eq!(duration(Duration::from_secs(400 * 86400)), "1y");
+        assert_eq!(duration(Duration::MAX), format!("{}y", i64::MAX / YEAR));
+    }
+
+    #[test]
+    fn elapsed_takes_fractional_seconds() {
+        assert_eq!(elapsed(0.0), "0s");
+        assert_eq!(elapsed(0.35), "350ms");
+        assert_eq!(elapsed(84.2), "1m");
+        assert_eq!(elapsed(f64::NAN), "0s");
+        assert_eq!(elapsed(f64::INFINITY), "0s");
+        assert_eq!(elapsed(2.0 * YEAR as f64), "2y");
+    }
+
+    #[test]
+    fn long_uses_the_two_largest_units_without_gaps() {
+        let s = Duration::from_secs;
+        let cases = [
+            (Duration::ZERO, "0 seconds"),
+            (ms(1), "1 millisecond"),
+            (ms(350), "350 milliseconds"),
+            (s(1), "1 second"),
+            (ms(1500), "1 second"),
+            (s(45), "45 seconds"),
+            (s(60), "1 minute"),
+            (s(3600 + 5 * 60), "1 hour 5 minutes"),
+            (s(3600 + 5), "1 hour"),
+            (s(3600 + 61), "1 hour 1 minute"),
+            (s(2 * 86400), "2 days"),
+            (s(86400 + 3600 + 60), "1 day 1 hour"),
+            (s(365 * 86400 + 3 * 86400), "1 year 3 days"),
+            (s(365 * 86400 + 3600), "1 year"),
+            (s(800 * 86400), "2 years 70 days"),
+        ];
+        for (d, want) in cases {
+            assert_eq!(long(d), want, "{d:?}");
+        }
+    }
+
+    #[test]
+    fn age_and_until_are_relative_to_now() {
+        let now = Local::now();
+        assert_eq!(age(now), "0s");
+        assert_eq!(age(now - chrono::Duration::hours(3)), "3h");
+        assert_eq!(age(now - chrono::Duration::days(800)), "2y");
+        // A time ahead is no time ago, and one past is no time away
+        assert_eq!(age(now + chrono::Duration::hours(1)), "0s");
+        assert_eq!(until(now - chrono::Duration::hours(1)), "0s");
+        let until_2h = until(now + chrono::Duration::hours(2) + chrono::Duration::seconds(5));
+        assert_eq!(until_2h, "2h");
+    }
+
+    #[test]
+    fn bytes_use_binary_units() {
+        let cases = [
+            (0, "0 B"),
+            (1, "1 B"),
+            (1023, "1023 B"),
+            (1024, "1.0 KiB"),
+            (1536, "1.5 KiB"),
+            (10 * 1024 - 1, "10.0 KiB"),
+            (10 * 1024, "10 KiB"),
+            (1024 * 1024 - 1, "1023 KiB"),
+            (1024 * 1024, "1.0 MiB"),
+            (63 * 1024 * 1024, "63 MiB"),
+            (1 << 30, "1.0 GiB"),
+            (1 << 40, "1.0 TiB"),
+            (1 << 50, "1024 TiB"),
+            (u64::MAX, "16777216 TiB"),
+        ];
+        for (n, want) in cases {
+            assert_eq!(bytes(n), want, "{n}");
+        }
+    }
+
+    #[test]
+    fn plurals() {
+        let cases = [
+            (1, "task", "task"),
+            (0, "task", "tasks"),
+            (2, "task", "tasks"),
+            (2, "entry", "entries"),
+            (2, "day", "days"),
+            (2, "match", "matches"),
+            (2, "box", "boxes"),
+            (2, "bus", "buses"),
+            (2, "wish", "wishes"),
+            (2, "y", "ys"),
+        ];
+        for (n, noun, want) in cases {
+            assert_eq!(plural(n, noun), want, "{n} {noun}");
+        }
+        assert_eq!(count(0, "entry"), "0 entries");
+        assert_eq!(count(1, "entry"), "1 entry");
+        assert_eq!(count(3, "task"), "3 tasks");
+    }
+
+    #[test]
+    fn times_in_the_local_zone() {
+        let t = NaiveDate::from_ymd_opt(2026, 10, 6)
+            .unwrap()
+            .and_hms_opt(9, 5, 0)
+            .unwrap()
+            .and_local_timezone(Local)
+            .unwrap();
+        assert_eq!(time(t), "Tue Oct 6 09:05");
+        assert_eq!(date_time(t), "2026-10-06 09:05");
+        assert_eq!(day(t), "Tue Oct 6");
+        assert_eq!(clock(t), "09:05");
+    }
+}
@@ -0,0 +1,77 @@
+//! The task commands act on when they aren't given one. `orch focus auth`
+//! sets it explicitly; with `orch focus --auto on`, being attached to a
+//! worker's tmux session makes that worker's task the focus instead, and
+//! the explicit one is the fallback. A name on the command line always
+//! wins. Both are kept in `.orch/focus.json`, not the config, since orch
+//! doesn't rewrite the user's config file.
+
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, error::OrchError, paths, session, state, task, tmux};
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Focus {
+    /// Set with `orch focus <name>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    /// Follow the attached tmux session.
+    #[serde(default)]
+    pub auto: bool,
+}
+
+pub fn load(dir: &Path) -> Focus {
+    state::load(&paths::focus_path(dir)).unwrap_or_default()
+}
+
+pub fn save(dir: &Path, focus: &Focus) -> Result<(), OrchError> {
+    let path = paths::focus_path(dir);
+    let json = serde_json::to_string_pretty(focus).unwrap_or_default();
+    fs::create_dir_all(paths::state_dir(dir))
+        .and_then(|_| task::write_atomic(&path, &json))
+        .map_err(|e| OrchError::io(&path, e))
+}
+
+/// The task whose worker session this process is attached to. None outside
+/// tmux, or in a session that isn't a worker's; never an error.
+pub fn attached(dir: &Path, config: &Config) -> Option<String> {
+    let current = tmux::current_session()?;
+    if !current.starts_with(session::prefix(config)) {
+        return None;
+    }
+    let path = session::task_for(dir, config, &current)?;
+    Some(path.file_stem()?.to_string_lossy().into_owned())
+}
+
+/// Where the focus comes from, for `orch focus`.
+pub enum Source {
+    Attached,
+    Explicit,
+}
+
+/// The focused task: the attached worker's with `auto` on, else the one
+/// set explicitly.
+pub fn current(dir: &Path, config: &Config) -> Option<(String, Source)> {
+    let focus = load(dir);
+    focus
+        .auto
+        .then(|| attached(dir, config))
+        .flatten()
+        .map(|name| (name, Source::Attached))
+        .or_else(|| focus.task.map(|name| (name, Source::Explicit)))
+}
+
+/// `name` if one was given, else the focused task.
+pub fn resolve(dir: &Path, config: &Config, name: Option<String>) -> Result<String, OrchError> {
+    if let Some(name) = name {
+        return Ok(name);
+    }
+    current(dir, config).map(|(name, _)| name).ok_or_else(|| {
+        OrchError::Usage(
+            "No task given and none focused; name one, or set a default with \
+             `orch focus <name>` (or `orch focus --auto on` to follow tmux)."
+                .into(),
+        )
+    })
+}
@@ -0,0 +1,67 @@
+//! A leading `---` block of `key: value` lines in a task file.
+
+/// Splits an optional leading `---` block off `content`, returning its inner
+/// lines and the body after it.
+pub fn split(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content.strip_prefix("---\n") else {
+        return (None, content);
+    };
+    if let Some(body) = rest.strip_prefix("---\n") {
+        return (Some(""), body);
+    }
+    match rest.find("\n---\n") {
+        Some(end) => (Some(&rest[..end + 1]), &rest[end + 5..]),
+        None => match rest.strip_suffix("\n---") {
+            Some(fm) => (Some(fm), ""),
+            None => (None, content),
+        },
+    }
+}
+
+/// Keys given more than once, each listed once. [`get`] reads the first.
+pub fn duplicate_keys(content: &str) -> Vec<String> {
+    let (fm, _) = split(content);
+    let mut seen = Vec::new();
+    let mut dups = Vec::new();
+    for key in fm
+        .unwrap_or("")
+        .lines()
+        .filter_map(|l| l.split_once(':').map(|(k, _)| k.trim()))
+    {
+        if seen.contains(&key) {
+            if !dups.iter().any(|d| d == key) {
+                dups.push(key.to_string());
+            }
+        } else {
+            seen.push(key);
+        }
+    }
+    dups
+}
+
+pub fn get(content: &str, key: &str) -> Option<String> {
+    let (fm, _) = split(content);
+    fm?.lines().find_map(|l| {
+        let (k, v) = l.split_once(':')?;
+        (k.trim() == key).then(|| v.trim().to_string())
+    })
+}
+
+/// Sets (or with `None`, removes) `key` in the frontmatter, adding the block
+/// if the file has none.
+pub fn set(content: &str, key: &str, value: Option<&str>) -> String {
+    let (fm, body) = split(content);
+    let mut lines: Vec<String> = fm
+        .unwrap_or("")
+        .lines()
+        .filter(|l| l.split_once(':').is_none_or(|(k, _)| k.trim() != key))
+        .map(str::to_string)
+        .collect();
+    if let Some(value) = value {
+        lines.push(format!("{key}: {value}"));
+    }
+    if lines.is_empty() {
+        return body.to_string();
+    }
+    format!("---\n{}\n---\n{body}", lines.join("\n"))
+}
@@ -0,0 +1,150 @@
+//! Repository context for `orch new --repo`, and the diffs `orch review`
+//! reads. Every call is `git -C <repo>` as an argv, like [`crate::tmux`].
+
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::fmt;
+
+/// Commits listed in the context block.
+const RECENT_COMMITS: usize = 5;
+
+/// Runs `git -C repo <args>`, returning its trimmed stdout.
+fn git(repo: &Path, args: &[&str]) -> Result<String, String> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("failed to run git: {e}"))?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        let first = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+        return Err(format!("git {} failed: {first}", args.join(" ")));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).trim_end().to_string())
+}
+
+/// `Ok(false)` when git runs but `path` isn't inside a work tree; `Err` when
+/// git itself can't be run.
+pub fn is_work_tree(path: &Path) -> Result<bool, String> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("failed to run git: {e}"))?;
+    Ok(out.status.success() && String::from_utf8_lossy(&out.stdout).trim() == "true")
+}
+
+/// `git status --porcelain` of `repo`.
+pub fn status(repo: &Path) -> Result<String, String> {
+    git(repo, &["status", "--porcelain"])
+}
+
+/// The branch `repo` has checked out; None when HEAD is detached.
+pub fn current_branch(repo: &Path) -> Result<Option<String>, String> {
+    let branch = git(repo, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+    Ok((branch != "HEAD").then_some(branch))
+}
+
+/// The branch work in `repo` usually merges into: what `origin/HEAD` points
+/// at, else a local `main` or `master`.
+pub fn default_branch(repo: &Path) -> Option<String> {
+    if let Ok(remote) = git(
+        repo,
+        &["symbolic-ref", "--short", "refs/remotes/origin/HEAD"],
+    ) {
+        return Some(remote);
+    }
+    ["main", "master"]
+        .into_iter()
+        .find(|branch| {
+            git(
+                repo,
+                &[
+                    "rev-parse",
+                    "--verify",
+                    "--quiet",
+                    &format!("refs/heads/{branch}"),
+                ],
+            )
+            .is_ok()
+        })
+        .map(String::from)
+}
+
+/// What `repo`'s working tree has that `base` doesn't: commits since the
+/// two diverged and uncommitted changes, as one diff. Untracked files
+/// aren't in it.
+pub fn diff_since(repo: &Path, base: &str) -> Result<String, String> {
+    let fork = git(repo, &["merge-base", base, "HEAD"])?;
+    git(repo, &["diff", &fork])
+}
+
+/// Switches `repo` to `branch`, creating it from HEAD if it doesn't exist.
+pub fn switch_branch(repo: &Path, branch: &str) -> Result<(), String> {
+    let exists = git(
+        repo,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/heads/{branch}"),
+        ],
+    )
+    .is_ok();
+    if exists {
+        git(repo, &["switch", branch])
+    } else {
+        git(repo, &["switch", "-c", branch])
+    }
+    .map(|_| ())
+}
+
+/// A `## Repository` section for the task body: path, branch, remote,
+/// uncommitted file count and recent commits. Parts git can't report are
+/// left out, with the reasons returned alongside.
+pub fn context(repo: &Path) -> (String, Vec<String>) {
+    let mut errors = Vec::new();
+    let mut ok = |r: Result<String, String>| r.map_err(|e| errors.push(e)).ok();
+
+    let branch = ok(git(repo, &["rev-parse", "--abbrev-ref", "HEAD"]));
+    let remote = git(repo, &["config", "--get", "remote.origin.url"]).ok();
+    let dirty = ok(status(repo)).map(|s| s.lines().count());
+    let commits = ok(git(
+        repo,
+        &["log", &format!("-{RECENT_COMMITS}"), "--format=%h %s"],
+    ));
+
+    let mut block = format!("## Repository\n\n- path: {}\n", repo.display());
+    if let Some(branch) = branch {
+        let branch = if branch == "HEAD" {
+            "(detached)".to_string()
+        } else {
+            branch
+        };
+        block.push_str(&format!("- branch: {branch}\n"));
+    }
+    if let Some(remote) = remote {
+        block.push_str(&format!("- remote: {remote}\n"));
+    }
+    if let Some(dirty) = dirty {
+        block.push_str(&format!(
+            "- uncommitted changes: {}\n",
+            fmt::count(dirty, "file")
+        ));
+    }
+    if let Some(commits) = commits.filter(|c| !c.is_empty()) {
+        block.push_str("\nRecent commits:\n\n");
+        for line in commits.lines() {
+            block.push_str(&format!("    {line}\n"));
+        }
+    }
+    (block, errors)
+}
@@ -0,0 +1,207 @@
+//! The running daemon's state in `.orch/daemon.json`, so other commands can
+//! tell whether it's alive and when it last ran the orchestrator.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{actions::ScanAction, config, notify::Notifiers, paths, state, task};
+
+/// A daemon that hasn't checked in for this long (and isn't mid-scan) is
+/// presumed dead. The daemon checks in every 30s.
+const STALE: Duration = Duration::from_secs(90);
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Health {
+    pub pid: u32,
+    /// RFC 3339 timestamps, like task frontmatter.
+    pub started: String,
+    pub last_tick: String,
+    #[serde(default)]
+    pub scanning_since: Option<String>,
+    #[serde(default)]
+    pub last_scan: Option<String>,
+    #[serde(default)]
+    pub last_scan_ok: Option<bool>,
+    /// What the orchestrator reported doing in the last scan, if it did.
+    #[serde(default)]
+    pub last_scan_actions: Option<Vec<ScanAction>>,
+    /// Reported actions by kind since the daemon started.
+    #[serde(default)]
+    pub action_counts: BTreeMap<String, u64>,
+    /// First line of the last scan's message.
+    #[serde(default)]
+    pub last_scan_trigger: Option<String>,
+    #[serde(default)]
+    pub last_scan_duration_secs: Option<f64>,
+    /// [`config::hash`] of the config file the daemon started with.
+    #[serde(default)]
+    pub config_hash: Option<String>,
+    #[serde(default)]
+    pub watching: Vec<PathBuf>,
+    /// `native` or `poll`; unset while the tasks dir isn't watched.
+    #[serde(default)]
+    pub watch_mode: Option<String>,
+    /// The notification backends in use, as [`Notifiers::describe`] puts it.
+    #[serde(default)]
+    pub notifications: Option<String>,
+    /// Set while the tasks dir is missing and being retried.
+    #[serde(default)]
+    pub missing: Option<MissingDir>,
+    /// Set while runs fail because claude can't reach its API.
+    #[serde(default)]
+    pub offline_since: Option<String>,
+    /// Times each supervised subsystem was restarted after a panic.
+    #[serde(default)]
+    pub restarts: BTreeMap<String, u32>,
+    /// The last such panic's message.
+    #[serde(default)]
+    pub last_panic: Option<String>,
+    /// Set while the notification breaker is holding notifications back.
+    #[serde(default)]
+    pub breaker: Option<Tripped>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Tripped {
+    pub since: String,
+    /// Held back for the next digest, by event.
+    pub held: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MissingDir {
+    pub since: String,
+    pub retry_at: String,
+    pub backoff_secs: u64,
+}
+
+pub fn load(dir: &Path) -> Option<Health> {
+    state::load(&paths::health_path(dir))
+}
+
+fn save(dir: &Path, health: &Health) {
+    let path = paths::health_path(dir);
+    let json = serde_json::to_string_pretty(health).unwrap_or_default();
+    let result =
+        fs::create_dir_all(paths::state_dir(dir)).and_then(|_| task::write_atomic(&path, &json));
+    if let Err(e) = result {
+        eprintln!("[orch] failed to save {}: {e}", path.display());
+    }
+}
+
+fn update(dir: &Path, f: impl FnOnce(&mut Health)) {
+    let mut health = load(dir).unwrap_or_default();
+    f(&mut health);
+    save(dir, &health);
+}
+
+/// A restart of the daemon loop in the same process keeps the start time
+/// and restart counts.
+pub fn started(dir: &Path, notifiers: &Notifiers) {
+    let now = task::timestamp(Local::now());
+    let pid = std::process::id();
+    let (started, restarts, last_panic) = match load(dir).filter(|h| h.pid == pid) {
+        Some(h) => (h.started, h.restarts, h.last_panic),
+        None => (now.clone(), BTreeMap::new(), None),
+    };
+    save(
+        dir,
+        &Health {
+            pid,
+            started,
+            last_tick: now,
+            config_hash: paths::config_path().and_then(|p| config::hash(&p)),
+            notifications: Some(notifiers.describe()),
+            restarts,
+            last_panic,
+            ..Health::default()
+        },
+    );
+}
+
+/// `mode` is `None` once the tasks dir is no longer watched.
+pub fn watching(dir: &Path, mode: Option<&str>) {
+    update(dir, |h| {
+        h.watching = mode.map(|_| dir.to_path_buf()).into_iter().collect();
+        h.watch_mode = mode.map(str::to_string);
+    });
+}
+
+pub fn missing(dir: &Path, missing: Option<MissingDir>) {
+    update(dir, |h| h.missing = missing);
+}
+
+pub fn restarted(dir: &Path, subsystem: &str, panic: &str) {
+    update(dir, |h| {
+        *h.restarts.entry(subsystem.to_string()).or_default() += 1;
+        h.last_panic = Some(panic.to_string());
+        h.scanning_since = None;
+    });
+}
+
+pub fn breaker(dir: &Path, tripped: Option<Tripped>) {
+    update(dir, |h| h.breaker = tripped);
+}
+
+pub fn tick(dir: &Path) {
+    update(dir, |h| h.last_tick = task::timestamp(Local::now()));
+}
+
+pub fn scan_started(dir: &Path) {
+    update(dir, |h| {
+        h.scanning_since = Some(task::timestamp(Local::now()))
+    });
+}
+
+pub fn scan_finished(
+    dir: &Path,
+    trigger: &str,
+    took: Duration,
+    ok: bool,
+    offline: bool,
+    actions: Option<&[ScanAction]>,
+) {
+    update(dir, |h| {
+        let now = task::timestamp(Local::now());
+        h.offline_since = if offline {
+            h.offline_since.take().or_else(|| Some(now.clone()))
+        } else {
+            None
+        };
+        h.scanning_since = None;
+        h.last_scan = Some(now.clone());
+        h.last_scan_ok = Some(ok);
+        h.last_scan_trigger = trigger.lines().next().map(str::to_string);
+        h.last_scan_duration_secs = Some(took.as_secs_f64());
+        h.last_tick = now;
+        for action in actions.unwrap_or_default() {
+            *h.action_counts.entry(action.kind.to_string()).or_default() += 1;
+        }
+        h.last_scan_actions = actions.map(<[_]>::to_vec);
+    });
+}
+
+impl Health {
+    pub fn last_scan(&self) -> Option<DateTime<Local>> {
+        self.last_scan.as_deref().and_then(task::parse_timestamp)
+    }
+
+    /// Whether the daemon that wrote this still seems to be running.
+    pub fn alive(&self) -> bool {
+        let proc = Path::new("/proc");
+        if proc.is_dir() && !proc.join(self.pid.to_string()).exists() {
+            return false;
+        }
+        let fresh = task::parse_timestamp(&self.last_tick)
+            .and_then(|t| (Local::now() - t).to_std().ok())
+            .is_some_and(|age| age < STALE);
+        fresh || self.scanning_since.is_some()
+    }
+}
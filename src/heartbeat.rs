@@ -0,0 +1,129 @@
+//! Spotting workers that have hung: the session is alive but its pane hasn't
+//! changed in `hung_after`. The daemon records a hash of each worker's pane
+//! in `.orch/heartbeat.json`; status and inbox read it back.
+
+use std::{
+    collections::{BTreeMap, hash_map::DefaultHasher},
+    fs,
+    hash::{Hash, Hasher},
+    path::Path,
+    time::Duration,
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, duration, inbox, paths, session, state, task, tmux};
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Beat {
+    hash: u64,
+    /// RFC 3339, when the pane last changed.
+    since: String,
+    /// Already reported as hung (so it's reported once).
+    #[serde(default)]
+    flagged: bool,
+}
+
+/// A worker whose pane hasn't changed in `hung_after`.
+pub struct Hung {
+    pub label: String,
+    pub session: String,
+    pub since: DateTime<Local>,
+}
+
+fn threshold(config: &Config) -> Duration {
+    duration::parse(&config.hung_after).unwrap_or_else(|e| {
+        eprintln!("[orch] {e}; using 60m for hung_after");
+        Duration::from_secs(60 * 60)
+    })
+}
+
+fn load(dir: &Path) -> BTreeMap<String, Beat> {
+    state::load(&paths::heartbeat_path(dir)).unwrap_or_default()
+}
+
+/// Hash of the last `hung_lines` non-empty pane lines, with digits removed
+/// so clocks, timers and spinners' elapsed counters don't count as output.
+fn pane_hash(session: &str, config: &Config) -> u64 {
+    let pane = tmux::capture_pane(session);
+    let lines: Vec<_> = pane.lines().filter(|l| !l.trim().is_empty()).collect();
+    let mut hasher = DefaultHasher::new();
+    for line in &lines[lines.len().saturating_sub(config.hung_lines)..] {
+        line.chars()
+            .filter(|c| !c.is_ascii_digit())
+            .collect::<String>()
+            .hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// When `session`'s pane last changed, if that's longer ago than
+/// `hung_after` and the pane still looks the same.
+pub fn hung_since(dir: &Path, config: &Config, session: &str) -> Option<DateTime<Local>> {
+    let beat = load(dir).remove(session)?;
+    let since = task::parse_timestamp(&beat.since)?;
+    let stale = (Local::now() - since).to_std().ok()? >= threshold(config);
+    (stale && beat.hash == pane_hash(session, config)).then_some(since)
+}
+
+/// Records every live worker's pane hash. Returns workers that just crossed
+/// `hung_after` while not waiting on the user.
+pub fn update(dir: &Path, config: &Config) -> Vec<Hung> {
+    let old = load(dir);
+    let mut beats = BTreeMap::new();
+    let mut hung = Vec::new();
+    let now = Local::now();
+    let threshold = threshold(config);
+    let patterns = inbox::patterns(config);
+
+    for task in task::list(dir, config) {
+        let session = task::session(config, &task.label, &task.content);
+        if session == session::CONSOLE || !tmux::has_session(&session) {
+            continue;
+        }
+        let hash = pane_hash(&session, config);
+        let mut beat = match old.get(&session) {
+            Some(b) if b.hash == hash => Beat {
+                hash,
+                since: b.since.clone(),
+                flagged: b.flagged,
+            },
+            _ => Beat {
+                hash,
+                since: task::timestamp(now),
+                flagged: false,
+            },
+        };
+        let since = task::parse_timestamp(&beat.since).unwrap_or(now);
+        let stale = (now - since).to_std().is_ok_and(|d| d >= threshold);
+        // A worker sitting on a question is waiting, not hung
+        let waiting = task::snoozed_until(&task.content).is_some()
+            || inbox::waiting(&patterns, &task.content, Some(&session)).is_some();
+        if stale && !waiting && !beat.flagged {
+            beat.flagged = true;
+            hung.push(Hung {
+                label: task.label,
+                session: session.clone(),
+                since,
+            });
+        }
+        beats.insert(session, beat);
+    }
+
+    let unchanged = beats.len() == old.len()
+        && beats.iter().all(|(s, b)| {
+            old.get(s)
+                .is_some_and(|o| o.hash == b.hash && o.flagged == b.flagged)
+        });
+    if !unchanged {
+        let path = paths::heartbeat_path(dir);
+        let json = serde_json::to_string_pretty(&beats).unwrap_or_default();
+        let result = fs::create_dir_all(paths::state_dir(dir))
+            .and_then(|_| task::write_atomic(&path, &json));
+        if let Err(e) = result {
+            eprintln!("[orch] failed to save {}: {e}", path.display());
+        }
+    }
+    hung
+}
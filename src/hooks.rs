@@ -0,0 +1,90 @@
+//! User commands run on daemon events, configured under `[hooks]`.
+
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+
+use clap::ValueEnum;
+use serde_json::Value;
+
+use crate::config::Config;
+
+#[derive(Clone, Copy, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum HookEvent {
+    /// A new task file appeared
+    NewTask,
+    /// Messages were drained from the inbox
+    Message,
+    /// An orchestrator run succeeded
+    ScanFinished,
+    /// An orchestrator run failed
+    ScanFailed,
+    /// The orchestrator reported actions that didn't happen
+    ScanDiscrepancy,
+    /// The tasks dir has been missing for longer than the grace period
+    TasksDirMissing,
+    /// An inbox item passed an `[escalation]` threshold
+    InboxEscalated,
+}
+
+impl HookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::NewTask => "new_task",
+            HookEvent::Message => "message",
+            HookEvent::ScanFinished => "scan_finished",
+            HookEvent::ScanFailed => "scan_failed",
+            HookEvent::ScanDiscrepancy => "scan_discrepancy",
+            HookEvent::TasksDirMissing => "tasks_dir_missing",
+            HookEvent::InboxEscalated => "inbox_escalated",
+        }
+    }
+}
+
+/// Runs `command` under `sh -c` with the payload as JSON on stdin, plus
+/// `ORCH_EVENT`, `ORCH_TASKS_DIR` and (when the payload names one) `ORCH_TASK`.
+pub fn run(
+    command: &str,
+    event: &str,
+    payload: &Value,
+    tasks_dir: &Path,
+) -> std::io::Result<Output> {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command])
+        .env("ORCH_EVENT", event)
+        .env("ORCH_TASKS_DIR", tasks_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(task) = payload.get("task").and_then(Value::as_str) {
+        cmd.env("ORCH_TASK", task);
+    }
+
+    let mut child = cmd.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(payload.to_string().as_bytes());
+    }
+    child.wait_with_output()
+}
+
+/// Daemon-side hook dispatch. Failures are logged, never fatal.
+pub fn fire(config: &Config, tasks_dir: &Path, event: HookEvent, payload: Value) {
+    let Some(command) = config.hooks.get(event.name()) else {
+        return;
+    };
+    match run(command, event.name(), &payload, tasks_dir) {
+        Ok(out) if !out.status.success() => {
+            eprintln!(
+                "[orch] {} hook exited with {}: {}",
+                event.name(),
+                out.status,
+                String::from_utf8_lossy(&out.stderr).trim()
+            );
+        }
+        Err(e) => eprintln!("[orch] {} hook failed: {e}", event.name()),
+        _ => {}
+    }
+}
@@ -0,0 +1,250 @@
+//! Tasks that need the user: a question in the summary or latest status
+//! entry, or a worker pane waiting on input. Items are ordered by an urgency
+//! [`Score`]; when each task entered the inbox is kept in `.orch/inbox.json`.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::{
+    config::{Config, PatternsMode, ScoringConfig},
+    error, fmt, frontmatter, heartbeat, paths, state, task, tmux,
+};
+
+/// Built-in inbox patterns, used unless `inbox_patterns_mode = "replace"`.
+const INBOX_PATTERNS: &[&str] = &[
+    "needs input",
+    "waiting for input",
+    "blocked",
+    "question",
+    "stuck",
+    "do you want to",
+];
+
+pub struct InboxItem {
+    pub name: String,
+    pub label: String,
+    pub session: Option<String>,
+    /// The line that matched.
+    pub reason: String,
+    /// When the task entered the inbox.
+    pub since: DateTime<Local>,
+    pub score: Score,
+    pub held: bool,
+    pub pinned: bool,
+}
+
+/// Why an item ranks where it does; each part is already weighted.
+#[derive(Serialize, Clone, Copy, Default)]
+pub struct Score {
+    /// From the `priority:` frontmatter.
+    pub priority: f64,
+    /// Hours in the inbox.
+    pub blocked: f64,
+    /// How close the `due:` date is.
+    pub due: f64,
+    /// A live worker is waiting at a prompt.
+    pub waiting: f64,
+}
+
+impl Score {
+    pub fn total(&self) -> f64 {
+        self.priority + self.blocked + self.due + self.waiting
+    }
+}
+
+/// A `due:` timestamp, or a date meaning the end of that day.
+fn due(content: &str) -> Option<DateTime<Local>> {
+    let due = frontmatter::get(content, "due")?;
+    task::parse_timestamp(&due).or_else(|| {
+        NaiveDate::parse_from_str(due.trim(), "%Y-%m-%d")
+            .ok()?
+            .and_hms_opt(23, 59, 59)?
+            .and_local_timezone(Local)
+            .single()
+    })
+}
+
+fn score(
+    weights: &ScoringConfig,
+    content: &str,
+    reason: &str,
+    since: DateTime<Local>,
+    now: DateTime<Local>,
+) -> Score {
+    let priority = frontmatter::get(content, "priority")
+        .and_then(|p| p.trim().parse::<f64>().ok())
+        .unwrap_or(0.0);
+    let hours = |d: chrono::TimeDelta| d.num_seconds() as f64 / 3600.0;
+    // Full weight once due (or overdue), half a day out, and so on
+    let due = due(content).map_or(0.0, |due| 1.0 / (1.0 + hours(due - now).max(0.0) / 24.0));
+    Score {
+        priority: weights.priority * priority,
+        blocked: weights.blocked_per_hour * hours(now - since).max(0.0),
+        due: weights.due * due,
+        waiting: if reason.starts_with("pane:") {
+            weights.waiting
+        } else {
+            0.0
+        },
+    }
+}
+
+/// When each task (by label) entered the inbox.
+fn load_seen(dir: &Path) -> BTreeMap<String, String> {
+    state::load(&paths::inbox_state_path(dir)).unwrap_or_default()
+}
+
+/// Carries a task's time in the inbox over to its new label after a move.
+pub fn relabel(dir: &Path, from: &str, to: &str) {
+    let mut seen = load_seen(dir);
+    if let Some(since) = seen.remove(from) {
+        seen.insert(to.to_string(), since);
+        save_seen(dir, &seen);
+    }
+}
+
+fn save_seen(dir: &Path, seen: &BTreeMap<String, String>) {
+    let path = paths::inbox_state_path(dir);
+    let result = fs::create_dir_all(paths::state_dir(dir)).and_then(|_| {
+        let json = serde_json::to_string_pretty(seen).unwrap_or_default();
+        task::write_atomic(&path, &json)
+    });
+    match result {
+        Ok(()) => {}
+        // Bookkeeping for `orch inbox`, which works on read-only tasks
+        Err(e) if error::read_only(&e) => {}
+        Err(e) => eprintln!("[orch] failed to save {}: {e}", path.display()),
+    }
+}
+
+impl InboxItem {
+    pub fn key(&self) -> String {
+        format!("{}\n{}", self.label, self.reason)
+    }
+
+    /// The item as `orch inbox --json` and `orch serve` show it.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "label": self.label,
+            "session": self.session,
+            "reason": self.reason,
+            "since": task::timestamp(self.since),
+            "score": self.score.total(),
+            "score_parts": self.score,
+            "held": self.held,
+            "pinned": self.pinned,
+        })
+    }
+}
+
+/// The configured inbox patterns, lowercased. They're plain substrings,
+/// matched literally against each lowercased line.
+pub fn patterns(config: &Config) -> Vec<String> {
+    let builtin = match config.inbox_patterns_mode {
+        PatternsMode::Extend => INBOX_PATTERNS,
+        PatternsMode::Replace => &[],
+    };
+    builtin
+        .iter()
+        .map(|p| p.to_string())
+        .chain(config.inbox_patterns.iter().map(|p| p.to_lowercase()))
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// Why a task is waiting on the user: a line in its summary, latest status
+/// entry, or (when `session` is live) the last few pane lines that matches
+/// one of `patterns`.
+pub fn waiting(patterns: &[String], content: &str, session: Option<&str>) -> Option<String> {
+    let matches = |line: &str| {
+        let line = line.to_lowercase();
+        patterns.iter().any(|p| line.contains(p.as_str()))
+    };
+
+    let mut candidates = task::section(content, "## Summary");
+    candidates.extend(task::section(content, "## Status").last());
+    let mut reason = candidates
+        .into_iter()
+        .find(|l| matches(l))
+        .map(str::to_string);
+    if reason.is_none()
+        && let Some(session) = session
+    {
+        let pane = tmux::capture_pane(session);
+        let tail: Vec<_> = pane.lines().filter(|l| !l.trim().is_empty()).collect();
+        reason = tail[tail.len().saturating_sub(5)..]
+            .iter()
+            .find(|l| matches(l))
+            .map(|l| format!("pane: {}", l.trim()));
+    }
+    reason.map(|r| r.trim().trim_start_matches("- ").to_string())
+}
+
+/// Tasks waiting on the user (see [`waiting`]) or whose worker looks hung,
+/// pinned ones first, then most urgent first (ties by name). Snoozed and held tasks are left out.
+pub fn items(dir: &Path, config: &Config) -> Vec<InboxItem> {
+    collect(dir, config, false)
+}
+
+/// [`items`], plus held tasks (`orch inbox --all`).
+pub fn items_with_held(dir: &Path, config: &Config) -> Vec<InboxItem> {
+    collect(dir, config, true)
+}
+
+fn collect(dir: &Path, config: &Config, with_held: bool) -> Vec<InboxItem> {
+    let patterns = patterns(config);
+    let old_seen = load_seen(dir);
+    let mut seen = BTreeMap::new();
+    let now = Local::now();
+
+    let mut items = Vec::new();
+    for task in task::list(dir, config) {
+        let held = task::held(&task.content);
+        if task::snoozed_until(&task.content).is_some() || (held && !with_held) {
+            continue;
+        }
+        let session = task::session(config, &task.label, &task.content);
+        let live = tmux::has_session(&session);
+
+        let reason =
+            waiting(&patterns, &task.content, live.then_some(session.as_str())).or_else(|| {
+                let since = heartbeat::hung_since(dir, config, &session)?;
+                Some(format!(
+                    "worker possibly hung, no output since {}",
+                    fmt::time(since)
+                ))
+            });
+
+        if let Some(reason) = reason {
+            let since = old_seen
+                .get(&task.label)
+                .and_then(|t| task::parse_timestamp(t))
+                .unwrap_or(now);
+            seen.insert(task.label.clone(), task::timestamp(since));
+            items.push(InboxItem {
+                score: score(&config.inbox.scoring, &task.content, &reason, since, now),
+                name: task.name,
+                label: task.label,
+                session: live.then_some(session),
+                reason,
+                since,
+                held,
+                pinned: task::pinned(&task.content),
+            });
+        }
+    }
+    if seen != old_seen && !config.read_only {
+        save_seen(dir, &seen);
+    }
+    items.sort_by(|a, b| {
+        b.pinned
+            .cmp(&a.pinned)
+            .then_with(|| b.score.total().total_cmp(&a.score.total()))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    items
+}
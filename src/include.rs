@@ -0,0 +1,76 @@
+//! `@include path/to/file.md` lines in the orchestrator prompt file, so it
+//! can be split into fragments. Paths are relative to the including file;
+//! lines inside ``` fences are left alone.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::frontmatter;
+
+/// Deepest chain of includes followed before giving up.
+const MAX_DEPTH: usize = 10;
+
+/// The file at `path` with its frontmatter dropped and its includes
+/// expanded, recursively.
+pub fn expand(path: &Path) -> Result<String, String> {
+    let mut stack = Vec::new();
+    expand_into(path, &mut stack)
+}
+
+fn expand_into(path: &Path, stack: &mut Vec<PathBuf>) -> Result<String, String> {
+    let chain = || {
+        stack
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    };
+    let real = path
+        .canonicalize()
+        .map_err(|e| format!("can't read {}: {e}", path.display()))?;
+    if stack.contains(&real) {
+        return Err(format!("include cycle: {} -> {}", chain(), real.display()));
+    }
+    if stack.len() >= MAX_DEPTH {
+        return Err(format!(
+            "includes nested over {MAX_DEPTH} deep: {}",
+            chain()
+        ));
+    }
+    let text =
+        fs::read_to_string(&real).map_err(|e| format!("can't read {}: {e}", real.display()))?;
+    stack.push(real.clone());
+
+    let base = real.parent().unwrap_or(Path::new("/"));
+    let mut out = String::new();
+    let mut fenced = false;
+    for (i, line) in frontmatter::split(&text).1.lines().enumerate() {
+        if line.trim_start().starts_with("```") {
+            fenced = !fenced;
+        }
+        match line.trim().strip_prefix("@include ") {
+            Some(target) if !fenced => {
+                let target = base.join(target.trim());
+                let fragment = expand_into(&target, stack).map_err(|e| {
+                    if target.exists() {
+                        e
+                    } else {
+                        format!(
+                            "{} (line {}): include {} not found",
+                            real.display(),
+                            i + 1,
+                            target.display()
+                        )
+                    }
+                })?;
+                out.push_str(fragment.trim_end());
+            }
+            _ => out.push_str(line),
+        }
+        out.push('\n');
+    }
+    stack.pop();
+    Ok(out)
+}
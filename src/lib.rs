@@ -0,0 +1,58 @@
+//! Task orchestrator for Claude Code workers.
+//!
+//! The `orch` binary only parses [`cli::Cli`] and dispatches to [`commands`].
+//! Everything here takes the tasks dir and [`config::Config`] explicitly, so
+//! other tools can list tasks ([`task::list`]) or run a scan
+//! ([`orchestrator::Runner`]) without going through the CLI.
+
+pub mod actions;
+pub mod audit;
+pub mod blobs;
+pub mod budget;
+pub mod bulk;
+pub mod cli;
+pub mod commands;
+pub mod config;
+pub mod costs;
+pub mod daemon;
+pub mod delegation;
+pub mod duration;
+pub mod edits;
+pub mod error;
+pub mod escalation;
+pub mod exits;
+pub mod export;
+pub mod fmt;
+pub mod focus;
+pub mod frontmatter;
+pub mod git;
+pub mod health;
+pub mod heartbeat;
+pub mod hooks;
+pub mod inbox;
+pub mod include;
+pub mod lint;
+pub mod log;
+pub mod messages;
+pub mod notify;
+pub mod orchestrator;
+pub mod output;
+pub mod paths;
+pub mod pauses;
+pub mod plan;
+pub mod prompts;
+pub mod queue;
+pub mod render;
+pub mod review;
+pub mod rollout;
+pub mod roots;
+pub mod runs;
+pub mod sandbox;
+pub mod session;
+pub mod state;
+pub mod supervisor;
+pub mod task;
+pub mod templates;
+pub mod tmux;
+pub mod transcript;
+pub mod warn;
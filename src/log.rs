@@ -0,0 +1,223 @@
+//! Each task's own log, `.orch/log/<task>.jsonl`: what orch did to or for
+//! the task outside its file. Holds and snoozes, messages routed to it, work
+//! delegated to or from it, notifications about it the breaker held back,
+//! priority escalations, adoption, recording and the close. `orch log`
+//! prints one task's, `orch timeline` merges them all.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{delegation, messages, paths, state, task};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Held,
+    Released,
+    Snoozed {
+        until: String,
+    },
+    Unsnoozed,
+    /// `orch - --to` sent it a message.
+    Message {
+        via: messages::Via,
+        message: String,
+    },
+    DelegatedTo {
+        to: String,
+        via: delegation::Via,
+        text: String,
+        /// `to` was created for it.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        created: bool,
+    },
+    DelegatedFrom {
+        from: String,
+        via: delegation::Via,
+        text: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        created: bool,
+    },
+    /// A notification about it the breaker held back.
+    NotificationHeld {
+        kind: String,
+        title: String,
+        body: String,
+    },
+    /// Its priority was raised after `age` in the inbox.
+    Escalated {
+        priority: i64,
+        age: String,
+    },
+    /// Made from a tmux session that was already running.
+    Adopted {
+        session: String,
+    },
+    RecordingStarted {
+        transcript: PathBuf,
+    },
+    RecordingStopped {
+        transcript: PathBuf,
+    },
+    /// `transcript` if it was ever recorded.
+    Closed {
+        outcome: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        transcript: Option<PathBuf>,
+    },
+}
+
+impl Event {
+    /// A short name for listings, at most 8 characters.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::Held => "hold",
+            Event::Released => "unhold",
+            Event::Snoozed { .. } => "snooze",
+            Event::Unsnoozed => "unsnooze",
+            Event::Message { .. } => "message",
+            Event::DelegatedTo { .. } | Event::DelegatedFrom { .. } => "handoff",
+            Event::NotificationHeld { .. } => "held",
+            Event::Escalated { .. } => "escalate",
+            Event::Adopted { .. } => "adopted",
+            Event::RecordingStarted { .. } => "record",
+            Event::RecordingStopped { .. } => "unrecord",
+            Event::Closed { .. } => "closed",
+        }
+    }
+
+    /// One line about it, first lines only of messages and delegated text.
+    pub fn detail(&self) -> String {
+        let first = |text: &str| text.lines().next().unwrap_or("").to_string();
+        match self {
+            Event::Held | Event::Released | Event::Unsnoozed => String::new(),
+            Event::Snoozed { until } => format!("until {until}"),
+            Event::Message { message, .. } => first(message),
+            Event::DelegatedTo { to, text, .. } => format!("to {to}: {}", first(text)),
+            Event::DelegatedFrom { from, text, .. } => format!("from {from}: {}", first(text)),
+            Event::NotificationHeld { kind, title, .. } => {
+                format!("{kind}: {}", title.trim_start_matches("orch: "))
+            }
+            Event::Escalated { priority, age } => {
+                format!("priority {priority} after {age} in the inbox")
+            }
+            Event::Adopted { session } => session.clone(),
+            Event::RecordingStarted { transcript } | Event::RecordingStopped { transcript } => {
+                transcript.display().to_string()
+            }
+            Event::Closed {
+                outcome,
+                transcript: Some(transcript),
+            } => format!("{outcome}, transcript {}", transcript.display()),
+            Event::Closed { outcome, .. } => outcome.clone(),
+        }
+    }
+}
+
+/// One line of a task's log.
+#[derive(Serialize, Deserialize)]
+pub struct Entry {
+    pub at: String,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+impl Entry {
+    pub fn at(&self) -> Option<DateTime<Local>> {
+        task::parse_timestamp(&self.at)
+    }
+}
+
+pub fn path(dir: &Path, task: &str) -> PathBuf {
+    paths::log_dir(dir).join(format!("{task}.jsonl"))
+}
+
+/// Appends `event` to `task`'s log. Failures are logged, not returned: what
+/// it records has already happened. A name that couldn't be a task's (one
+/// the orchestrator made up, say) gets no log.
+pub fn record(dir: &Path, task: &str, event: Event) {
+    if let Err(e) = task::validate_name(task) {
+        eprintln!("not logging {}: {e}", event.kind());
+        return;
+    }
+    let entry = Entry {
+        at: task::timestamp(Local::now()),
+        event,
+    };
+    let path = path(dir, task);
+    let line = serde_json::to_string(&entry).unwrap_or_default();
+    let result = fs::create_dir_all(paths::log_dir(dir)).and_then(|_| {
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(file, "{line}")
+    });
+    if let Err(e) = result {
+        eprintln!("failed to append to {}: {e}", path.display());
+    }
+}
+
+/// `task`'s log, oldest first.
+pub fn entries(dir: &Path, task: &str) -> impl Iterator<Item = Entry> + use<> {
+    state::lines(&path(dir, task))
+}
+
+/// The tasks that have a log, by name.
+pub fn tasks(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(paths::log_dir(dir)) else {
+        return Vec::new();
+    };
+    let mut names: Vec<_> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+        .filter_map(|p| Some(p.file_stem()?.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_round_trip_as_tagged_lines() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        record(dir, "recon", Event::Held);
+        record(
+            dir,
+            "recon",
+            Event::Message {
+                via: messages::Via::TaskFile,
+                message: "check staging\ntoo".into(),
+            },
+        );
+        record(
+            dir,
+            "infra",
+            Event::Closed {
+                outcome: "done".into(),
+                transcript: None,
+            },
+        );
+
+        let text = fs::read_to_string(path(dir, "recon")).unwrap();
+        let first = text.lines().next().unwrap();
+        assert!(first.contains(r#""event":"held""#), "{first}");
+        let events: Vec<_> = entries(dir, "recon").map(|e| e.event).collect();
+        assert!(events[0] == Event::Held);
+        assert_eq!(events[1].kind(), "message");
+        assert_eq!(events[1].detail(), "check staging");
+        assert_eq!(tasks(dir), ["infra", "recon"]);
+        record(dir, "../escape", Event::Held);
+        assert!(!dir.join(".orch/escape.jsonl").exists());
+        assert_eq!(tasks(dir), ["infra", "recon"]);
+        assert_eq!(entries(dir, "auth").count(), 0);
+    }
+}
@@ -1,316 +1,351 @@
-use std::{
-    collections::HashSet,
-    fs,
-    io::Write,
-    path::{Path, PathBuf},
-    process::{Command, Stdio},
-    sync::mpsc,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+use std::{path::Path, time::Instant};
+
+use clap::Parser;
+use orch::{
+    audit,
+    cli::{Cli, Cmd, DaemonCmd, HooksCmd, QueueCmd, RunsCmd, StateCmd, StatusArgs},
+    commands::*,
+    config::{self, Config},
+    daemon, duration,
+    error::OrchError,
+    fmt, focus,
+    orchestrator::Claude,
+    output, paths, render, roots, warn,
 };
 
-use clap::{Parser, Subcommand};
-use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
-
-const SCAN_MSG: &str = "\
-    [scan] Scan ~/tasks/ and tmux sessions. For any unstarted task without a worker, \
-    spin up an interactive tmux worker session. Spawn task-checker sub-agents for active workers. \
-    Report what you did.";
-
-// CLI
-
-#[derive(Parser)]
-#[command(name = "orch", about = "Task orchestrator for Claude Code workers")]
-struct Cli {
-    #[command(subcommand)]
-    command: Option<Cmd>,
-}
-
-#[derive(Subcommand)]
-enum Cmd {
-    /// Run the background watcher daemon
-    Daemon,
-    /// Show status of all tasks and workers
-    Status,
-    /// Attach to a task's tmux session
-    Jump { name: String },
-    /// Trigger a one-shot orchestrator scan
-    Scan,
-    /// Send a message to the orchestrator
-    #[command(name = "-")]
-    Msg { message: Vec<String> },
-}
-
-// Paths
-
-fn tasks_dir() -> PathBuf {
-    dirs::home_dir().unwrap_or_default().join("tasks")
-}
-
-fn inbox_dir() -> PathBuf {
-    tasks_dir().join(".inbox")
-}
-
-fn repo_dir() -> String {
-    std::env::var("ORCH_REPO").expect("ORCH_REPO must be set")
-}
-
-// Inbox
-
-fn write_inbox(msg: &str) {
-    let dir = inbox_dir();
-    fs::create_dir_all(&dir).expect("failed to create inbox dir");
-    let nanos = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_nanos();
-    let path = dir.join(format!("{nanos}-{}.msg", std::process::id()));
-    fs::write(path, msg).expect("failed to write inbox message");
-}
-
-fn drain_inbox() -> Option<String> {
-    let mut entries: Vec<_> = fs::read_dir(inbox_dir())
-        .ok()?
-        .flatten()
-        .filter(|e| e.path().extension().is_some_and(|ext| ext == "msg"))
-        .collect();
-    if entries.is_empty() {
-        return None;
-    }
-    entries.sort_by_key(|e| e.file_name());
-
-    let mut messages = Vec::new();
-    for entry in entries {
-        if let Ok(msg) = fs::read_to_string(entry.path()) {
-            if !msg.trim().is_empty() {
-                messages.push(msg);
-            }
-            let _ = fs::remove_file(entry.path());
-        }
+fn main() {
+    let start = Instant::now();
+    let dir = paths::tasks_dir();
+    let config_path = paths::config_path();
+    // Parsed by hand so usage errors are audited too
+    let cli = Cli::try_parse();
+    if cli.as_ref().is_ok_and(Cli::json) {
+        warn::hold();
     }
-    (!messages.is_empty()).then(|| messages.join("\n"))
-}
-
-// Orchestrator
-
-fn run_orchestrator(message: &str) {
-    eprintln!("[orch] {message}");
-
-    let mut child = match Command::new("claude")
-        .args(["--model", "opus", "--agent", "orchestrator", "-p", "--dangerously-skip-permissions"])
-        .env("ORCH_REPO", repo_dir())
-        .env_remove("CLAUDECODE")
-        .stdin(Stdio::piped())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .spawn()
-    {
-        Ok(c) => c,
+    let mut config = config_path
+        .as_deref()
+        .map_or_else(Config::default, config::load);
+    config.read_only |= std::env::var("ORCH_READ_ONLY").is_ok_and(|v| v == "1");
+    config.read_only |= cli.as_ref().is_ok_and(|cli| cli.read_only);
+    // Nothing is written in read-only mode, the audit log included
+    let audit = (!config.read_only).then(|| config.audit_log.clone());
+    let cli = match cli {
+        Ok(cli) => cli,
         Err(e) => {
-            eprintln!("[orch] failed to run claude: {e}");
-            return;
+            if let (Some(audit), Ok(dir)) = (&audit, &dir) {
+                audit::record(dir, audit, e.exit_code(), start.elapsed());
+            }
+            e.exit();
         }
     };
-
-    if let Some(mut stdin) = child.stdin.take() {
-        let _ = stdin.write_all(message.as_bytes());
+    // Only now, so `--help` works without a tasks dir
+    let dir = dir.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(e.exit_code());
+    });
+
+    fmt::set_utc(cli.utc);
+    let deny_warnings = cli.deny_warnings;
+    let mut result = run(cli, &dir, config_path.as_deref(), config);
+    warn::flush();
+    if deny_warnings && result.is_ok() && warn::count() > 0 {
+        result = Err(OrchError::Failed(format!(
+            "{}, failing for --deny-warnings.",
+            fmt::count(warn::count(), "warning")
+        )));
     }
-
-    match child.wait() {
-        Ok(s) if !s.success() => eprintln!("[orch] claude exited with {s}"),
-        Err(e) => eprintln!("[orch] claude wait failed: {e}"),
-        _ => {}
+    let code = result.as_ref().map_or_else(OrchError::exit_code, |()| 0);
+    if let Some(audit) = &audit {
+        audit::record(&dir, audit, code, start.elapsed());
     }
-}
-
-// Helpers
-
-fn has_tmux_session(name: &str) -> bool {
-    Command::new("tmux")
-        .args(["has-session", "-t", name])
-        .stderr(Stdio::null())
-        .status()
-        .is_ok_and(|s| s.success())
-}
-
-fn known_tasks(dir: &Path) -> HashSet<String> {
-    let Ok(entries) = fs::read_dir(dir) else {
-        return HashSet::new();
-    };
-    entries
-        .flatten()
-        .filter(|e| e.path().extension().is_some_and(|ext| ext == "md"))
-        .filter_map(|e| e.file_name().into_string().ok())
-        .collect()
-}
-
-/// Lines between `heading` and the next `## ` (or EOF), excluding blanks.
-fn extract_section<'a>(content: &'a str, heading: &str) -> Vec<&'a str> {
-    let mut lines = content.lines();
-    if !lines.any(|l| l.trim().starts_with(heading)) {
-        return Vec::new();
+    if let Err(e) = result {
+        let msg = e.to_string();
+        if !msg.is_empty() {
+            eprintln!("{msg}");
+        }
+        std::process::exit(code);
     }
-    lines
-        .take_while(|l| !l.trim().starts_with("## "))
-        .filter(|l| !l.trim().is_empty())
-        .collect()
 }
 
-// Commands
-
-fn cmd_status() {
-    let dir = tasks_dir();
-    println!("## Tasks\n");
-
-    let Ok(entries) = fs::read_dir(&dir) else {
-        println!("  ~/tasks/ not found");
-        return;
-    };
-
-    let mut found = false;
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if !path.extension().is_some_and(|e| e == "md") {
-            continue;
-        }
-        found = true;
-
-        let name = path.file_stem().unwrap_or_default().to_string_lossy();
-        let content = fs::read_to_string(&path).unwrap_or_default();
-        let summary = extract_section(&content, "## Summary");
-
-        let session = content
-            .lines()
-            .find_map(|l| l.trim().strip_prefix("session:").map(|s| s.trim().to_string()))
-            .unwrap_or_else(|| format!("task-{name}"));
-
-        let worker = if has_tmux_session(&session) {
-            format!("running ({session})")
-        } else {
-            "none".into()
-        };
-
-        println!("  {name}  [worker: {worker}]");
-        if summary.is_empty() {
-            let desc = content
-                .lines()
-                .find(|l| !l.trim().is_empty())
-                .unwrap_or("")
-                .trim()
-                .trim_start_matches('#')
-                .trim();
-            println!("    {desc}");
-        } else {
-            for line in &summary {
-                println!("    {line}");
-            }
-        }
-        println!();
+fn run(
+    cli: Cli,
+    dir: &Path,
+    config_path: Option<&Path>,
+    mut config: Config,
+) -> Result<(), OrchError> {
+    config.exact_names |= cli.exact;
+    let format = cli.output_format;
+    if format.is_some()
+        && !matches!(
+            cli.command,
+            None | Some(Cmd::Status(_) | Cmd::Inbox(_) | Cmd::Show { .. } | Cmd::Runs { .. })
+        )
+    {
+        return Err(OrchError::Usage(
+            "--output-format is for orch status, inbox, show and runs.".into(),
+        ));
     }
-
-    if !found {
-        println!("  (no tasks)");
+    if config.read_only
+        && let Some(what) = cli.command.as_ref().and_then(Cmd::mutation)
+    {
+        return Err(OrchError::Failed(format!(
+            "Read-only mode: `orch {what}` would change things; drop --read-only \
+             (or unset ORCH_READ_ONLY / `read_only` in the config) to run it."
+        )));
     }
-}
-
-fn cmd_jump(name: &str) {
-    let session = if name.starts_with("task-") {
-        name.to_string()
-    } else {
-        format!("task-{name}")
-    };
-
-    if !has_tmux_session(&session) {
-        eprintln!("No tmux session '{session}' found.");
-        let _ = Command::new("tmux").arg("ls").status();
-        return;
+    if cli.ascii {
+        render::force_ascii();
     }
-
-    let action = if std::env::var("TMUX").is_ok() {
-        "switch-client"
-    } else {
-        "attach-session"
-    };
-    let _ = Command::new("tmux").args([action, "-t", &session]).status();
-}
-
-fn cmd_daemon() {
-    let dir = tasks_dir();
-    let inbox = inbox_dir();
-    fs::create_dir_all(&dir).ok();
-    fs::create_dir_all(&inbox).ok();
-
-    eprintln!("[orch] daemon started, watching {}", dir.display());
-
-    // Fold pending inbox messages into the initial scan
-    let mut startup_msg = String::new();
-    if let Some(msgs) = drain_inbox() {
-        startup_msg.push_str("[message] ");
-        startup_msg.push_str(&msgs);
-        startup_msg.push_str("\n\n");
+    if let Some(path) = &cli.output {
+        output::redirect(path, cli.append).map_err(|e| OrchError::io(path, e))?;
     }
-    startup_msg.push_str(SCAN_MSG);
-    eprintln!("[orch] running initial scan...");
-    run_orchestrator(&startup_msg);
-
-    let mut tasks = known_tasks(&dir);
-    let (tx, rx) = mpsc::channel();
-    let mut debouncer =
-        new_debouncer(Duration::from_secs(3), tx).expect("failed to create watcher");
-    debouncer
-        .watcher()
-        .watch(&dir, RecursiveMode::Recursive)
-        .expect("failed to watch ~/tasks");
-
-    eprintln!("[orch] watching for changes (polling every 60m)...");
-
-    loop {
-        match rx.recv_timeout(Duration::from_secs(60 * 60)) {
-            Ok(Ok(events)) => {
-                let inbox_msgs = events
-                    .iter()
-                    .any(|e| e.path.starts_with(&inbox))
-                    .then(|| drain_inbox())
-                    .flatten();
 
-                let current = known_tasks(&dir);
-                let new_tasks: Vec<_> = current.difference(&tasks).cloned().collect();
-                tasks = current;
-
-                let mut parts = Vec::new();
-                if let Some(msgs) = inbox_msgs {
-                    parts.push(format!("[message] {msgs}"));
-                }
-                for task in &new_tasks {
-                    parts.push(format!("[new-task] {task}"));
-                }
-                if !parts.is_empty() {
-                    run_orchestrator(&parts.join("\n\n"));
+    let focused = |name: Option<String>| focus::resolve(dir, &config, name);
+    match cli.command {
+        Some(Cmd::Status(args)) => cmd_status(dir, &config, &args, format),
+        None => cmd_status(dir, &config, &StatusArgs::default(), format),
+        Some(Cmd::Summary { pick, tmux_bind }) => cmd_summary(dir, &config, pick, tmux_bind),
+        Some(Cmd::Inbox(args)) => cmd_inbox(dir, &config, &args, format),
+        Some(Cmd::Show { name, json }) => cmd_show(dir, &config, &focused(name)?, json, format),
+        Some(Cmd::Console { refresh }) => cmd_console(dir, &config, refresh),
+        Some(Cmd::Jump(args)) => cmd_jump(dir, &config, &args),
+        Some(Cmd::Spawn {
+            names,
+            all_unstarted,
+            stagger,
+            dry_run,
+        }) => {
+            let names = (!all_unstarted).then_some(names.as_slice());
+            cmd_spawn(dir, &config, names, stagger.as_deref(), dry_run)
+        }
+        Some(Cmd::Kill { batch, plan }) => cmd_kill(dir, &config, &batch, &plan),
+        Some(Cmd::Close {
+            batch,
+            plan,
+            force,
+            outcome,
+        }) => cmd_close(dir, &config, &batch, &plan, force, outcome),
+        Some(Cmd::Archive {
+            older_than,
+            status,
+            plan,
+        }) => cmd_archive(
+            dir,
+            &config,
+            older_than.as_deref(),
+            status.as_deref(),
+            &plan,
+        ),
+        Some(Cmd::Bump(args)) => cmd_bump(dir, &config, &args),
+        Some(Cmd::New {
+            name,
+            text,
+            ask,
+            no_confirm,
+            repo,
+            branch,
+        }) => cmd_new(
+            dir,
+            &config,
+            &name,
+            &text,
+            ask.then_some(!no_confirm),
+            repo.as_deref(),
+            branch.as_deref(),
+        ),
+        Some(Cmd::Adopt {
+            session,
+            name,
+            keep_name,
+        }) => cmd_adopt(dir, &config, &session, name.as_deref(), keep_name),
+        Some(Cmd::Snooze { name, duration }) => {
+            cmd_snooze(dir, &config, &focused(name)?, &duration)
+        }
+        Some(Cmd::Unsnooze { name }) => cmd_unsnooze(dir, &config, &focused(name)?),
+        Some(Cmd::Schedule {
+            name, at, after, ..
+        }) => cmd_schedule(
+            dir,
+            &config,
+            &focused(name)?,
+            at.as_deref(),
+            after.as_deref(),
+        ),
+        Some(Cmd::Hold { name }) => cmd_hold(dir, &config, &focused(name)?),
+        Some(Cmd::Unhold { name }) => cmd_unhold(dir, &config, &focused(name)?),
+        Some(Cmd::Pin { name }) => cmd_pin(dir, &config, &focused(name)?),
+        Some(Cmd::Unpin { name }) => cmd_unpin(dir, &config, &focused(name)?),
+        Some(Cmd::Touch { name }) => cmd_touch(dir, &config, &focused(name)?),
+        Some(Cmd::Record { name, stop }) => cmd_record(dir, &config, &focused(name)?, stop),
+        Some(Cmd::Review {
+            name,
+            interactive,
+            dry_run,
+        }) => cmd_review(dir, &config, &focused(name)?, interactive, dry_run),
+        Some(Cmd::Focus { name, clear, auto }) => {
+            cmd_focus(dir, &config, name.as_deref(), clear, auto)
+        }
+        Some(Cmd::Move {
+            name,
+            project,
+            plan,
+        }) => cmd_move(dir, &config, &name, &project, &plan),
+        Some(Cmd::Daemon {
+            command: Some(DaemonCmd::Status { json }),
+            ..
+        }) => cmd_daemon_status(dir, &config, json),
+        Some(Cmd::Daemon {
+            command: None,
+            poll_watch,
+            poll_interval,
+            max_scans,
+            prompt,
+        }) => {
+            let mut config = config;
+            config.poll_watch |= poll_watch;
+            if let Some(interval) = poll_interval {
+                if duration::parse(&interval)
+                    .map_err(OrchError::Parse)?
+                    .is_zero()
+                {
+                    return Err(OrchError::Usage(
+                        "--poll-interval must be more than 0.".into(),
+                    ));
                 }
+                config.poll_watch = true;
+                config.poll_interval = interval;
             }
-            Ok(Err(e)) => eprintln!("[orch] watch error: {e:?}"),
-            Err(mpsc::RecvTimeoutError::Timeout) => {
-                eprintln!("[orch] periodic check...");
-                run_orchestrator(SCAN_MSG);
-            }
-            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            config.prompt_flag = prompt;
+            daemon_preflight(dir, &config)?;
+            let runner = Claude {
+                repo: paths::repo_dir()?,
+                tasks_dir: dir.to_path_buf(),
+                sandbox: config.sandbox,
+                prompt: roots::Prompt {
+                    base: roots::global(&config).1,
+                    append: None,
+                },
+                report_actions: true,
+            };
+            daemon::run_supervised(dir, &config, &runner, max_scans)
         }
-    }
-}
-
-fn main() {
-    let cli = Cli::parse();
-
-    match cli.command {
-        Some(Cmd::Status) | None => cmd_status(),
-        Some(Cmd::Jump { name }) => cmd_jump(&name),
-        Some(Cmd::Daemon) => cmd_daemon(),
-        Some(Cmd::Scan) => {
-            write_inbox(SCAN_MSG);
-            eprintln!("[orch] scan triggered");
+        Some(Cmd::MigratePrefix { old, new, dry_run }) => {
+            cmd_migrate_prefix(dir, &config, &old, &new, dry_run)
+        }
+        Some(Cmd::Log { name, json }) => cmd_log(dir, &config, &focused(name)?, json),
+        Some(Cmd::Timeline {
+            since,
+            until,
+            task,
+            json,
+        }) => cmd_timeline(
+            dir,
+            &config,
+            &since,
+            until.as_deref(),
+            task.as_deref(),
+            json,
+        ),
+        Some(Cmd::Scan {
+            name,
+            force,
+            wait,
+            dry_run,
+            detach,
+        }) => cmd_scan(dir, &config, name.as_deref(), force, wait, dry_run, detach),
+        Some(Cmd::ScanRun { id, message }) => cmd_scan_run(dir, &config, &id, &message),
+        Some(Cmd::Serve { port }) => cmd_serve(dir, &config, port),
+        Some(Cmd::Msg {
+            to: Some(name),
+            message,
+            ..
+        }) => cmd_msg_to(dir, &config, &name, &message.join(" ")),
+        Some(Cmd::Msg {
+            as_task: Some(name),
+            message,
+            ..
+        }) => cmd_msg_as_task(dir, &config, Some(&name), &message.join(" ")),
+        Some(Cmd::Msg {
+            as_task: None,
+            message,
+            queue_on_failure,
+            ..
+        }) => cmd_msg(dir, &config, &message.join(" "), queue_on_failure),
+        Some(Cmd::Queue {
+            command: QueueCmd::List,
+        }) => cmd_queue_list(dir),
+        Some(Cmd::Queue {
+            command: QueueCmd::Drop { id },
+        }) => cmd_queue_drop(dir, &id),
+        Some(Cmd::Delegate {
+            name,
+            to,
+            text,
+            create,
+        }) => cmd_delegate(dir, &config, &name, &to, &text, create),
+        Some(Cmd::WorkerExited { name, code }) => cmd_worker_exited(dir, &config, &name, code),
+        Some(Cmd::Replay {
+            name,
+            model,
+            dry_run,
+        }) => cmd_replay(dir, &config, &focused(name)?, model.as_deref(), dry_run),
+        Some(Cmd::Stats {
+            by_task: true,
+            csv,
+            json,
+            ..
+        }) => cmd_stats_by_task(dir, csv, json),
+        Some(Cmd::Stats {
+            by,
+            since,
+            until,
+            csv,
+            json,
+            ..
+        }) => cmd_stats(dir, &config, by, &since, until.as_deref(), csv, json),
+        Some(Cmd::Runs {
+            command: Some(RunsCmd::Show { id }),
+            ..
+        }) => cmd_runs_show(dir, id.as_deref(), format),
+        Some(Cmd::Runs {
+            command: Some(RunsCmd::Diff { a, b }),
+            ..
+        }) => cmd_runs_diff(dir, &a, &b),
+        Some(Cmd::Runs {
+            command: None,
+            limit,
+        }) => cmd_runs(dir, limit, format),
+        Some(Cmd::Doctor { fix }) => cmd_doctor(dir, &config, config_path, fix),
+        Some(Cmd::Validate { fix }) => cmd_validate(dir, &config, fix),
+        Some(Cmd::Prompt { root, prompt }) => {
+            let mut config = config;
+            config.prompt_flag = prompt;
+            cmd_prompt(dir, &config, root.as_deref())
         }
-        Some(Cmd::Msg { message }) => {
-            write_inbox(&message.join(" "));
-            eprintln!("[orch] message sent");
+        Some(Cmd::Which { prompt }) => {
+            let mut config = config;
+            config.prompt_flag = prompt;
+            cmd_which(dir, &config, config_path)
         }
+        Some(Cmd::Version { verbose }) => cmd_version(dir, config_path, verbose),
+        Some(Cmd::Export { to, path, sync }) => cmd_export(dir, &config, to, &path, sync),
+        Some(Cmd::Hooks {
+            command:
+                HooksCmd::Test {
+                    event,
+                    task,
+                    payload,
+                },
+        }) => cmd_hooks_test(
+            dir,
+            &config,
+            config_path,
+            event,
+            task.as_deref(),
+            payload.as_deref(),
+        ),
+        Some(Cmd::State {
+            command: StateCmd::Check { repair },
+        }) => cmd_state_check(dir, &config, repair),
     }
 }
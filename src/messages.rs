@@ -0,0 +1,73 @@
+//! The `.inbox` spool: `orch -` drops messages here and the daemon drains
+//! them into its next orchestrator run. `orch - --to` skips the spool and
+//! hands the message to a task instead, noting it in the task's [`crate::log`].
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::OrchError;
+
+pub fn write(dir: &Path, msg: &str) -> Result<(), OrchError> {
+    spool(dir, msg).map(|_| ())
+}
+
+/// [`write`], returning the message's file, which is gone once the daemon
+/// has taken it.
+pub fn spool(dir: &Path, msg: &str) -> Result<PathBuf, OrchError> {
+    fs::create_dir_all(dir).map_err(|e| OrchError::io(dir, e))?;
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let path = dir.join(format!("{nanos}-{}.msg", std::process::id()));
+    fs::write(&path, msg).map_err(|e| OrchError::io(&path, e))?;
+    Ok(path)
+}
+
+/// How many messages are waiting for the daemon.
+pub fn pending(dir: &Path) -> usize {
+    fs::read_dir(dir).map_or(0, |entries| {
+        entries
+            .flatten()
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "msg"))
+            .count()
+    })
+}
+
+pub fn drain(dir: &Path) -> Option<String> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "msg"))
+        .collect();
+    if entries.is_empty() {
+        return None;
+    }
+    entries.sort_by_key(|e| e.file_name());
+
+    let mut messages = Vec::new();
+    for entry in entries {
+        if let Ok(msg) = fs::read_to_string(entry.path()) {
+            if !msg.trim().is_empty() {
+                messages.push(msg);
+            }
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+    (!messages.is_empty()).then(|| messages.join("\n"))
+}
+
+/// How a routed message reached its task.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Via {
+    /// Typed into the worker's live session
+    Session,
+    /// Added to the task file's `## Instructions`, for the next worker
+    TaskFile,
+}
@@ -0,0 +1,655 @@
+//! Notifications for new inbox items, items left there past an
+//! `[escalation]` threshold, and tasks the orchestrator reports blocked,
+//! through each configured [`Notifier`]. A [`Breaker`] turns a burst of them
+//! into digests.
+
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local};
+use serde_json::json;
+
+use crate::{
+    actions::{ActionKind, ScanAction},
+    config::{BackendKind, Config, Escape, NotificationsConfig},
+    duration,
+    escalation::{self, Step},
+    fmt,
+    health::Tripped,
+    hooks::{self, HookEvent},
+    inbox::{self, InboxItem},
+    log::{self, Event},
+    task,
+};
+
+/// What a notification is about.
+#[derive(Clone, Copy)]
+pub enum EventKind {
+    /// A task landed in `orch inbox`.
+    NeedsAttention,
+    /// The orchestrator reported a task blocked.
+    Blocked,
+    /// An inbox item passed an `[escalation]` threshold.
+    Escalated,
+    /// What the [`Breaker`] held back.
+    Digest,
+    /// `orch inbox --notify-test`.
+    Test,
+}
+
+impl EventKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            EventKind::NeedsAttention => "needs_attention",
+            EventKind::Blocked => "blocked",
+            EventKind::Escalated => "escalated",
+            EventKind::Digest => "digest",
+            EventKind::Test => "test",
+        }
+    }
+}
+
+/// One notification, however it's delivered.
+pub struct TaskEvent {
+    pub kind: EventKind,
+    pub task: Option<String>,
+    pub title: String,
+    pub body: String,
+}
+
+impl TaskEvent {
+    fn payload(&self) -> serde_json::Value {
+        json!({
+            "event": self.kind.name(),
+            "title": self.title,
+            "body": self.body,
+            "task": self.task,
+        })
+    }
+}
+
+/// A way of telling the user about a [`TaskEvent`]. [`Notifiers`] holds the
+/// configured ones.
+pub trait Notifier {
+    fn notify(&self, event: &TaskEvent) -> Result<(), String>;
+    /// Shown in the daemon log, `daemon status` and errors.
+    fn describe(&self) -> String;
+}
+
+/// Turns a finished command into a notification result.
+fn finished(out: std::io::Result<Output>) -> Result<(), String> {
+    match out {
+        Ok(out) if out.status.success() => Ok(()),
+        Ok(out) => Err(format!(
+            "exited with {}: {}",
+            out.status,
+            String::from_utf8_lossy(&out.stderr).trim()
+        )),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub enum DesktopNotifier {
+    /// `osascript`'s `display notification`
+    MacOs,
+    NotifySend,
+}
+
+impl DesktopNotifier {
+    /// The desktop's notifier, if there's one to reach.
+    pub fn detect() -> Option<DesktopNotifier> {
+        if cfg!(target_os = "macos") && on_path("osascript") {
+            Some(DesktopNotifier::MacOs)
+        } else if on_path("notify-send")
+            && (std::env::var_os("DISPLAY").is_some()
+                || std::env::var_os("WAYLAND_DISPLAY").is_some())
+        {
+            Some(DesktopNotifier::NotifySend)
+        } else {
+            None
+        }
+    }
+}
+
+impl Notifier for DesktopNotifier {
+    fn notify(&self, event: &TaskEvent) -> Result<(), String> {
+        let (title, body) = (event.title.as_str(), event.body.as_str());
+        finished(match self {
+            // Title and body go in as argv, never spliced into the script
+            DesktopNotifier::MacOs => Command::new("osascript")
+                .args([
+                    "-e",
+                    "on run argv",
+                    "-e",
+                    "display notification (item 2 of argv) with title (item 1 of argv)",
+                    "-e",
+                    "end run",
+                    title,
+                    body,
+                ])
+                .output(),
+            DesktopNotifier::NotifySend => Command::new("notify-send").args([title, body]).output(),
+        })
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            DesktopNotifier::MacOs => "desktop (osascript)".into(),
+            DesktopNotifier::NotifySend => "desktop (notify-send)".into(),
+        }
+    }
+}
+
+/// Runs a program like a hook, with the event as JSON on stdin.
+pub struct CommandNotifier {
+    pub command: String,
+    pub tasks_dir: PathBuf,
+}
+
+impl Notifier for CommandNotifier {
+    fn notify(&self, event: &TaskEvent) -> Result<(), String> {
+        finished(hooks::run(
+            &self.command,
+            "notification",
+            &event.payload(),
+            &self.tasks_dir,
+        ))
+    }
+
+    fn describe(&self) -> String {
+        "command".into()
+    }
+}
+
+/// POSTs the event as JSON to a URL, with `curl`.
+pub struct WebhookNotifier {
+    pub url: String,
+}
+
+impl Notifier for WebhookNotifier {
+    fn notify(&self, event: &TaskEvent) -> Result<(), String> {
+        let mut child = Command::new("curl")
+            .args(["-fsS", "-m", "10", "-X", "POST"])
+            .args([
+                "-H",
+                "Content-Type: application/json",
+                "--data-binary",
+                "@-",
+            ])
+            .arg(&self.url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("failed to run curl: {e}"))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(event.payload().to_string().as_bytes());
+        }
+        finished(child.wait_with_output())
+    }
+
+    fn describe(&self) -> String {
+        "webhook".into()
+    }
+}
+
+/// Escape sequences written to a tty.
+pub struct TerminalNotifier {
+    pub tty: PathBuf,
+    pub escapes: Vec<Escape>,
+}
+
+impl Notifier for TerminalNotifier {
+    fn notify(&self, event: &TaskEvent) -> Result<(), String> {
+        write_escapes(&self.tty, &self.escapes, &event.title, &event.body)
+    }
+
+    fn describe(&self) -> String {
+        "terminal".into()
+    }
+}
+
+/// Drops everything, for `backend = "none"` or when nothing is available.
+pub struct NullNotifier;
+
+impl Notifier for NullNotifier {
+    fn notify(&self, _event: &TaskEvent) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        "none".into()
+    }
+}
+
+/// Every configured notifier that's available, each sent every event.
+pub struct Notifiers(Vec<Box<dyn Notifier>>);
+
+impl Notifiers {
+    /// Builds `notifiers` from the config, or just `backend` when that's
+    /// empty. Backends that aren't available here are left out.
+    pub fn from_config(config: &NotificationsConfig, tasks_dir: &Path) -> Notifiers {
+        let command = || -> Option<Box<dyn Notifier>> {
+            let command = config.command.clone()?;
+            Some(Box::new(CommandNotifier {
+                command,
+                tasks_dir: tasks_dir.to_path_buf(),
+            }))
+        };
+        let desktop = || DesktopNotifier::detect().map(|d| Box::new(d) as Box<dyn Notifier>);
+        let kinds = if config.notifiers.is_empty() {
+            std::slice::from_ref(&config.backend)
+        } else {
+            &config.notifiers
+        };
+        let notifiers = kinds
+            .iter()
+            .filter_map(|kind| match kind {
+                BackendKind::Auto => desktop().or_else(command),
+                BackendKind::Desktop => desktop(),
+                BackendKind::Command => command(),
+                BackendKind::Webhook => config
+                    .webhook
+                    .clone()
+                    .map(|url| Box::new(WebhookNotifier { url }) as Box<dyn Notifier>),
+                BackendKind::Terminal => terminal(config),
+                BackendKind::None => None,
+            })
+            .collect();
+        Notifiers(notifiers)
+    }
+
+    /// Sends through a fixed list, for callers with their own notifiers.
+    pub fn new(notifiers: Vec<Box<dyn Notifier>>) -> Notifiers {
+        Notifiers(notifiers)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Sends `event` through every notifier, even after one fails; the
+    /// error names each that did.
+    pub fn notify(&self, event: &TaskEvent) -> Result<(), String> {
+        let failed: Vec<_> = self
+            .0
+            .iter()
+            .filter_map(|n| {
+                n.notify(event)
+                    .err()
+                    .map(|e| format!("{}: {e}", n.describe()))
+            })
+            .collect();
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(failed.join("; "))
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        if self.0.is_empty() {
+            return NullNotifier.describe();
+        }
+        self.0
+            .iter()
+            .map(|n| n.describe())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// The terminal backend, if there's a tty to write to: the configured one,
+/// else the controlling terminal (none for a daemon started from a service).
+fn terminal(config: &NotificationsConfig) -> Option<Box<dyn Notifier>> {
+    let tty = config.tty.clone().unwrap_or_else(|| "/dev/tty".into());
+    OpenOptions::new().write(true).open(&tty).ok()?;
+    Some(Box::new(TerminalNotifier {
+        tty,
+        escapes: config.escapes.clone(),
+    }))
+}
+
+/// Opened per notification, so a tty that's gone just fails this one.
+fn write_escapes(tty: &Path, escapes: &[Escape], title: &str, body: &str) -> Result<(), String> {
+    // Control characters in task text could end the sequence early
+    let clean = |s: &str| s.chars().filter(|c| !c.is_control()).collect::<String>();
+    let (title, body) = (clean(title), clean(body));
+    let mut out = String::new();
+    for escape in escapes {
+        match escape {
+            Escape::Bel => out.push('\x07'),
+            Escape::Osc9 => out.push_str(&format!("\x1b]9;{title}: {body}\x07")),
+            Escape::Osc777 => out.push_str(&format!(
+                "\x1b]777;notify;{};{body}\x07",
+                title.replace(';', ",")
+            )),
+        }
+    }
+    OpenOptions::new()
+        .write(true)
+        .open(tty)
+        .and_then(|mut f| f.write_all(out.as_bytes()))
+        .map_err(|e| format!("{}: {e}", tty.display()))
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+/// Notes a notification the [`Breaker`] held back in its task's log.
+fn record_held(dir: &Path, event: &TaskEvent) {
+    let Some(label) = &event.task else {
+        return;
+    };
+    let name = label.rsplit('/').next().unwrap_or(label);
+    log::record(
+        dir,
+        name,
+        Event::NotificationHeld {
+            kind: event.kind.name().into(),
+            title: event.title.clone(),
+            body: event.body.clone(),
+        },
+    );
+}
+
+/// Notifications past `breaker_max` within `breaker_window` are held back
+/// rather than sent, so a dead API key or a flood of failing workers makes
+/// one digest a window instead of a popup each. It resets, with a last
+/// digest, once the rate is back under the limit.
+pub struct Breaker {
+    max: usize,
+    window: Duration,
+    /// When each notification in the window came, sent or held.
+    recent: VecDeque<DateTime<Local>>,
+    tripped: Option<Trip>,
+}
+
+struct Trip {
+    since: DateTime<Local>,
+    /// When the current digest's window started.
+    from: DateTime<Local>,
+    held: BTreeMap<&'static str, u64>,
+}
+
+impl Breaker {
+    pub fn new(config: &NotificationsConfig) -> Breaker {
+        Breaker {
+            max: config.breaker_max,
+            // Invalid values were reset with a warning when the config loaded
+            window: duration::parse(&config.breaker_window).unwrap_or_default(),
+            recent: VecDeque::new(),
+            tripped: None,
+        }
+    }
+
+    fn forget_before(&mut self, now: DateTime<Local>) {
+        while let Some(at) = self.recent.front() {
+            if (now - *at).to_std().unwrap_or_default() < self.window {
+                break;
+            }
+            self.recent.pop_front();
+        }
+    }
+
+    /// Whether a notification of `kind` may go out now; if not, it's
+    /// counted for the next digest.
+    fn admit(&mut self, kind: EventKind, now: DateTime<Local>) -> bool {
+        if self.max == 0 {
+            return true;
+        }
+        self.forget_before(now);
+        self.recent.push_back(now);
+        if self.tripped.is_none() && self.recent.len() > self.max {
+            eprintln!(
+                "[orch] more than {} within {}, holding notifications back for a digest",
+                fmt::count(self.max, "notification"),
+                fmt::duration(self.window)
+            );
+            self.tripped = Some(Trip {
+                since: now,
+                from: now,
+                held: BTreeMap::new(),
+            });
+        }
+        match &mut self.tripped {
+            Some(trip) => {
+                *trip.held.entry(kind.name()).or_default() += 1;
+                false
+            }
+            None => true,
+        }
+    }
+
+    /// The digest that's due, if any: a window after the last, or when the
+    /// rate is back under the limit, which also resets the breaker.
+    fn digest(&mut self, now: DateTime<Local>) -> Option<TaskEvent> {
+        self.forget_before(now);
+        let calm = self.recent.len() <= self.max;
+        let trip = self.tripped.as_mut()?;
+        let waited = (now - trip.from).to_std().unwrap_or_default();
+        if !calm && waited < self.window {
+            return None;
+        }
+        let total: u64 = trip.held.values().sum();
+        let mut counts: Vec<_> = trip.held.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let counts: Vec<String> = counts.iter().map(|(k, n)| format!("{n} {k}")).collect();
+        let event = (total > 0).then(|| TaskEvent {
+            kind: EventKind::Digest,
+            task: None,
+            title: format!(
+                "orch: {} in the last {}",
+                fmt::count(total as usize, "event"),
+                fmt::duration(waited)
+            ),
+            body: format!("{}; run `orch inbox` or `orch timeline`", counts.join(", ")),
+        });
+        if calm {
+            eprintln!("[orch] notifications are back under the limit, sending them again");
+            self.tripped = None;
+        } else {
+            trip.from = now;
+            trip.held.clear();
+        }
+        event
+    }
+
+    /// For `daemon status`.
+    pub fn tripped(&self) -> Option<Tripped> {
+        self.tripped.as_ref().map(|trip| Tripped {
+            since: task::timestamp(trip.since),
+            held: trip.held.iter().map(|(k, n)| (k.to_string(), *n)).collect(),
+        })
+    }
+}
+
+/// Notifies about inbox items the daemon hasn't announced yet.
+pub struct InboxNotifier {
+    notifiers: Notifiers,
+    seen: HashSet<String>,
+    breaker: Breaker,
+    dir: PathBuf,
+    /// Held-back notifications aren't recorded; see [`Config::read_only`].
+    read_only: bool,
+}
+
+impl InboxNotifier {
+    /// Starts from the current inbox so a daemon restart doesn't re-announce.
+    pub fn new(config: &Config, dir: &Path) -> InboxNotifier {
+        let notifiers = Notifiers::from_config(&config.notifications, dir);
+        eprintln!("[orch] notifications: {}", notifiers.describe());
+        let seen = inbox::items(dir, config)
+            .iter()
+            .map(InboxItem::key)
+            .collect();
+        InboxNotifier {
+            notifiers,
+            seen,
+            breaker: Breaker::new(&config.notifications),
+            dir: dir.to_path_buf(),
+            read_only: config.read_only,
+        }
+    }
+
+    pub fn notifiers(&self) -> &Notifiers {
+        &self.notifiers
+    }
+
+    pub fn breaker(&self) -> &Breaker {
+        &self.breaker
+    }
+
+    /// Sends `event` unless the breaker holds it back, in which case it
+    /// goes to the daemon log and, unless read-only, its task's log instead.
+    fn send(&mut self, event: &TaskEvent) {
+        if !self.breaker.admit(event.kind, Local::now()) {
+            eprintln!("[orch] notification held back: {}", event.title);
+            if !self.read_only {
+                record_held(&self.dir, event);
+            }
+            return;
+        }
+        self.deliver(event);
+    }
+
+    fn deliver(&self, event: &TaskEvent) {
+        if let Err(e) = self.notifiers.notify(event) {
+            eprintln!("[orch] notification failed: {e}");
+        }
+    }
+
+    /// Sends the breaker's digest if one is due. Call it regularly.
+    pub fn flush(&mut self) {
+        if let Some(digest) = self.breaker.digest(Local::now()) {
+            let title = digest.title.trim_start_matches("orch: ");
+            eprintln!("[orch] {title}: {}", digest.body);
+            self.deliver(&digest);
+        }
+    }
+
+    pub fn check(&mut self, config: &Config, dir: &Path) {
+        let items = inbox::items(dir, config);
+        for item in &items {
+            if self.seen.contains(&item.key()) {
+                continue;
+            }
+            self.send(&TaskEvent {
+                kind: EventKind::NeedsAttention,
+                task: Some(item.name.clone()),
+                title: format!("orch: {} needs attention", item.name),
+                body: item.reason.clone(),
+            });
+        }
+        self.seen = items.iter().map(InboxItem::key).collect();
+    }
+
+    /// Escalates inbox items past an `[escalation]` threshold, notifying and
+    /// firing the `inbox_escalated` hook for each step taken.
+    pub fn escalate(&mut self, config: &Config, dir: &Path) {
+        for (item, step) in escalation::check(dir, config) {
+            let age = fmt::age(item.since);
+            let title = match step {
+                Step::Priority(priority) => format!(
+                    "orch: {} waited {age}, priority raised to {priority}",
+                    item.name
+                ),
+                Step::Notify | Step::Renotify => {
+                    format!("orch: {} still needs attention ({age})", item.name)
+                }
+            };
+            eprintln!("[orch] {}", title.trim_start_matches("orch: "));
+            self.send(&TaskEvent {
+                kind: EventKind::Escalated,
+                task: Some(item.name.clone()),
+                title,
+                body: item.reason.clone(),
+            });
+            let priority = match step {
+                Step::Priority(priority) => Some(priority),
+                Step::Notify | Step::Renotify => None,
+            };
+            let payload = json!({
+                "event": HookEvent::InboxEscalated.name(),
+                "task": item.name,
+                "label": item.label,
+                "reason": item.reason,
+                "since": task::timestamp(item.since),
+                "step": step.name(),
+                "priority": priority,
+            });
+            hooks::fire(config, dir, HookEvent::InboxEscalated, payload);
+        }
+    }
+
+    /// Notifies about tasks the orchestrator says it found blocked.
+    pub fn blocked(&mut self, actions: &[ScanAction]) {
+        for action in actions.iter().filter(|a| a.kind == ActionKind::Blocked) {
+            self.send(&TaskEvent {
+                kind: EventKind::Blocked,
+                task: Some(action.task.clone()),
+                title: format!("orch: {} is blocked", action.task),
+                body: action
+                    .reason
+                    .clone()
+                    .unwrap_or_else(|| "reported by the orchestrator".into()),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths;
+
+    /// A notifier that sends nowhere and holds back all but the first.
+    fn notifier(dir: &Path, read_only: bool) -> InboxNotifier {
+        let mut config = Config {
+            read_only,
+            ..Config::default()
+        };
+        config.notifications.backend = BackendKind::None;
+        config.notifications.breaker_max = 1;
+        InboxNotifier::new(&config, dir)
+    }
+
+    fn event(name: &str) -> TaskEvent {
+        TaskEvent {
+            kind: EventKind::NeedsAttention,
+            task: Some(name.into()),
+            title: format!("orch: {name} needs attention"),
+            body: "needs: a decision".into(),
+        }
+    }
+
+    #[test]
+    fn held_back_notifications_are_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut notifier = notifier(dir.path(), false);
+        notifier.send(&event("recon"));
+        notifier.send(&event("auth"));
+        assert_eq!(log::tasks(dir.path()), ["auth"]);
+        let held: Vec<_> = log::entries(dir.path(), "auth").collect();
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].event.kind(), "held");
+    }
+
+    #[test]
+    fn read_only_holds_back_without_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut notifier = notifier(dir.path(), true);
+        notifier.send(&event("recon"));
+        notifier.send(&event("auth"));
+        assert!(notifier.breaker().tripped().is_some());
+        assert!(!paths::state_dir(dir.path()).exists());
+    }
+}
@@ -0,0 +1,407 @@
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Read, Write},
+    path::PathBuf,
+    process::{ChildStdin, Command, Stdio},
+    thread::{self, JoinHandle},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::{self, ScanAction},
+    delegation, include, roots,
+    sandbox::{self, SandboxMode},
+};
+
+/// Substrings (lowercased) that claude prints when it can't authenticate.
+const AUTH_ERRORS: &[&str] = &[
+    "not logged in",
+    "please run /login",
+    "claude login",
+    "invalid api key",
+    "authentication_error",
+    "oauth token has expired",
+    "401 unauthorized",
+];
+
+/// Whether claude's output looks like an authentication failure.
+pub fn auth_error(output: &str) -> bool {
+    let output = output.to_lowercase();
+    AUTH_ERRORS.iter().any(|sig| output.contains(sig))
+}
+
+/// Substrings (lowercased) that claude prints when it can't reach the API,
+/// or the API is briefly unavailable: worth trying again later.
+const OFFLINE_ERRORS: &[&str] = &[
+    "connection error",
+    "fetch failed",
+    "getaddrinfo",
+    "enotfound",
+    "eai_again",
+    "econnrefused",
+    "econnreset",
+    "etimedout",
+    "network is unreachable",
+    "request timed out",
+    "overloaded_error",
+    "503 service unavailable",
+];
+
+/// Whether claude's output looks like it failed for lack of a connection.
+pub fn offline_error(output: &str) -> bool {
+    let output = output.to_lowercase();
+    OFFLINE_ERRORS.iter().any(|sig| output.contains(sig))
+}
+
+/// Reads all of `from` without echoing it.
+fn capture(mut from: impl Read + Send + 'static) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut captured = String::new();
+        let _ = from.read_to_string(&mut captured);
+        captured
+    })
+}
+
+/// What `claude -p --output-format json` prints.
+#[derive(Deserialize)]
+struct Reply {
+    #[serde(default)]
+    result: String,
+    total_cost_usd: Option<f64>,
+}
+
+/// The reply text and its cost from claude's JSON output. Output that isn't
+/// JSON (an older claude, a wrapper script) is the reply as is.
+fn reply(stdout: &str) -> (String, Option<f64>) {
+    match serde_json::from_str::<Reply>(stdout.trim()) {
+        Ok(reply) => (reply.result, reply.total_cost_usd),
+        Err(_) => (stdout.to_string(), None),
+    }
+}
+
+/// Echoes `from` line by line to stderr while keeping a copy.
+fn tee(from: impl Read + Send + 'static) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut captured = String::new();
+        for line in BufReader::new(from).lines().map_while(Result::ok) {
+            eprintln!("{line}");
+            captured.push_str(&line);
+            captured.push('\n');
+        }
+        captured
+    })
+}
+
+/// Writes `input` to claude's stdin from a thread, so a prompt bigger than
+/// the pipe buffer can't stall us while claude is writing, then closes it.
+/// A claude that exits without reading all of it is logged, not fatal.
+fn feed(stdin: ChildStdin, input: String) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut stdin = stdin;
+        let result = stdin
+            .write_all(input.as_bytes())
+            .and_then(|_| stdin.flush());
+        match result {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => {
+                eprintln!("[orch] claude exited before reading the whole prompt")
+            }
+            Err(e) => eprintln!("[orch] failed to send the prompt to claude: {e}"),
+        }
+    })
+}
+
+/// Runs a trivial `claude -p` to check that claude is installed and logged in.
+pub fn check_auth() -> Result<(), String> {
+    let mut child = Command::new("claude")
+        .args(["--model", "haiku", "-p"])
+        .env_remove("CLAUDECODE")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run claude: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"Reply with the single word: ok");
+    }
+    let out = child
+        .wait_with_output()
+        .map_err(|e| format!("claude wait failed: {e}"))?;
+    if out.status.success() {
+        return Ok(());
+    }
+    let output = format!(
+        "{}{}",
+        String::from_utf8_lossy(&out.stdout),
+        String::from_utf8_lossy(&out.stderr)
+    );
+    if auth_error(&output) {
+        return Err("claude appears unauthenticated; run `claude login`".into());
+    }
+    let first = output.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+    Err(format!("claude exited with {}: {first}", out.status))
+}
+
+/// Default prompt for `orch new --ask`; `draft_prompt` in the config
+/// replaces it with a file.
+pub const DRAFT_PROMPT: &str = "\
+You turn a one-line goal into a task brief for an AI coding agent. Reply with only the \
+markdown for the task file, no preamble, in this shape:
+
+# <short title>
+
+<two or three sentences: what to do and why>
+
+## Context
+
+<relevant background, constraints, and anything the agent should look at first>
+
+## Acceptance criteria
+
+- <checkable outcome>
+- <checkable outcome>
+
+Don't invent facts about the codebase; where something is unknown, say what to find out. \
+The goal:
+";
+
+/// Sends `input` to a one-shot `claude -p` and returns its reply, trimmed.
+pub fn ask(input: String) -> Result<String, String> {
+    let mut child = Command::new("claude")
+        .args(["--model", "opus", "-p"])
+        .env_remove("CLAUDECODE")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run claude: {e}"))?;
+    let writer = child.stdin.take().map(|stdin| feed(stdin, input));
+    let out = child
+        .wait_with_output()
+        .map_err(|e| format!("claude wait failed: {e}"))?;
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        if auth_error(&format!("{stdout}{stderr}")) {
+            return Err("claude appears unauthenticated; run `claude login`".into());
+        }
+        let first = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+        return Err(format!("claude exited with {}: {first}", out.status));
+    }
+    Ok(stdout.trim().to_string())
+}
+
+/// Asks claude to expand `goal` into a task file body, following `prompt`.
+pub fn draft(prompt: &str, goal: &str) -> Result<String, String> {
+    let text = ask(format!("{prompt}\n{goal}\n"))?;
+    // Some replies come wrapped in a ```markdown fence anyway
+    let text = match text.strip_prefix("```") {
+        Some(rest) => rest
+            .split_once('\n')
+            .map_or("", |(_, body)| body)
+            .trim_end()
+            .trim_end_matches("```"),
+        None => &text,
+    };
+    if text.trim().is_empty() {
+        return Err("claude returned an empty draft".into());
+    }
+    Ok(text.trim().to_string())
+}
+
+/// Everything an orchestrator turn was given, prompt files expanded, so
+/// `orch replay` can send it again as it was.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Sent {
+    pub message: String,
+    /// `--system-prompt`; None means `--agent orchestrator`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub append_system_prompt: Option<String>,
+}
+
+/// The model orchestrator runs use unless a replay picks another.
+pub const MODEL: &str = "opus";
+
+/// The outcome of one orchestrator turn.
+pub struct Run {
+    pub ok: bool,
+    /// What the orchestrator printed (stdout then stderr).
+    pub output: String,
+    /// Its `orch-actions` block, if it was asked for one and gave one.
+    pub actions: Option<Vec<ScanAction>>,
+    /// What it cost, when claude said.
+    pub cost_usd: Option<f64>,
+    /// What it was given; None if it failed before claude started.
+    pub sent: Option<Sent>,
+}
+
+/// Runs one orchestrator turn for a message. [`Claude`] is the real one.
+pub trait Runner {
+    fn run(&self, message: &str) -> Run;
+
+    /// [`Runner::run`] with a root's prompt instead of the default one.
+    fn run_with(&self, message: &str, _prompt: &roots::Prompt) -> Run {
+        self.run(message)
+    }
+}
+
+/// `claude -p` with the orchestrator agent, run in `tasks_dir` with
+/// `$ORCH_REPO` set to `repo`.
+pub struct Claude {
+    pub repo: String,
+    pub tasks_dir: PathBuf,
+    /// Which permission flags it gets, see [`sandbox::claude_args`].
+    pub sandbox: SandboxMode,
+    /// Prompt files for runs that don't pass their own, expanded
+    /// ([`include`]) on every run so edits apply to the next scan.
+    pub prompt: roots::Prompt,
+    /// Ask for an `orch-actions` block ([`actions::PROMPT`]) and explain
+    /// delegation ([`delegation::PROMPT`]).
+    pub report_actions: bool,
+}
+
+impl Runner for Claude {
+    fn run(&self, message: &str) -> Run {
+        self.run_with(message, &self.prompt)
+    }
+
+    fn run_with(&self, message: &str, prompt: &roots::Prompt) -> Run {
+        eprintln!("[orch] {message}");
+        match self.prepare(message, prompt) {
+            Some(sent) => self.send(sent, MODEL),
+            None => Run::failed(),
+        }
+    }
+}
+
+impl Run {
+    fn failed() -> Run {
+        Run {
+            ok: false,
+            output: String::new(),
+            actions: None,
+            cost_usd: None,
+            sent: None,
+        }
+    }
+}
+
+impl Claude {
+    /// What a turn for `message` with `prompt` would be given, prompt files
+    /// expanded. None if one can't be read, which is logged.
+    pub fn prepare(&self, message: &str, prompt: &roots::Prompt) -> Option<Sent> {
+        let system_prompt = match &prompt.base {
+            Some(path) => match include::expand(path) {
+                Ok(prompt) => Some(prompt),
+                Err(e) => {
+                    eprintln!("[orch] orchestrator prompt: {e}");
+                    return None;
+                }
+            },
+            None => None,
+        };
+        let mut appended = Vec::new();
+        if let Some(path) = &prompt.append {
+            match include::expand(path) {
+                Ok(text) => appended.push(text.trim_end().to_string()),
+                Err(e) => {
+                    eprintln!("[orch] root prompt: {e}");
+                    return None;
+                }
+            }
+        }
+        if self.report_actions {
+            appended.push(actions::PROMPT.to_string());
+            appended.push(delegation::PROMPT.to_string());
+        }
+        Some(Sent {
+            message: message.to_string(),
+            system_prompt,
+            append_system_prompt: (!appended.is_empty()).then(|| appended.join("\n\n")),
+        })
+    }
+
+    /// One turn with exactly `sent`, on `model`.
+    pub fn send(&self, sent: Sent, model: &str) -> Run {
+        let mut cmd = Command::new("claude");
+        cmd.args(["--model", model, "-p", "--output-format", "json"])
+            .args(sandbox::claude_args(self.sandbox, &self.tasks_dir))
+            .current_dir(&self.tasks_dir);
+        match &sent.system_prompt {
+            Some(prompt) => cmd.args(["--system-prompt", prompt]),
+            None => cmd.args(["--agent", "orchestrator"]),
+        };
+        if let Some(append) = &sent.append_system_prompt {
+            cmd.args(["--append-system-prompt", append]);
+        }
+        let mut child = match cmd
+            .env("ORCH_REPO", &self.repo)
+            .env_remove("CLAUDECODE")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("[orch] failed to run claude: {e}");
+                return Run::failed();
+            }
+        };
+
+        // The reply is echoed once it's in, stderr as it comes; both are
+        // kept to diagnose failures
+        let stdout = child.stdout.take().map(capture);
+        let stderr = child.stderr.take().map(tee);
+        // The prompt goes in on stdin, never argv: no ARG_MAX limit, and it
+        // stays out of `ps`
+        let writer = child
+            .stdin
+            .take()
+            .map(|stdin| feed(stdin, sent.message.clone()));
+        let status = child.wait();
+        if let Some(writer) = writer {
+            let _ = writer.join();
+        }
+        let (reply, cost_usd) = reply(&stdout.and_then(|t| t.join().ok()).unwrap_or_default());
+        for line in reply.lines() {
+            println!("{line}");
+        }
+        let mut captured = reply;
+        if !captured.is_empty() && !captured.ends_with('\n') {
+            captured.push('\n');
+        }
+        captured.extend(stderr.and_then(|t| t.join().ok()));
+
+        let ok = match status {
+            Ok(s) if !s.success() => {
+                eprintln!("[orch] claude exited with {s}");
+                if auth_error(&captured) {
+                    eprintln!("[orch] claude appears unauthenticated; run `claude login`");
+                }
+                false
+            }
+            Err(e) => {
+                eprintln!("[orch] claude wait failed: {e}");
+                false
+            }
+            _ => true,
+        };
+        let actions = self
+            .report_actions
+            .then(|| actions::parse(&captured))
+            .flatten();
+        Run {
+            ok,
+            output: captured,
+            actions,
+            cost_usd,
+            sent: Some(sent),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Where command output goes: stdout, or the file given by `--output`.
+//!
+//! Commands print through [`out!`](crate::out) / [`outln!`](crate::outln)
+//! instead of `print!`, so one flag redirects everything they'd show.
+//! Diagnostics stay on stderr.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, IsTerminal, Write},
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+
+static FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Sends all later output to `path`, truncating it unless `append`.
+pub fn redirect(path: &Path, append: bool) -> io::Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)?;
+    let _ = FILE.set(Mutex::new(file));
+    Ok(())
+}
+
+/// Whether output lands on a terminal (never true once redirected).
+pub fn is_terminal() -> bool {
+    FILE.get().is_none() && io::stdout().is_terminal()
+}
+
+/// Pushes out what's been written, for output read as it comes (JSON
+/// lines).
+pub fn flush() {
+    let result = match FILE.get() {
+        Some(file) => file.lock().unwrap().flush(),
+        None => io::stdout().lock().flush(),
+    };
+    if result
+        .as_ref()
+        .is_err_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+    {
+        std::process::exit(0);
+    }
+}
+
+#[doc(hidden)]
+pub fn write_fmt(args: std::fmt::Arguments) {
+    let result = match FILE.get() {
+        Some(file) => file.lock().unwrap().write_fmt(args),
+        None => io::stdout().lock().write_fmt(args),
+    };
+    if let Err(e) = result {
+        // e.g. `orch status | head`
+        if e.kind() == io::ErrorKind::BrokenPipe {
+            std::process::exit(0);
+        }
+        eprintln!("[orch] failed to write output: {e}");
+        std::process::exit(1);
+    }
+}
+
+#[macro_export]
+macro_rules! out {
+    ($($arg:tt)*) => {
+        $crate::output::write_fmt(format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! outln {
+    () => {
+        $crate::output::write_fmt(format_args!("\n"))
+    };
+    ($($arg:tt)*) => {
+        $crate::output::write_fmt(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
@@ -0,0 +1,208 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::OrchError;
+
+/// A path from the environment, if the variable is set and not empty.
+fn env_path(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// `$HOME`, and only that: without it a container gets an error naming
+/// the variable to set, not whatever its passwd entry calls home (often
+/// `/`).
+fn home_dir() -> Option<PathBuf> {
+    env_path("HOME")
+}
+
+/// `~/tasks`, if there's a home directory to put it in.
+fn global_tasks_dir() -> Option<PathBuf> {
+    home_dir().map(|home| home.join("tasks"))
+}
+
+/// The nearest `.orch` directory at or above the current directory that
+/// holds a `config.toml` or a `tasks/`, found the way git finds `.git`.
+/// The global tasks dir's own state dir doesn't count.
+pub fn project_dir() -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    let global_state = global_tasks_dir().map(|dir| dir.join(".orch"));
+    cwd.ancestors()
+        .map(|dir| dir.join(".orch"))
+        .filter(|candidate| Some(candidate) != global_state.as_ref())
+        .find(|candidate| {
+            candidate.join("config.toml").is_file() || candidate.join("tasks").is_dir()
+        })
+}
+
+/// `$ORCH_TASKS_DIR`, else `.orch/tasks` of the project we're in (see
+/// [`project_dir`]), else `~/tasks`. Fails when there's no home directory
+/// to find `~/tasks` in.
+pub fn tasks_dir() -> Result<PathBuf, OrchError> {
+    if let Some(dir) = env_path("ORCH_TASKS_DIR") {
+        return Ok(dir);
+    }
+    project_dir()
+        .map(|project| project.join("tasks"))
+        .or_else(global_tasks_dir)
+        .ok_or_else(|| {
+            OrchError::Config(
+                "No tasks dir: HOME isn't set, so there's no ~/tasks. Set ORCH_TASKS_DIR \
+                 (and ORCH_STATE_DIR or ORCH_CONFIG if those should live elsewhere too), \
+                 or set HOME."
+                    .into(),
+            )
+        })
+}
+
+/// Spool of `orch -` messages waiting for the daemon: `inbox/` in
+/// `$ORCH_STATE_DIR` when that's set, else `.inbox` in the tasks dir.
+pub fn inbox_dir(tasks_dir: &Path) -> PathBuf {
+    match env_path("ORCH_STATE_DIR") {
+        Some(state) => state.join("inbox"),
+        None => tasks_dir.join(".inbox"),
+    }
+}
+
+/// orch's own state: `$ORCH_STATE_DIR`, else `.orch` under the tasks dir
+/// (hidden, so never scanned as tasks).
+pub fn state_dir(tasks_dir: &Path) -> PathBuf {
+    env_path("ORCH_STATE_DIR").unwrap_or_else(|| tasks_dir.join(".orch"))
+}
+
+pub fn prompts_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("prompts.json")
+}
+
+pub fn heartbeat_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("heartbeat.json")
+}
+
+pub fn health_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("daemon.json")
+}
+
+pub fn usage_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("usage.json")
+}
+
+pub fn inbox_state_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("inbox.json")
+}
+
+pub fn escalation_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("escalation.json")
+}
+
+pub fn export_ids_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("export.json")
+}
+
+pub fn rollout_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("rollout.json")
+}
+
+pub fn runs_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("runs.jsonl")
+}
+
+/// `orch record` transcripts, `<task name>.log`.
+pub fn transcripts_dir(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("transcripts")
+}
+
+/// What an `orch review --interactive` reviewer was given, by task name.
+pub fn review_input_path(tasks_dir: &Path, name: &str) -> PathBuf {
+    state_dir(tasks_dir)
+        .join("reviews")
+        .join(format!("{name}.md"))
+}
+
+/// What `orch console` last told its session about.
+pub fn console_snapshot_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("console-snapshot.json")
+}
+
+/// Bulk command changes waiting for the next scan, see [`crate::bulk`].
+pub fn bulk_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("bulk.jsonl")
+}
+
+/// Each task's log, `<task name>.jsonl`, see [`crate::log`].
+pub fn log_dir(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("log")
+}
+
+/// Messages waiting for the orchestrator to be reachable, see
+/// [`crate::queue`].
+pub fn queue_dir(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("queue")
+}
+
+/// Spend per task, see [`crate::costs`].
+pub fn costs_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("costs.json")
+}
+
+/// The focused task and whether it follows tmux, see [`crate::focus`].
+pub fn focus_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("focus.json")
+}
+
+/// How workers last exited, see [`crate::exits`].
+pub fn exits_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("exits.json")
+}
+
+/// Held while an orchestrator run is going, see [`crate::runs::Lock`].
+pub fn run_lock_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("run.lock")
+}
+
+/// One lock file per task name, held while orch edits it, see
+/// [`crate::edits`].
+pub fn locks_dir(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("locks")
+}
+
+/// Runs' prompts and outputs, by content, see [`crate::blobs`].
+pub fn blobs_dir(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("blobs")
+}
+
+/// Output of runs `orch scan --async` started without a daemon.
+pub fn run_logs_dir(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("runs")
+}
+
+/// Default for `audit_log.path`.
+pub fn audit_path(tasks_dir: &Path) -> PathBuf {
+    state_dir(tasks_dir).join("audit.jsonl")
+}
+
+/// `$ORCH_CONFIG`, else the project's `.orch/config.toml` if it has one,
+/// else `~/.config/orch/config.toml`. None without a home or config
+/// directory to look in: orch runs on defaults then.
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env_path("ORCH_CONFIG") {
+        return Some(path);
+    }
+    if let Some(config) = project_dir()
+        .map(|project| project.join("config.toml"))
+        .filter(|config| config.is_file())
+    {
+        return Some(config);
+    }
+    if home_dir().is_none() && env_path("XDG_CONFIG_HOME").is_none() {
+        return None;
+    }
+    dirs::config_dir().map(|dir| dir.join("orch").join("config.toml"))
+}
+
+/// How commands that show the config path put it when there's none.
+pub const NO_CONFIG: &str =
+    "none: ORCH_CONFIG unset and no HOME or XDG_CONFIG_HOME to look in (defaults)";
+
+pub fn repo_dir() -> Result<String, OrchError> {
+    std::env::var("ORCH_REPO").map_err(|_| OrchError::Config("ORCH_REPO must be set".into()))
+}
@@ -0,0 +1,61 @@
+//! How long a task sat held or snoozed, from the hold, unhold, snooze and
+//! unsnooze entries in its [`crate::log`], so that time can be left out of
+//! its active duration when it's closed.
+
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+
+use crate::{
+    log::{self, Event},
+    task,
+};
+
+/// Seconds within `from..to` that `task` spent held or snoozed. A hold
+/// lasts until it's released (or `to`); a snooze until it runs out, is
+/// lifted or is replaced by another. Overlaps count once.
+pub fn paused_secs(dir: &Path, task: &str, from: DateTime<Local>, to: DateTime<Local>) -> i64 {
+    let mut entries: Vec<_> = log::entries(dir, task)
+        .filter_map(|e| Some((e.at()?, e.event)))
+        .collect();
+    entries.sort_by_key(|(at, _)| *at);
+
+    let mut spans = Vec::new();
+    let mut held = None;
+    let mut snoozed: Option<(DateTime<Local>, DateTime<Local>)> = None;
+    for (at, event) in entries {
+        match event {
+            Event::Held => {
+                held.get_or_insert(at);
+            }
+            Event::Released => spans.extend(held.take().map(|start| (start, at))),
+            Event::Snoozed { until } => {
+                spans.extend(snoozed.take().map(|(start, until)| (start, until.min(at))));
+                snoozed = task::parse_timestamp(&until).map(|until| (at, until));
+            }
+            Event::Unsnoozed => {
+                spans.extend(snoozed.take().map(|(start, until)| (start, until.min(at))));
+            }
+            _ => {}
+        }
+    }
+    spans.extend(held.map(|start| (start, to)));
+    spans.extend(snoozed.map(|(start, until)| (start, until.min(to))));
+
+    let mut spans: Vec<_> = spans
+        .into_iter()
+        .map(|(start, end)| (start.max(from), end.min(to)))
+        .filter(|(start, end)| start < end)
+        .collect();
+    spans.sort();
+    let mut total = 0;
+    let mut reached = from;
+    for (start, end) in spans {
+        let start = start.max(reached);
+        if end > start {
+            total += (end - start).num_seconds();
+            reached = end;
+        }
+    }
+    total
+}
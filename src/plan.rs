@@ -0,0 +1,375 @@
+//! Destructive commands build a [`Plan`] of typed steps before touching
+//! anything, so previewing (`--dry-run`), confirming (`-i`), running and
+//! reporting (`--json`) work the same way for all of them.
+
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    fs,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{
+    config::{self, Config},
+    edits,
+    error::OrchError,
+    frontmatter, task, tmux,
+};
+
+#[derive(Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Step {
+    KillSession {
+        session: String,
+    },
+    RenameSession {
+        from: String,
+        to: String,
+    },
+    MoveFile {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    DeleteFile {
+        path: PathBuf,
+    },
+    /// Edits are made against the content the plan was made from: `hash`
+    /// is its [`config::fnv`], and a file that no longer matches is left
+    /// alone.
+    EditFrontmatter {
+        path: PathBuf,
+        key: String,
+        value: Option<String>,
+        #[serde(skip)]
+        hash: u64,
+    },
+    /// Points the task's `session:` line at `session`, or removes it.
+    SetSession {
+        path: PathBuf,
+        session: Option<String>,
+        #[serde(skip)]
+        hash: u64,
+    },
+}
+
+impl Step {
+    /// "kill session task-x", "move a.md -> done/a.md", paths relative to
+    /// `dir`.
+    pub fn describe(&self, dir: &Path) -> String {
+        let rel = |p: &Path| p.strip_prefix(dir).unwrap_or(p).display().to_string();
+        match self {
+            Step::KillSession { session } => format!("kill session {session}"),
+            Step::RenameSession { from, to } => format!("rename session {from} -> {to}"),
+            Step::MoveFile { from, to } => format!("move {} -> {}", rel(from), rel(to)),
+            Step::DeleteFile { path } => format!("delete {}", rel(path)),
+            Step::EditFrontmatter {
+                path,
+                key,
+                value: Some(value),
+                ..
+            } => format!("set {key}: {value} in {}", rel(path)),
+            Step::EditFrontmatter {
+                path,
+                key,
+                value: None,
+                ..
+            } => format!("remove {key} from {}", rel(path)),
+            Step::SetSession {
+                path,
+                session: Some(session),
+                ..
+            } => format!("set session: {session} in {}", rel(path)),
+            Step::SetSession {
+                path,
+                session: None,
+                ..
+            } => format!("remove session: from {}", rel(path)),
+        }
+    }
+
+    /// Edits go through [`edits::edit_planned`]. `written` has the hash of
+    /// what earlier steps of the same item wrote, by path, which later edits
+    /// of that file expect instead of the planned one.
+    fn run(
+        &self,
+        dir: &Path,
+        config: &Config,
+        written: &mut HashMap<PathBuf, u64>,
+    ) -> Result<(), String> {
+        match self {
+            Step::KillSession { session } => tmux::kill_session(session),
+            Step::RenameSession { from, to } => tmux::rename_session(from, to),
+            Step::MoveFile { from, to } => {
+                to.parent()
+                    .map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|_| fs::rename(from, to))
+                    .map_err(|e| OrchError::io(from, e).to_string())?;
+                if let Some(hash) = written.remove(from) {
+                    written.insert(to.clone(), hash);
+                }
+                Ok(())
+            }
+            Step::DeleteFile { path } => {
+                fs::remove_file(path).map_err(|e| OrchError::io(path, e).to_string())
+            }
+            Step::EditFrontmatter {
+                path,
+                key,
+                value,
+                hash,
+            } => planned(dir, config, path, *hash, written, |content| {
+                Some(frontmatter::set(content, key, value.as_deref()))
+            }),
+            Step::SetSession {
+                path,
+                session,
+                hash,
+            } => {
+                let missing = Cell::new(false);
+                planned(dir, config, path, *hash, written, |content| {
+                    let updated = task::set_session(content, session.as_deref());
+                    missing.set(updated.is_none());
+                    updated
+                })?;
+                if missing.get() {
+                    return Err(format!("no session: line in {}", path.display()));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Applies `change` to the task file at `path` if it still hashes to what
+/// the plan (or an earlier step, per `written`) left it at.
+fn planned(
+    dir: &Path,
+    config: &Config,
+    path: &Path,
+    hash: u64,
+    written: &mut HashMap<PathBuf, u64>,
+    change: impl Fn(&str) -> Option<String>,
+) -> Result<(), String> {
+    let expected = written.get(path).copied().unwrap_or(hash);
+    match edits::edit_planned(dir, config, path, expected, change).map_err(|e| e.to_string())? {
+        edits::Outcome::Written(content) => {
+            written.insert(path.to_path_buf(), config::fnv(content.as_bytes()));
+            Ok(())
+        }
+        edits::Outcome::Unchanged => Ok(()),
+        edits::Outcome::Dropped => Err(format!(
+            "{} changed since the plan was made; run it again",
+            path.display()
+        )),
+    }
+}
+
+#[derive(Serialize, PartialEq)]
+#[serde(tag = "outcome", content = "error", rename_all = "snake_case")]
+pub enum Outcome {
+    Planned,
+    Done,
+    /// Declined at the prompt.
+    Skipped,
+    Failed(String),
+}
+
+/// Everything to do for one task. Steps run in order; one failing stops the
+/// rest of the item.
+#[derive(Serialize)]
+pub struct Item {
+    pub task: String,
+    pub steps: Vec<Step>,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+pub struct Plan<'a> {
+    dir: PathBuf,
+    config: &'a Config,
+    pub items: Vec<Item>,
+}
+
+impl<'a> Plan<'a> {
+    pub fn new(dir: &Path, config: &'a Config) -> Plan<'a> {
+        Plan {
+            dir: dir.to_path_buf(),
+            config,
+            items: Vec::new(),
+        }
+    }
+
+    /// The tasks dir the plan's steps are relative to.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn add(&mut self, task: &str, steps: Vec<Step>) {
+        self.items.push(Item {
+            task: task.to_string(),
+            steps,
+            outcome: Outcome::Planned,
+        });
+    }
+
+    /// A task that can't be planned, e.g. it doesn't exist.
+    pub fn add_error(&mut self, task: &str, error: String) {
+        self.items.push(Item {
+            task: task.to_string(),
+            steps: Vec::new(),
+            outcome: Outcome::Failed(error),
+        });
+    }
+
+    /// Asks about each planned item on stderr: y(es), n(o), a(ll remaining),
+    /// q(uit, skipping the rest). Declined items become [`Outcome::Skipped`].
+    pub fn confirm(&mut self) -> Result<(), String> {
+        if !std::io::stdin().is_terminal() {
+            return Err("-i needs a terminal to ask on".into());
+        }
+        let mut all = false;
+        let mut quit = false;
+        for item in &mut self.items {
+            if item.outcome != Outcome::Planned || all {
+                continue;
+            }
+            if quit {
+                item.outcome = Outcome::Skipped;
+                continue;
+            }
+            let steps: Vec<_> = item.steps.iter().map(|s| s.describe(&self.dir)).collect();
+            eprint!("{}: {}? [y/n/a/q] ", item.task, steps.join(", "));
+            let _ = std::io::stderr().flush();
+            let mut answer = String::new();
+            let _ = std::io::stdin().read_line(&mut answer);
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => {}
+                "a" | "all" => all = true,
+                "q" | "quit" => {
+                    quit = true;
+                    item.outcome = Outcome::Skipped;
+                }
+                _ => item.outcome = Outcome::Skipped,
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs every still-planned item, recording how each went.
+    pub fn execute(&mut self) {
+        for item in &mut self.items {
+            if item.outcome != Outcome::Planned {
+                continue;
+            }
+            let mut written = HashMap::new();
+            item.outcome = item
+                .steps
+                .iter()
+                .try_for_each(|step| step.run(&self.dir, self.config, &mut written))
+                .map_or_else(Outcome::Failed, |()| Outcome::Done);
+        }
+    }
+
+    /// One line per item: "would ..." before execution, what happened after.
+    pub fn lines(&self) -> Vec<(bool, String)> {
+        self.items
+            .iter()
+            .map(|item| {
+                let steps: Vec<_> = item.steps.iter().map(|s| s.describe(&self.dir)).collect();
+                let steps = steps.join(", ");
+                let (ok, line) = match &item.outcome {
+                    Outcome::Planned => (true, format!("would {steps}")),
+                    Outcome::Done => (true, steps),
+                    Outcome::Skipped => (true, format!("skipped ({steps})")),
+                    Outcome::Failed(e) => (false, e.clone()),
+                };
+                (ok, format!("{}: {line}", item.task))
+            })
+            .collect()
+    }
+
+    pub fn failed(&self) -> Vec<&str> {
+        self.items
+            .iter()
+            .filter(|i| matches!(i.outcome, Outcome::Failed(_)))
+            .map(|i| i.task.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(path: &Path, key: &str, planned: &str) -> Step {
+        Step::EditFrontmatter {
+            path: path.to_path_buf(),
+            key: key.into(),
+            value: Some("x".into()),
+            hash: config::fnv(planned.as_bytes()),
+        }
+    }
+
+    fn run(dir: &Path, steps: Vec<Step>) -> Outcome {
+        let config = Config::default();
+        let mut plan = Plan::new(dir, &config);
+        plan.add("t", steps);
+        plan.execute();
+        plan.items.remove(0).outcome
+    }
+
+    #[test]
+    fn edits_of_one_file_follow_each_other() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, to) = (dir.path().join("t.md"), dir.path().join("done/t.md"));
+        fs::write(&path, "# T\n").unwrap();
+        let steps = vec![
+            edit(&path, "a", "# T\n"),
+            edit(&path, "b", "# T\n"),
+            Step::MoveFile {
+                from: path.clone(),
+                to: to.clone(),
+            },
+            edit(&to, "c", "# T\n"),
+        ];
+        assert!(run(dir.path(), steps) == Outcome::Done);
+        assert_eq!(
+            fs::read_to_string(&to).unwrap(),
+            "---\na: x\nb: x\nc: x\n---\n# T\n"
+        );
+    }
+
+    #[test]
+    fn a_file_changed_since_planning_is_left_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("t.md");
+        fs::write(&path, "# T\n\nEdited by the worker.\n").unwrap();
+        let Outcome::Failed(e) = run(dir.path(), vec![edit(&path, "a", "# T\n")]) else {
+            panic!("the edit went ahead");
+        };
+        assert!(
+            e.ends_with("t.md changed since the plan was made; run it again"),
+            "{e}"
+        );
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# T\n\nEdited by the worker.\n"
+        );
+    }
+
+    #[test]
+    fn an_unreadable_file_is_left_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("t.md");
+        let big = "x".repeat(2 * 1024 * 1024);
+        fs::write(&path, &big).unwrap();
+        let Outcome::Failed(e) = run(dir.path(), vec![edit(&path, "a", &big)]) else {
+            panic!("the edit went ahead");
+        };
+        assert!(e.contains("unreadable, too large"), "{e}");
+        assert!(fs::read_to_string(&path).unwrap() == big);
+    }
+}
@@ -0,0 +1,78 @@
+//! The last orchestrator prompt that mentioned each task, kept in
+//! `.orch/prompts.json` so `orch replay` can re-send it.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, paths, state, task};
+
+#[derive(Serialize, Deserialize)]
+pub struct Prompt {
+    pub message: String,
+    /// RFC 3339, when the prompt was sent.
+    pub at: String,
+}
+
+fn load(dir: &Path) -> BTreeMap<String, Prompt> {
+    state::load(&paths::prompts_path(dir)).unwrap_or_default()
+}
+
+/// Whether `message` is about task `name`: its worker session (worker
+/// messages) or `<name>.md` (new-task events) appears as a whole word.
+fn mentions(message: &str, name: &str, session: &str) -> bool {
+    [session.to_string(), format!("{name}.md")]
+        .iter()
+        .any(|needle| {
+            message.match_indices(needle.as_str()).any(|(i, m)| {
+                let before = message[..i].chars().next_back();
+                let after = message[i + m.len()..].chars().next();
+                !before.is_some_and(|c| c.is_alphanumeric() || c == '-')
+                    && !after.is_some_and(|c| c.is_alphanumeric() || c == '-')
+            })
+        })
+}
+
+/// Names of the tasks `message` is about.
+pub fn mentioned(dir: &Path, config: &Config, message: &str) -> Vec<String> {
+    task::files(dir, config)
+        .iter()
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            let content = task::read(path, config).ok()?;
+            let session = task::session(config, &task::label(dir, path), &content);
+            mentions(message, &name, &session).then_some(name)
+        })
+        .collect()
+}
+
+/// Stores `message` as the last prompt for every task it mentions.
+pub fn record(dir: &Path, config: &Config, message: &str) {
+    let names = mentioned(dir, config, message);
+    if names.is_empty() {
+        return;
+    }
+    let mut prompts = load(dir);
+    let at = task::timestamp(Local::now());
+    for name in names {
+        let prompt = Prompt {
+            message: message.to_string(),
+            at: at.clone(),
+        };
+        prompts.insert(name, prompt);
+    }
+    let path = paths::prompts_path(dir);
+    let result = fs::create_dir_all(paths::state_dir(dir)).and_then(|_| {
+        let json = serde_json::to_string_pretty(&prompts).unwrap_or_default();
+        task::write_atomic(&path, &json)
+    });
+    if let Err(e) = result {
+        eprintln!("[orch] failed to save {}: {e}", path.display());
+    }
+}
+
+/// The last prompt recorded for task `name`.
+pub fn last(dir: &Path, name: &str) -> Option<Prompt> {
+    load(dir).remove(name)
+}
@@ -0,0 +1,75 @@
+//! Messages held back while the orchestrator can't be reached, one JSON
+//! file each in `.orch/queue/`, named by id. `orch - --queue-on-failure`
+//! puts a message here instead of `.inbox` while the daemon's runs are
+//! failing offline, and the daemon puts back inbox messages whose run
+//! failed that way. After its next successful run the daemon replays them
+//! oldest first.
+
+use std::{
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::OrchError, paths, state, task};
+
+#[derive(Serialize, Deserialize)]
+pub struct Entry {
+    /// The file name without `.json`; sorts oldest first.
+    #[serde(skip)]
+    pub id: String,
+    /// RFC 3339, when it was queued.
+    pub at: String,
+    pub message: String,
+}
+
+/// Queues `message`, returning its id.
+pub fn push(dir: &Path, message: &str) -> Result<String, OrchError> {
+    let queue = paths::queue_dir(dir);
+    fs::create_dir_all(&queue).map_err(|e| OrchError::io(&queue, e))?;
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let id = format!("{nanos}-{}", std::process::id());
+    let entry = Entry {
+        id: id.clone(),
+        at: task::timestamp(Local::now()),
+        message: message.to_string(),
+    };
+    let path = queue.join(format!("{id}.json"));
+    let json = serde_json::to_string_pretty(&entry).unwrap_or_default();
+    task::write_atomic(&path, &json).map_err(|e| OrchError::io(path, e))?;
+    Ok(id)
+}
+
+/// Queued messages, oldest first. Files that don't parse are skipped with a
+/// warning.
+pub fn list(dir: &Path) -> Vec<Entry> {
+    let Ok(read) = fs::read_dir(paths::queue_dir(dir)) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = read
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .filter_map(|path| {
+            let mut entry: Entry = state::load(path)?;
+            entry.id = path.file_stem()?.to_string_lossy().into_owned();
+            Some(entry)
+        })
+        .collect()
+}
+
+/// Removes the entry `id`.
+pub fn remove(dir: &Path, id: &str) -> Result<(), OrchError> {
+    let path = paths::queue_dir(dir).join(format!("{id}.json"));
+    fs::remove_file(&path).map_err(|e| OrchError::io(path, e))
+}
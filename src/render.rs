@@ -0,0 +1,292 @@
+//! Output formats. A command that supports `--output-format` builds a typed report
+//! and hands it to [`emit`]; its own shorthands (`--json`, `--compact`) just
+//! pick a [`Format`]. JSON is always the report serialized as is, so every
+//! format shows the same data.
+//!
+//! Renderers write text without escape codes unless [`Style::color`] says
+//! so, and lay out for [`Style::width`] rather than asking the terminal, so
+//! the same report always renders the same way. Below [`NARROW`] columns
+//! `--compact` turns into one short line per item with state glyphs, which
+//! are ASCII when [`Style::ascii`] says so.
+
+use std::{
+    fmt::Write as _,
+    process::{Command, Stdio},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::{error::OrchError, out, outln, output, warn};
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    /// The default, for reading
+    Human,
+    /// One aligned line per item
+    Compact,
+    /// The whole report as one JSON document
+    Json,
+    /// One JSON object per item
+    Jsonl,
+    /// Tab-separated fields, one item per line, stable across versions
+    Porcelain,
+    /// Comma-separated with a header row
+    Csv,
+}
+
+impl Format {
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Human => "human",
+            Format::Compact => "compact",
+            Format::Json => "json",
+            Format::Jsonl => "jsonl",
+            Format::Porcelain => "porcelain",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+/// Terminals narrower than this get the narrow `--compact` layout, and
+/// status and inbox use it without being asked.
+pub const NARROW: usize = 60;
+
+/// Set by `--ascii`.
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+/// Makes every [`Style`] ASCII, for `--ascii`.
+pub fn force_ascii() {
+    ASCII.store(true, Ordering::Relaxed);
+}
+
+/// Whether the locale is UTF-8, by the first of `LC_ALL`, `LC_CTYPE` and
+/// `LANG` that's set. None set is the C locale, which isn't.
+fn utf8_locale() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .is_some_and(|locale| {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
+/// How text renderers may decorate their output.
+#[derive(Clone, Copy)]
+pub struct Style {
+    /// ANSI colors are allowed.
+    pub color: bool,
+    /// Columns available, or None when there's no limit (a pipe, a file).
+    pub width: Option<usize>,
+    /// Glyphs have to be ASCII.
+    pub ascii: bool,
+}
+
+impl Style {
+    /// Colors and width of the terminal output goes to, if it is one.
+    pub fn detect() -> Self {
+        let tty = output::is_terminal();
+        Style {
+            color: tty && std::env::var_os("NO_COLOR").is_none(),
+            width: if tty { terminal_width() } else { None },
+            ascii: ASCII.load(Ordering::Relaxed) || !utf8_locale(),
+        }
+    }
+
+    /// The terminal is narrower than [`NARROW`].
+    pub fn narrow(&self) -> bool {
+        self.width.is_some_and(|w| w < NARROW)
+    }
+
+    /// `glyph`, or `ascii` in its place when glyphs have to be ASCII.
+    pub fn glyph(&self, glyph: &'static str, ascii: &'static str) -> &'static str {
+        if self.ascii { ascii } else { glyph }
+    }
+
+    /// A legend for `glyphs`, each `(glyph, ascii, meaning)`, wrapped to
+    /// the width.
+    pub fn legend(&self, glyphs: &[(&'static str, &'static str, &str)]) -> String {
+        let columns = self.width.unwrap_or(usize::MAX);
+        let mut lines = vec![String::new()];
+        for (glyph, ascii, meaning) in glyphs {
+            let key = format!("{} {meaning}", self.glyph(glyph, ascii));
+            let line = lines.last_mut().expect("starts with one");
+            if line.is_empty() {
+                *line = key;
+            } else if width(line) + 2 + width(&key) <= columns {
+                line.push_str("  ");
+                line.push_str(&key);
+            } else {
+                lines.push(key);
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// `text` in red when colors are allowed.
+    pub fn red(&self, text: &str) -> String {
+        self.paint(31, text)
+    }
+
+    /// `text` in yellow when colors are allowed.
+    pub fn yellow(&self, text: &str) -> String {
+        self.paint(33, text)
+    }
+
+    fn paint(&self, color: u8, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{color}m{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// `$COLUMNS`, else what `stty size` says about the controlling terminal.
+fn terminal_width() -> Option<usize> {
+    if let Some(cols) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(cols);
+    }
+    let tty = std::fs::File::open("/dev/tty").ok()?;
+    let out = Command::new("stty")
+        .arg("size")
+        .stdin(tty)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let size = String::from_utf8_lossy(&out.stdout);
+    size.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Something a command prints, in any of the formats it supports.
+pub trait Report: Serialize {
+    /// Text formats it renders besides [`Format::Human`]; JSON is always
+    /// available.
+    const FORMATS: &'static [Format] = &[];
+
+    /// Renders as [`Format::Human`] or one of [`Report::FORMATS`].
+    fn render(&self, format: Format, style: Style) -> String;
+
+    /// What [`Format::Jsonl`] prints: one line of JSON per item, the
+    /// report itself by default.
+    fn lines(&self) -> Vec<String> {
+        vec![serde_json::to_string(self).unwrap_or_default()]
+    }
+}
+
+/// What a command with a compact format shows unless told otherwise:
+/// human, or compact on a narrow terminal, where human lines would wrap.
+pub fn default_format() -> Format {
+    if Style::detect().narrow() {
+        Format::Compact
+    } else {
+        Format::Human
+    }
+}
+
+/// Prints one [`Format::Jsonl`] item and flushes it, for commands that
+/// stream items as they read them rather than building a report first.
+pub fn line<T: Serialize>(item: &T) -> Result<(), OrchError> {
+    let line = serde_json::to_string(item)
+        .map_err(|e| OrchError::Failed(format!("failed to serialize: {e}")))?;
+    outln!("{line}");
+    output::flush();
+    Ok(())
+}
+
+/// Prints `report` as `format`, or fails if `command` doesn't have it.
+pub fn emit<R: Report>(command: &str, report: &R, format: Format) -> Result<(), OrchError> {
+    let failed = |e: serde_json::Error| OrchError::Failed(format!("failed to serialize: {e}"));
+    match format {
+        Format::Json => {
+            let value = serde_json::to_value(report).map_err(failed)?;
+            let text = serde_json::to_string_pretty(&warn::attach(value)).map_err(failed)?;
+            outln!("{text}");
+        }
+        Format::Jsonl => {
+            for line in report.lines() {
+                outln!("{line}");
+                output::flush();
+            }
+        }
+        _ if format == Format::Human || R::FORMATS.contains(&format) => {
+            out!("{}", report.render(format, Style::detect()));
+        }
+        _ => {
+            let mut offered = vec!["human"];
+            offered.extend(R::FORMATS.iter().map(|f| f.name()));
+            offered.extend(["json", "jsonl"]);
+            return Err(OrchError::Usage(format!(
+                "`orch {command}` has no {} format; try {}.",
+                format.name(),
+                offered.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Marks a pinned task.
+pub const PIN: &str = "📌";
+
+/// Columns `text` takes up, counting [`PIN`] as the two it's drawn with.
+pub fn width(text: &str) -> usize {
+    text.chars()
+        .map(|c| if PIN.starts_with(c) { 2 } else { 1 })
+        .sum()
+}
+
+/// `text` padded with spaces to `columns`, by [`width`].
+pub fn pad(text: &str, columns: usize) -> String {
+    format!("{text}{}", " ".repeat(columns.saturating_sub(width(text))))
+}
+
+/// `name` cut to `columns`. When other names in `all` share its start, the
+/// end is kept, since that's what tells them apart; otherwise the start.
+pub fn shorten(name: &str, all: &[&str], columns: usize) -> String {
+    let len = name.chars().count();
+    if len <= columns {
+        return name.to_string();
+    }
+    let keep = columns.saturating_sub(1);
+    let shared = all.iter().filter(|other| **other != name).any(|other| {
+        let common = other.chars().zip(name.chars()).take_while(|(a, b)| a == b);
+        common.count() >= 3
+    });
+    if shared {
+        let tail: String = name.chars().skip(len - keep).collect();
+        format!("…{tail}")
+    } else {
+        let head: String = name.chars().take(keep).collect();
+        format!("{head}…")
+    }
+}
+
+/// A CSV row, quoting fields that need it.
+pub fn csv_row(fields: &[&str]) -> String {
+    let mut row = String::new();
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            row.push(',');
+        }
+        if field.contains([',', '"', '\n']) {
+            let _ = write!(row, "\"{}\"", field.replace('"', "\"\""));
+        } else {
+            row.push_str(field);
+        }
+    }
+    row.push('\n');
+    row
+}
+
+/// A porcelain row: fields joined by tabs, with tabs and newlines inside
+/// them turned into spaces.
+pub fn porcelain_row(fields: &[&str]) -> String {
+    let fields: Vec<_> = fields
+        .iter()
+        .map(|f| f.replace(['\t', '\n'], " "))
+        .collect();
+    format!("{}\n", fields.join("\t"))
+}
@@ -0,0 +1,187 @@
+//! `orch review`: a second claude looking over a worker's output. The
+//! reviewer gets the task brief and what the worker changed: the diff of the
+//! task's `workdir:` against its `base:` branch, or, when there's nothing to
+//! diff, the end of the worker's transcript or pane. Findings go under the
+//! task's `## Review`, and a blocking verdict sets `status: needs-input`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{config::Config, error::OrchError, frontmatter, git, lint, tmux, transcript};
+
+/// Default prompt for `orch review`; `review_prompt` in the config replaces
+/// it with a file. Replies must end with the verdict line [`verdict`] reads.
+pub const REVIEW_PROMPT: &str = "\
+You review the work of an AI coding agent. Below are the task it was given and either \
+the changes it made or, when there's no diff, the end of its terminal output. Check the \
+work against the task: bugs, missing pieces, acceptance criteria not met, risky changes, \
+and anything a maintainer would send back. Be specific and brief, one bullet per finding, \
+citing file and line where you can; don't restate what's fine. Use no markdown headings. \
+Mark each finding that has to be fixed before the work is done with `[blocking]`.
+
+End your reply with exactly one of these lines:
+VERDICT: BLOCKING
+VERDICT: OK
+";
+
+/// Diffs are cut to this many bytes; the brief and prompt still fit.
+const MAX_DIFF_BYTES: usize = 100 * 1024;
+
+/// Lines of worker output shown when there's no diff.
+const OUTPUT_LINES: usize = 300;
+
+/// What the reviewer is shown besides the brief.
+pub enum Evidence {
+    Diff {
+        repo: PathBuf,
+        base: String,
+        diff: String,
+        /// The diff's full size, when it was cut.
+        cut_from: Option<usize>,
+    },
+    /// Why there's no diff, and the worker's output if there's any.
+    Output { why: String, text: Option<String> },
+}
+
+impl Evidence {
+    /// "the diff against main", for the review entry.
+    pub fn describe(&self) -> String {
+        match self {
+            Evidence::Diff { base, cut_from, .. } => match cut_from {
+                Some(_) => format!("the diff against {base}, cut to {MAX_DIFF_BYTES} bytes"),
+                None => format!("the diff against {base}"),
+            },
+            Evidence::Output { why, text: Some(_) } => format!("the worker's output ({why})"),
+            Evidence::Output { why, text: None } => format!("the task file alone ({why})"),
+        }
+    }
+}
+
+/// `text` cut to at most `max` bytes at a line end.
+fn cut(text: String, max: usize) -> (String, Option<usize>) {
+    if text.len() <= max {
+        return (text, None);
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let end = text[..end].rfind('\n').unwrap_or(end);
+    (text[..end].to_string(), Some(text.len()))
+}
+
+/// The diff for the task in `content`, else why there isn't one.
+fn diff(content: &str) -> Result<Evidence, String> {
+    let workdir = frontmatter::get(content, "workdir").ok_or("no workdir: recorded")?;
+    let repo = PathBuf::from(&workdir);
+    if !git::is_work_tree(&repo)? {
+        return Err(format!("{workdir} isn't a git work tree"));
+    }
+    let base = frontmatter::get(content, "base")
+        .or_else(|| git::default_branch(&repo))
+        .ok_or_else(|| format!("no base: recorded and no main or master in {workdir}"))?;
+    let diff = git::diff_since(&repo, &base)?;
+    if diff.trim().is_empty() {
+        return Err(format!("no changes against {base}"));
+    }
+    let (diff, cut_from) = cut(diff, MAX_DIFF_BYTES);
+    Ok(Evidence::Diff {
+        repo,
+        base,
+        diff,
+        cut_from,
+    })
+}
+
+/// The diff of the task `name`, else the tail of its transcript or of its
+/// worker's pane in `session`.
+pub fn gather(dir: &Path, name: &str, session: &str, content: &str) -> Evidence {
+    diff(content).unwrap_or_else(|why| {
+        let text = transcript::tail(dir, name, OUTPUT_LINES).or_else(|| {
+            let pane = tmux::capture_pane_joined(session);
+            (!pane.trim().is_empty()).then_some(pane)
+        });
+        Evidence::Output { why, text }
+    })
+}
+
+/// Everything the reviewer reads: `prompt`, the brief (quarantined if it
+/// looks like it instructs the reader, see [`lint`]) and the evidence.
+pub fn input(prompt: &str, label: &str, content: &str, evidence: &Evidence) -> String {
+    let findings = lint::findings(content);
+    let brief = if findings.is_empty() {
+        content.trim_end().to_string()
+    } else {
+        lint::quarantine(label, content, &findings)
+    };
+    let mut input = format!(
+        "{}\n\n## The task: {label}.md\n\n{brief}\n\n",
+        prompt.trim_end()
+    );
+    match evidence {
+        Evidence::Diff {
+            repo,
+            base,
+            diff,
+            cut_from,
+        } => {
+            input.push_str(&format!(
+                "## Changes in {} since {base}\n\n",
+                repo.display()
+            ));
+            if let Some(full) = cut_from {
+                input.push_str(&format!(
+                    "(The diff is {full} bytes; only the first {MAX_DIFF_BYTES} are shown.)\n\n"
+                ));
+            }
+            input.push_str(&format!("```diff\n{diff}\n```\n"));
+        }
+        Evidence::Output { why, text } => {
+            input.push_str(&format!("## No diff ({why})\n\n"));
+            match text {
+                Some(text) => input.push_str(&format!(
+                    "The end of the worker's terminal output:\n\n```\n{}\n```\n",
+                    text.trim_end()
+                )),
+                None => input.push_str("There's no worker output either; review the task file.\n"),
+            }
+        }
+    }
+    input
+}
+
+/// The review prompt: `review_prompt` from the config, else the built-in.
+pub fn prompt(config: &Config) -> Result<String, OrchError> {
+    let Some(path) = &config.review_prompt else {
+        return Ok(REVIEW_PROMPT.to_string());
+    };
+    fs::read_to_string(path).map_err(|e| OrchError::io(path, e))
+}
+
+/// A reply, split into its findings and its verdict: `Some(true)` for
+/// blocking, None when the reply didn't end with a verdict line.
+pub fn verdict(reply: &str) -> (String, Option<bool>) {
+    let mut lines: Vec<&str> = reply.trim_end().lines().collect();
+    let verdict = lines.last().and_then(|line| {
+        let line = line.trim().trim_matches('*').to_lowercase();
+        match line.strip_prefix("verdict:")?.trim().trim_matches('*') {
+            "blocking" => Some(true),
+            "ok" => Some(false),
+            _ => None,
+        }
+    });
+    if verdict.is_some() {
+        lines.pop();
+    }
+    // A heading would end the `## Review` section early
+    let findings: Vec<&str> = lines
+        .iter()
+        .map(|line| match line.trim_start() {
+            heading if heading.starts_with('#') => heading.trim_start_matches('#').trim_start(),
+            _ => line,
+        })
+        .collect();
+    (findings.join("\n").trim().to_string(), verdict)
+}
@@ -0,0 +1,213 @@
+//! `orch spawn`: workers for several tasks, started in priority order and,
+//! with `--stagger`, one at a time. With a daemon running, a staggered
+//! rollout is queued in `.orch/rollout.json` and the daemon asks for each
+//! start when its time comes, so the rollout outlives the terminal.
+
+use std::{fs, path::Path};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    budget,
+    config::Config,
+    error::OrchError,
+    fmt, frontmatter, paths, state,
+    task::{self, Progress, Task},
+    tmux,
+};
+
+/// One queued start.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Start {
+    pub label: String,
+    /// RFC 3339, not before then.
+    pub at: String,
+}
+
+fn load(dir: &Path) -> Vec<Start> {
+    state::load(&paths::rollout_path(dir)).unwrap_or_default()
+}
+
+fn save(dir: &Path, starts: &[Start]) -> Result<(), OrchError> {
+    let path = paths::rollout_path(dir);
+    let json = serde_json::to_string_pretty(starts).unwrap_or_default();
+    fs::create_dir_all(paths::state_dir(dir))
+        .and_then(|_| task::write_atomic(&path, &json))
+        .map_err(|e| OrchError::io(&path, e))
+}
+
+fn live(config: &Config, task: &Task) -> bool {
+    tmux::has_session(&task::session(config, &task.label, &task.content))
+}
+
+/// Tasks with a live worker.
+fn running(dir: &Path, config: &Config) -> usize {
+    task::list(dir, config)
+        .iter()
+        .filter(|t| live(config, t))
+        .count()
+}
+
+/// Why the task can't have a worker started now, if it can't.
+fn unstartable(dir: &Path, config: &Config, task: &Task) -> Option<String> {
+    let content = &task.content;
+    if live(config, task) {
+        return Some("has a worker".into());
+    }
+    if task::progress(content) == Progress::Done {
+        return Some("done".into());
+    }
+    if task::held(content) {
+        return Some("held".into());
+    }
+    if let Some(at) = task::start_after(content) {
+        return Some(format!("scheduled for {}", fmt::time(at)));
+    }
+    if let Some((used, limit)) = budget::over(dir, &task.name, content) {
+        return Some(format!("over budget ({used} of {limit} scans)"));
+    }
+    None
+}
+
+fn priority(task: &Task) -> f64 {
+    frontmatter::get(&task.content, "priority")
+        .and_then(|p| p.trim().parse().ok())
+        .unwrap_or(0.0)
+}
+
+/// What `orch spawn` is going to do.
+pub struct Plan {
+    /// In the order they start.
+    pub start: Vec<Task>,
+    /// Labels that can't start, or don't fit under `max_workers`, with why.
+    pub skipped: Vec<(String, String)>,
+}
+
+/// The tasks to start workers for, highest priority first, then oldest
+/// first: `names`, or with None every task without a worker.
+pub fn plan(dir: &Path, config: &Config, names: Option<&[String]>) -> Result<Plan, OrchError> {
+    let mut tasks = match names {
+        None => task::list(dir, config),
+        Some(names) => names
+            .iter()
+            .map(|name| {
+                let (_, path) = task::resolve(dir, name, config)?;
+                Ok(task::load(dir, &path, config))
+            })
+            .collect::<Result<_, OrchError>>()?,
+    };
+    tasks.sort_by(|a, b| {
+        priority(b)
+            .total_cmp(&priority(a))
+            .then_with(|| a.created.cmp(&b.created))
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    tasks.dedup_by(|a, b| a.label == b.label);
+    let mut slots = config
+        .max_workers
+        .map(|max| max.saturating_sub(running(dir, config)));
+    let mut start = Vec::new();
+    let mut skipped = Vec::new();
+    for task in tasks {
+        // --all-unstarted passes over these without a word
+        if names.is_none()
+            && (live(config, &task) || task::progress(&task.content) == Progress::Done)
+        {
+            continue;
+        }
+        let reason = unstartable(dir, config, &task).or_else(|| {
+            (slots == Some(0)).then(|| {
+                format!(
+                    "over max_workers ({})",
+                    config.max_workers.unwrap_or_default()
+                )
+            })
+        });
+        match reason {
+            Some(reason) => skipped.push((task.label, reason)),
+            None => {
+                slots = slots.map(|n| n - 1);
+                start.push(task);
+            }
+        }
+    }
+    Ok(Plan { start, skipped })
+}
+
+/// The message asking the orchestrator to start workers for `labels`.
+pub fn message(labels: &[String]) -> String {
+    let files: Vec<String> = labels.iter().map(|l| format!("{l}.md")).collect();
+    format!(
+        "[spawn] Start a worker for each of these tasks that has none, and update its \
+         status: {}.",
+        files.join(", ")
+    )
+}
+
+/// Queues `starts` for the daemon, replacing any queued start of the same
+/// tasks.
+pub fn queue(dir: &Path, starts: Vec<Start>) -> Result<(), OrchError> {
+    let mut queued = load(dir);
+    queued.retain(|q| !starts.iter().any(|s| s.label == q.label));
+    queued.extend(starts);
+    save(dir, &queued)
+}
+
+/// When each queued start still to come is due, for the daemon's wait.
+/// Those waiting for a worker slot are looked at again every tick.
+pub fn pending(dir: &Path) -> Vec<(String, DateTime<Local>)> {
+    let now = Local::now();
+    load(dir)
+        .into_iter()
+        .filter_map(|s| Some((s.label, task::parse_timestamp(&s.at)?)))
+        .filter(|(_, at)| *at > now)
+        .collect()
+}
+
+/// Takes the queued starts that are due off the queue, returning the
+/// message asking for them. Tasks that got a worker, were held or scheduled
+/// meanwhile are dropped, and while `max_workers` are running the rest wait.
+pub fn take_due(dir: &Path, config: &Config) -> Option<String> {
+    let queued = load(dir);
+    if queued.is_empty() {
+        return None;
+    }
+    let queued_len = queued.len();
+    let now = Local::now();
+    let mut slots = config
+        .max_workers
+        .map(|max| max.saturating_sub(running(dir, config)));
+    let mut due = Vec::new();
+    let mut left = Vec::new();
+    for start in queued {
+        if task::parse_timestamp(&start.at).is_some_and(|at| at > now) {
+            left.push(start);
+            continue;
+        }
+        let Ok((_, path)) = task::resolve(dir, &start.label, config) else {
+            eprintln!("[orch] spawn: skipping {} (it's gone)", start.label);
+            continue;
+        };
+        if let Some(reason) = unstartable(dir, config, &task::load(dir, &path, config)) {
+            eprintln!("[orch] spawn: skipping {} ({reason})", start.label);
+            continue;
+        }
+        if slots == Some(0) {
+            left.push(start);
+            continue;
+        }
+        slots = slots.map(|n| n - 1);
+        due.push(start.label);
+    }
+    if left.len() != queued_len
+        && let Err(e) = save(dir, &left)
+    {
+        eprintln!("[orch] {e}");
+    }
+    if due.is_empty() {
+        return None;
+    }
+    eprintln!("[orch] spawn: starting {}", due.join(", "));
+    Some(message(&due))
+}
@@ -0,0 +1,411 @@
+//! Tasks roots and the orchestrator prompt each one runs with. A root is
+//! the top level of the tasks dir or one of its project folders; either can
+//! hold a prompt file (`root_prompts.file`) that's appended to, or replaces,
+//! the global prompt for scans covering its tasks.
+//!
+//! The global prompt is the first of: `--prompt`, `orchestrator_prompt` in
+//! the config, `orchestrator.md` beside the orch binary, and claude's
+//! installed `orchestrator` agent. A `--prompt` beats root files too.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::config::{Config, RootPromptMode};
+
+/// Where a prompt came from, in resolution order.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Source {
+    Flag,
+    RootLocal,
+    Config,
+    BesideExe,
+    Agent,
+}
+
+impl Source {
+    pub const ALL: [Source; 5] = [
+        Source::Flag,
+        Source::RootLocal,
+        Source::Config,
+        Source::BesideExe,
+        Source::Agent,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Source::Flag => "--prompt",
+            Source::RootLocal => "root-local file",
+            Source::Config => "orchestrator_prompt in the config",
+            Source::BesideExe => "beside the orch binary",
+            Source::Agent => "claude's orchestrator agent",
+        }
+    }
+}
+
+/// The prompt files one orchestrator run uses.
+#[derive(Clone, PartialEq, Default)]
+pub struct Prompt {
+    /// Replaces claude's system prompt, `@include`s expanded; None runs the
+    /// installed `orchestrator` agent.
+    pub base: Option<PathBuf>,
+    /// Appended to the system prompt, from a root in append mode.
+    pub append: Option<PathBuf>,
+}
+
+impl Prompt {
+    /// One line saying what the prompt is made of.
+    pub fn describe(&self) -> String {
+        let base = match &self.base {
+            Some(path) => path.display().to_string(),
+            None => "the orchestrator agent".into(),
+        };
+        match &self.append {
+            Some(path) => format!("{base} + {}", path.display()),
+            None => base,
+        }
+    }
+}
+
+/// `orchestrator.md` next to the running binary, whether or not it exists.
+pub fn beside_exe() -> Option<PathBuf> {
+    Some(env::current_exe().ok()?.parent()?.join("orchestrator.md"))
+}
+
+/// Where claude keeps the user's `orchestrator` agent.
+pub fn agent_file() -> Option<PathBuf> {
+    Some(
+        dirs::home_dir()?
+            .join(".claude")
+            .join("agents")
+            .join("orchestrator.md"),
+    )
+}
+
+/// The first of `--prompt`, the config and the file beside the binary that's
+/// set, else the agent.
+pub fn global(config: &Config) -> (Source, Option<PathBuf>) {
+    if let Some(path) = &config.prompt_flag {
+        return (Source::Flag, Some(path.clone()));
+    }
+    if let Some(path) = &config.orchestrator_prompt {
+        return (Source::Config, Some(path.clone()));
+    }
+    match beside_exe().filter(|p| p.is_file()) {
+        Some(path) => (Source::BesideExe, Some(path)),
+        None => (Source::Agent, None),
+    }
+}
+
+/// The root of a task label or path relative to the tasks dir: its project
+/// folder, or None at the top level.
+pub fn root_of(label: &str) -> Option<&str> {
+    label.split_once('/').map(|(root, _)| root)
+}
+
+/// Every root: the top level, then project folders by name.
+pub fn roots(dir: &Path) -> Vec<Option<String>> {
+    let mut folders: Vec<_> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| !name.starts_with('.') && name != "done")
+        .collect();
+    folders.sort();
+    std::iter::once(None)
+        .chain(folders.into_iter().map(Some))
+        .collect()
+}
+
+/// How a root is shown: `work/`, or `the top level`.
+pub fn show(root: Option<&str>) -> String {
+    match root {
+        Some(root) => format!("{root}/"),
+        None => "the top level".into(),
+    }
+}
+
+/// The root's prompt file, whether or not it exists.
+pub fn local(dir: &Path, config: &Config, root: Option<&str>) -> PathBuf {
+    match root {
+        Some(root) => dir.join(root),
+        None => dir.to_path_buf(),
+    }
+    .join(&config.root_prompts.file)
+}
+
+/// The prompt scans of `root` run with, and where its base came from.
+pub fn resolve(dir: &Path, config: &Config, root: Option<&str>) -> (Source, Prompt) {
+    let (source, base) = global(config);
+    let local = Some(local(dir, config, root)).filter(|p| p.is_file());
+    match local {
+        Some(path) if source != Source::Flag => match config.root_prompts.mode {
+            RootPromptMode::Replace => (
+                Source::RootLocal,
+                Prompt {
+                    base: Some(path),
+                    append: None,
+                },
+            ),
+            RootPromptMode::Append => (
+                source,
+                Prompt {
+                    base,
+                    append: Some(path),
+                },
+            ),
+        },
+        _ => (source, Prompt { base, append: None }),
+    }
+}
+
+/// [`resolve`] without the source.
+pub fn effective(dir: &Path, config: &Config, root: Option<&str>) -> Prompt {
+    resolve(dir, config, root).1
+}
+
+/// How to divide one scan between roots whose prompts differ: each part is
+/// a prompt and a note scoping the run to its roots, or there's one part
+/// with no note when they all agree. When the message is about particular
+/// tasks (`labels`, relative to the tasks dir), only the parts covering
+/// them are kept.
+pub fn split(dir: &Path, config: &Config, labels: &[String]) -> Vec<(Prompt, Option<String>)> {
+    let mut groups: Vec<(Prompt, Vec<Option<String>>)> = Vec::new();
+    for root in roots(dir) {
+        let prompt = effective(dir, config, root.as_deref());
+        match groups.iter_mut().find(|(p, _)| *p == prompt) {
+            Some((_, roots)) => roots.push(root),
+            None => groups.push((prompt, vec![root])),
+        }
+    }
+    if groups.len() <= 1 {
+        let prompt = groups.pop().map(|(p, _)| p).unwrap_or_default();
+        return vec![(prompt, None)];
+    }
+    groups
+        .into_iter()
+        .filter_map(|(prompt, roots)| {
+            let shown: Vec<_> = roots.iter().map(|r| show(r.as_deref())).collect();
+            let shown = shown.join(", ");
+            if labels.is_empty() {
+                let note = format!(
+                    "[roots] This run covers only the tasks in {shown}. The others are \
+                     scanned separately with their own prompt; leave them and their workers alone."
+                );
+                return Some((prompt, Some(note)));
+            }
+            let mine: Vec<_> = labels
+                .iter()
+                .filter(|l| roots.iter().any(|r| r.as_deref() == root_of(l)))
+                .map(|l| {
+                    let l = l.trim_end_matches(".md");
+                    format!("{l}.md")
+                })
+                .collect();
+            (!mine.is_empty()).then(|| {
+                let note = format!(
+                    "[roots] This run covers only {} (in {shown}); anything else above is \
+                     handled in a separate run with its own prompt.",
+                    mine.join(", ")
+                );
+                (prompt, Some(note))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tasks dir with a top-level task, a `work/` root with its own prompt
+    /// file and a `home/` root without one, next to a config prompt and a
+    /// flag prompt.
+    struct Setup {
+        tmp: tempfile::TempDir,
+    }
+
+    impl Setup {
+        fn new() -> Self {
+            let tmp = tempfile::tempdir().unwrap();
+            let tasks = tmp.path().join("tasks");
+            for root in ["work", "home"] {
+                fs::create_dir_all(tasks.join(root)).unwrap();
+            }
+            fs::write(tasks.join("work").join("orchestrator.md"), "work\n").unwrap();
+            fs::write(tmp.path().join("config.md"), "config\n").unwrap();
+            fs::write(tmp.path().join("flag.md"), "flag\n").unwrap();
+            Setup { tmp }
+        }
+
+        fn tasks(&self) -> PathBuf {
+            self.tmp.path().join("tasks")
+        }
+
+        fn path(&self, name: &str) -> PathBuf {
+            self.tmp.path().join(name)
+        }
+
+        fn work(&self) -> PathBuf {
+            self.tasks().join("work").join("orchestrator.md")
+        }
+
+        fn config(&self, flag: bool, config: bool, mode: RootPromptMode) -> Config {
+            let mut c = Config {
+                prompt_flag: flag.then(|| self.path("flag.md")),
+                orchestrator_prompt: config.then(|| self.path("config.md")),
+                ..Config::default()
+            };
+            c.root_prompts.mode = mode;
+            c
+        }
+    }
+
+    /// What [`resolve`] gives when neither the flag nor the config is set:
+    /// the file beside the test binary if there is one, else the agent.
+    fn fallback() -> (Source, Option<PathBuf>) {
+        match beside_exe().filter(|p| p.is_file()) {
+            Some(path) => (Source::BesideExe, Some(path)),
+            None => (Source::Agent, None),
+        }
+    }
+
+    #[test]
+    fn sources_are_listed_in_resolution_order() {
+        let labels: Vec<_> = Source::ALL.iter().map(|s| s.label()).collect();
+        assert_eq!(
+            labels,
+            [
+                "--prompt",
+                "root-local file",
+                "orchestrator_prompt in the config",
+                "beside the orch binary",
+                "claude's orchestrator agent",
+            ]
+        );
+    }
+
+    #[test]
+    fn the_flag_beats_root_files_and_the_config() {
+        let s = Setup::new();
+        for mode in [RootPromptMode::Replace, RootPromptMode::Append] {
+            let config = s.config(true, true, mode);
+            for root in [None, Some("work"), Some("home")] {
+                let (source, prompt) = resolve(&s.tasks(), &config, root);
+                assert!(source == Source::Flag);
+                assert_eq!(prompt.base, Some(s.path("flag.md")));
+                assert_eq!(prompt.append, None);
+            }
+        }
+    }
+
+    #[test]
+    fn a_root_file_in_replace_mode_beats_the_config() {
+        let s = Setup::new();
+        let config = s.config(false, true, RootPromptMode::Replace);
+        let (source, prompt) = resolve(&s.tasks(), &config, Some("work"));
+        assert!(source == Source::RootLocal);
+        assert_eq!(prompt.base, Some(s.work()));
+        assert_eq!(prompt.append, None);
+
+        // Only for its own root
+        let (source, prompt) = resolve(&s.tasks(), &config, Some("home"));
+        assert!(source == Source::Config);
+        assert_eq!(prompt.base, Some(s.path("config.md")));
+    }
+
+    #[test]
+    fn a_root_file_in_append_mode_extends_what_comes_after_it() {
+        let s = Setup::new();
+        let config = s.config(false, true, RootPromptMode::Append);
+        let (source, prompt) = resolve(&s.tasks(), &config, Some("work"));
+        assert!(source == Source::Config);
+        assert_eq!(prompt.base, Some(s.path("config.md")));
+        assert_eq!(prompt.append, Some(s.work()));
+
+        let config = s.config(false, false, RootPromptMode::Append);
+        let (source, prompt) = resolve(&s.tasks(), &config, Some("work"));
+        let (fallback, base) = fallback();
+        assert!(source == fallback);
+        assert_eq!(prompt.base, base);
+        assert_eq!(prompt.append, Some(s.work()));
+    }
+
+    #[test]
+    fn the_config_beats_the_binary_and_the_agent() {
+        let s = Setup::new();
+        let config = s.config(false, true, RootPromptMode::Replace);
+        let (source, prompt) = resolve(&s.tasks(), &config, None);
+        assert!(source == Source::Config);
+        assert_eq!(prompt.base, Some(s.path("config.md")));
+
+        let config = s.config(false, false, RootPromptMode::Replace);
+        let (source, prompt) = resolve(&s.tasks(), &config, None);
+        let (fallback, base) = fallback();
+        assert!(source == fallback);
+        assert_eq!(prompt.base, base);
+    }
+
+    #[test]
+    fn the_top_level_file_covers_only_the_top_level() {
+        let s = Setup::new();
+        fs::write(s.tasks().join("orchestrator.md"), "top\n").unwrap();
+        let config = s.config(false, true, RootPromptMode::Replace);
+        let prompt = effective(&s.tasks(), &config, None);
+        assert_eq!(prompt.base, Some(s.tasks().join("orchestrator.md")));
+        let prompt = effective(&s.tasks(), &config, Some("home"));
+        assert_eq!(prompt.base, Some(s.path("config.md")));
+    }
+
+    #[test]
+    fn the_file_name_comes_from_the_config() {
+        let s = Setup::new();
+        let mut config = s.config(false, true, RootPromptMode::Replace);
+        config.root_prompts.file = "PROMPT.md".into();
+        let prompt = effective(&s.tasks(), &config, Some("work"));
+        assert_eq!(prompt.base, Some(s.path("config.md")));
+
+        fs::write(s.tasks().join("work").join("PROMPT.md"), "work\n").unwrap();
+        let prompt = effective(&s.tasks(), &config, Some("work"));
+        assert_eq!(prompt.base, Some(s.tasks().join("work").join("PROMPT.md")));
+    }
+
+    #[test]
+    fn roots_that_agree_share_one_run() {
+        let s = Setup::new();
+        let config = s.config(true, true, RootPromptMode::Replace);
+        let parts = split(&s.tasks(), &config, &[]);
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].1, None);
+    }
+
+    #[test]
+    fn roots_that_differ_get_a_run_each() {
+        let s = Setup::new();
+        let config = s.config(false, true, RootPromptMode::Replace);
+        let parts = split(&s.tasks(), &config, &[]);
+        let notes: Vec<_> = parts
+            .iter()
+            .map(|(_, note)| note.clone().unwrap())
+            .collect();
+        assert_eq!(parts[0].0.base, Some(s.path("config.md")));
+        assert!(
+            notes[0].contains("in the top level, home/."),
+            "{}",
+            notes[0]
+        );
+        assert_eq!(parts[1].0.base, Some(s.work()));
+        assert!(notes[1].contains("in work/."), "{}", notes[1]);
+        assert_eq!(parts.len(), 2);
+
+        // A message about one task only runs the part covering it
+        let parts = split(&s.tasks(), &config, &["work/auth.md".into()]);
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].0.base, Some(s.work()));
+        let note = parts[0].1.as_deref().unwrap();
+        assert!(note.contains("only work/auth.md (in work/)"), "{note}");
+    }
+}
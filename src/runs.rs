@@ -0,0 +1,222 @@
+//! History of daemon scans, one JSON line per run in `.orch/runs.jsonl`.
+//! Appended, never rewritten, so it can be read as a stream.
+//!
+//! Runs also happen outside the daemon (`orch scan --async` with none
+//! running), so a run holds [`Lock`] while claude works, and one process's
+//! runs wait for another's.
+
+use std::{
+    collections::HashSet,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::ScanAction,
+    blobs,
+    config::Config,
+    orchestrator::{Run, Sent},
+    paths, state, task,
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct RunRecord {
+    /// RFC 3339, when the run started.
+    pub at: String,
+    pub ok: bool,
+    pub duration_secs: f64,
+    /// What the orchestrator reported doing, if it did.
+    #[serde(default)]
+    pub actions: Option<Vec<ScanAction>>,
+    /// What [`sandbox::check`](crate::sandbox::check) flagged.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suspicious: Vec<String>,
+    /// It succeeded without changing a file, session or watched repo.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_effect: bool,
+    /// It failed because claude couldn't reach its API.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub offline: bool,
+    /// Dollars, when claude reported it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    /// The [`crate::queue`] entry it replayed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queued: Option<String>,
+    /// The `orch scan --async` requests it answered.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requests: Vec<String>,
+    /// [`Sent`] as JSON, in [`blobs`], while the run is recent enough.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Its output, in [`blobs`], likewise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// The run `orch replay` re-sent the prompt of.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replay_of: Option<String>,
+    /// The model, when a replay picked one other than
+    /// [`MODEL`](crate::orchestrator::MODEL).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl RunRecord {
+    pub fn at(&self) -> Option<DateTime<Local>> {
+        task::parse_timestamp(&self.at)
+    }
+
+    /// The run's start time as `20261015-042709`: unique, since the daemon
+    /// runs one scan at a time.
+    pub fn id(&self) -> String {
+        self.at().map_or_else(
+            || self.at.clone(),
+            |t| t.format("%Y%m%d-%H%M%S").to_string(),
+        )
+    }
+}
+
+/// Keeps what `run` was given and printed on `record` as blobs, and drops
+/// the blobs of runs older than the newest `run_history`.
+pub fn store(dir: &Path, config: &Config, record: &mut RunRecord, run: &Run) {
+    if config.run_history == 0 {
+        blobs::prune(dir, &HashSet::new());
+        return;
+    }
+    record.prompt = run
+        .sent
+        .as_ref()
+        .and_then(|sent| blobs::put(dir, &serde_json::to_string(sent).unwrap_or_default()));
+    record.output = blobs::put(dir, &run.output);
+    let all: Vec<_> = iter(dir).collect();
+    let recent = all
+        .len()
+        .saturating_sub(config.run_history.saturating_sub(1));
+    let keep = all[recent..]
+        .iter()
+        .chain([&*record])
+        .flat_map(|r| [r.prompt.clone(), r.output.clone()])
+        .flatten()
+        .collect();
+    blobs::prune(dir, &keep);
+}
+
+/// What the run was given, while its blob is kept.
+pub fn sent(dir: &Path, record: &RunRecord) -> Option<Sent> {
+    serde_json::from_str(&blobs::get(dir, record.prompt.as_deref()?)?).ok()
+}
+
+pub fn append(dir: &Path, record: &RunRecord) {
+    let path = paths::runs_path(dir);
+    let line = serde_json::to_string(record).unwrap_or_default();
+    let result = fs::create_dir_all(paths::state_dir(dir)).and_then(|_| {
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(file, "{line}")
+    });
+    if let Err(e) = result {
+        eprintln!("[orch] failed to append to {}: {e}", path.display());
+    }
+}
+
+/// Runs oldest first, read lazily. Lines that don't parse are skipped.
+pub fn iter(dir: &Path) -> impl Iterator<Item = RunRecord> {
+    state::lines(&paths::runs_path(dir))
+}
+
+/// Starts a request marker in a scan message: `[scan-request <id>]`. The
+/// daemon takes markers out before the orchestrator sees the message.
+const REQUEST: &str = "[scan-request ";
+
+/// A new `orch scan --async` request id, like `req-20261015-042709-4242`.
+pub fn request_id() -> String {
+    format!(
+        "req-{}-{}",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        std::process::id()
+    )
+}
+
+/// `message` marked as answering request `id`.
+pub fn with_request(message: &str, id: &str) -> String {
+    format!("{REQUEST}{id}]\n{message}")
+}
+
+/// `message` without its request markers, and the ids they held.
+pub fn take_requests(message: &str) -> (String, Vec<String>) {
+    let mut ids = Vec::new();
+    let mut rest = message.to_string();
+    while let Some(start) = rest.find(REQUEST) {
+        let Some(len) = rest[start..].find(']') else {
+            break;
+        };
+        ids.push(rest[start + REQUEST.len()..start + len].trim().to_string());
+        let end = start + len + 1;
+        let end = end + rest[end..].len() - rest[end..].trim_start().len();
+        rest.replace_range(start..end, "");
+    }
+    (rest, ids)
+}
+
+/// Where a detached run's output goes.
+pub fn log_path(dir: &Path, id: &str) -> PathBuf {
+    paths::run_logs_dir(dir).join(format!("{id}.log"))
+}
+
+/// Held while a run is going: `.orch/run.lock`, holding the pid. Dropping
+/// it removes the file. A lock left by a process that's gone is taken over.
+pub struct Lock {
+    path: PathBuf,
+}
+
+impl Lock {
+    /// Waits for any other process's run to finish, then takes the lock.
+    /// Failing to write it is logged and the run goes ahead unlocked.
+    pub fn take(dir: &Path) -> Option<Lock> {
+        let path = paths::run_lock_path(dir);
+        let mut waiting = false;
+        loop {
+            let created = fs::create_dir_all(paths::state_dir(dir))
+                .and_then(|_| OpenOptions::new().write(true).create_new(true).open(&path));
+            match created {
+                Ok(mut file) => {
+                    let _ = write!(file, "{}", std::process::id());
+                    return Some(Lock { path });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let holder = fs::read_to_string(&path).unwrap_or_default();
+                    if !holder.trim().parse().is_ok_and(alive) {
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    if !waiting {
+                        eprintln!("[orch] waiting for run by pid {} to finish", holder.trim());
+                        waiting = true;
+                    }
+                    thread::sleep(Duration::from_secs(1));
+                }
+                Err(e) => {
+                    eprintln!("[orch] failed to take {}: {e}", path.display());
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Whether process `pid` exists. Without `/proc` it's assumed to.
+fn alive(pid: u32) -> bool {
+    let proc = Path::new("/proc");
+    !proc.is_dir() || proc.join(pid.to_string()).exists()
+}
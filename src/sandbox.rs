@@ -0,0 +1,470 @@
+//! Keeping the orchestrator to the tasks dir. claude always runs there; with
+//! `sandbox = "strict"` it also gets an allow-list instead of
+//! `--dangerously-skip-permissions`, so file edits are confined to the tasks
+//! dir and Bash to tmux and orch. Either way each daemon run is checked
+//! afterwards: a [`Fingerprint`] of the tasks dir (and of `sandbox_repos`)
+//! is taken before and after, and [`check`] reports what looks wrong.
+//! Neither part can stop a determined model; they make misbehavior visible.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    actions::{ActionKind, ScanAction},
+    config::Config,
+    fmt, git, tmux,
+};
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SandboxMode {
+    /// Skip claude's permission checks, as before
+    #[default]
+    Off,
+    /// Allow-listed tools only, edits confined to the tasks dir
+    Strict,
+}
+
+/// Permission flags for claude running the orchestrator on `tasks_dir`.
+pub fn claude_args(mode: SandboxMode, tasks_dir: &Path) -> Vec<String> {
+    match mode {
+        SandboxMode::Off => vec!["--dangerously-skip-permissions".into()],
+        SandboxMode::Strict => {
+            // `//` anchors a permission path at the filesystem root
+            let tasks = format!("/{}/**", tasks_dir.display());
+            let tools = [
+                "Read".to_string(),
+                "Glob".into(),
+                "Grep".into(),
+                "Task".into(),
+                format!("Edit({tasks})"),
+                format!("MultiEdit({tasks})"),
+                format!("Write({tasks})"),
+                "Bash(tmux:*)".into(),
+                "Bash(orch:*)".into(),
+            ];
+            vec!["--allowedTools".into(), tools.join(",")]
+        }
+    }
+}
+
+/// Size and mtime of one file.
+type Stamp = (u64, Option<SystemTime>);
+
+/// What a run could have changed: every file under the tasks dir except
+/// orch's own state, the tmux sessions, and `git status` of each of
+/// `sandbox_repos`.
+#[derive(Default, PartialEq)]
+pub struct Fingerprint {
+    files: BTreeMap<PathBuf, Stamp>,
+    sessions: BTreeSet<String>,
+    repos: BTreeMap<PathBuf, Option<String>>,
+}
+
+/// Directories orch itself writes to during a run.
+const OWN: &[&str] = &[".orch", ".inbox"];
+
+fn walk(root: &Path, dir: &Path, files: &mut BTreeMap<PathBuf, Stamp>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let rel = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+        if meta.is_dir() {
+            if dir == root && OWN.iter().any(|own| rel == Path::new(own)) {
+                continue;
+            }
+            walk(root, &path, files);
+        } else {
+            files.insert(rel, (meta.len(), meta.modified().ok()));
+        }
+    }
+}
+
+impl Fingerprint {
+    pub fn take(dir: &Path, config: &Config) -> Fingerprint {
+        let mut files = BTreeMap::new();
+        walk(dir, dir, &mut files);
+        let repos = config
+            .sandbox_repos
+            .iter()
+            .map(|repo| (repo.clone(), git::status(repo).ok()))
+            .collect();
+        Fingerprint {
+            files,
+            sessions: tmux::sessions().into_iter().collect(),
+            repos,
+        }
+    }
+
+    /// Files and sessions only, cheap enough to take on every daemon wakeup.
+    pub fn light(dir: &Path) -> Fingerprint {
+        let mut files = BTreeMap::new();
+        walk(dir, dir, &mut files);
+        Fingerprint {
+            files,
+            sessions: tmux::sessions().into_iter().collect(),
+            repos: BTreeMap::new(),
+        }
+    }
+
+    /// Sessions there were in `self` that `later` doesn't have.
+    pub fn gone(&self, later: &Fingerprint) -> Vec<String> {
+        self.sessions.difference(&later.sessions).cloned().collect()
+    }
+
+    /// Files changed between `self` and `later`.
+    pub fn changes(&self, later: &Fingerprint) -> Diff {
+        diff(&self.files, &later.files)
+    }
+}
+
+/// Files added, removed and modified between two fingerprints.
+#[derive(Default)]
+pub struct Diff {
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+pub fn diff(before: &BTreeMap<PathBuf, Stamp>, after: &BTreeMap<PathBuf, Stamp>) -> Diff {
+    let mut d = Diff::default();
+    for (path, stamp) in after {
+        match before.get(path) {
+            None => d.added.push(path.clone()),
+            Some(old) if old != stamp => d.modified.push(path.clone()),
+            Some(_) => {}
+        }
+    }
+    d.removed = before
+        .keys()
+        .filter(|path| !after.contains_key(*path))
+        .cloned()
+        .collect();
+    d
+}
+
+/// A task file: a `.md` outside any hidden directory.
+fn is_task_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md")
+        && !path
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+}
+
+/// What's suspicious about a run that went from `before` to `after`:
+/// anything but task files changed, a task file vanished without landing in
+/// `done/`, a watched repo changed, or the orchestrator reported edits yet
+/// nothing changed.
+pub fn check(
+    before: &Fingerprint,
+    after: &Fingerprint,
+    actions: Option<&[ScanAction]>,
+) -> Vec<String> {
+    let d = diff(&before.files, &after.files);
+    let mut found = Vec::new();
+
+    for (verb, paths) in [
+        ("added", &d.added),
+        ("modified", &d.modified),
+        ("removed", &d.removed),
+    ] {
+        for path in paths.iter().filter(|p| !is_task_file(p)) {
+            found.push(format!(
+                "{verb} a file that isn't a task: {}",
+                path.display()
+            ));
+        }
+    }
+
+    // Closing moves a task into done/, under a stamped name
+    let closed: BTreeSet<String> = d
+        .added
+        .iter()
+        .filter(|p| p.starts_with("done"))
+        .filter_map(|p| p.file_name())
+        .map(|f| f.to_string_lossy().into_owned())
+        .collect();
+    for path in d.removed.iter().filter(|p| is_task_file(p)) {
+        let file = path.file_name().unwrap_or_default().to_string_lossy();
+        let moved = closed.iter().any(|c| c.ends_with(&format!("-{file}")));
+        let renamed = d.added.iter().any(|p| p.file_name() == path.file_name());
+        if !moved && !renamed {
+            found.push(format!("deleted task file {}", path.display()));
+        }
+    }
+
+    for (repo, status) in &after.repos {
+        let was = before.repos.get(repo).cloned().flatten();
+        if let (Some(was), Some(now)) = (was, status)
+            && &was != now
+        {
+            let old: BTreeSet<&str> = was.lines().collect();
+            let new: Vec<&str> = now.lines().filter(|l| !old.contains(l)).collect();
+            found.push(format!(
+                "{} changed during the run{}",
+                repo.display(),
+                if new.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {}", new.join(", "))
+                }
+            ));
+        }
+    }
+
+    let edits = actions
+        .unwrap_or_default()
+        .iter()
+        .filter(|a| matches!(a.kind, ActionKind::Updated | ActionKind::Closed))
+        .count();
+    if edits > 0 && d.is_empty() {
+        found.push(format!(
+            "reported {} (updates or closes), but nothing in the tasks dir changed",
+            fmt::count(edits, "task change")
+        ));
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// The files half of a [`Fingerprint`]; the tests leave tmux alone.
+    fn files(dir: &Path) -> Fingerprint {
+        let mut files = BTreeMap::new();
+        walk(dir, dir, &mut files);
+        Fingerprint {
+            files,
+            ..Fingerprint::default()
+        }
+    }
+
+    /// Writes `rel` under `dir` with an mtime of `secs`, so a rewrite is
+    /// seen whatever the filesystem's timestamp resolution.
+    fn write(dir: &Path, rel: &str, content: &str, secs: u64) {
+        let path = dir.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    fn paths(paths: &[PathBuf]) -> Vec<&str> {
+        paths.iter().map(|p| p.to_str().unwrap()).collect()
+    }
+
+    fn action(kind: ActionKind) -> ScanAction {
+        ScanAction {
+            kind,
+            task: "auth".into(),
+            session: None,
+            reason: None,
+        }
+    }
+
+    /// A tasks dir with two tasks, one in a project, and orch's own state.
+    fn tasks() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        write(tmp.path(), "auth.md", "# Auth\n", 1);
+        write(tmp.path(), "backend/api.md", "# API\n", 1);
+        write(tmp.path(), ".orch/runs.jsonl", "{}\n", 1);
+        write(tmp.path(), ".inbox/1.msg", "hi\n", 1);
+        tmp
+    }
+
+    #[test]
+    fn diff_sorts_files_into_added_removed_and_modified() {
+        let tmp = tasks();
+        let dir = tmp.path();
+        write(dir, "keep.md", "same\n", 1);
+        let before = files(dir);
+        write(dir, "auth.md", "# Auth\n\nMore.\n", 1);
+        write(dir, "keep.md", "same\n", 1);
+        write(dir, "backend/new.md", "# New\n", 2);
+        fs::remove_file(dir.join("backend/api.md")).unwrap();
+        // Same size, later mtime
+        write(dir, "touched.md", "x\n", 1);
+        let middle = files(dir);
+        write(dir, "touched.md", "y\n", 2);
+        let after = files(dir);
+
+        let d = diff(&before.files, &after.files);
+        assert_eq!(paths(&d.added), ["backend/new.md", "touched.md"]);
+        assert_eq!(paths(&d.removed), ["backend/api.md"]);
+        assert_eq!(paths(&d.modified), ["auth.md"]);
+        assert_eq!(
+            paths(&diff(&middle.files, &after.files).modified),
+            ["touched.md"]
+        );
+        assert!(diff(&after.files, &after.files).is_empty());
+    }
+
+    #[test]
+    fn own_state_is_left_out_at_the_top_only() {
+        let tmp = tasks();
+        let dir = tmp.path();
+        write(dir, "backend/.orch/notes.md", "x\n", 1);
+        let before = files(dir);
+        write(dir, ".orch/runs.jsonl", "{}\n{}\n", 2);
+        write(dir, ".inbox/2.msg", "hello\n", 2);
+        assert!(diff(&before.files, &files(dir).files).is_empty());
+        write(dir, "backend/.orch/notes.md", "y\n", 2);
+        assert_eq!(
+            paths(&diff(&before.files, &files(dir).files).modified),
+            ["backend/.orch/notes.md"]
+        );
+    }
+
+    #[test]
+    fn task_edits_are_expected() {
+        let tmp = tasks();
+        let dir = tmp.path();
+        let before = files(dir);
+        write(dir, "auth.md", "# Auth\n\n## Status\n- started\n", 2);
+        write(dir, "backend/cache.md", "# Cache\n", 2);
+        let actions = [action(ActionKind::Updated), action(ActionKind::Started)];
+        assert_eq!(
+            check(&before, &files(dir), Some(&actions)),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn files_that_arent_tasks_are_flagged() {
+        let tmp = tasks();
+        let dir = tmp.path();
+        write(dir, "notes.txt", "x\n", 1);
+        write(dir, ".hidden/old.md", "x\n", 1);
+        let before = files(dir);
+        write(dir, "script.sh", "rm -rf ~\n", 2);
+        write(dir, ".hidden/old.md", "changed\n", 2);
+        fs::remove_file(dir.join("notes.txt")).unwrap();
+        assert_eq!(
+            check(&before, &files(dir), None),
+            [
+                "added a file that isn't a task: script.sh",
+                "modified a file that isn't a task: .hidden/old.md",
+                "removed a file that isn't a task: notes.txt",
+            ]
+        );
+    }
+
+    #[test]
+    fn closed_and_moved_tasks_arent_deletions() {
+        let tmp = tasks();
+        let dir = tmp.path();
+        let before = files(dir);
+        fs::rename(dir.join("auth.md"), dir.join("backend/auth.md")).unwrap();
+        fs::create_dir(dir.join("done")).unwrap();
+        fs::rename(
+            dir.join("backend/api.md"),
+            dir.join("done/2026-10-15-api.md"),
+        )
+        .unwrap();
+        let actions = [action(ActionKind::Closed)];
+        assert_eq!(
+            check(&before, &files(dir), Some(&actions)),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn deleted_tasks_are_flagged() {
+        let tmp = tasks();
+        let dir = tmp.path();
+        let before = files(dir);
+        fs::remove_file(dir.join("backend/api.md")).unwrap();
+        // In done/, but not under the deleted task's name
+        write(dir, "done/2026-10-15-other.md", "x\n", 2);
+        assert_eq!(
+            check(&before, &files(dir), None),
+            ["deleted task file backend/api.md"]
+        );
+    }
+
+    #[test]
+    fn reported_edits_with_nothing_changed_are_flagged() {
+        let tmp = tasks();
+        let dir = tmp.path();
+        let before = files(dir);
+        let after = files(dir);
+        let actions = [
+            action(ActionKind::Updated),
+            action(ActionKind::Closed),
+            action(ActionKind::Started),
+        ];
+        assert_eq!(
+            check(&before, &after, Some(&actions)),
+            ["reported 2 task changes (updates or closes), but nothing in the tasks dir changed"]
+        );
+        // Starting workers doesn't touch the tasks dir
+        let actions = [action(ActionKind::Started), action(ActionKind::Killed)];
+        assert_eq!(check(&before, &after, Some(&actions)), Vec::<String>::new());
+        assert_eq!(check(&before, &after, None), Vec::<String>::new());
+    }
+
+    #[test]
+    fn watched_repos_that_change_are_flagged() {
+        let status = |s: Option<&str>| {
+            let mut f = Fingerprint::default();
+            f.repos
+                .insert(PathBuf::from("/code/app"), s.map(str::to_string));
+            f
+        };
+        let before = status(Some(" M src/main.rs\n"));
+        assert_eq!(
+            check(
+                &before,
+                &status(Some(" M src/main.rs\n?? src/new.rs\n")),
+                None
+            ),
+            ["/code/app changed during the run: ?? src/new.rs"]
+        );
+        assert_eq!(
+            check(&before, &status(Some("")), None),
+            ["/code/app changed during the run"]
+        );
+        assert_eq!(check(&before, &before, None), Vec::<String>::new());
+        // Not a repo, or git failed: nothing to compare
+        assert_eq!(check(&status(None), &before, None), Vec::<String>::new());
+        assert_eq!(check(&before, &status(None), None), Vec::<String>::new());
+    }
+
+    #[test]
+    fn strict_mode_confines_edits_to_the_tasks_dir() {
+        let dir = Path::new("/home/me/tasks");
+        assert_eq!(
+            claude_args(SandboxMode::Off, dir),
+            ["--dangerously-skip-permissions"]
+        );
+        let args = claude_args(SandboxMode::Strict, dir);
+        assert_eq!(args[0], "--allowedTools");
+        let tools: Vec<_> = args[1].split(',').collect();
+        assert!(tools.contains(&"Edit(//home/me/tasks/**)"));
+        assert!(tools.contains(&"Write(//home/me/tasks/**)"));
+        assert!(tools.contains(&"Bash(tmux:*)"));
+        assert!(!tools.contains(&"Bash"));
+        assert!(!args.iter().any(|a| a.contains("dangerously")));
+    }
+}
@@ -0,0 +1,91 @@
+//! Worker session names, built from the `session_format` template.
+//!
+//! `{{name}}` is the task file name and `{{project}}` its project folder
+//! (empty at the top level), e.g. `orch/{{project}}/{{name}}`. Going from a
+//! session back to its task compares against every task's name rather than
+//! un-templating, so templates only need to be unambiguous enough to not
+//! collide: `{{name}}` must appear exactly once and placeholders can't touch.
+
+use std::path::{Path, PathBuf};
+
+use crate::{config::Config, task};
+
+pub const DEFAULT_FORMAT: &str = "task-{{name}}";
+
+/// The `orch console` session. Never a worker, whatever `session_format`
+/// is.
+pub const CONSOLE: &str = "orch-console";
+
+pub fn validate(format: &str) -> Result<(), String> {
+    if format.matches("{{name}}").count() != 1 {
+        return Err("must contain {{name}} exactly once".into());
+    }
+    if format.matches("{{project}}").count() > 1 {
+        return Err("may contain {{project}} at most once".into());
+    }
+    if format.contains("}}{{") {
+        return Err("placeholders must be separated by some text".into());
+    }
+    let rest = format.replace("{{name}}", "").replace("{{project}}", "");
+    if rest.contains("{{") || rest.contains("}}") {
+        return Err("only {{name}} and {{project}} are supported".into());
+    }
+    if rest.contains(['.', ':']) {
+        return Err("tmux doesn't allow '.' or ':' in session names".into());
+    }
+    Ok(())
+}
+
+/// The default session for the task at `label` (e.g. `backend/auth`). At
+/// the top level `{{project}}` drops out along with one separator next to
+/// it, so `orch/{{project}}/{{name}}` gives `orch/auth`, not `orch//auth`.
+pub fn name(config: &Config, label: &str) -> String {
+    let (project, name) = label.rsplit_once('/').unwrap_or(("", label));
+    let mut format = config.session_format.clone();
+    if project.is_empty()
+        && let Some(i) = format.find("{{project}}")
+    {
+        let end = i + "{{project}}".len();
+        let is_sep = |c: char| !c.is_alphanumeric() && c != '{' && c != '}';
+        let range = match (
+            format[..i].chars().next_back(),
+            format[end..].chars().next(),
+        ) {
+            (_, Some(c)) if is_sep(c) => i..end + c.len_utf8(),
+            (Some(c), _) if is_sep(c) => i - c.len_utf8()..end,
+            _ => i..end,
+        };
+        format.replace_range(range, "");
+    }
+    format
+        .replace("{{project}}", project)
+        .replace("{{name}}", name)
+}
+
+/// The fixed text a worker session starts with, before any placeholder
+/// (`task-` by default).
+pub fn prefix(config: &Config) -> &str {
+    let format = config.session_format.as_str();
+    &format[..format.find("{{").unwrap_or(format.len())]
+}
+
+/// The task whose worker is `session`.
+pub fn task_for(dir: &Path, config: &Config, session: &str) -> Option<PathBuf> {
+    task::files(dir, config).into_iter().find(|path| {
+        task::read(path, config)
+            .is_ok_and(|c| task::session(config, &task::label(dir, path), &c) == session)
+    })
+}
+
+/// Tells the orchestrator about a non-default `session_format`, appended to
+/// its messages.
+pub fn prompt_note(config: &Config) -> Option<String> {
+    (config.session_format != DEFAULT_FORMAT).then(|| {
+        format!(
+            "[config] Name worker sessions `{}` instead of task-<name> ({{{{name}}}} is the \
+             task file name, {{{{project}}}} its project folder or empty at the top level). \
+             Only sessions matching that pattern are workers.",
+            config.session_format
+        )
+    })
+}
@@ -0,0 +1,417 @@
+//! Reading orch's own files under `.orch/` so that a damaged one costs its
+//! bad lines, not the command, and the checks behind `orch state check`.
+//! A JSONL file that doesn't parse is read past line by line; a JSON file
+//! that doesn't parse reads as missing. Either is warned about once.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    bulk, config::Config, escalation, exits, fmt, focus, health::Health, heartbeat::Beat, log,
+    paths, prompts::Prompt, rollout, runs::RunRecord, task,
+};
+
+/// Files already warned about in this process.
+static WARNED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+fn warn(path: &Path, what: &str) {
+    let mut warned = WARNED.lock().unwrap_or_else(|e| e.into_inner());
+    if !warned.iter().any(|p| p == path) {
+        warned.push(path.to_path_buf());
+        crate::warn!(
+            StateCorrupt,
+            "{}: {what}; `orch state check --repair` sets it aside",
+            path.display()
+        );
+    }
+}
+
+/// The records of a JSONL file, oldest first, read lazily. Lines that don't
+/// parse (a torn write, a truncated copy) are skipped with a warning.
+pub fn lines<T: DeserializeOwned>(path: &Path) -> impl Iterator<Item = T> + use<T> {
+    let path = path.to_path_buf();
+    fs::File::open(&path)
+        .into_iter()
+        .flat_map(|file| BufReader::new(file).split(b'\n').map_while(Result::ok))
+        .filter_map(move |line| {
+            let line = String::from_utf8_lossy(&line);
+            if line.trim().is_empty() {
+                return None;
+            }
+            serde_json::from_str(&line)
+                .inspect_err(|_| warn(&path, "skipping lines that don't parse"))
+                .ok()
+        })
+}
+
+/// A JSON file's contents, or None if it's missing or doesn't parse (with a
+/// warning).
+pub fn load<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let text = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text)
+        .inspect_err(|_| warn(path, "doesn't parse, ignoring it"))
+        .ok()
+}
+
+/// How a state file is laid out, and a check that a line or the whole file
+/// parses as what orch writes there.
+enum Kind {
+    Lines(fn(&str) -> bool),
+    Json(fn(&str) -> bool),
+}
+
+fn parses<T: DeserializeOwned>(text: &str) -> bool {
+    serde_json::from_str::<T>(text).is_ok()
+}
+
+/// Every state file orch reads, with its layout.
+fn files(dir: &Path, config: &Config) -> Vec<(PathBuf, Kind)> {
+    let audit = config
+        .audit_log
+        .path
+        .clone()
+        .unwrap_or_else(|| paths::audit_path(dir));
+    let mut files = vec![
+        (paths::runs_path(dir), Kind::Lines(parses::<RunRecord>)),
+        (paths::bulk_path(dir), Kind::Lines(parses::<bulk::Change>)),
+        (audit, Kind::Lines(parses::<serde_json::Value>)),
+        (
+            paths::prompts_path(dir),
+            Kind::Json(parses::<BTreeMap<String, Prompt>>),
+        ),
+        (
+            paths::heartbeat_path(dir),
+            Kind::Json(parses::<BTreeMap<String, Beat>>),
+        ),
+        (paths::health_path(dir), Kind::Json(parses::<Health>)),
+        (
+            paths::costs_path(dir),
+            Kind::Json(parses::<BTreeMap<String, f64>>),
+        ),
+        (
+            paths::exits_path(dir),
+            Kind::Json(parses::<BTreeMap<String, exits::Exit>>),
+        ),
+        (paths::focus_path(dir), Kind::Json(parses::<focus::Focus>)),
+        (
+            paths::usage_path(dir),
+            Kind::Json(parses::<BTreeMap<String, u32>>),
+        ),
+        (
+            paths::inbox_state_path(dir),
+            Kind::Json(parses::<BTreeMap<String, String>>),
+        ),
+        (
+            paths::escalation_path(dir),
+            Kind::Json(parses::<BTreeMap<String, escalation::Escalation>>),
+        ),
+        (
+            paths::export_ids_path(dir),
+            Kind::Json(parses::<BTreeMap<String, String>>),
+        ),
+        (
+            paths::rollout_path(dir),
+            Kind::Json(parses::<Vec<rollout::Start>>),
+        ),
+        (
+            paths::console_snapshot_path(dir),
+            Kind::Json(parses::<serde_json::Value>),
+        ),
+    ];
+    files.extend(
+        log::tasks(dir)
+            .iter()
+            .map(|name| (log::path(dir, name), Kind::Lines(parses::<log::Entry>))),
+    );
+    files
+}
+
+/// Something wrong with one state file.
+pub struct Problem {
+    pub path: PathBuf,
+    pub what: String,
+    /// Line numbers (from 1) that don't parse, for a JSONL file.
+    bad_lines: Vec<usize>,
+    /// The whole file is unusable.
+    corrupt: bool,
+}
+
+impl Problem {
+    /// Whether `--repair` can do anything about it.
+    pub fn repairable(&self) -> bool {
+        self.corrupt || !self.bad_lines.is_empty()
+    }
+}
+
+/// What a check found: the files that were fine (with a detail) and the
+/// problems.
+#[derive(Default)]
+pub struct Report {
+    pub ok: Vec<(PathBuf, String)>,
+    pub problems: Vec<Problem>,
+}
+
+/// Reads every state file and looks for transcripts of tasks that no longer
+/// exist, open or closed.
+pub fn check(dir: &Path, config: &Config) -> Report {
+    let mut report = Report::default();
+    for (path, kind) in files(dir, config) {
+        let Ok(bytes) = fs::read(&path) else {
+            continue;
+        };
+        let text = String::from_utf8_lossy(&bytes);
+        match kind {
+            Kind::Lines(valid) => {
+                let lines: Vec<_> = text.lines().collect();
+                let bad_lines: Vec<_> = lines
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, l)| !l.trim().is_empty() && !valid(l))
+                    .map(|(i, _)| i + 1)
+                    .collect();
+                if bad_lines.is_empty() {
+                    report.ok.push((path, format!("{} lines", lines.len())));
+                } else {
+                    let shown: Vec<_> = bad_lines.iter().take(5).map(usize::to_string).collect();
+                    let more = if bad_lines.len() > 5 { ", ..." } else { "" };
+                    report.problems.push(Problem {
+                        what: format!(
+                            "{} of {} lines don't parse (line {}{more})",
+                            bad_lines.len(),
+                            lines.len(),
+                            shown.join(", ")
+                        ),
+                        path,
+                        bad_lines,
+                        corrupt: false,
+                    });
+                }
+            }
+            Kind::Json(valid) if valid(&text) => report.ok.push((path, "parses".into())),
+            Kind::Json(_) => report.problems.push(Problem {
+                path,
+                what: "doesn't parse".into(),
+                bad_lines: Vec::new(),
+                corrupt: true,
+            }),
+        }
+    }
+
+    let mut names: Vec<_> = task::list(dir, config)
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    names.extend(task::archived(dir).into_iter().map(|a| a.name));
+    let logs = fs::read_dir(paths::transcripts_dir(dir))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "log"));
+    for path in logs {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        if !names.iter().any(|n| *n == name) {
+            report.problems.push(Problem {
+                what: format!("transcript of {name}, which isn't a task here or in done/; delete it if it's not wanted"),
+                path,
+                bad_lines: Vec::new(),
+                corrupt: false,
+            });
+        }
+    }
+    report
+}
+
+/// `path` with `.corrupt` added to its file name.
+pub fn sidecar(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".corrupt");
+    path.with_file_name(name)
+}
+
+/// Sets a problem's damage aside: bad JSONL lines are appended to the
+/// `.corrupt` sidecar and the rest rewritten in place; an unparseable JSON
+/// file is moved to the sidecar, and the heartbeat rebuilt from the live
+/// workers. Returns what was done.
+pub fn repair(dir: &Path, config: &Config, problem: &Problem) -> std::io::Result<String> {
+    let corrupt = sidecar(&problem.path);
+    let shown = corrupt.file_name().unwrap_or_default().to_string_lossy();
+    if problem.corrupt {
+        fs::rename(&problem.path, &corrupt)?;
+        if problem.path == paths::heartbeat_path(dir) {
+            crate::heartbeat::update(dir, config);
+            return Ok(format!("moved to {}, rebuilt", shown));
+        }
+        return Ok(format!(
+            "moved to {}; orch writes a new one when it next needs it",
+            shown
+        ));
+    }
+    // Lines are copied as they are: a good line isn't rewritten, even where
+    // it isn't UTF-8
+    let bytes = fs::read(&problem.path)?;
+    let (mut good, mut bad) = (Vec::new(), Vec::new());
+    for (i, line) in bytes.split_inclusive(|&b| b == b'\n').enumerate() {
+        let to = if problem.bad_lines.contains(&(i + 1)) {
+            &mut bad
+        } else {
+            &mut good
+        };
+        to.extend_from_slice(line);
+        if !line.ends_with(b"\n") {
+            to.push(b'\n');
+        }
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&corrupt)?;
+    file.write_all(&bad)?;
+    task::write_atomic(&problem.path, &good)?;
+    Ok(format!(
+        "moved {} to {}",
+        fmt::count(problem.bad_lines.len(), "line"),
+        shown
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::{costs, runs};
+
+    const RUN: &str = r#"{"at":"2026-10-15T09:30:00+00:00","ok":true,"duration_secs":1.5}"#;
+
+    /// A tasks dir with `files` written under `.orch/`.
+    fn state(files: &[(&str, &[u8])]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let state = paths::state_dir(dir.path());
+        fs::create_dir_all(&state).unwrap();
+        for (name, bytes) in files {
+            fs::write(state.join(name), bytes).unwrap();
+        }
+        dir
+    }
+
+    /// `text` cut off halfway, as a torn write or a full disk leaves it.
+    fn truncated(text: &str) -> &str {
+        &text[..text.len() / 2]
+    }
+
+    #[test]
+    fn lines_read_past_truncated_and_garbled_lines() {
+        let text = format!(
+            "{RUN}\n{}\n\n\u{0}\u{0}\u{0}\n{RUN}\n{}",
+            truncated(RUN),
+            truncated(RUN)
+        );
+        let dir = state(&[("runs.jsonl", text.as_bytes())]);
+        let records: Vec<Value> = lines(&paths::runs_path(dir.path())).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(runs::iter(dir.path()).count(), 2);
+    }
+
+    #[test]
+    fn lines_of_a_missing_or_empty_file_are_none() {
+        let dir = state(&[("bulk.jsonl", b"")]);
+        assert_eq!(lines::<Value>(&paths::runs_path(dir.path())).count(), 0);
+        assert_eq!(lines::<Value>(&paths::bulk_path(dir.path())).count(), 0);
+    }
+
+    #[test]
+    fn lines_that_are_not_utf8_are_skipped() {
+        let mut bytes = format!("{RUN}\n").into_bytes();
+        bytes.extend_from_slice(b"\xff\xfe{\"at\"\n");
+        bytes.extend_from_slice(format!("{RUN}\n").as_bytes());
+        let dir = state(&[("runs.jsonl", &bytes)]);
+        assert_eq!(runs::iter(dir.path()).count(), 2);
+    }
+
+    #[test]
+    fn a_truncated_json_file_reads_as_missing() {
+        let dir = state(&[("costs.json", br#"{"recon": 0.5, "auth"#)]);
+        assert!(load::<BTreeMap<String, f64>>(&paths::costs_path(dir.path())).is_none());
+        assert!(costs::load(dir.path()).is_empty());
+        assert!(load::<Value>(&paths::health_path(dir.path())).is_none());
+    }
+
+    #[test]
+    fn check_finds_truncated_files() {
+        let runs = format!("{RUN}\n{RUN}\n{}", truncated(RUN));
+        let dir = state(&[
+            ("runs.jsonl", runs.as_bytes()),
+            ("costs.json", br#"{"recon": 0."#),
+            ("usage.json", br#"{"recon": 2}"#),
+        ]);
+        let report = check(dir.path(), &Config::default());
+        let problems: Vec<_> = report
+            .problems
+            .iter()
+            .map(|p| {
+                (
+                    p.path.file_name().unwrap().to_str().unwrap(),
+                    p.what.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                ("runs.jsonl", "1 of 3 lines don't parse (line 3)"),
+                ("costs.json", "doesn't parse"),
+            ]
+        );
+        assert!(report.problems.iter().all(Problem::repairable));
+        assert!(report.ok.iter().any(|(p, _)| p.ends_with("usage.json")));
+    }
+
+    #[test]
+    fn repair_sets_bad_lines_aside_and_keeps_good_ones_byte_for_byte() {
+        // Not UTF-8 but still a good line, as far as a lossy read can tell
+        let good = b"{\"command\":\"st\xffatus\"}\r\n".to_vec();
+        let mut bytes = good.clone();
+        bytes.extend_from_slice(b"{\"command\":\"ju\n");
+        bytes.extend_from_slice(&good);
+        bytes.extend_from_slice(b"{\"comm");
+        let dir = state(&[("audit.jsonl", &bytes)]);
+        let path = paths::audit_path(dir.path());
+        let config = Config::default();
+
+        let report = check(dir.path(), &config);
+        let [problem] = &report.problems[..] else {
+            panic!("{} problems", report.problems.len());
+        };
+        assert_eq!(problem.path, path);
+        assert_eq!(
+            repair(dir.path(), &config, problem).unwrap(),
+            "moved 2 lines to audit.jsonl.corrupt"
+        );
+        assert_eq!(fs::read(&path).unwrap(), [good.clone(), good].concat());
+        assert_eq!(
+            fs::read(sidecar(&path)).unwrap(),
+            b"{\"command\":\"ju\n{\"comm\n"
+        );
+        assert!(check(dir.path(), &config).problems.is_empty());
+    }
+
+    #[test]
+    fn repair_moves_an_unparseable_json_file_aside() {
+        let dir = state(&[("costs.json", br#"{"recon": 0."#)]);
+        let config = Config::default();
+        let report = check(dir.path(), &config);
+        let path = paths::costs_path(dir.path());
+        repair(dir.path(), &config, &report.problems[0]).unwrap();
+        assert!(!path.exists());
+        assert_eq!(fs::read(sidecar(&path)).unwrap(), br#"{"recon": 0."#);
+        assert!(check(dir.path(), &config).problems.is_empty());
+    }
+}
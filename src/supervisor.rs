@@ -0,0 +1,236 @@
+//! Keeps the daemon going through its own bugs. The daemon runs its watch
+//! loop, scans and notifications on one thread, so that loop is the one
+//! subsystem supervised: a panic in it is caught, logged with a backtrace,
+//! counted in `.orch/daemon.json`, and the loop started over after a
+//! backoff. Too many panics close together and the daemon exits with
+//! [`GAVE_UP`], for a service manager to restart it from scratch.
+
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    collections::VecDeque,
+    panic::{self, AssertUnwindSafe},
+    sync::Once,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::fmt;
+
+/// Exit code of a daemon that stopped restarting its loop (`EX_SOFTWARE`).
+pub const GAVE_UP: i32 = 70;
+
+/// When to restart and when to stop.
+pub struct Policy {
+    /// More panics than this within [`Policy::window`] and it gives up.
+    pub max_restarts: usize,
+    pub window: Duration,
+    /// Wait before the first restart; it doubles with each panic still in
+    /// the window, up to [`Policy::max_backoff`].
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+/// The daemon loop's.
+pub const DAEMON: Policy = Policy {
+    max_restarts: 5,
+    window: Duration::from_secs(10 * 60),
+    backoff: Duration::from_secs(1),
+    max_backoff: Duration::from_secs(60),
+};
+
+/// A supervised subsystem that kept panicking.
+#[derive(Debug)]
+pub struct GaveUp {
+    pub panics: usize,
+}
+
+/// What a panic said, for logs.
+pub fn message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "(no message)".into()
+    }
+}
+
+/// Logs panics as `[orch] panicked at <where>: <message>` with a backtrace,
+/// whatever `RUST_BACKTRACE` says.
+fn log_panics() {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        panic::set_hook(Box::new(|info| {
+            let at = info
+                .location()
+                .map(|l| format!("{}:{}", l.file(), l.line()))
+                .unwrap_or_else(|| "?".into());
+            eprintln!(
+                "[orch] panicked at {at}: {}\n{}",
+                message(info.payload()),
+                Backtrace::force_capture()
+            );
+        }));
+    });
+}
+
+/// The wait before the next restart, with `recent` panics in the window.
+fn backoff(policy: &Policy, recent: usize) -> Duration {
+    let doublings = recent.saturating_sub(1).min(16) as u32;
+    (policy.backoff * 2u32.pow(doublings)).min(policy.max_backoff)
+}
+
+/// Runs `body` until it returns, starting it over each time it panics.
+/// `restarted` gets the panic's message before each restart.
+pub fn supervise<T>(
+    name: &str,
+    policy: &Policy,
+    mut restarted: impl FnMut(&str),
+    mut body: impl FnMut() -> T,
+) -> Result<T, GaveUp> {
+    log_panics();
+    let mut panics: VecDeque<Instant> = VecDeque::new();
+    loop {
+        let payload = match panic::catch_unwind(AssertUnwindSafe(&mut body)) {
+            Ok(value) => return Ok(value),
+            Err(payload) => payload,
+        };
+        let now = Instant::now();
+        panics.retain(|&t| now.duration_since(t) < policy.window);
+        panics.push_back(now);
+        if panics.len() > policy.max_restarts {
+            eprintln!(
+                "[orch] {name} panicked {} times within {}; giving up",
+                panics.len(),
+                fmt::duration(policy.window)
+            );
+            return Err(GaveUp {
+                panics: panics.len(),
+            });
+        }
+        let wait = backoff(policy, panics.len());
+        eprintln!(
+            "[orch] {name} panicked; restarting in {} ({} of {} allowed within {})",
+            fmt::duration(wait),
+            panics.len(),
+            policy.max_restarts,
+            fmt::duration(policy.window)
+        );
+        restarted(&message(payload.as_ref()));
+        thread::sleep(wait);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    /// Restarts at once, and gives up after `max_restarts` panics within a
+    /// minute.
+    fn policy(max_restarts: usize) -> Policy {
+        Policy {
+            max_restarts,
+            window: Duration::from_secs(60),
+            backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        }
+    }
+
+    /// A subsystem that panics its first `panics` runs, then returns how
+    /// many runs it took.
+    struct Flaky {
+        panics: usize,
+        runs: Cell<usize>,
+    }
+
+    impl Flaky {
+        fn new(panics: usize) -> Self {
+            Flaky {
+                panics,
+                runs: Cell::new(0),
+            }
+        }
+
+        fn run(&self) -> usize {
+            let run = self.runs.get() + 1;
+            self.runs.set(run);
+            if run <= self.panics {
+                panic!("run {run} failed");
+            }
+            run
+        }
+    }
+
+    #[test]
+    fn a_subsystem_that_returns_is_not_restarted() {
+        let flaky = Flaky::new(0);
+        let mut restarts = Vec::new();
+        let result = supervise(
+            "test",
+            &policy(3),
+            |p| restarts.push(p.to_string()),
+            || flaky.run(),
+        );
+        assert_eq!(result.unwrap(), 1);
+        assert!(restarts.is_empty());
+    }
+
+    #[test]
+    fn panics_restart_it_until_it_returns() {
+        let flaky = Flaky::new(3);
+        let mut restarts = Vec::new();
+        let result = supervise(
+            "test",
+            &policy(3),
+            |p| restarts.push(p.to_string()),
+            || flaky.run(),
+        );
+        assert_eq!(result.unwrap(), 4);
+        assert_eq!(restarts, ["run 1 failed", "run 2 failed", "run 3 failed"]);
+    }
+
+    #[test]
+    fn too_many_panics_give_up() {
+        let flaky = Flaky::new(usize::MAX);
+        let result = supervise("test", &policy(3), |_| {}, || flaky.run());
+        assert_eq!(result.unwrap_err().panics, 4);
+        assert_eq!(flaky.runs.get(), 4);
+    }
+
+    #[test]
+    fn panics_outside_the_window_are_forgotten() {
+        let flaky = Flaky::new(10);
+        let policy = Policy {
+            window: Duration::ZERO,
+            ..policy(1)
+        };
+        assert_eq!(
+            supervise("test", &policy, |_| {}, || flaky.run()).unwrap(),
+            11
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_max() {
+        let policy = Policy {
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+            ..policy(10)
+        };
+        let waits: Vec<_> = (1..=5).map(|n| backoff(&policy, n).as_secs()).collect();
+        assert_eq!(waits, [1, 2, 4, 5, 5]);
+    }
+
+    #[test]
+    fn panic_messages_are_read_from_either_payload() {
+        let payload = panic::catch_unwind(|| panic!("static")).unwrap_err();
+        assert_eq!(message(payload.as_ref()), "static");
+        let payload = panic::catch_unwind(|| panic!("formatted {}", 1)).unwrap_err();
+        assert_eq!(message(payload.as_ref()), "formatted 1");
+        let payload = panic::catch_unwind(|| panic::panic_any(7)).unwrap_err();
+        assert_eq!(message(payload.as_ref()), "(no message)");
+    }
+}
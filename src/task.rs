@@ -0,0 +1,1006 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, SecondsFormat};
+
+use crate::{
+    config::Config,
+    edits::{self, OnConflict, Outcome},
+    error::OrchError,
+    fmt, frontmatter, session,
+    warn::{self, Code},
+};
+
+pub struct Task {
+    pub name: String,
+    /// Path relative to the tasks dir without `.md`, e.g. `backend/auth`.
+    pub label: String,
+    pub content: String,
+    pub created: DateTime<Local>,
+    /// `created` comes from the file's mtime rather than frontmatter.
+    pub created_approx: bool,
+    /// File mtime, capped at now.
+    pub modified: DateTime<Local>,
+    /// Why the file couldn't be read; `content` is empty then.
+    pub unreadable: Option<String>,
+    /// What's ambiguous about the file, see [`warnings`].
+    pub warnings: Vec<String>,
+}
+
+/// A task file under `done/`.
+pub struct Archived {
+    pub name: String,
+    pub path: PathBuf,
+    pub archived: DateTime<Local>,
+}
+
+/// Task files under `dir`, including project subfolders. Hidden directories
+/// (`.inbox`) and `done/` are skipped. Symlinked files are listed under their
+/// link path when `follow_symlinks` is set and resolve to a regular file;
+/// symlinked directories are never descended. Other `.md` entries that aren't
+/// regular files (FIFOs, sockets) are listed so they can be reported as
+/// unreadable, but are never opened. Root prompt files
+/// (`root_prompts.file`) aren't tasks.
+pub fn files(dir: &Path, config: &Config) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !name.starts_with('.') && name != "done" {
+                files.extend(self::files(&path, config));
+            }
+            continue;
+        }
+        if path.extension().is_none_or(|ext| ext != "md")
+            || entry.file_name().to_string_lossy() == config.root_prompts.file
+        {
+            continue;
+        }
+        let listed = if file_type.is_symlink() {
+            config.follow_symlinks && fs::canonicalize(&path).is_ok_and(|p| p.is_file())
+        } else {
+            true
+        };
+        if listed {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Dirs outside `dir` that symlinked tasks resolve into, each once.
+pub fn link_dirs(dir: &Path, config: &Config) -> Vec<PathBuf> {
+    let root = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let mut dirs: Vec<PathBuf> = files(dir, config)
+        .iter()
+        .filter(|p| p.is_symlink())
+        .filter_map(|p| Some(fs::canonicalize(p).ok()?.parent()?.to_path_buf()))
+        .filter(|p| !p.starts_with(&root))
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+/// Every task under `dir`, loaded.
+pub fn list(dir: &Path, config: &Config) -> Vec<Task> {
+    files(dir, config)
+        .iter()
+        .map(|path| load(dir, path, config))
+        .collect()
+}
+
+/// Task name -> path relative to `dir`, e.g. `auth` -> `backend/auth.md`.
+pub fn known(dir: &Path, config: &Config) -> HashMap<String, String> {
+    files(dir, config)
+        .iter()
+        .filter_map(|p| {
+            let name = p.file_stem()?.to_string_lossy().into_owned();
+            let rel = p.strip_prefix(dir).ok()?.to_string_lossy().into_owned();
+            Some((name, rel))
+        })
+        .collect()
+}
+
+pub fn find(dir: &Path, name: &str, config: &Config) -> Option<PathBuf> {
+    files(dir, config)
+        .into_iter()
+        .find(|p| p.file_stem().is_some_and(|s| s == name))
+}
+
+/// Most candidates listed when a name is ambiguous.
+const MAX_CANDIDATES: usize = 8;
+
+/// The task `input` names, as (name, path): an exact name or label, else
+/// the only task whose name or label starts with `input`, else the only one
+/// containing it, ignoring case. With `exact_names` (`--exact`) only exact
+/// matches count. An ambiguous `input` is a usage error listing the
+/// candidates.
+pub fn resolve(dir: &Path, input: &str, config: &Config) -> Result<(String, PathBuf), OrchError> {
+    let tasks: Vec<(String, String, PathBuf)> = files(dir, config)
+        .into_iter()
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            Some((name, label(dir, &path), path))
+        })
+        .collect();
+    let found = |(name, _, path): &(String, String, PathBuf)| (name.clone(), path.clone());
+
+    if let Some(task) = tasks.iter().find(|(_, l, _)| l == input) {
+        return Ok(found(task));
+    }
+    let named: Vec<_> = tasks.iter().filter(|(n, _, _)| n == input).collect();
+    if let [task, others @ ..] = named.as_slice() {
+        if !others.is_empty() {
+            let others: Vec<_> = others.iter().map(|(_, l, _)| l.as_str()).collect();
+            crate::warn!(
+                DuplicateTaskName,
+                "'{input}' names {} and {}; using {}",
+                task.1,
+                others.join(", "),
+                task.1
+            );
+        }
+        return Ok(found(task));
+    }
+    if config.exact_names || input.is_empty() {
+        return Err(OrchError::no_task(input));
+    }
+    let query = input.to_lowercase();
+    let prefix: Vec<_> = tasks
+        .iter()
+        .filter(|(n, l, _)| {
+            n.to_lowercase().starts_with(&query) || l.to_lowercase().starts_with(&query)
+        })
+        .collect();
+    let matches = if prefix.is_empty() {
+        tasks
+            .iter()
+            .filter(|(_, l, _)| l.to_lowercase().contains(&query))
+            .collect()
+    } else {
+        prefix
+    };
+    match matches.as_slice() {
+        [] => Err(OrchError::no_task(input)),
+        [task] => Ok(found(task)),
+        many => {
+            let mut labels: Vec<&str> = many.iter().map(|(_, l, _)| l.as_str()).collect();
+            labels.sort_unstable();
+            let more = labels.len().saturating_sub(MAX_CANDIDATES);
+            labels.truncate(MAX_CANDIDATES);
+            let more = if more > 0 {
+                format!(" and {more} more")
+            } else {
+                String::new()
+            };
+            Err(OrchError::Usage(format!(
+                "'{input}' matches {} tasks: {}{more}. Give more of the name.",
+                many.len(),
+                labels.join(", ")
+            )))
+        }
+    }
+}
+
+/// [`resolve`] for changes that make no sense on a closed task: a name
+/// found only in `done/` fails saying it's closed and can't be `what`
+/// (e.g. "pinned").
+pub fn resolve_open(
+    dir: &Path,
+    input: &str,
+    config: &Config,
+    what: &str,
+) -> Result<(String, PathBuf), OrchError> {
+    match resolve(dir, input, config) {
+        Err(OrchError::NotFound(e)) => {
+            Err(match archived(dir).into_iter().find(|a| a.name == input) {
+                Some(a) => OrchError::NotFound(format!(
+                    "Task '{input}' is closed ({}) and can't be {what}.",
+                    a.path.display()
+                )),
+                None => OrchError::NotFound(e),
+            })
+        }
+        found => found,
+    }
+}
+
+/// `path` relative to `dir` without `.md`, e.g. `backend/auth`.
+pub fn label(dir: &Path, path: &Path) -> String {
+    path.strip_prefix(dir)
+        .unwrap_or(path)
+        .with_extension("")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Reads a task for a command that goes on to use or change it: an
+/// unreadable file (see [`read_checked`]) stops the command instead of
+/// passing for an empty task.
+pub fn read(path: &Path, config: &Config) -> Result<String, OrchError> {
+    read_checked(path, config.max_task_bytes)
+        .map_err(|reason| OrchError::Failed(format!("{}: unreadable, {reason}", path.display())))
+}
+
+pub const DEFAULT_MAX_BYTES: u64 = 1024 * 1024;
+
+/// How long a read may take before the file counts as unreadable: a hung
+/// network mount, or a special file swapped in after the type check.
+pub const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Reads a task file through its resolved path, so symlinked tasks parse the
+/// same as regular ones, or says in a few words why not: permission denied,
+/// not a regular file (checked before opening, so FIFOs can't block), over
+/// `max_bytes`, not UTF-8, timed out.
+pub fn read_checked(path: &Path, max_bytes: u64) -> Result<String, String> {
+    read_within(path, max_bytes, READ_TIMEOUT)
+}
+
+fn read_within(path: &Path, max_bytes: u64, timeout: Duration) -> Result<String, String> {
+    let resolved = fs::canonicalize(path).map_err(|e| describe_io(&e))?;
+    let meta = fs::metadata(&resolved).map_err(|e| describe_io(&e))?;
+    if !meta.is_file() {
+        return Err("not a regular file".into());
+    }
+    if meta.len() > max_bytes {
+        return Err(format!("too large ({})", fmt::bytes(meta.len())));
+    }
+    // On a thread, so a read that never returns is left behind rather than
+    // waited on
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(fs::read_to_string(&resolved).map_err(|e| describe_io(&e)));
+    });
+    rx.recv_timeout(timeout)
+        .unwrap_or_else(|_| Err(format!("read timed out after {}", fmt::duration(timeout))))
+}
+
+fn describe_io(e: &std::io::Error) -> String {
+    match e.kind() {
+        std::io::ErrorKind::PermissionDenied => "permission denied".into(),
+        std::io::ErrorKind::NotFound => "not found".into(),
+        std::io::ErrorKind::InvalidData => "not UTF-8 text".into(),
+        _ => e.to_string(),
+    }
+}
+
+/// Tasks that can't be read, as `(label, reason)`.
+pub fn unreadable(dir: &Path, config: &Config) -> Vec<(String, String)> {
+    files(dir, config)
+        .iter()
+        .filter_map(|path| {
+            let reason = read_checked(path, config.max_task_bytes).err()?;
+            Some((label(dir, path), reason))
+        })
+        .collect()
+}
+
+/// Loads a task file. Without a `created:` field the creation time falls back
+/// to the earlier of mtime and now; with `backfill_created` it's then written
+/// back so frontmatter is the source of truth from here on.
+pub fn load(dir: &Path, path: &Path, config: &Config) -> Task {
+    let name = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let label = label(dir, path);
+    let (mut content, unreadable) = match read_checked(path, config.max_task_bytes) {
+        Ok(content) => (content, None),
+        Err(reason) => (String::new(), Some(reason)),
+    };
+    let now = Local::now();
+    let modified = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(DateTime::<Local>::from)
+        .unwrap_or(now)
+        .min(now);
+
+    let stamped = frontmatter::get(&content, "created").and_then(|c| parse_timestamp(&c));
+    let (created, created_approx) = match stamped {
+        Some(created) => (created, false),
+        None => {
+            let created = modified;
+            let stamp = timestamp(created);
+            let backfill = |content: &str| {
+                frontmatter::get(content, "created")
+                    .is_none()
+                    .then(|| frontmatter::set(content, "created", Some(&stamp)))
+            };
+            let written = (config.backfill_created && !config.read_only && unreadable.is_none())
+                .then(|| edits::edit(dir, config, path, OnConflict::Reapply, backfill));
+            match written {
+                Some(Ok(Outcome::Written(updated))) => {
+                    content = updated;
+                    (created, false)
+                }
+                _ => (created, true),
+            }
+        }
+    };
+
+    if let Some(reason) = &unreadable {
+        crate::warn!(UnreadableTask, "{label}.md: {reason}");
+    }
+    let ambiguities = ambiguities(&content);
+    for (code, warning) in &ambiguities {
+        warn::emit(*code, format!("{label}.md: {warning}"));
+    }
+    Task {
+        name,
+        label,
+        warnings: ambiguities.into_iter().map(|(_, w)| w).collect(),
+        content,
+        created,
+        created_approx,
+        modified,
+        unreadable,
+    }
+}
+
+/// Tasks closed into `done/`, with when they were archived (from the
+/// `YYYYmmdd-HHMMSS-` prefix `orch close` adds, else the file's mtime).
+pub fn archived(dir: &Path) -> Vec<Archived> {
+    let Ok(entries) = fs::read_dir(dir.join("done")) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "md") {
+                return None;
+            }
+            let stem = path.file_stem()?.to_string_lossy().into_owned();
+            let stamped = stem.get(..15).and_then(|stamp| {
+                NaiveDateTime::parse_from_str(stamp, "%Y%m%d-%H%M%S")
+                    .ok()?
+                    .and_local_timezone(Local)
+                    .single()
+            });
+            let (name, archived) = match stamped {
+                Some(t) if stem.as_bytes().get(15) == Some(&b'-') => (stem[16..].to_string(), t),
+                _ => (stem, entry.metadata().ok()?.modified().ok()?.into()),
+            };
+            Some(Archived {
+                name,
+                path,
+                archived,
+            })
+        })
+        .collect()
+}
+
+/// Task names become file names, tmux session names and parts of prompts,
+/// so keep them to one plain path component without control characters.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("task name is empty".into());
+    }
+    if name.chars().any(char::is_control) {
+        return Err(format!("task name {name:?} contains control characters"));
+    }
+    if name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!(
+            "task name '{name}' can't contain slashes or start with '.'"
+        ));
+    }
+    Ok(())
+}
+
+/// A project folder to move tasks into: relative path components under the
+/// tasks dir, none of them `..` or hidden (`.orch`, `.inbox`), and not the
+/// `done/` archive. `.` is the top level.
+pub fn validate_project(project: &str) -> Result<(), String> {
+    if project == "." {
+        return Ok(());
+    }
+    if project.is_empty() || project.starts_with('/') || project.contains('\\') {
+        return Err(format!("project '{project}' must be a relative path"));
+    }
+    if project.chars().any(char::is_control) {
+        return Err(format!("project {project:?} contains control characters"));
+    }
+    let mut parts = project.trim_end_matches('/').split('/');
+    if parts.clone().any(|p| p.is_empty() || p.starts_with('.')) {
+        return Err(format!(
+            "project '{project}' can't contain '..', '.' or hidden folders"
+        ));
+    }
+    if parts.next() == Some("done") {
+        return Err("done/ is the archive; use orch close".into());
+    }
+    Ok(())
+}
+
+/// Fails if `name` can't be a new task: it isn't a valid file name, it
+/// would get the `orch console` session, or a task has it already.
+pub fn check_new(dir: &Path, config: &Config, name: &str) -> Result<(), OrchError> {
+    validate_name(name).map_err(OrchError::Usage)?;
+    if session::name(config, name) == session::CONSOLE {
+        return Err(OrchError::Usage(format!(
+            "'{name}' would get the session {}, which `orch console` uses.",
+            session::CONSOLE
+        )));
+    }
+    if let Some(existing) = find(dir, name, config) {
+        return Err(OrchError::Failed(format!(
+            "{} already exists.",
+            existing.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Writes a new task file at the tasks-dir root, stamped with `created:`.
+pub fn create(dir: &Path, name: &str, body: &str) -> std::io::Result<PathBuf> {
+    validate_name(name).map_err(std::io::Error::other)?;
+    let content = frontmatter::set(
+        &format!("{}\n", body.trim_end()),
+        "created",
+        Some(&timestamp(Local::now())),
+    );
+    let path = dir.join(format!("{name}.md"));
+    fs::create_dir_all(dir)?;
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+/// A file-name-safe slug of the first sentence of `text`, at most ~40 chars.
+pub fn slug(text: &str) -> String {
+    let sentence = text
+        .split(['.', '!', '?', '\n'])
+        .find(|s| !s.trim().is_empty())
+        .unwrap_or("");
+    let mut slug = String::new();
+    for word in sentence
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        if !slug.is_empty() && slug.len() + word.len() >= 40 {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_lowercase());
+    }
+    slug
+}
+
+/// Replaces the file via a temp file + rename so readers never see a partial
+/// write. Symlinks are resolved first so the link itself survives.
+pub fn write_atomic(path: &Path, content: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let tmp = path.with_file_name(format!(
+        ".{}.tmp",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, &path)
+}
+
+/// RFC 3339 at second precision, the format of frontmatter timestamps.
+pub fn timestamp(t: DateTime<Local>) -> String {
+    t.to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+pub fn parse_timestamp(s: &str) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|t| t.with_timezone(&Local))
+}
+
+/// A timestamp at the start of a status entry, e.g.
+/// `- 2026-03-02 14:05: pushed fix` or `- [2026-03-02T14:05:00Z] ...`.
+pub fn status_time(line: &str) -> Option<DateTime<Local>> {
+    status_entry(line).map(|entry| entry.0)
+}
+
+/// A status entry's timestamp, whether it was only a date (legacy
+/// `- 2026-03-02 pushed fix` lines, taken as midnight), and the text after it.
+pub fn status_entry(line: &str) -> Option<(DateTime<Local>, bool, &str)> {
+    let line = line
+        .trim()
+        .trim_start_matches(['-', '*', ' ', '['])
+        .trim_start();
+    let token = line.split([' ', ']', ',']).next()?;
+    let (time, date_only, rest) = if let Some(t) = parse_timestamp(token.trim_end_matches(':')) {
+        (t, false, &line[token.len()..])
+    } else if let Some(naive) = line
+        .get(..16)
+        .and_then(|s| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").ok())
+    {
+        (
+            naive.and_local_timezone(Local).single()?,
+            false,
+            &line[16..],
+        )
+    } else {
+        let date = NaiveDate::parse_from_str(line.get(..10)?, "%Y-%m-%d").ok()?;
+        let naive = date.and_hms_opt(0, 0, 0)?;
+        (naive.and_local_timezone(Local).single()?, true, &line[10..])
+    };
+    let rest = rest.trim_start_matches([']', ':', ',', ' ']).trim_end();
+    Some((time, date_only, rest))
+}
+
+/// The newest timestamped entry under `## Status`.
+pub fn latest_status(content: &str) -> Option<DateTime<Local>> {
+    section(content, "## Status")
+        .into_iter()
+        .filter_map(status_time)
+        .max()
+}
+
+/// `status:` values that say a worker is on the task.
+const RUNNING_STATUSES: &[&str] = &["running", "active", "in progress", "in-progress", "working"];
+
+/// Whether the task's `status:` field says a worker is on it.
+pub fn claims_running(content: &str) -> bool {
+    frontmatter::get(content, "status")
+        .is_some_and(|s| RUNNING_STATUSES.contains(&s.to_lowercase().as_str()))
+}
+
+/// Where a task stands on a kanban board, from its `status:` field.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Progress {
+    /// No `status:` yet, or one like `todo`.
+    New,
+    Blocked,
+    InProgress,
+    /// A `status:` orch doesn't know, as written.
+    Other(String),
+    Done,
+}
+
+impl Progress {
+    pub fn label(&self) -> &str {
+        match self {
+            Progress::New => "new",
+            Progress::Blocked => "blocked",
+            Progress::InProgress => "in progress",
+            Progress::Other(_) => "other",
+            Progress::Done => "done",
+        }
+    }
+}
+
+/// The task's [`Progress`], matching `status:` ignoring case.
+pub fn progress(content: &str) -> Progress {
+    let Some(raw) = frontmatter::get(content, "status").filter(|s| !s.trim().is_empty()) else {
+        return Progress::New;
+    };
+    let status = raw.trim().to_lowercase();
+    match status.as_str() {
+        "new" | "todo" | "open" | "pending" | "queued" | "unstarted" => Progress::New,
+        "blocked" | "waiting" | "stuck" | "needs input" | "needs-input" => Progress::Blocked,
+        "review" | "in review" | "in-review" => Progress::InProgress,
+        s if RUNNING_STATUSES.contains(&s) => Progress::InProgress,
+        "done" | "complete" | "completed" | "closed" | "finished" | "merged" => Progress::Done,
+        _ => Progress::Other(raw.trim().to_string()),
+    }
+}
+
+/// Whether the task is parked with `hold: true`: the orchestrator leaves it
+/// alone until `orch unhold`.
+pub fn held(content: &str) -> bool {
+    frontmatter::get(content, "hold").is_some_and(|v| v == "true")
+}
+
+/// `pinned: true`, set by `orch pin`: listed before everything else.
+pub fn pinned(content: &str) -> bool {
+    frontmatter::get(content, "pinned").is_some_and(|v| v == "true")
+}
+
+/// When a `snoozed_until:` still in the future ends.
+pub fn snoozed_until(content: &str) -> Option<DateTime<Local>> {
+    frontmatter::get(content, "snoozed_until")
+        .and_then(|t| parse_timestamp(&t))
+        .filter(|t| *t > Local::now())
+}
+
+/// A task's `start_after:` (set by `orch schedule`), past or not.
+pub fn start_time(content: &str) -> Option<DateTime<Local>> {
+    frontmatter::get(content, "start_after").and_then(|t| parse_timestamp(&t))
+}
+
+/// When a `start_after:` still in the future arrives; until then no worker
+/// should be started for the task.
+pub fn start_after(content: &str) -> Option<DateTime<Local>> {
+    start_time(content).filter(|t| *t > Local::now())
+}
+
+/// The worker session recorded in a task file, else the `session_format`
+/// default for the task at `label`.
+pub fn session(config: &Config, label: &str, content: &str) -> String {
+    content
+        .lines()
+        .find_map(|l| {
+            l.trim()
+                .strip_prefix("session:")
+                .map(|s| s.trim().to_string())
+        })
+        .unwrap_or_else(|| session::name(config, label))
+}
+
+/// `content` with its `session:` line pointing at `session`, or with `None`
+/// removed. Returns None if there's no such line.
+pub fn set_session(content: &str, session: Option<&str>) -> Option<String> {
+    let mut found = false;
+    let lines: Vec<_> = content
+        .split_inclusive('\n')
+        .map(|line| {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            if found || !line.trim().starts_with("session:") {
+                return line.to_string();
+            }
+            found = true;
+            let Some(session) = session else {
+                return String::new();
+            };
+            let newline = if line.ends_with('\n') { "\n" } else { "" };
+            format!("{indent}session: {session}{newline}")
+        })
+        .collect();
+    found.then(|| lines.concat())
+}
+
+/// Lines between `heading` and the next `## ` (or EOF), excluding blanks.
+/// If the heading appears more than once, the last one wins.
+pub fn section<'a>(content: &'a str, heading: &str) -> Vec<&'a str> {
+    let lines: Vec<_> = content.lines().collect();
+    let Some(start) = lines.iter().rposition(|l| l.trim().starts_with(heading)) else {
+        return Vec::new();
+    };
+    lines[start + 1..]
+        .iter()
+        .take_while(|l| !l.trim().starts_with("## "))
+        .filter(|l| !l.trim().is_empty())
+        .copied()
+        .collect()
+}
+
+/// Lines of `content` starting a `## Status` section.
+fn status_headings(lines: &[&str]) -> Vec<usize> {
+    (0..lines.len())
+        .filter(|&i| lines[i].trim().starts_with("## Status"))
+        .collect()
+}
+
+/// Things a worker left ambiguous: repeated `## Status` sections (only the
+/// last is read) and repeated frontmatter keys (only the first is).
+pub fn warnings(content: &str) -> Vec<String> {
+    ambiguities(content).into_iter().map(|(_, w)| w).collect()
+}
+
+/// [`warnings`] with their codes.
+fn ambiguities(content: &str) -> Vec<(Code, String)> {
+    let mut warnings = Vec::new();
+    let sections = status_headings(&content.lines().collect::<Vec<_>>()).len();
+    if sections > 1 {
+        warnings.push((
+            Code::DuplicateStatusSection,
+            format!("{sections} ## Status sections, only the last is read"),
+        ));
+    }
+    for key in frontmatter::duplicate_keys(content) {
+        warnings.push((
+            Code::DuplicateFrontmatterKey,
+            format!("frontmatter key '{key}' repeated, only the first is read"),
+        ));
+    }
+    warnings
+}
+
+/// `content` with all its `## Status` sections merged into one where the
+/// last was, entries in time order (undated ones stay after the entry
+/// before them). None if there's at most one section.
+pub fn merge_status(content: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let headings = status_headings(&lines);
+    let &last = headings.last()?;
+    if headings.len() < 2 {
+        return None;
+    }
+    let end_of = |start: usize| {
+        lines[start + 1..]
+            .iter()
+            .position(|l| l.trim().starts_with("## "))
+            .map_or(lines.len(), |n| start + 1 + n)
+    };
+
+    let mut entries = Vec::new();
+    let mut previous = None;
+    for &start in &headings {
+        for line in &lines[start + 1..end_of(start)] {
+            if line.trim().is_empty() {
+                continue;
+            }
+            previous = status_time(line).or(previous);
+            entries.push((previous, *line));
+        }
+    }
+    // Stable, so same-time and undated entries keep their order
+    entries.sort_by_key(|(time, _)| *time);
+
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if !headings.contains(&i) {
+            out.push(lines[i].to_string());
+            i += 1;
+            continue;
+        }
+        if i == last {
+            out.push(lines[i].to_string());
+            out.push(String::new());
+            out.extend(entries.iter().map(|(_, l)| l.to_string()));
+        }
+        let end = end_of(i);
+        if i == last && end < lines.len() {
+            out.push(String::new());
+        }
+        i = end;
+    }
+    let mut merged = out.join("\n");
+    if content.ends_with('\n') {
+        merged.push('\n');
+    }
+    Some(merged)
+}
+
+/// `content` with `text` added as a dated entry at the end of its
+/// `## Instructions` section, which is created before `## Status` (or at
+/// the end) if there isn't one.
+pub fn add_instruction(content: &str, at: DateTime<Local>, text: &str) -> String {
+    add_entry(content, "## Instructions", Some("## Status"), at, text)
+}
+
+/// `content` with its `priority:` raised by one, and the new priority.
+pub fn bump_priority(content: &str) -> (String, i64) {
+    let priority = frontmatter::get(content, "priority")
+        .and_then(|p| p.parse::<i64>().ok())
+        .unwrap_or(0)
+        + 1;
+    let content = frontmatter::set(content, "priority", Some(&priority.to_string()));
+    (content, priority)
+}
+
+/// `content` with `text` added as a dated entry at the end of its
+/// `## Review` section, which is created before `## Status` (or at the end)
+/// if there isn't one.
+pub fn add_review(content: &str, at: DateTime<Local>, text: &str) -> String {
+    add_entry(content, "## Review", Some("## Status"), at, text)
+}
+
+/// `content` with `text` added as a dated entry at the end of its
+/// `## Status` section, which is created at the end if there isn't one.
+pub fn add_status(content: &str, at: DateTime<Local>, text: &str) -> String {
+    add_entry(content, "## Status", None, at, text)
+}
+
+/// A dated entry at the end of the last `heading` section, created before
+/// the first `before` section (or at the end) if there isn't one.
+fn add_entry(
+    content: &str,
+    heading: &str,
+    before: Option<&str>,
+    at: DateTime<Local>,
+    text: &str,
+) -> String {
+    let mut entry = Vec::new();
+    for (i, line) in text.trim().lines().enumerate() {
+        entry.push(match i {
+            0 => format!("- {}: {line}", at.format("%Y-%m-%d %H:%M")),
+            _ if line.trim().is_empty() => String::new(),
+            _ => format!("  {line}"),
+        });
+    }
+
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let is = |l: &String, h: &str| l.trim().starts_with(h);
+    match lines.iter().rposition(|l| is(l, heading)) {
+        Some(start) => {
+            let end = lines[start + 1..]
+                .iter()
+                .position(|l| is(l, "## "))
+                .map_or(lines.len(), |n| start + 1 + n);
+            // After the last entry, not the blank lines before the next section
+            let mut at = end;
+            while at > start + 1 && lines[at - 1].trim().is_empty() {
+                at -= 1;
+            }
+            if at == start + 1 {
+                entry.insert(0, String::new());
+            }
+            lines.splice(at..at, entry);
+        }
+        None => {
+            let at = before
+                .and_then(|before| lines.iter().position(|l| is(l, before)))
+                .unwrap_or(lines.len());
+            let mut section = vec![heading.to_string(), String::new()];
+            section.extend(entry);
+            section.push(String::new());
+            if at > 0 && !lines[at - 1].trim().is_empty() {
+                section.insert(0, String::new());
+            }
+            if at == lines.len() {
+                section.pop();
+            }
+            lines.splice(at..at, section);
+        }
+    }
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_dirs_are_the_targets_outside_the_tasks_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (dir, notes) = (tmp.path().join("tasks"), tmp.path().join("notes"));
+        fs::create_dir_all(dir.join("proj")).unwrap();
+        fs::create_dir_all(&notes).unwrap();
+        for name in ["a", "b"] {
+            fs::write(notes.join(format!("{name}.md")), "# T\n").unwrap();
+            std::os::unix::fs::symlink(
+                notes.join(format!("{name}.md")),
+                dir.join(format!("{name}.md")),
+            )
+            .unwrap();
+        }
+        fs::write(dir.join("proj/c.md"), "# C\n").unwrap();
+        std::os::unix::fs::symlink(dir.join("proj/c.md"), dir.join("c.md")).unwrap();
+
+        let config = Config::default();
+        let notes = fs::canonicalize(&notes).unwrap();
+        assert_eq!(link_dirs(&dir, &config), [notes]);
+        let config = Config {
+            follow_symlinks: false,
+            ..Config::default()
+        };
+        assert!(link_dirs(&dir, &config).is_empty());
+    }
+
+    #[test]
+    fn two_status_sections_merge_in_time_order() {
+        let content = "# T\n\n## Status\n- 2026-10-15 09:30 second\n- an undated note\n\n\
+                       ## Status\n- 2026-10-15 09:12 first\n";
+        assert_eq!(
+            warnings(content),
+            ["2 ## Status sections, only the last is read"]
+        );
+        let merged = merge_status(content).unwrap();
+        assert_eq!(
+            merged,
+            "# T\n\n## Status\n\n- 2026-10-15 09:12 first\n- 2026-10-15 09:30 second\n\
+             - an undated note\n"
+        );
+        assert!(warnings(&merged).is_empty());
+        assert_eq!(merge_status(&merged), None);
+    }
+
+    #[test]
+    fn status_sections_between_other_headings_merge_into_the_last() {
+        let content = "# T\n\n## Status\n- 2026-10-15 10:00 c\n\n## Context\nSome context.\n\n\
+                       ## Status\n- 2026-10-15 09:00 a\n\n## Next\n- ship it\n\n\
+                       ## Status\n- 2026-10-15 09:30 b\n";
+        assert_eq!(
+            warnings(content),
+            ["3 ## Status sections, only the last is read"]
+        );
+        let merged = merge_status(content).unwrap();
+        assert_eq!(
+            merged,
+            "# T\n\n## Context\nSome context.\n\n## Next\n- ship it\n\n## Status\n\n\
+             - 2026-10-15 09:00 a\n- 2026-10-15 09:30 b\n- 2026-10-15 10:00 c\n"
+        );
+        assert!(warnings(&merged).is_empty());
+    }
+
+    #[test]
+    fn a_merged_section_keeps_a_blank_line_before_the_next_heading() {
+        let content = "## Status\n- 2026-10-15 09:30 b\n\n## Status\n- 2026-10-15 09:00 a\n\
+                       ## Next\n- ship it\n";
+        assert_eq!(
+            merge_status(content).unwrap(),
+            "## Status\n\n- 2026-10-15 09:00 a\n- 2026-10-15 09:30 b\n\n## Next\n- ship it\n"
+        );
+    }
+
+    #[test]
+    fn names_with_control_characters_are_rejected() {
+        for name in ["two\nlines", "tab\there", "esc\x1b[31m", "nul\0", "cr\r"] {
+            let err = validate_name(name).unwrap_err();
+            assert!(err.contains("control characters"), "{name:?}: {err}");
+        }
+    }
+
+    #[test]
+    fn names_that_would_leave_the_tasks_dir_are_rejected() {
+        for name in ["", "  ", "a/b", "..", ".hidden", "../up", "a\\b"] {
+            assert!(validate_name(name).is_err(), "{name:?}");
+        }
+    }
+
+    #[test]
+    fn shell_syntax_in_a_name_stays_literal() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["foo; rm -rf ~", "$(touch pwned)", "`id`", "it's \"quoted\""] {
+            validate_name(name).unwrap();
+            let path = create(dir.path(), name, "Body.").unwrap();
+            assert_eq!(path, dir.path().join(format!("{name}.md")));
+            assert!(fs::read_to_string(&path).unwrap().ends_with("Body.\n"));
+        }
+        // Nothing ran: the tasks dir holds the four files and nothing else
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 4);
+        assert!(!std::env::temp_dir().join("pwned").exists());
+    }
+
+    #[test]
+    fn an_oversized_file_is_unreadable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("big.md");
+        fs::write(&path, "x".repeat(2048)).unwrap();
+        assert_eq!(
+            read_checked(&path, 1024).unwrap_err(),
+            "too large (2.0 KiB)"
+        );
+        assert_eq!(read_checked(&path, 2048).unwrap().len(), 2048);
+    }
+
+    #[test]
+    fn a_file_without_read_permission_is_unreadable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("locked.md");
+        fs::write(&path, "# Locked\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+        // Root reads it anyway
+        if fs::read(&path).is_ok() {
+            eprintln!("skipped: mode 000 doesn't stop this user reading");
+            return;
+        }
+        assert_eq!(read_checked(&path, 1024).unwrap_err(), "permission denied");
+        let config = Config::default();
+        let err = read(&path, &config).unwrap_err().to_string();
+        assert!(
+            err.ends_with("locked.md: unreadable, permission denied"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn a_fifo_is_refused_without_blocking() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pipe.md");
+        let made = std::process::Command::new("mkfifo").arg(&path).status();
+        if !made.is_ok_and(|s| s.success()) {
+            eprintln!("skipped: no mkfifo");
+            return;
+        }
+        assert_eq!(read_checked(&path, 1024).unwrap_err(), "not a regular file");
+    }
+}
@@ -0,0 +1,90 @@
+//! The daemon's messages to the orchestrator, from the `[messages]` config
+//! table. Templates are plain text with `{{name}}` placeholders from
+//! [`VARS`]; nothing else is special.
+
+use std::path::Path;
+
+use crate::config::Config;
+
+/// Every placeholder a template may use. Which ones have a value depends on
+/// the message; see the `[messages]` fields in [`crate::config`].
+pub const VARS: [&str; 6] = [
+    "tasks_dir",
+    "task",
+    "session",
+    "changed_files",
+    "snapshot",
+    "asleep",
+];
+
+pub const SCAN: &str = "\
+    [scan] Scan {{tasks_dir}} and tmux sessions. For any unstarted task without a worker, \
+    spin up an interactive tmux worker session. Spawn task-checker sub-agents for active workers. \
+    Report what you did.";
+
+pub const WAKE: &str = "\
+    [wake] The machine was asleep for about {{asleep}}; nothing was watched meanwhile.\n\
+    Worker sessions that disappeared: {{session}}\n\
+    Files changed: {{changed_files}}";
+
+pub const WORKER_EXIT: &str = "[worker-missing] {{task}}.md: {{session}} isn't running";
+
+/// The placeholder names in `template`, in order, repeats included.
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    template.split("{{").skip(1).filter_map(|rest| {
+        let (name, _) = rest.split_once("}}")?;
+        Some(name.trim())
+    })
+}
+
+/// Fails naming the first placeholder that isn't one of [`VARS`].
+pub fn check(template: &str) -> Result<(), String> {
+    match placeholders(template).find(|name| !VARS.contains(name)) {
+        Some(name) => Err(format!(
+            "unknown placeholder {{{{{name}}}}} (known: {})",
+            VARS.join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+/// `template` with each placeholder replaced by its value in `vars`. One
+/// without a value renders empty, with a warning naming the template
+/// (`what`).
+pub fn render(what: &str, template: &str, vars: &[(&str, &str)]) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let name = rest[start + 2..start + 2 + len].trim();
+        match vars.iter().find(|(n, _)| *n == name) {
+            Some((_, value)) => out.push_str(value),
+            None => crate::warn!(
+                TemplateValue,
+                "messages.{what}: {{{{{name}}}}} has no value here, left empty"
+            ),
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The full-scan message.
+pub fn scan(config: &Config, dir: &Path) -> String {
+    let dir = dir.display().to_string();
+    render("scan", &config.messages.scan, &[("tasks_dir", &dir)])
+}
+
+/// The hourly scan's message.
+pub fn periodic(config: &Config, dir: &Path) -> String {
+    let dir = dir.display().to_string();
+    render(
+        "periodic",
+        &config.messages.periodic,
+        &[("tasks_dir", &dir)],
+    )
+}
@@ -0,0 +1,440 @@
+//! Every tmux call goes through here, as an argv (no shell). Where a shell
+//! string is unavoidable, a command for tmux to run in a new pane or to pipe
+//! a pane into, it's built with [`quote`].
+
+use std::{
+    collections::HashSet,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use chrono::{DateTime, Local};
+
+/// Quotes `s` as a single POSIX shell word.
+pub fn quote(s: &str) -> String {
+    if !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:@%+=,".contains(c))
+    {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// `session` as an exact target: a bare name is also matched as a prefix or
+/// pattern, so a missing `task-a` would find `task-ab`.
+pub fn exact(session: &str) -> String {
+    format!("={session}")
+}
+
+/// The active pane of `session`'s current window, matched as [`exact`]ly:
+/// commands that take a pane don't accept a bare `=name`.
+pub fn pane(session: &str) -> String {
+    format!("={session}:")
+}
+
+/// Shell command for a read-only client attached to `session` on the server
+/// at `socket`, to run inside another tmux pane.
+pub fn nested_attach(socket: &str, session: &str) -> String {
+    format!(
+        "env -u TMUX tmux -S {} attach-session -r -t {}",
+        quote(socket),
+        quote(&exact(session))
+    )
+}
+
+/// Runs `tmux <args>` attached to the terminal. Returns whether it succeeded.
+pub fn run(args: &[&str]) -> bool {
+    Command::new("tmux")
+        .args(args)
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+pub fn has_session(name: &str) -> bool {
+    Command::new("tmux")
+        .args(["has-session", "-t", &exact(name)])
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// A running session, as `tmux list-sessions` reports it.
+pub struct Session {
+    pub name: String,
+    pub windows: u32,
+    pub created: Option<DateTime<Local>>,
+    pub attached: bool,
+    pub activity: Option<DateTime<Local>>,
+}
+
+/// Tab-separated, with the name last so it can contain anything but a
+/// newline.
+const SESSION_FORMAT: &str = "#{session_windows}\t#{session_created}\t#{session_attached}\t#{session_activity}\t#{session_name}";
+
+fn timestamp(secs: &str) -> Option<DateTime<Local>> {
+    DateTime::from_timestamp(secs.parse().ok()?, 0).map(|t| t.with_timezone(&Local))
+}
+
+fn parse_session(line: &str) -> Option<Session> {
+    let mut fields = line.splitn(5, '\t');
+    let windows = fields.next()?.parse().ok()?;
+    let created = timestamp(fields.next()?);
+    let attached = fields.next()?.parse::<u32>().is_ok_and(|n| n > 0);
+    let activity = timestamp(fields.next()?);
+    let name = fields.next()?.to_string();
+    Some(Session {
+        name,
+        windows,
+        created,
+        attached,
+        activity,
+    })
+}
+
+/// All running sessions, in one tmux call; none if there's no server.
+pub fn list() -> Vec<Session> {
+    Command::new("tmux")
+        .args(["list-sessions", "-F", SESSION_FORMAT])
+        .stderr(Stdio::null())
+        .output()
+        .map(|out| {
+            String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .filter_map(parse_session)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Names of all running sessions.
+pub fn sessions() -> HashSet<String> {
+    list().into_iter().map(|s| s.name).collect()
+}
+
+pub fn capture_pane(session: &str) -> String {
+    Command::new("tmux")
+        .args(["capture-pane", "-p", "-t", &pane(session)])
+        .stderr(Stdio::null())
+        .output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).into_owned())
+        .unwrap_or_default()
+}
+
+/// Like [`capture_pane`], with lines the pane wrapped joined back up.
+pub fn capture_pane_joined(session: &str) -> String {
+    Command::new("tmux")
+        .args(["capture-pane", "-p", "-J", "-t", &pane(session)])
+        .stderr(Stdio::null())
+        .output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).into_owned())
+        .unwrap_or_default()
+}
+
+/// The last `lines` lines of the session's pane, scrollback included, with
+/// wrapped lines joined and trailing blank lines left off.
+pub fn capture_history(session: &str, lines: usize) -> String {
+    let out = Command::new("tmux")
+        .args([
+            "capture-pane",
+            "-p",
+            "-J",
+            "-S",
+            &format!("-{lines}"),
+            "-t",
+            &pane(session),
+        ])
+        .stderr(Stdio::null())
+        .output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).into_owned())
+        .unwrap_or_default();
+    let all: Vec<&str> = out.trim_end().lines().collect();
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+pub fn send_enter(session: &str) -> Result<(), String> {
+    let status = Command::new("tmux")
+        .args(["send-keys", "-t", &pane(session), "Enter"])
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("tmux send-keys -t {session} failed"))
+    }
+}
+
+/// Types `text` into `session` literally, then presses Enter.
+pub fn send_line(session: &str, text: &str) -> Result<(), String> {
+    send_literal(session, text)?;
+    send_enter(session)
+}
+
+/// Longest piece of text given to one `send-keys`.
+const CHUNK: usize = 512;
+
+/// Types `text` into a claude session in `CHUNK`-sized pieces, line breaks as
+/// `\` + Enter so only the final Enter submits it.
+pub fn send_text(session: &str, text: &str) -> Result<(), String> {
+    let lines: Vec<_> = text.trim_end().lines().collect();
+    for (i, line) in lines.iter().enumerate() {
+        let mut rest = *line;
+        while !rest.is_empty() {
+            let mut end = rest.len().min(CHUNK);
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            send_literal(session, &rest[..end])?;
+            rest = &rest[end..];
+        }
+        if i + 1 < lines.len() {
+            send_literal(session, "\\")?;
+            send_enter(session)?;
+        }
+    }
+    send_enter(session)
+}
+
+fn send_literal(session: &str, text: &str) -> Result<(), String> {
+    let status = Command::new("tmux")
+        .args(["send-keys", "-t", &pane(session), "-l", text])
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("tmux send-keys -t {session} failed"))
+    }
+}
+
+/// The id of the first pane of `session`'s current window, like `%3`.
+pub fn first_pane(session: &str) -> Option<String> {
+    let out = Command::new("tmux")
+        .args(["list-panes", "-t", &pane(session), "-F", "#{pane_id}"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let panes = String::from_utf8_lossy(&out.stdout);
+    panes.lines().next().map(str::to_string)
+}
+
+/// Has tmux run the tmux `command` when `pane`'s program exits, then close
+/// the pane as it would have anyway. Replaces any earlier such hook.
+pub fn on_pane_died(pane: &str, command: &str) -> Result<(), String> {
+    let hook = format!("{command} ; kill-pane -t {pane}");
+    for args in [
+        ["set-option", "-p", "-t", pane, "remain-on-exit", "on"],
+        ["set-hook", "-p", "-t", pane, "pane-died", &hook],
+    ] {
+        let out = Command::new("tmux")
+            .args(args)
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !out.status.success() {
+            return Err(String::from_utf8_lossy(&out.stderr).trim().to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Starts a detached `session` in `cwd` running the shell command `command`.
+pub fn new_session(session: &str, cwd: &Path, command: &str) -> Result<(), String> {
+    let status = Command::new("tmux")
+        .args(["new-session", "-d", "-s", session, "-c"])
+        .arg(cwd)
+        .arg(command)
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("tmux new-session -s {session} failed"))
+    }
+}
+
+/// Opens a window `name` in `session`, in `cwd`, running the shell command
+/// `command`, without switching anyone's client to it.
+pub fn new_window(session: &str, name: &str, cwd: &Path, command: &str) -> Result<(), String> {
+    let status = Command::new("tmux")
+        .args(["new-window", "-d", "-t", &pane(session), "-n", name, "-c"])
+        .arg(cwd)
+        .arg(command)
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("tmux new-window -t {session} failed"))
+    }
+}
+
+pub fn kill_session(session: &str) -> Result<(), String> {
+    let status = Command::new("tmux")
+        .args(["kill-session", "-t", &exact(session)])
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("tmux kill-session -t {session} failed"))
+    }
+}
+
+pub fn rename_session(session: &str, to: &str) -> Result<(), String> {
+    let status = Command::new("tmux")
+        .args(["rename-session", "-t", &exact(session), to])
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("tmux rename-session -t {session} failed"))
+    }
+}
+
+/// Appends everything the session's active pane prints to `file`, unless
+/// the pane is already piped somewhere.
+pub fn pipe_pane(session: &str, file: &Path) -> Result<(), String> {
+    let command = format!("cat >> {}", quote(&file.to_string_lossy()));
+    let status = Command::new("tmux")
+        .args(["pipe-pane", "-o", "-t", &pane(session), &command])
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("tmux pipe-pane -t {session} failed"))
+    }
+}
+
+/// Stops piping the session's active pane.
+pub fn unpipe_pane(session: &str) -> Result<(), String> {
+    let status = Command::new("tmux")
+        .args(["pipe-pane", "-t", &pane(session)])
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("tmux pipe-pane -t {session} failed"))
+    }
+}
+
+/// The session of the tmux client orch runs in; None outside tmux. Asks
+/// the server `$TMUX` points at, so it's one quick call.
+pub fn current_session() -> Option<String> {
+    std::env::var_os("TMUX").filter(|t| !t.is_empty())?;
+    let out = Command::new("tmux")
+        .args(["display-message", "-p", "#S"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|out| out.status.success())?;
+    let name = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+/// Whether the session's active pane is being piped.
+pub fn pane_piped(session: &str) -> bool {
+    Command::new("tmux")
+        .args([
+            "display-message",
+            "-p",
+            "-t",
+            &pane(session),
+            "#{pane_pipe}",
+        ])
+        .stderr(Stdio::null())
+        .output()
+        .is_ok_and(|out| String::from_utf8_lossy(&out.stdout).trim() == "1")
+}
+
+/// Last time there was activity in the session, per tmux.
+pub fn session_activity(session: &str) -> Option<DateTime<Local>> {
+    let out = Command::new("tmux")
+        .args([
+            "display-message",
+            "-p",
+            "-t",
+            &pane(session),
+            "#{session_activity}",
+        ])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let secs = String::from_utf8_lossy(&out.stdout).trim().parse().ok()?;
+    DateTime::from_timestamp(secs, 0).map(|t| t.with_timezone(&Local))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Names and notes a shell would do something with.
+    const PAYLOADS: &[&str] = &[
+        "task-foo; rm -rf ~",
+        "$(touch pwned)",
+        "`touch pwned`",
+        "it's \"quoted\"",
+        "two\nlines",
+        "back\\slash $HOME * ?",
+        "",
+        "plain-name",
+    ];
+
+    /// The words `sh` splits `command` into, after its quoting is undone.
+    fn words(command: &str) -> Vec<String> {
+        let out = Command::new("sh")
+            .args(["-c", &format!("printf '%s\\0' {command}")])
+            .current_dir(std::env::temp_dir())
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{command}");
+        let mut words: Vec<_> = String::from_utf8(out.stdout)
+            .unwrap()
+            .split('\0')
+            .map(str::to_string)
+            .collect();
+        words.pop();
+        words
+    }
+
+    #[test]
+    fn quoted_payloads_arrive_literally() {
+        for payload in PAYLOADS {
+            assert_eq!(words(&quote(payload)), [*payload], "{}", quote(payload));
+        }
+    }
+
+    #[test]
+    fn quote_leaves_plain_words_alone() {
+        assert_eq!(quote("task-recon"), "task-recon");
+        assert_eq!(quote("/tmp/tmux-0/default"), "/tmp/tmux-0/default");
+        assert_eq!(quote("a b"), "'a b'");
+        assert_eq!(quote(""), "''");
+    }
+
+    #[test]
+    fn nested_attach_passes_names_as_one_word() {
+        for payload in PAYLOADS {
+            let command = nested_attach("/tmp/tmux 0/default", payload);
+            assert_eq!(
+                words(&command),
+                [
+                    "env",
+                    "-u",
+                    "TMUX",
+                    "tmux",
+                    "-S",
+                    "/tmp/tmux 0/default",
+                    "attach-session",
+                    "-r",
+                    "-t",
+                    &format!("={payload}"),
+                ],
+                "{command}"
+            );
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Worker transcripts from `orch record`: a session's active pane piped into
+//! `.orch/transcripts/<task>.log` for as long as the pane lives. Only that
+//! pane is captured, not splits or other windows opened later.
+
+use std::{
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use crate::{config::Config, paths, tmux};
+
+pub const DEFAULT_WARN_BYTES: u64 = 50 * 1024 * 1024;
+
+pub fn path(dir: &Path, name: &str) -> PathBuf {
+    paths::transcripts_dir(dir).join(format!("{name}.log"))
+}
+
+/// Whether the worker's pane is being recorded. Piping ends with the pane,
+/// so a dead session is never recording.
+pub fn recording(session: &str) -> bool {
+    tmux::pane_piped(session)
+}
+
+/// The transcript's size, if it's over `transcript_warn_bytes`.
+pub fn oversized(dir: &Path, config: &Config, name: &str) -> Option<u64> {
+    let size = fs::metadata(path(dir, name)).ok()?.len();
+    (size > config.transcript_warn_bytes).then_some(size)
+}
+
+/// The last `lines` non-blank lines of the task's transcript, with terminal
+/// escape sequences taken out. Only the file's last 64 KiB are read.
+pub fn tail(dir: &Path, name: &str, lines: usize) -> Option<String> {
+    const WINDOW: u64 = 64 * 1024;
+    let mut file = File::open(path(dir, name)).ok()?;
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(WINDOW)))
+        .ok()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    let text = plain(&String::from_utf8_lossy(&bytes));
+    let kept: Vec<_> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    Some(kept[kept.len().saturating_sub(lines)..].join("\n"))
+}
+
+/// `text` without CSI sequences, other escapes and carriage returns.
+fn plain(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => {
+                if chars.next() == Some('[') {
+                    for c in chars.by_ref() {
+                        if c.is_ascii_alphabetic() || c == '~' {
+                            break;
+                        }
+                    }
+                }
+            }
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,147 @@
+//! Warnings: something orch worked around but a person should fix. Every
+//! one goes through [`warn!`](crate::warn) with a stable [`Code`], and
+//! lands on stderr as `warning: W012 duplicate-status-section: ...`, once
+//! per process. A command printing JSON holds them instead ([`hold`]), and
+//! a JSON object it prints gets them as a `warnings` array; whatever isn't
+//! taken that way goes to stderr at exit ([`flush`]). Either way stdout
+//! stays parseable. `--deny-warnings` turns any warning into exit code 1.
+
+use std::sync::{
+    Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+
+use serde::Serialize;
+
+/// What a warning is about. The ids are stable: add new codes, never
+/// renumber or reuse old ones.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Code {
+    ConfigInvalid,
+    ConfigConflict,
+    ConfigTemplate,
+    ConfigSessionFormat,
+    TemplateValue,
+    StateCorrupt,
+    UnreadableTask,
+    DuplicateStatusSection,
+    DuplicateFrontmatterKey,
+    DuplicateTaskName,
+}
+
+impl Code {
+    pub fn id(self) -> &'static str {
+        match self {
+            Code::ConfigInvalid => "W001",
+            Code::ConfigConflict => "W002",
+            Code::ConfigTemplate => "W003",
+            Code::ConfigSessionFormat => "W004",
+            Code::TemplateValue => "W005",
+            Code::StateCorrupt => "W010",
+            Code::UnreadableTask => "W011",
+            Code::DuplicateStatusSection => "W012",
+            Code::DuplicateFrontmatterKey => "W013",
+            Code::DuplicateTaskName => "W014",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Code::ConfigInvalid => "config-invalid",
+            Code::ConfigConflict => "config-conflict",
+            Code::ConfigTemplate => "config-template",
+            Code::ConfigSessionFormat => "config-session-format",
+            Code::TemplateValue => "template-missing-value",
+            Code::StateCorrupt => "state-corrupt",
+            Code::UnreadableTask => "unreadable-task",
+            Code::DuplicateStatusSection => "duplicate-status-section",
+            Code::DuplicateFrontmatterKey => "duplicate-frontmatter-key",
+            Code::DuplicateTaskName => "duplicate-task-name",
+        }
+    }
+}
+
+/// One warning, as JSON output carries it.
+#[derive(Serialize, Clone, PartialEq)]
+pub struct Warning {
+    pub code: &'static str,
+    pub name: &'static str,
+    pub message: String,
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "warning: {} {}: {}", self.code, self.name, self.message)
+    }
+}
+
+/// Every distinct warning so far, and which of them are still held.
+static SEEN: Mutex<Vec<Warning>> = Mutex::new(Vec::new());
+static HELD: Mutex<Vec<Warning>> = Mutex::new(Vec::new());
+static HOLDING: AtomicBool = AtomicBool::new(false);
+
+/// Keeps later warnings off stderr until [`take`] or [`flush`], for a
+/// command whose output is JSON.
+pub fn hold() {
+    HOLDING.store(true, Ordering::Relaxed);
+}
+
+#[doc(hidden)]
+pub fn emit(code: Code, message: String) {
+    let warning = Warning {
+        code: code.id(),
+        name: code.name(),
+        message,
+    };
+    let mut seen = SEEN.lock().unwrap_or_else(|e| e.into_inner());
+    if seen.contains(&warning) {
+        return;
+    }
+    seen.push(warning.clone());
+    if HOLDING.load(Ordering::Relaxed) {
+        HELD.lock().unwrap_or_else(|e| e.into_inner()).push(warning);
+    } else {
+        eprintln!("{warning}");
+    }
+}
+
+/// The held warnings, which the caller now reports.
+pub fn take() -> Vec<Warning> {
+    std::mem::take(&mut *HELD.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// `value` with the held warnings added as `warnings`, if it's an object
+/// without a `warnings` of its own and there are any. Other JSON keeps its
+/// shape, and its warnings stay held for [`flush`].
+pub fn attach(mut value: serde_json::Value) -> serde_json::Value {
+    if let Some(object) = value
+        .as_object_mut()
+        .filter(|object| !object.contains_key("warnings"))
+    {
+        let warnings = take();
+        if !warnings.is_empty() {
+            object.insert("warnings".into(), serde_json::json!(warnings));
+        }
+    }
+    value
+}
+
+/// Prints the held warnings nothing took to stderr.
+pub fn flush() {
+    for warning in take() {
+        eprintln!("{warning}");
+    }
+}
+
+/// How many distinct warnings there were, held or not.
+pub fn count() -> usize {
+    SEEN.lock().unwrap_or_else(|e| e.into_inner()).len()
+}
+
+/// Reports a warning: `warn!(DuplicateStatusSection, "{label}.md: ...")`.
+#[macro_export]
+macro_rules! warn {
+    ($code:ident, $($arg:tt)*) => {
+        $crate::warn::emit($crate::warn::Code::$code, format!($($arg)*))
+    };
+}
@@ -0,0 +1,88 @@
+//! What the CLI tests share: an orch binary run against a tasks dir of its
+//! own, with its own HOME, no config unless a test writes one, no terminal
+//! and an empty tmux socket dir.
+
+// Each test binary uses its own part of this
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+use chrono::{Duration, Local, SecondsFormat};
+use tempfile::TempDir;
+
+/// A tasks dir under its own HOME.
+pub struct Fixture {
+    pub home: TempDir,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        let home = tempfile::tempdir().unwrap();
+        fs::create_dir_all(home.path().join("tasks")).unwrap();
+        Fixture { home }
+    }
+
+    pub fn tasks(&self) -> PathBuf {
+        self.home.path().join("tasks")
+    }
+
+    /// Writes `label.md` as is.
+    pub fn write(&self, label: &str, content: &str) -> &Self {
+        let path = self.tasks().join(format!("{label}.md"));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+        self
+    }
+
+    /// Writes `label.md` with a `created:` of `hours` ago before `body`.
+    pub fn task(&self, label: &str, hours: i64, frontmatter: &str, body: &str) -> &Self {
+        let created =
+            (Local::now() - Duration::hours(hours)).to_rfc3339_opts(SecondsFormat::Secs, false);
+        self.write(
+            label,
+            &format!("---\ncreated: {created}\n{frontmatter}---\n{body}"),
+        )
+    }
+
+    /// `orch args`, ready to run: the fixture's environment and nothing
+    /// else.
+    pub fn command(&self, args: &[&str]) -> Command {
+        let home = self.home.path();
+        let mut command = Command::new(env!("CARGO_BIN_EXE_orch"));
+        command
+            .args(args)
+            .env_clear()
+            .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+            .env("HOME", home)
+            .env("LANG", "C.UTF-8")
+            .env("ORCH_TASKS_DIR", self.tasks())
+            .env("ORCH_CONFIG", home.join("config.toml"))
+            .env("TMUX_TMPDIR", home);
+        command
+    }
+
+    /// Runs `orch args`, however it ends.
+    pub fn output(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+
+    /// `orch args`, which has to succeed; what it printed.
+    pub fn orch(&self, args: &[&str]) -> String {
+        let out = self.output(args);
+        assert!(
+            out.status.success(),
+            "orch {args:?} failed: {}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        String::from_utf8(out.stdout).unwrap()
+    }
+}
+
+/// `lines`, each ending in a newline: the output as it would print.
+pub fn lines(lines: &[&str]) -> String {
+    lines.iter().map(|line| format!("{line}\n")).collect()
+}
@@ -0,0 +1,26 @@
+//! `orch daemon` flags that are checked before it starts.
+
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn poll_interval_has_to_be_a_duration() {
+    let f = Fixture::new();
+    for (interval, error) in [
+        ("soon", "invalid duration 'soon'"),
+        ("0s", "--poll-interval must be more than 0."),
+    ] {
+        let out = f.output(&["daemon", "--poll-interval", interval]);
+        assert_eq!(out.status.code(), Some(2), "{interval}");
+        let stderr = String::from_utf8(out.stderr).unwrap();
+        assert!(stderr.contains(error), "{stderr}");
+    }
+}
+
+#[test]
+fn max_scans_has_to_be_positive() {
+    let f = Fixture::new();
+    let out = f.output(&["daemon", "--max-scans", "0"]);
+    assert_eq!(out.status.code(), Some(2));
+}
@@ -0,0 +1,39 @@
+//! orch processes editing one task file at once take turns on its lock, so
+//! none of their edits is lost.
+
+mod common;
+
+use std::{fs, process::Stdio};
+
+use common::Fixture;
+
+const PROCESSES: usize = 12;
+
+#[test]
+fn concurrent_bumps_all_land() {
+    let f = Fixture::new();
+    f.write("t", "# T\n");
+    let children: Vec<_> = (0..PROCESSES)
+        .map(|_| {
+            f.command(&["bump", "t"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    for child in children {
+        let out = child.wait_with_output().unwrap();
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+    }
+    let content = fs::read_to_string(f.tasks().join("t.md")).unwrap();
+    assert!(
+        content.contains(&format!("priority: {PROCESSES}\n")),
+        "{content}"
+    );
+    assert!(f.tasks().join(".orch/locks/t").exists());
+}
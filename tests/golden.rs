@@ -0,0 +1,251 @@
+//! Golden output of `orch status` and `orch inbox`, run as the binary
+//! against a fixture tasks dir with no tmux server, no config and no
+//! terminal. The status cases were recorded from the original single-file
+//! binary; project folders and the inbox came later, and theirs from the
+//! last binary before the library split. The [`fixture`] cases cover pins,
+//! holds and the compact and porcelain layouts.
+
+mod common;
+
+use std::fs;
+
+use common::{Fixture, lines};
+
+const RECON: &str = "# Recon\n\n## Summary\nSurvey the API.\nStart with auth.\n\n\
+                     ## Status\n- 2026-10-15 09:12 needs input: which API key to use\n";
+
+const AUTH_TESTS: &str = "---\nsession: work-auth\n---\n# Auth tests\n\n\
+                          ## Status\n- 2026-10-15 10:00 question: mock the clock?\n";
+
+#[test]
+fn status() {
+    let f = Fixture::new();
+    f.write("recon", RECON);
+    assert_eq!(
+        f.orch(&["status"]),
+        lines(&[
+            "## Tasks",
+            "",
+            "  recon  [worker: none]",
+            "    Survey the API.",
+            "    Start with auth.",
+            "",
+        ])
+    );
+}
+
+#[test]
+fn status_without_a_summary() {
+    let f = Fixture::new();
+    f.write("auth-tests", "# Auth tests\n\nMock the clock.\n");
+    assert_eq!(
+        f.orch(&["status"]),
+        lines(&[
+            "## Tasks",
+            "",
+            "  auth-tests  [worker: none]",
+            "    Auth tests",
+            "",
+        ])
+    );
+}
+
+#[test]
+fn status_is_the_default_command() {
+    let f = Fixture::new();
+    f.write("recon", RECON);
+    assert_eq!(f.orch(&[]), f.orch(&["status"]));
+}
+
+#[test]
+fn status_without_tasks() {
+    let f = Fixture::new();
+    assert_eq!(
+        f.orch(&["status"]),
+        lines(&["## Tasks", "", "  (no tasks)"])
+    );
+}
+
+#[test]
+fn status_without_a_tasks_dir() {
+    let f = Fixture::new();
+    fs::remove_dir(f.tasks()).unwrap();
+    assert_eq!(
+        f.orch(&["status"]),
+        lines(&["## Tasks", "", "  ~/tasks/ not found"])
+    );
+}
+
+#[test]
+fn status_with_projects() {
+    let f = Fixture::new();
+    f.write("recon", RECON)
+        .write("backend/auth-tests", AUTH_TESTS);
+    assert_eq!(
+        f.orch(&["status"]),
+        lines(&[
+            "## Tasks",
+            "",
+            "  recon  [worker: none]",
+            "    Survey the API.",
+            "    Start with auth.",
+            "",
+            "  backend/auth-tests  [worker: none]",
+            "    Auth tests",
+            "",
+        ])
+    );
+}
+
+#[test]
+fn inbox() {
+    let f = Fixture::new();
+    f.write("recon", RECON)
+        .write("backend/auth-tests", AUTH_TESTS);
+    assert_eq!(
+        f.orch(&["inbox"]),
+        lines(&[
+            "## Inbox",
+            "",
+            "  backend/auth-tests  (0s in inbox)",
+            "    2026-10-15 10:00 question: mock the clock?",
+            "",
+            "  recon  (0s in inbox)",
+            "    2026-10-15 09:12 needs input: which API key to use",
+            "",
+        ])
+    );
+}
+
+#[test]
+fn inbox_empty() {
+    let f = Fixture::new();
+    assert_eq!(
+        f.orch(&["inbox"]),
+        lines(&["## Inbox", "", "  (nothing needs attention)"])
+    );
+}
+
+/// A pinned task, a held one in a project, and one whose status says a
+/// worker is running without a session.
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .task(
+            "recon",
+            3,
+            "priority: high\npinned: true\n",
+            "# Recon\n\n## Summary\nSurvey the API.\n\n## Status\n- 2026-10-15 09:12 needs input: which API key to use\n",
+        )
+        .task(
+            "backend/auth",
+            26,
+            "status: blocked\nhold: true\n",
+            "# Auth\n\n## Summary\nBlocked on the token lifetime decision.\n",
+        )
+        .task(
+            "backend/auth-tests",
+            1,
+            "status: in progress\n",
+            "# Auth tests\n\n## Status\n- 2026-10-15 10:00 question: mock the clock?\n",
+        );
+    fixture
+}
+
+#[test]
+fn status_with_pins_and_holds() {
+    let f = fixture();
+    assert_eq!(
+        f.orch(&["status"]),
+        lines(&[
+            "## Pinned",
+            "",
+            "  recon 📌  [worker: none] [priority: high]",
+            "    Survey the API.",
+            "",
+            "## Tasks",
+            "",
+            "  backend/auth ⏸  [worker: none]",
+            "    Blocked on the token lifetime decision.",
+            "",
+            "  backend/auth-tests  [worker: missing! status says running; `orch jump auth-tests --spawn`]",
+            "    Auth tests",
+            "",
+        ])
+    );
+}
+
+#[test]
+fn status_compact() {
+    let f = fixture();
+    assert_eq!(
+        f.orch(&["status", "--compact"]),
+        lines(&[
+            "## Pinned",
+            "",
+            "  recon 📌            2026-10-15 09:12 needs input: which API key to use  -",
+            "",
+            "## Tasks",
+            "",
+            "  backend/auth ⏸      Blocked on the token lifetime decision.             -",
+            "  backend/auth-tests  2026-10-15 10:00 question: mock the clock?          missing!",
+            "",
+        ])
+    );
+}
+
+#[test]
+fn status_porcelain() {
+    let f = fixture();
+    assert_eq!(
+        f.orch(&["--output-format", "porcelain", "status"]),
+        lines(&[
+            "recon\tnew\tnone\ttask-recon\t2026-10-15 09:12 needs input: which API key to use",
+            "backend/auth\tblocked\tnone\ttask-auth\tBlocked on the token lifetime decision.",
+            "backend/auth-tests\tin progress\tmissing\ttask-auth-tests\t2026-10-15 10:00 question: mock the clock?",
+        ])
+    );
+}
+
+#[test]
+fn inbox_with_pins() {
+    let f = fixture();
+    assert_eq!(
+        f.orch(&["inbox"]),
+        lines(&[
+            "## Inbox",
+            "",
+            "  recon 📌  (0s in inbox)",
+            "    2026-10-15 09:12 needs input: which API key to use",
+            "",
+            "  backend/auth-tests  (0s in inbox)",
+            "    2026-10-15 10:00 question: mock the clock?",
+            "",
+        ])
+    );
+}
+
+#[test]
+fn inbox_compact() {
+    let f = fixture();
+    assert_eq!(
+        f.orch(&["inbox", "--compact"]),
+        lines(&[
+            "  recon 📌            2026-10-15 09:12 needs input: which API key to use",
+            "  backend/auth-tests  2026-10-15 10:00 question: mock the clock?",
+        ])
+    );
+}
+
+#[test]
+fn inbox_all() {
+    let f = fixture();
+    assert_eq!(
+        f.orch(&["--output-format", "porcelain", "inbox", "--all"]),
+        lines(&[
+            "recon\t\t0.0\t2026-10-15 09:12 needs input: which API key to use",
+            "backend/auth\t\t0.0\tBlocked on the token lifetime decision.",
+            "backend/auth-tests\t\t0.0\t2026-10-15 10:00 question: mock the clock?",
+        ])
+    );
+}
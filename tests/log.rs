@@ -0,0 +1,70 @@
+//! `orch log`: one task's log, from the commands that write to it, kept
+//! after the task is closed.
+
+mod common;
+
+use std::fs;
+
+use common::Fixture;
+use serde_json::Value;
+
+#[test]
+fn commands_log_to_their_task_and_close_notes_the_transcript() {
+    let f = Fixture::new();
+    f.task("recon", 3, "", "# Recon\n");
+    f.task("infra", 3, "", "# Infra\n");
+    f.orch(&["hold", "recon"]);
+    f.orch(&["unhold", "recon"]);
+    f.orch(&["-", "--to", "recon", "check staging too"]);
+    f.orch(&["delegate", "recon", "--to", "infra", "Fix the DNS records"]);
+    let transcripts = f.tasks().join(".orch/transcripts");
+    fs::create_dir_all(&transcripts).unwrap();
+    fs::write(transcripts.join("recon.log"), "output\n").unwrap();
+    f.orch(&["close", "recon", "--outcome", "done"]);
+
+    let out = f.orch(&["log", "recon", "--json"]);
+    let events: Vec<String> = out
+        .lines()
+        .map(|line| {
+            let value: Value = serde_json::from_str(line).unwrap();
+            value["event"].as_str().unwrap().to_string()
+        })
+        .collect();
+    assert_eq!(
+        events,
+        ["held", "released", "message", "delegated_to", "closed"],
+        "{out}"
+    );
+    let closed: Value = serde_json::from_str(out.lines().last().unwrap()).unwrap();
+    assert_eq!(closed["outcome"], "done");
+    assert!(
+        closed["transcript"]
+            .as_str()
+            .unwrap()
+            .ends_with(".orch/transcripts/recon.log")
+    );
+
+    let out = f.orch(&["log", "recon"]);
+    assert!(out.contains(" message  check staging too\n"), "{out}");
+    assert!(
+        out.contains(" handoff  to infra: Fix the DNS records\n"),
+        "{out}"
+    );
+    assert!(out.contains("\ntranscript: "), "{out}");
+    assert!(out.trim_end().ends_with("recon.log (7 B)"), "{out}");
+
+    let out = f.orch(&["log", "infra"]);
+    assert!(
+        out.contains(" handoff  from recon: Fix the DNS records"),
+        "{out}"
+    );
+    assert!(!out.contains("transcript"), "{out}");
+}
+
+#[test]
+fn a_task_with_nothing_logged_says_so() {
+    let f = Fixture::new();
+    f.task("recon", 3, "", "# Recon\n");
+    assert_eq!(f.orch(&["log", "recon"]), "(nothing logged for recon)\n");
+    assert!(!f.output(&["log", "nope"]).status.success());
+}
@@ -0,0 +1,24 @@
+//! `orch move`: into a project subfolder, and back to the top level.
+
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn moving_a_task_to_where_it_already_is_says_so() {
+    let f = Fixture::new();
+    f.task("work/recon", 3, "", "# Recon\n");
+    f.task("infra", 3, "", "# Infra\n");
+
+    let out = f.output(&["move", "recon", "work"]);
+    assert!(!out.status.success());
+    let err = String::from_utf8_lossy(&out.stderr);
+    assert!(err.contains("recon is already in work/."), "{err}");
+    assert!(!err.contains("already exists"), "{err}");
+
+    let err = String::from_utf8_lossy(&f.output(&["mv", "infra", "."]).stderr).into_owned();
+    assert!(err.contains("infra is already at the top level."), "{err}");
+
+    f.orch(&["move", "recon", "."]);
+    assert!(f.tasks().join("recon.md").exists());
+}
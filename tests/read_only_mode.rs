@@ -0,0 +1,128 @@
+//! `--read-only`, `ORCH_READ_ONLY=1` and `read_only = true`: read commands
+//! leave every file as it was, and mutating ones are refused.
+
+mod common;
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use common::Fixture;
+
+/// Every file under `dir`, with its content and mtime.
+fn tree(dir: &Path) -> BTreeMap<PathBuf, (Vec<u8>, SystemTime)> {
+    let mut files = BTreeMap::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files.extend(tree(&path));
+        } else {
+            let modified = fs::metadata(&path).unwrap().modified().unwrap();
+            files.insert(path.clone(), (fs::read(&path).unwrap(), modified));
+        }
+    }
+    files
+}
+
+/// Tasks that give read commands reasons to write: one in the inbox (whose
+/// first sighting is recorded) and one with no `created:` (backfilled, as
+/// the config asks).
+fn fixture() -> Fixture {
+    let f = Fixture::new();
+    fs::write(
+        f.home.path().join("config.toml"),
+        "backfill_created = true\n",
+    )
+    .unwrap();
+    f.task(
+        "recon",
+        3,
+        "priority: high\n",
+        "# Recon\n\n## Status\n- 2026-10-15 09:12 needs input: which API key to use\n",
+    );
+    fs::write(f.tasks().join("auth.md"), "# Auth\n\nAdd auth.\n").unwrap();
+    f
+}
+
+const READS: &[&[&str]] = &[
+    &["status"],
+    &["status", "--json"],
+    &["inbox"],
+    &["show", "recon"],
+    &["runs"],
+    &["summary"],
+    &["which"],
+];
+
+#[test]
+fn a_read_only_status_run_leaves_the_state_dir_untouched() {
+    let f = fixture();
+    let before = tree(f.home.path());
+    for args in READS {
+        let mut args = args.to_vec();
+        args.insert(0, "--read-only");
+        f.orch(&args);
+    }
+    assert!(!f.tasks().join(".orch").exists());
+    assert!(tree(f.home.path()) == before);
+
+    // The same runs without --read-only do write, so the above means something
+    f.orch(&["inbox"]);
+    assert!(f.tasks().join(".orch").is_dir());
+    assert!(
+        fs::read_to_string(f.tasks().join("auth.md"))
+            .unwrap()
+            .contains("created:")
+    );
+}
+
+#[test]
+fn the_environment_and_config_turn_it_on_too() {
+    let f = fixture();
+    let before = tree(f.home.path());
+    for args in READS {
+        let out = f.command(args).env("ORCH_READ_ONLY", "1").output().unwrap();
+        assert!(out.status.success(), "orch {args:?}");
+    }
+    assert!(tree(f.home.path()) == before);
+
+    fs::write(
+        f.home.path().join("config.toml"),
+        "backfill_created = true\nread_only = true\n",
+    )
+    .unwrap();
+    let before = tree(f.home.path());
+    for args in READS {
+        f.orch(args);
+    }
+    assert!(tree(f.home.path()) == before);
+}
+
+#[test]
+fn mutating_commands_are_refused() {
+    let f = fixture();
+    let before = tree(f.home.path());
+    for args in [
+        &["new", "x", "Do x."][..],
+        &["-", "close", "recon"],
+        &["close", "recon"],
+        &["hold", "recon"],
+        &["snooze", "recon", "--for", "1h"],
+        &["scan"],
+        &["status", "--refresh-sessions"],
+    ] {
+        let mut args = args.to_vec();
+        args.insert(0, "--read-only");
+        let out = f.output(&args);
+        assert_eq!(out.status.code(), Some(1), "orch {args:?}");
+        let stderr = String::from_utf8(out.stderr).unwrap();
+        assert!(stderr.contains("read-only"), "orch {args:?}: {stderr}");
+    }
+    assert!(tree(f.home.path()) == before);
+}
@@ -0,0 +1,186 @@
+//! Containers: a tasks dir orch can't write to, and no HOME. Read
+//! commands work, writes fail saying what to do, and the ORCH_* variables
+//! stand in for HOME.
+
+mod common;
+
+use std::{fs, os::unix::fs::PermissionsExt, path::Path, process::Output};
+
+use common::Fixture;
+
+fn stderr(out: &Output) -> String {
+    String::from_utf8(out.stderr.clone()).unwrap()
+}
+
+/// Sets the mode of `dir` and everything under it: 0o555 for dirs and
+/// 0o444 for files when `writable` is false, 0o755 and 0o644 when true.
+fn chmod(dir: &Path, writable: bool) {
+    let (dirs, files) = if writable {
+        (0o755, 0o644)
+    } else {
+        (0o555, 0o444)
+    };
+    // Children first on the way down, so a read-only parent doesn't hide them
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            chmod(&path, writable);
+        } else {
+            fs::set_permissions(&path, fs::Permissions::from_mode(files)).unwrap();
+        }
+    }
+    fs::set_permissions(dir, fs::Permissions::from_mode(dirs)).unwrap();
+}
+
+/// A fixture whose tasks dir (state included) is read-only, made writable
+/// again on drop so the tempdir can go.
+struct ReadOnly(Fixture);
+
+impl ReadOnly {
+    fn new() -> Self {
+        let f = Fixture::new();
+        f.task(
+            "recon",
+            3,
+            "priority: high\n",
+            "# Recon\n\n## Status\n- 2026-10-15 09:12 needs input: which API key to use\n",
+        );
+        // State from an earlier run, as a mounted copy would have
+        f.orch(&["status"]);
+        fs::create_dir_all(f.tasks().join(".orch")).unwrap();
+        chmod(&f.tasks(), false);
+        ReadOnly(f)
+    }
+
+    /// Whether the mode bits stop writes here. They don't for root, which
+    /// containers often are; then only the read half of a test means
+    /// anything.
+    fn enforced(&self) -> bool {
+        let probe = self.0.tasks().join("probe");
+        let enforced = fs::write(&probe, "").is_err();
+        let _ = fs::remove_file(probe);
+        enforced
+    }
+}
+
+impl Drop for ReadOnly {
+    fn drop(&mut self) {
+        chmod(&self.0.tasks(), true);
+    }
+}
+
+#[test]
+fn read_commands_work_on_a_read_only_tasks_dir() {
+    let ro = ReadOnly::new();
+    let f = &ro.0;
+    let export = f.home.path().join("todo.txt");
+    let export = export.to_str().unwrap();
+    for args in [
+        &["status"][..],
+        &["status", "--json"],
+        &["inbox"],
+        &["show", "recon"],
+        &["runs"],
+        &["export", "todo-txt", export],
+    ] {
+        let out = f.output(args);
+        assert!(out.status.success(), "orch {args:?}: {}", stderr(&out));
+        assert_eq!(stderr(&out), "", "orch {args:?}");
+    }
+    assert!(fs::read_to_string(export).unwrap().contains("recon"));
+}
+
+#[test]
+fn writes_to_a_read_only_tasks_dir_are_explained() {
+    let ro = ReadOnly::new();
+    if !ro.enforced() {
+        eprintln!("skipped: the mode bits don't stop this user writing");
+        return;
+    }
+    let f = &ro.0;
+    let out = f.output(&["new", "auth", "Add", "auth."]);
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = stderr(&out);
+    assert!(stderr.contains("auth.md: "), "{stderr}");
+    assert!(stderr.contains("ORCH_STATE_DIR"), "{stderr}");
+    assert!(!stderr.contains("panicked"), "{stderr}");
+    assert!(!f.tasks().join("auth.md").exists());
+}
+
+#[test]
+fn a_writable_state_dir_takes_messages_for_read_only_tasks() {
+    let ro = ReadOnly::new();
+    let f = &ro.0;
+    let state = f.home.path().join("state");
+    let out = f
+        .command(&["-", "check", "staging", "too"])
+        .env("ORCH_STATE_DIR", &state)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(fs::read_dir(state.join("inbox")).unwrap().count(), 1);
+}
+
+#[test]
+fn no_home_and_no_tasks_dir_is_an_error() {
+    let f = Fixture::new();
+    let out = f
+        .command(&["status"])
+        .env_remove("HOME")
+        .env_remove("ORCH_TASKS_DIR")
+        .current_dir(f.home.path())
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = stderr(&out);
+    assert!(stderr.contains("HOME isn't set"), "{stderr}");
+    assert!(stderr.contains("Set ORCH_TASKS_DIR"), "{stderr}");
+}
+
+#[test]
+fn no_home_runs_from_the_environment() {
+    let f = Fixture::new();
+    let state = f.home.path().join("state");
+    let orch = |args: &[&str]| {
+        f.command(args)
+            .env_remove("HOME")
+            .env("ORCH_STATE_DIR", &state)
+            .current_dir(f.home.path())
+            .output()
+            .unwrap()
+    };
+    let out = orch(&["new", "auth", "Add", "auth."]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(f.tasks().join("auth.md").is_file());
+
+    let out = orch(&["status", "--output-format", "porcelain"]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(String::from_utf8_lossy(&out.stdout).starts_with("auth\t"));
+
+    // State goes where ORCH_STATE_DIR says, none of it in the tasks dir
+    let out = orch(&["-", "check", "staging", "too"]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(fs::read_dir(state.join("inbox")).unwrap().count(), 1);
+    assert!(!f.tasks().join(".inbox").exists());
+    assert!(!f.tasks().join(".orch").exists());
+
+    let out = orch(&["which"]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    let which = String::from_utf8_lossy(&out.stdout);
+    assert!(which.contains(state.to_str().unwrap()), "{which}");
+}
+
+#[test]
+fn no_home_and_no_config_runs_on_defaults() {
+    let f = Fixture::new();
+    let out = f
+        .command(&["which"])
+        .env_remove("HOME")
+        .env_remove("ORCH_CONFIG")
+        .current_dir(f.home.path())
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let which = String::from_utf8_lossy(&out.stdout);
+    assert!(which.contains("ORCH_CONFIG unset"), "{which}");
+}
@@ -0,0 +1,35 @@
+//! `orch spawn --dry-run`: which tasks would get workers, and in what order.
+
+mod common;
+
+use common::Fixture;
+
+fn fixture() -> Fixture {
+    let f = Fixture::new();
+    f.task("old", 5, "", "# Old\n");
+    f.task("urgent", 1, "priority: 3\n", "# Urgent\n");
+    f.task("finished", 2, "status: done\n", "# Finished\n");
+    f
+}
+
+#[test]
+fn all_unstarted_takes_every_task_without_a_worker() {
+    let out = fixture().orch(&["spawn", "--all-unstarted", "--dry-run"]);
+    assert_eq!(out, common::lines(&["  1. urgent", "  2. old"]));
+}
+
+#[test]
+fn names_take_only_those_tasks_and_say_why_one_is_skipped() {
+    let out = fixture().orch(&["spawn", "old", "finished", "--dry-run"]);
+    assert_eq!(
+        out,
+        common::lines(&["  1. old", "     finished: skipped, done"])
+    );
+}
+
+#[test]
+fn names_and_all_unstarted_conflict() {
+    let out = fixture().output(&["spawn", "old", "--all-unstarted"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert_eq!(fixture().output(&["spawn"]).status.code(), Some(2));
+}
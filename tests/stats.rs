@@ -0,0 +1,39 @@
+//! `orch stats`: creation times from a file's mtime count, marked approx.
+
+mod common;
+
+use common::Fixture;
+use serde_json::Value;
+
+fn fixture() -> Fixture {
+    let f = Fixture::new();
+    f.task("stamped", 3, "", "# Stamped\n");
+    f.write("handwritten", "# Handwritten\n");
+    f
+}
+
+#[test]
+fn mtime_creation_times_are_marked_approx() {
+    let out = fixture().orch(&["stats", "--since", "1d"]);
+    assert_eq!(
+        out.lines().next(),
+        Some("created 2 (1 approx), completed 0, avg time to done -"),
+        "{out}"
+    );
+}
+
+#[test]
+fn json_counts_them_separately() {
+    let out = fixture().orch(&["stats", "--since", "1d", "--json"]);
+    let value: Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(value["created"], 2);
+    assert_eq!(value["created_approx"], 1);
+}
+
+#[test]
+fn no_marker_when_every_task_is_stamped() {
+    let f = Fixture::new();
+    f.task("stamped", 3, "", "# Stamped\n");
+    let out = f.orch(&["stats", "--since", "1d"]);
+    assert!(out.starts_with("created 1, completed 0,"), "{out}");
+}
@@ -0,0 +1,35 @@
+//! Against a real tmux server in the fixture's socket dir: a task's worker
+//! is its session by exact name, never one that merely starts with it.
+
+mod common;
+
+use std::process::Command;
+
+use common::Fixture;
+
+/// `tmux args` on the fixture's server; None without tmux installed.
+fn tmux(f: &Fixture, args: &[&str]) -> Option<bool> {
+    let status = Command::new("tmux")
+        .args(args)
+        .env("TMUX_TMPDIR", f.home.path())
+        .env_remove("TMUX")
+        .status()
+        .ok()?;
+    Some(status.success())
+}
+
+#[test]
+fn a_longer_session_name_is_not_the_worker() {
+    let f = Fixture::new();
+    f.write("a", "# A\n");
+    if tmux(&f, &["new-session", "-d", "-s", "task-ab", "sleep 60"]) != Some(true) {
+        eprintln!("skipped: no tmux to start a session with");
+        return;
+    }
+    let status = f.orch(&["status"]);
+    f.output(&["kill", "a"]);
+    let alive = tmux(&f, &["has-session", "-t", "=task-ab"]);
+    tmux(&f, &["kill-server"]);
+    assert!(status.contains("[worker: none]"), "{status}");
+    assert_eq!(alive, Some(true), "orch kill a killed task-ab");
+}
@@ -0,0 +1,109 @@
+//! Task files orch can't read: status reports them, and commands that would
+//! rewrite them fail instead of writing back an empty task.
+
+mod common;
+
+use std::{fs, os::unix::fs::PermissionsExt, process::Output};
+
+use common::Fixture;
+
+const BIG: &str = "# Big\n\nThis task is longer than the configured cap of 64 bytes,\nby a line.\n";
+
+fn stderr(out: &Output) -> String {
+    String::from_utf8(out.stderr.clone()).unwrap()
+}
+
+/// A fixture with `big.md` over a 64-byte `max_task_bytes`.
+fn fixture() -> Fixture {
+    let f = Fixture::new();
+    f.write("big", BIG);
+    fs::write(f.home.path().join("config.toml"), "max_task_bytes = 64\n").unwrap();
+    f
+}
+
+#[test]
+fn status_reports_an_oversized_task() {
+    let f = fixture();
+    let out = f.orch(&["status"]);
+    assert!(
+        out.contains("  big  [worker: none] [unreadable: too large (75 B)]"),
+        "{out}"
+    );
+}
+
+#[test]
+fn writes_to_an_oversized_task_fail_and_leave_it_alone() {
+    let f = fixture();
+    let path = f.tasks().join("big.md");
+    for args in [
+        &["pin", "big"][..],
+        &["hold", "big"],
+        &["snooze", "big", "--for", "1h"],
+        &["schedule", "big", "--in", "1h"],
+        &["touch", "big"],
+    ] {
+        let out = f.output(args);
+        assert_eq!(out.status.code(), Some(1), "orch {args:?}");
+        assert!(
+            stderr(&out).contains("unreadable, too large"),
+            "orch {args:?}: {}",
+            stderr(&out)
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), BIG, "orch {args:?}");
+    }
+}
+
+#[test]
+fn writes_to_a_task_without_read_permission_fail() {
+    let f = Fixture::new();
+    f.write("locked", "# Locked\n");
+    let path = f.tasks().join("locked.md");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+    if fs::read(&path).is_ok() {
+        eprintln!("skipped: mode 000 doesn't stop this user reading");
+        return;
+    }
+    let out = f.output(&["pin", "locked"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(
+        stderr(&out).contains("unreadable, permission denied"),
+        "{}",
+        stderr(&out)
+    );
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "# Locked\n");
+}
+
+#[test]
+fn closing_an_oversized_task_leaves_it_in_place() {
+    let f = fixture();
+    let path = f.tasks().join("big.md");
+    for args in [&["close", "big"][..], &["close", "big", "--force"]] {
+        let out = f.output(args);
+        assert_eq!(out.status.code(), Some(1), "orch {args:?}");
+        assert!(
+            stderr(&out).contains("unreadable, too large"),
+            "{}",
+            stderr(&out)
+        );
+        assert_eq!(fs::read(&path).unwrap(), BIG.as_bytes(), "orch {args:?}");
+    }
+    assert!(!f.tasks().join("done").exists());
+}
+
+#[test]
+fn closing_a_task_without_read_permission_leaves_it_in_place() {
+    let f = Fixture::new();
+    f.write("locked", "# Locked\n");
+    let path = f.tasks().join("locked.md");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+    if fs::read(&path).is_ok() {
+        eprintln!("skipped: mode 000 doesn't stop this user reading");
+        return;
+    }
+    let out = f.output(&["close", "locked"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(!f.tasks().join("done").exists());
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"# Locked\n");
+}
@@ -0,0 +1,60 @@
+//! `orch validate`: every task file's structure, and `--fix` merging
+//! repeated `## Status` sections.
+
+mod common;
+
+use std::fs;
+
+use common::Fixture;
+
+const RECON: &str = "# Recon\n\n## Status\n- 2026-10-15 10:00 c\n\n## Context\nNotes.\n\n\
+                     ## Status\n- 2026-10-15 09:00 a\n\n## Status\n- 2026-10-15 09:30 b\n";
+
+fn fixture() -> Fixture {
+    let f = Fixture::new();
+    f.write("recon", RECON);
+    f.write("auth", "---\npriority: 1\npriority: 2\n---\n# Auth\n");
+    f.write("ok", "# Ok\n\n## Status\n- 2026-10-15 09:00 fine\n");
+    f
+}
+
+#[test]
+fn problems_are_listed_and_fail() {
+    let f = fixture();
+    let out = f.output(&["validate"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "FAIL  auth.md: frontmatter key 'priority' repeated, only the first is read\n\
+         FAIL  recon.md: 3 ## Status sections, only the last is read\n"
+    );
+    assert!(
+        String::from_utf8(out.stderr)
+            .unwrap()
+            .contains("Run `orch validate --fix` to merge repeated ## Status sections.")
+    );
+    assert_eq!(
+        fs::read_to_string(f.tasks().join("recon.md")).unwrap(),
+        RECON
+    );
+}
+
+#[test]
+fn fix_merges_status_sections_and_leaves_keys_to_a_person() {
+    let f = fixture();
+    let out = f.output(&["validate", "--fix"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "FAIL  auth.md: frontmatter key 'priority' repeated, only the first is read\n\
+         fixed recon.md: merged its ## Status sections\n"
+    );
+    assert_eq!(
+        fs::read_to_string(f.tasks().join("recon.md")).unwrap(),
+        "# Recon\n\n## Context\nNotes.\n\n## Status\n\n\
+         - 2026-10-15 09:00 a\n- 2026-10-15 09:30 b\n- 2026-10-15 10:00 c\n"
+    );
+
+    f.write("auth", "---\npriority: 2\n---\n# Auth\n");
+    assert_eq!(f.orch(&["validate"]), "3 tasks checked, no problems.\n");
+}
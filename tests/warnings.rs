@@ -0,0 +1,143 @@
+//! Warnings while a command prints JSON: they go to stderr, or into the
+//! document when it's an object, and stdout stays one parseable document
+//! (or one per line).
+
+mod common;
+
+use std::{fs, process::Output};
+
+use common::Fixture;
+use serde_json::Value;
+
+/// A task with a duplicated frontmatter key and two `## Status` sections,
+/// and a config file that doesn't parse: three warnings on any read.
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.task(
+        "recon",
+        3,
+        "priority: high\npriority: low\n",
+        "# Recon\n\n## Status\n- 2026-10-15 09:12 needs input: which API key to use\n\n\
+         ## Status\n- 2026-10-15 09:30 question: staging or prod?\n",
+    );
+    fs::write(
+        fixture.home.path().join("config.toml"),
+        "poll_interval = [\n",
+    )
+    .unwrap();
+    fixture
+}
+
+fn stderr(out: &Output) -> String {
+    String::from_utf8(out.stderr.clone()).unwrap()
+}
+
+/// The codes of the warnings `out` printed to stderr, in order.
+fn warned(out: &Output) -> Vec<String> {
+    stderr(out)
+        .lines()
+        .filter_map(|line| line.strip_prefix("warning: "))
+        .map(|rest| rest.split(' ').next().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn status_json_stays_one_document() {
+    let f = fixture();
+    let out = f.output(&["status", "--json"]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    // An array has nowhere to put them, so they go to stderr, once each
+    let value: Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(value.as_array().unwrap().len(), 1);
+    assert_eq!(warned(&out), ["W001", "W012", "W013"]);
+}
+
+#[test]
+fn stats_json_carries_them() {
+    let f = fixture();
+    let out = f.output(&["stats", "--json"]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    let value: Value = serde_json::from_slice(&out.stdout).unwrap();
+    let codes: Vec<_> = value["warnings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|w| w["code"].as_str().unwrap())
+        .collect();
+    assert!(codes.contains(&"W001"), "{value}");
+    assert!(value["buckets"].is_array());
+    assert_eq!(warned(&out), Vec::<String>::new());
+}
+
+#[test]
+fn status_json_lines_sends_warnings_to_stderr() {
+    let f = fixture();
+    let out = f.output(&["status", "--json-lines"]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    let stdout = String::from_utf8(out.stdout.clone()).unwrap();
+    assert_eq!(stdout.lines().count(), 1);
+    for line in stdout.lines() {
+        let value: Value = serde_json::from_str(line).unwrap();
+        assert_eq!(value["label"], "recon");
+        assert!(value.get("warnings").is_none_or(Value::is_array));
+    }
+    assert_eq!(warned(&out), ["W001", "W012", "W013"]);
+}
+
+#[test]
+fn show_json_keeps_its_own_warnings() {
+    let f = fixture();
+    let out = f.output(&["show", "recon", "--json"]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    let value: Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(value["label"], "recon");
+    // The task's own warnings, as strings; orch's go to stderr
+    assert!(
+        value["warnings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(Value::is_string)
+    );
+    assert_eq!(warned(&out), ["W001", "W012", "W013"]);
+}
+
+#[test]
+fn format_json_stays_one_document() {
+    let f = fixture();
+    let out = f.output(&["--output-format", "json", "inbox"]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    let value: Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(value[0]["label"], "recon");
+    assert_eq!(warned(&out), ["W001", "W012", "W013"]);
+}
+
+#[test]
+fn porcelain_sends_warnings_to_stderr() {
+    let f = fixture();
+    let out = f.output(&["status", "--output-format", "porcelain"]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    let stdout = String::from_utf8(out.stdout.clone()).unwrap();
+    assert!(stdout.starts_with("recon\t"), "{stdout}");
+    assert!(!stdout.contains("warning"), "{stdout}");
+    assert_eq!(warned(&out), ["W001", "W012", "W013"]);
+}
+
+#[test]
+fn deny_warnings_fails_after_printing_the_json() {
+    let f = fixture();
+    let out = f.output(&["--deny-warnings", "status", "--json"]);
+    assert_eq!(out.status.code(), Some(1));
+    let value: Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(value.as_array().unwrap().len(), 1);
+    assert_eq!(warned(&out), ["W001", "W012", "W013"]);
+}
+
+#[test]
+fn no_warnings_no_key() {
+    let f = Fixture::new();
+    f.task("recon", 3, "", "# Recon\n");
+    let value: Value = serde_json::from_str(&f.orch(&["status", "--json"])).unwrap();
+    assert!(value.get("warnings").is_none(), "{value}");
+    assert!(f.output(&["--deny-warnings", "status"]).status.success());
+}
@@ -0,0 +1,121 @@
+//! `orch which`: the prompt resolution order it prints, and what each root
+//! ends up with.
+
+mod common;
+
+use std::fs;
+
+use common::{Fixture, lines};
+
+/// A `work/` root with its own prompt, a `home/` root without, and a
+/// config naming a prompt of its own.
+fn fixture(mode: &str) -> Fixture {
+    let f = Fixture::new();
+    let home = f.home.path();
+    fs::create_dir_all(f.tasks().join("work")).unwrap();
+    fs::create_dir_all(f.tasks().join("home")).unwrap();
+    fs::write(f.tasks().join("work/orchestrator.md"), "Work rules.\n").unwrap();
+    fs::write(home.join("config.md"), "Config rules.\n").unwrap();
+    fs::write(home.join("flag.md"), "Flag rules.\n").unwrap();
+    fs::write(
+        home.join("config.toml"),
+        format!(
+            "orchestrator_prompt = \"{}\"\n\n[root_prompts]\nmode = \"{mode}\"\n",
+            home.join("config.md").display()
+        ),
+    )
+    .unwrap();
+    f
+}
+
+/// `orch args` with the fixture's paths and the binary's dir made stable.
+fn stable(f: &Fixture, args: &[&str]) -> String {
+    let exe = std::path::Path::new(env!("CARGO_BIN_EXE_orch"));
+    f.orch(args)
+        .replace(exe.parent().unwrap().to_str().unwrap(), "$BIN")
+        .replace(f.home.path().to_str().unwrap(), "$HOME")
+}
+
+#[test]
+fn which_lists_the_order_and_each_roots_prompt() {
+    let f = fixture("replace");
+    assert_eq!(
+        stable(&f, &["which"]),
+        lines(&[
+            "config: $HOME/config.toml",
+            "tasks dir: $HOME/tasks",
+            "state dir: $HOME/tasks/.orch",
+            "orchestrator prompt, first match wins:",
+            "  1. --prompt: (not given)",
+            "  2. root-local file: <root>/orchestrator.md, replaces the rest of this list",
+            "  3. orchestrator_prompt in the config: $HOME/config.md",
+            "  4. beside the orch binary: $BIN/orchestrator.md (missing)",
+            "  5. claude's orchestrator agent: $HOME/.claude/agents/orchestrator.md (missing)",
+            "roots:",
+            "  the top level  $HOME/config.md  [orchestrator_prompt in the config]",
+            "  home/          $HOME/config.md  [orchestrator_prompt in the config]",
+            "  work/          $HOME/tasks/work/orchestrator.md  [root-local file]",
+        ])
+    );
+}
+
+#[test]
+fn which_with_append_mode() {
+    let f = fixture("append");
+    let out = stable(&f, &["which"]);
+    assert!(
+        out.contains(
+            "  2. root-local file: <root>/orchestrator.md, appended to the rest of this list\n"
+        ),
+        "{out}"
+    );
+    assert!(
+        out.contains(
+            "  work/          $HOME/config.md + $HOME/tasks/work/orchestrator.md  \
+             [orchestrator_prompt in the config + root-local file]\n"
+        ),
+        "{out}"
+    );
+}
+
+#[test]
+fn which_with_the_flag() {
+    let f = fixture("replace");
+    let flag = f.home.path().join("flag.md");
+    let out = stable(&f, &["which", "--prompt", flag.to_str().unwrap()]);
+    assert!(out.contains("  1. --prompt: $HOME/flag.md\n"), "{out}");
+    let roots = out.split_once("roots:\n").unwrap().1;
+    assert_eq!(roots.lines().count(), 3, "{out}");
+    assert!(
+        roots
+            .lines()
+            .all(|l| l.ends_with("$HOME/flag.md  [--prompt]")),
+        "{out}"
+    );
+}
+
+#[test]
+fn prompt_prints_a_roots_prompt() {
+    let f = fixture("replace");
+    let flag = f.home.path().join("flag.md");
+    let flag = flag.to_str().unwrap();
+    for (args, file, text) in [
+        (
+            &["prompt", "--root", "work"][..],
+            "$HOME/tasks/work/orchestrator.md",
+            "Work rules.",
+        ),
+        (
+            &["prompt", "--root", "home"],
+            "$HOME/config.md",
+            "Config rules.",
+        ),
+        (
+            &["prompt", "--root", "work", "--prompt", flag],
+            "$HOME/flag.md",
+            "Flag rules.",
+        ),
+    ] {
+        assert_eq!(stable(&f, args), lines(&[&format!("# {file}"), text]));
+    }
+}